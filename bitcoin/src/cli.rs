@@ -1,6 +1,6 @@
 #![cfg(feature = "cli")]
 
-use crate::{BitcoinCoreApi, BitcoinCoreBuilder, Error};
+use crate::{BitcoinCoreApi, BitcoinCoreBuilder, BitcoinNetwork, Error, FeeEstimation, FeeEstimationMode, SatPerVbyte};
 use bitcoincore_rpc::{bitcoin::Network, Auth};
 use clap::Parser;
 use std::{sync::Arc, time::Duration};
@@ -32,15 +32,46 @@ pub struct BitcoinOpts {
     #[cfg_attr(feature = "light-client", clap(conflicts_with_all(["light", "bitcoin_wif"])))]
     pub bitcoin_rpc_pass: Option<String>,
 
+    /// Bitcoin network to use: `mainnet`, `testnet`, `signet` or `regtest`. Connecting to
+    /// bitcoin-core on another network fails. If unset, the network of bitcoin-core is used.
+    #[clap(long, env = "BITCOIN_NETWORK")]
+    pub bitcoin_network: Option<BitcoinNetwork>,
+
     /// Timeout in milliseconds to wait for connection to bitcoin-core.
     #[clap(long, default_value = "60000")]
     pub bitcoin_connection_timeout_ms: u64,
 
+    /// Interval in milliseconds at which to check whether a payout entered the mempool.
+    #[clap(long, default_value = "1000")]
+    pub bitcoin_mempool_poll_interval_ms: u64,
+
+    /// Number of connections to bitcoin-core, so that independent RPC calls run concurrently.
+    #[clap(long, default_value = "4")]
+    pub btc_rpc_connections: usize,
+
     /// Url of the electrs server. If unset, a default fallback
     /// is used depending on the detected network.
     #[clap(long)]
     pub electrs_url: Option<String>,
 
+    /// Estimate the fee rate of payouts with bitcoin-core's `estimatesmartfee`, targeting
+    /// confirmation within this many blocks. If unset, the fee rate of the oracle is used.
+    #[clap(long)]
+    pub btc_fee_target_blocks: Option<u16>,
+
+    /// Fee estimation mode: `economical` or `conservative`.
+    #[clap(long, default_value = "conservative", requires = "btc_fee_target_blocks")]
+    pub btc_fee_mode: FeeEstimationMode,
+
+    /// Minimum fee rate in sat/vByte to pay with the estimate of bitcoin-core. The fee rate of the
+    /// oracle is used if bitcoin-core cannot estimate it.
+    #[clap(long, default_value = "1", requires = "btc_fee_target_blocks")]
+    pub btc_min_fee_rate: u64,
+
+    /// Signal BIP125 replaceability on payouts, so that their fees can be bumped.
+    #[clap(long)]
+    pub btc_rbf: bool,
+
     /// Experimental: Run in light client mode
     #[cfg_attr(feature = "light-client", clap(long, requires_all(["bitcoin_wif"])))]
     #[cfg(feature = "light-client")]
@@ -70,19 +101,40 @@ impl BitcoinOpts {
             .set_auth(self.new_auth())
             .set_wallet_name(wallet_name)
             .set_electrs_url(self.electrs_url.clone())
+            .set_fee_estimation(self.fee_estimation())
+            .set_replaceable(self.btc_rbf)
+            .set_mempool_poll_interval(Duration::from_millis(self.bitcoin_mempool_poll_interval_ms))
+            .set_rpc_connections(self.btc_rpc_connections)
+            .set_network(self.bitcoin_network)
+    }
+
+    fn fee_estimation(&self) -> Option<FeeEstimation> {
+        self.btc_fee_target_blocks.map(|target_blocks| FeeEstimation {
+            target_blocks,
+            mode: self.btc_fee_mode,
+            min_fee_rate: SatPerVbyte(self.btc_min_fee_rate),
+        })
     }
 
     #[cfg(feature = "light-client")]
     fn new_light_client(&self) -> Result<BitcoinLight, Error> {
-        Ok(BitcoinLight::new(
-            self.electrs_url.clone(),
-            get_private_key_from_file(self.bitcoin_wif.as_ref().expect("Private key not set"))?,
-        )?)
+        let mut private_key = get_private_key_from_file(self.bitcoin_wif.as_ref().expect("Private key not set"))?;
+        if let Some(network) = self.bitcoin_network {
+            // WIF only distinguishes mainnet from the test networks
+            if (network == BitcoinNetwork::Mainnet) != (private_key.network == Network::Bitcoin) {
+                return Err(Error::NetworkMismatch(network, private_key.network.into()));
+            }
+            private_key.network = network.into();
+        }
+        Ok(BitcoinLight::new(self.electrs_url.clone(), private_key)?)
     }
 
+    /// Connect to bitcoind and wait for it to sync, then create or load the wallet. Observers only
+    /// read wallets that are already loaded, so they skip the latter.
     pub async fn new_client(
         &self,
         wallet_name: Option<String>,
+        observer: bool,
     ) -> Result<Arc<dyn BitcoinCoreApi + Send + Sync>, Error> {
         cfg_if::cfg_if! {
             if #[cfg(feature = "light-client")] {
@@ -94,7 +146,9 @@ impl BitcoinOpts {
                         .build_and_connect(Duration::from_millis(self.bitcoin_connection_timeout_ms))
                         .await?;
                     bitcoin_core.sync().await?;
-                    bitcoin_core.create_or_load_wallet().await?;
+                    if !observer {
+                        bitcoin_core.create_or_load_wallet().await?;
+                    }
                     Arc::new(bitcoin_core)
                 })
             } else {
@@ -103,7 +157,9 @@ impl BitcoinOpts {
                     .build_and_connect(Duration::from_millis(self.bitcoin_connection_timeout_ms))
                     .await?;
                 bitcoin_core.sync().await?;
-                bitcoin_core.create_or_load_wallet().await?;
+                if !observer {
+                    bitcoin_core.create_or_load_wallet().await?;
+                }
                 Ok(Arc::new(bitcoin_core))
             }
         }
@@ -1,10 +1,11 @@
-use crate::{BitcoinError, BitcoinLightError, ElectrsError};
+use crate::{BitcoinError, BitcoinLightError, BitcoinNetwork, ElectrsError};
 use bitcoincore_rpc::{
     bitcoin::{
         consensus::encode::Error as BitcoinEncodeError,
         hashes::{hex::Error as HashHexError, Error as HashesError},
         secp256k1::Error as Secp256k1Error,
         util::{address::Error as AddressError, key::Error as KeyError},
+        Network, Txid,
     },
     jsonrpc::{error::RpcError, Error as JsonRpcError},
 };
@@ -60,20 +61,34 @@ pub enum Error {
     TransactionSigningError,
     #[error("Failed to obtain public key")]
     MissingPublicKey,
+    #[error("Private key not found in the wallet descriptors")]
+    MissingPrivateKey,
     #[error("Failed to connect")]
     ConnectionRefused,
+    #[error("Timed out waiting for an idle bitcoin-core connection")]
+    ConnectionPoolTimeout,
     #[error("Wallet not found")]
     WalletNotFound,
     #[error("Invalid Bitcoin network")]
     InvalidBitcoinNetwork,
+    #[error("Expected the {0} bitcoin network, but connected to {1}")]
+    NetworkMismatch(BitcoinNetwork, BitcoinNetwork),
     #[error("Transaction contains more than one return-to-self utxo")]
     TooManyReturnToSelfAddresses,
     #[error("ArithmeticError")]
     ArithmeticError,
     #[error("MissingBitcoinFeeInfo")]
     MissingBitcoinFeeInfo,
+    #[error("Cannot bump the fee of a transaction that does not signal replaceability")]
+    ReplaceByFeeDisabled,
+    #[error("Transaction {0} did not show up in the mempool in time")]
+    MempoolTimeout(Txid),
+    #[error("Failed to import descriptor: {0}")]
+    DescriptorImportError(String),
     #[error("FailedToConstructWalletName")]
     FailedToConstructWalletName,
+    #[error("Not submitting in observer mode: would {0}")]
+    ObserverMode(String),
 }
 
 impl Error {
@@ -144,6 +159,10 @@ pub enum ConversionError {
     InvalidFormat,
     #[error("Invalid payload")]
     InvalidPayload,
+    #[error("Witness version {0} addresses are not supported by the parachain")]
+    UnsupportedWitnessVersion(u8),
+    #[error("Address is not valid for network {0}, found {1}")]
+    InvalidNetwork(Network, Network),
     #[error("Could not convert block hash")]
     BlockHashError,
 }
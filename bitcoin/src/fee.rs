@@ -0,0 +1,121 @@
+use crate::{json, SatPerVbyte};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Mode of Bitcoin Core's `estimatesmartfee`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeEstimationMode {
+    /// Lower estimate that is more responsive to short-term drops in the fee market
+    Economical,
+    /// Higher estimate that is more likely to confirm within the target
+    Conservative,
+}
+
+impl Default for FeeEstimationMode {
+    fn default() -> Self {
+        FeeEstimationMode::Conservative
+    }
+}
+
+impl FromStr for FeeEstimationMode {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "economical" => Ok(FeeEstimationMode::Economical),
+            "conservative" => Ok(FeeEstimationMode::Conservative),
+            _ => Err("Could not parse input as FeeEstimationMode".to_string()),
+        }
+    }
+}
+
+impl From<FeeEstimationMode> for json::EstimateMode {
+    fn from(mode: FeeEstimationMode) -> Self {
+        match mode {
+            FeeEstimationMode::Economical => json::EstimateMode::Economical,
+            FeeEstimationMode::Conservative => json::EstimateMode::Conservative,
+        }
+    }
+}
+
+/// How the node estimates the fee rate of payouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimation {
+    /// Number of blocks the payout should confirm within
+    pub target_blocks: u16,
+    pub mode: FeeEstimationMode,
+    /// Lower bound of the estimate
+    pub min_fee_rate: SatPerVbyte,
+}
+
+impl FeeEstimation {
+    /// Parameters of the `estimatesmartfee` call.
+    pub(crate) fn estimate_smart_fee_args(&self) -> [Value; 2] {
+        [json!(self.target_blocks), json!(json::EstimateMode::from(self.mode))]
+    }
+
+    /// The fee rate to pay given the result of `estimatesmartfee`, never below the minimum. `None` if
+    /// the node cannot estimate, e.g. because it has not seen enough blocks yet.
+    pub(crate) fn fee_rate(&self, result: &json::EstimateSmartFeeResult) -> Option<SatPerVbyte> {
+        match result.fee_rate {
+            // the estimate is in BTC/kvB
+            Some(fee_rate) => Some(SatPerVbyte(fee_rate.to_sat().div_ceil(1000).max(self.min_fee_rate.0))),
+            None => {
+                log::warn!("Fee estimation unavailable: {:?}", result.errors);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Amount;
+
+    fn fee_estimation(mode: FeeEstimationMode) -> FeeEstimation {
+        FeeEstimation {
+            target_blocks: 6,
+            mode,
+            min_fee_rate: SatPerVbyte(2),
+        }
+    }
+
+    #[test]
+    fn test_estimate_smart_fee_args() {
+        assert_eq!(
+            fee_estimation(FeeEstimationMode::Economical).estimate_smart_fee_args(),
+            [json!(6), json!("ECONOMICAL")]
+        );
+        assert_eq!(
+            fee_estimation(FeeEstimationMode::Conservative).estimate_smart_fee_args(),
+            [json!(6), json!("CONSERVATIVE")]
+        );
+    }
+
+    #[test]
+    fn test_fee_rate_is_at_least_minimum() {
+        let fee_estimation = fee_estimation(FeeEstimationMode::Conservative);
+        let result = |fee_rate, errors| json::EstimateSmartFeeResult {
+            fee_rate,
+            errors,
+            blocks: 6,
+        };
+
+        // 0.00012345 BTC/kvB is 12.345 sat/vB, rounded up
+        assert_eq!(
+            fee_estimation.fee_rate(&result(Some(Amount::from_sat(12_345)), None)),
+            Some(SatPerVbyte(13))
+        );
+        assert_eq!(
+            fee_estimation.fee_rate(&result(Some(Amount::from_sat(1_000)), None)),
+            Some(SatPerVbyte(2))
+        );
+        assert_eq!(
+            fee_estimation.fee_rate(&result(
+                None,
+                Some(vec!["Insufficient data or no feerate found".to_string()])
+            )),
+            None
+        );
+    }
+}
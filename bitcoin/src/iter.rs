@@ -204,8 +204,8 @@ mod tests {
         trait BitcoinCoreApi {
             fn network(&self) -> Network;
             async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, Error>;
-            fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error>;
-            fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error>;
+            async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error>;
+            async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error>;
             async fn get_block_count(&self) -> Result<u64, Error>;
             async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error>;
             async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, Error>;
@@ -213,8 +213,8 @@ mod tests {
             async fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error>;
             async fn get_new_address(&self) -> Result<Address, Error>;
             async fn get_new_public_key(&self) -> Result<PublicKey, Error>;
-            fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error>;
-            fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error>;
+            async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error>;
+            async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error>;
             async fn add_new_deposit_key(
                 &self,
                 public_key: PublicKey,
@@ -253,7 +253,7 @@ mod tests {
                 &self,
                 addresses: Vec<Address>,
             ) -> Result<(), Error>;
-            fn get_utxo_count(&self) -> Result<usize, Error>;
+            async fn get_utxo_count(&self) -> Result<usize, Error>;
             async fn bump_fee(
                 &self,
                 txid: &Txid,
@@ -262,6 +262,8 @@ mod tests {
             ) -> Result<Txid, Error>;
             async fn is_in_mempool(&self, txid: Txid) -> Result<bool, Error>;
             async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, Error>;
+            async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, Error>;
+            async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), Error>;
         }
     }
 
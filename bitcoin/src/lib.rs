@@ -9,7 +9,12 @@ pub use light::{BitcoinLight, Error as BitcoinLightError};
 mod addr;
 mod electrs;
 mod error;
+mod fee;
 mod iter;
+mod network;
+mod observer;
+mod pool;
+mod wallet;
 
 use async_trait::async_trait;
 use backoff::{backoff::Backoff, future::retry, ExponentialBackoff};
@@ -28,7 +33,12 @@ pub use bitcoincore_rpc::{
         secp256k1,
         secp256k1::{constants::PUBLIC_KEY_SIZE, SecretKey},
         util::{
-            self, address::Payload, key, merkleblock::PartialMerkleTree, psbt, psbt::serialize::Serialize,
+            self,
+            address::{Payload, WitnessVersion},
+            key,
+            merkleblock::PartialMerkleTree,
+            psbt,
+            psbt::serialize::Serialize,
             uint::Uint256,
         },
         Address, Amount, Block, BlockHeader, Network, OutPoint, PrivateKey, PubkeyHash, PublicKey, Script, ScriptHash,
@@ -44,13 +54,18 @@ pub use bitcoincore_rpc::{
 };
 pub use electrs::{ElectrsClient, Error as ElectrsError};
 pub use error::{BitcoinRpcError, ConversionError, Error};
+pub use fee::{FeeEstimation, FeeEstimationMode};
 pub use iter::{reverse_stream_transactions, stream_blocks, stream_in_chain_transactions};
 use log::{info, trace, warn};
+pub use network::BitcoinNetwork;
+pub use observer::ObserverBitcoin;
+use pool::ClientPool;
 use serde_json::error::Category as SerdeJsonCategory;
 pub use sp_core::H256;
 use std::{
     convert::TryInto,
     future::Future,
+    str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -58,6 +73,8 @@ use tokio::{
     sync::{Mutex, OwnedMutexGuard},
     time::{sleep, timeout},
 };
+use util::bip32::DerivationPath;
+pub use wallet::WalletType;
 
 #[macro_use]
 extern crate num_derive;
@@ -80,6 +97,12 @@ const RETRY_DURATION: Duration = Duration::from_millis(1000);
 // Time to sleep before checking if the rescan is done yet.
 const RESCAN_POLL_INTERVAL: Duration = Duration::from_secs(10);
 
+/// Default number of connections to bitcoin-core, matching its default number of RPC threads.
+pub const DEFAULT_RPC_CONNECTIONS: usize = 4;
+
+/// Default time to sleep before checking if a transaction entered the mempool yet.
+pub const DEFAULT_MEMPOOL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 // The default initial interval value (1 second).
 const INITIAL_INTERVAL: Duration = Duration::from_millis(1000);
 
@@ -131,9 +154,9 @@ pub trait BitcoinCoreApi {
 
     async fn get_block_count(&self) -> Result<u64, Error>;
 
-    fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error>;
+    async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error>;
 
-    fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error>;
+    async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error>;
 
     async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error>;
 
@@ -147,9 +170,9 @@ pub trait BitcoinCoreApi {
 
     async fn get_new_public_key(&self) -> Result<PublicKey, Error>;
 
-    fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error>;
+    async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error>;
 
-    fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error>;
+    async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error>;
 
     async fn add_new_deposit_key(&self, public_key: PublicKey, secret_key: Vec<u8>) -> Result<(), Error>;
 
@@ -196,11 +219,19 @@ pub trait BitcoinCoreApi {
 
     async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), Error>;
 
-    fn get_utxo_count(&self) -> Result<usize, Error>;
+    async fn get_utxo_count(&self) -> Result<usize, Error>;
 
     async fn is_in_mempool(&self, txid: Txid) -> Result<bool, Error>;
 
     async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, Error>;
+
+    /// The fee rate the node estimates for a new payout, `None` if fee estimation is not configured or the
+    /// node has no estimate.
+    async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, Error>;
+
+    /// Wait until the transaction is in the mempool, or already mined, to detect broadcasts that
+    /// were dropped. Fails with `Error::MempoolTimeout` if it is not seen within `timeout`.
+    async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), Error>;
 }
 
 struct LockedTransaction {
@@ -219,21 +250,13 @@ impl LockedTransaction {
     }
 }
 
-fn parse_bitcoin_network(src: &str) -> Result<Network, Error> {
-    match src {
-        "main" => Ok(Network::Bitcoin),
-        "test" => Ok(Network::Testnet),
-        "regtest" => Ok(Network::Regtest),
-        _ => Err(Error::InvalidBitcoinNetwork),
-    }
-}
-
 struct ConnectionInfo {
     chain: String,
     version: usize,
 }
 
-fn get_info(rpc: &Client) -> Result<ConnectionInfo, Error> {
+async fn get_info(rpc: &ClientPool) -> Result<ConnectionInfo, Error> {
+    let rpc = rpc.get().await?;
     let blockchain_info = rpc.get_blockchain_info()?;
     let network_info = rpc.get_network_info()?;
     Ok(ConnectionInfo {
@@ -243,11 +266,11 @@ fn get_info(rpc: &Client) -> Result<ConnectionInfo, Error> {
 }
 
 /// Connect to a bitcoin-core full node or timeout.
-async fn connect(rpc: &Client, connection_timeout: Duration) -> Result<Network, Error> {
+async fn connect(rpc: &ClientPool, connection_timeout: Duration) -> Result<BitcoinNetwork, Error> {
     info!("Connecting to bitcoin-core...");
     timeout(connection_timeout, async move {
         loop {
-            match get_info(rpc) {
+            match get_info(rpc).await {
                 Err(err)
                     if err.is_transport_error() =>
                 {
@@ -277,7 +300,7 @@ async fn connect(rpc: &Client, connection_timeout: Duration) -> Result<Network,
                         return Err(Error::IncompatibleVersion(version))
                     }
 
-                    return parse_bitcoin_network(&chain);
+                    return BitcoinNetwork::from_chain(&chain);
                 }
                 Err(err) => return Err(err),
             }
@@ -291,6 +314,11 @@ pub struct BitcoinCoreBuilder {
     auth: Auth,
     wallet_name: Option<String>,
     electrs_url: Option<String>,
+    fee_estimation: Option<FeeEstimation>,
+    replaceable: bool,
+    mempool_poll_interval: Duration,
+    rpc_connections: usize,
+    network: Option<BitcoinNetwork>,
 }
 
 impl BitcoinCoreBuilder {
@@ -300,6 +328,11 @@ impl BitcoinCoreBuilder {
             auth: Auth::None,
             wallet_name: None,
             electrs_url: None,
+            fee_estimation: None,
+            replaceable: false,
+            mempool_poll_interval: DEFAULT_MEMPOOL_POLL_INTERVAL,
+            rpc_connections: DEFAULT_RPC_CONNECTIONS,
+            network: None,
         }
     }
 
@@ -318,6 +351,34 @@ impl BitcoinCoreBuilder {
         self
     }
 
+    pub fn set_fee_estimation(mut self, fee_estimation: Option<FeeEstimation>) -> Self {
+        self.fee_estimation = fee_estimation;
+        self
+    }
+
+    /// Whether to signal BIP125 replaceability on new transactions, which is needed to bump their fees.
+    pub fn set_replaceable(mut self, replaceable: bool) -> Self {
+        self.replaceable = replaceable;
+        self
+    }
+
+    pub fn set_mempool_poll_interval(mut self, mempool_poll_interval: Duration) -> Self {
+        self.mempool_poll_interval = mempool_poll_interval;
+        self
+    }
+
+    /// Network that bitcoin-core has to be on when connecting, any if `None`.
+    pub fn set_network(mut self, network: Option<BitcoinNetwork>) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Number of connections that RPC calls are spread over, at least one.
+    pub fn set_rpc_connections(mut self, rpc_connections: usize) -> Self {
+        self.rpc_connections = rpc_connections.max(1);
+        self
+    }
+
     fn new_client(&self) -> Result<Client, Error> {
         let url = match self.wallet_name {
             Some(ref x) => format!("{}/wallet/{}", self.url, x),
@@ -339,34 +400,93 @@ impl BitcoinCoreBuilder {
         Ok(Client::from_jsonrpc(rpc_client))
     }
 
+    fn new_client_pool(&self) -> Result<ClientPool, Error> {
+        let clients = (0..self.rpc_connections)
+            .map(|_| self.new_client())
+            .collect::<Result<_, _>>()?;
+        // by then, a call on an idle connection would have timed out as well
+        Ok(ClientPool::new(clients, TRANSPORT_TIMEOUT))
+    }
+
     pub fn build_with_network(self, network: Network) -> Result<BitcoinCore, Error> {
-        BitcoinCore::new(self.new_client()?, self.wallet_name, network, self.electrs_url)
+        BitcoinCore::new(
+            self.new_client_pool()?,
+            self.wallet_name,
+            network,
+            self.electrs_url,
+            self.fee_estimation,
+            self.replaceable,
+            self.mempool_poll_interval,
+        )
     }
 
     pub async fn build_and_connect(self, connection_timeout: Duration) -> Result<BitcoinCore, Error> {
-        let client = self.new_client()?;
+        let client = self.new_client_pool()?;
         let network = connect(&client, connection_timeout).await?;
-        BitcoinCore::new(client, self.wallet_name, network, self.electrs_url)
+        if let Some(expected) = self.network {
+            expected.verify(network)?;
+        }
+        BitcoinCore::new(
+            client,
+            self.wallet_name,
+            network.into(),
+            self.electrs_url,
+            self.fee_estimation,
+            self.replaceable,
+            self.mempool_poll_interval,
+        )
     }
 }
 
+/// Arguments of `createrawtransaction` for a payment to `address`, with an op_return output if
+/// `request_id` is set.
+fn create_raw_transaction_args(
+    address: String,
+    amount: Amount,
+    request_id: Option<H256>,
+    replaceable: bool,
+) -> Result<[serde_json::Value; 4], Error> {
+    let mut outputs = serde_json::Map::<String, serde_json::Value>::new();
+    // add the payment output
+    outputs.insert(address, serde_json::Value::from(amount.to_btc()));
+
+    if let Some(request_id) = request_id {
+        // add the op_return data - bitcoind will add op_return and the length automatically
+        outputs.insert("data".to_string(), serde_json::Value::from(request_id.to_hex()));
+    }
+
+    Ok([
+        serde_json::to_value::<&[json::CreateRawTransactionInput]>(&[])?,
+        serde_json::to_value(outputs)?,
+        serde_json::to_value(0i64)?, /* locktime - default 0: see https://developer.bitcoin.org/reference/rpc/createrawtransaction.html */
+        serde_json::to_value(replaceable)?, // BIP125-replaceable, aka Replace By Fee (RBF)
+    ])
+}
+
 #[derive(Clone)]
 pub struct BitcoinCore {
-    rpc: Arc<Client>,
+    rpc: Arc<ClientPool>,
     wallet_name: Option<String>,
     network: Network,
     transaction_creation_lock: Arc<Mutex<()>>,
     electrs_client: ElectrsClient,
+    fee_estimation: Option<FeeEstimation>,
+    /// whether new transactions signal BIP125 replaceability
+    replaceable: bool,
+    mempool_poll_interval: Duration,
     #[cfg(feature = "regtest-manual-mining")]
     auto_mine: bool,
 }
 
 impl BitcoinCore {
     fn new(
-        client: Client,
+        client: ClientPool,
         wallet_name: Option<String>,
         network: Network,
         electrs_url: Option<String>,
+        fee_estimation: Option<FeeEstimation>,
+        replaceable: bool,
+        mempool_poll_interval: Duration,
     ) -> Result<Self, Error> {
         Ok(BitcoinCore {
             rpc: Arc::new(client),
@@ -374,6 +494,9 @@ impl BitcoinCore {
             network,
             transaction_creation_lock: Arc::new(Mutex::new(())),
             electrs_client: ElectrsClient::new(electrs_url, network)?,
+            fee_estimation,
+            replaceable,
+            mempool_poll_interval,
             #[cfg(feature = "regtest-manual-mining")]
             auto_mine: false,
         })
@@ -388,7 +511,7 @@ impl BitcoinCore {
     pub async fn sync(&self) -> Result<(), Error> {
         info!("Waiting for bitcoin-core to sync...");
         loop {
-            let info = self.rpc.get_blockchain_info()?;
+            let info = self.rpc.get().await?.get_blockchain_info()?;
             // NOTE: initial_block_download is always true on regtest
             // but testnet and mainnet never reach 100% verification
             if !info.initial_block_download || info.verification_progress.eq(&1.0) {
@@ -401,28 +524,14 @@ impl BitcoinCore {
     }
 
     /// Wrapper of rust_bitcoincore_rpc::create_raw_transaction_hex that accepts an optional op_return
-    fn create_raw_transaction_hex(
+    async fn create_raw_transaction_hex(
         &self,
         address: String,
         amount: Amount,
         request_id: Option<H256>,
     ) -> Result<String, Error> {
-        let mut outputs = serde_json::Map::<String, serde_json::Value>::new();
-        // add the payment output
-        outputs.insert(address, serde_json::Value::from(amount.to_btc()));
-
-        if let Some(request_id) = request_id {
-            // add the op_return data - bitcoind will add op_return and the length automatically
-            outputs.insert("data".to_string(), serde_json::Value::from(request_id.to_hex()));
-        }
-
-        let args = [
-            serde_json::to_value::<&[json::CreateRawTransactionInput]>(&[])?,
-            serde_json::to_value(outputs)?,
-            serde_json::to_value(0i64)?, /* locktime - default 0: see https://developer.bitcoin.org/reference/rpc/createrawtransaction.html */
-            serde_json::to_value(true)?, // BIP125-replaceable, aka Replace By Fee (RBF)
-        ];
-        Ok(self.rpc.call("createrawtransaction", &args)?)
+        let args = create_raw_transaction_args(address, amount, request_id, self.replaceable)?;
+        Ok(self.rpc.get().await?.call("createrawtransaction", &args)?)
     }
 
     async fn fund_and_sign_transaction(
@@ -443,17 +552,17 @@ impl BitcoinCore {
             let funding_opts = FundRawTransactionOptions {
                 fee_rate: Some(Amount::from_sat(fee_rate)),
                 change_address: return_to_self_address.clone(),
-                replaceable: Some(true),
+                replaceable: Some(self.replaceable),
                 ..Default::default()
             };
 
+            let rpc = self.rpc.get().await?;
             // fund the transaction: adds required inputs, and possibly a return-to-self output
-            let funded_raw_tx = self.rpc.fund_raw_transaction(raw_tx, Some(&funding_opts), None)?;
+            let funded_raw_tx = rpc.fund_raw_transaction(raw_tx, Some(&funding_opts), None)?;
 
             // sign the transaction
             let signed_funded_raw_tx =
-                self.rpc
-                    .sign_raw_transaction_with_wallet(&funded_raw_tx.transaction()?, None, None)?;
+                rpc.sign_raw_transaction_with_wallet(&funded_raw_tx.transaction()?, None, None)?;
 
             // Make sure signing is successful
             if signed_funded_raw_tx.errors.is_some() {
@@ -492,6 +601,7 @@ impl BitcoinCore {
                 // to add the op_return afterwards. However, this function fails if no inputs are
                 // specified, as is the case for us prior to calling fund_raw_transaction.
                 self.create_raw_transaction_hex(recipient.clone(), Amount::from_sat(sat), request_id)
+                    .await
             })
             .await?;
 
@@ -508,25 +618,23 @@ impl BitcoinCore {
 
         // place the transaction into the mempool, this is fine to retry
         let txid = self
-            .with_wallet(|| async { Ok(self.rpc.send_raw_transaction(&transaction.transaction)?) })
+            .with_wallet(|| async { Ok(self.rpc.get().await?.send_raw_transaction(&transaction.transaction)?) })
             .await?;
 
         #[cfg(feature = "regtest-manual-mining")]
         if self.auto_mine {
             log::debug!("Auto-mining!");
 
-            self.rpc
-                .generate_to_address(1, &self.rpc.get_new_address(None, Some(AddressType::Bech32))?)?;
+            self.mine_block().await?;
         }
 
         Ok(txid)
     }
 
     #[cfg(feature = "regtest-manual-mining")]
-    pub fn mine_block(&self) -> Result<BlockHash, Error> {
-        Ok(self
-            .rpc
-            .generate_to_address(1, &self.rpc.get_new_address(None, Some(AddressType::Bech32))?)?[0])
+    pub async fn mine_block(&self) -> Result<BlockHash, Error> {
+        let rpc = self.rpc.get().await?;
+        Ok(rpc.generate_to_address(1, &rpc.get_new_address(None, Some(AddressType::Bech32))?)?[0])
     }
 
     async fn with_retry_on_timeout<F, R, T>(&self, call: F) -> Result<T, Error>
@@ -589,7 +697,7 @@ impl BitcoinCore {
     pub async fn wallet_has_public_key(&self, public_key: PublicKey) -> Result<bool, Error> {
         self.with_wallet(|| async {
             let address = Address::p2wpkh(&public_key, self.network).map_err(ConversionError::from)?;
-            let address_info = self.rpc.get_address_info(&address)?;
+            let address_info = self.rpc.get().await?.get_address_info(&address)?;
             let wallet_pubkey = address_info.pubkey.ok_or(Error::MissingPublicKey)?;
             Ok(wallet_pubkey == public_key)
         })
@@ -597,13 +705,78 @@ impl BitcoinCore {
     }
 
     pub async fn import_private_key(&self, privkey: PrivateKey) -> Result<(), Error> {
-        self.with_wallet(|| async { Ok(self.rpc.import_private_key(&privkey, None, None)?) })
+        self.with_wallet(|| async { self.import_key(&privkey, None, true).await })
             .await
     }
 
+    /// The type of the loaded wallet, which determines how keys are imported and exported.
+    pub async fn wallet_type(&self) -> Result<WalletType, Error> {
+        let wallet_info: serde_json::Value = self.rpc.get().await?.call("getwalletinfo", &[])?;
+        Ok(WalletType::from_wallet_info(&wallet_info))
+    }
+
+    /// Import the p2wpkh address of `private_key`, with the RPC that the wallet type supports.
+    async fn import_key(&self, private_key: &PrivateKey, label: Option<&str>, rescan: bool) -> Result<(), Error> {
+        let wallet_type = self.wallet_type().await?;
+        let rpc = self.rpc.get().await?;
+        match wallet_type {
+            WalletType::Legacy => Ok(rpc.import_private_key(private_key, label, Some(rescan))?),
+            WalletType::Descriptor => {
+                let descriptor = wallet::private_key_descriptor(private_key);
+                let descriptor_info: serde_json::Value =
+                    rpc.call("getdescriptorinfo", &[serde_json::to_value(&descriptor)?])?;
+                let checksum = descriptor_info["checksum"]
+                    .as_str()
+                    .ok_or_else(|| Error::DescriptorImportError("missing descriptor checksum".to_string()))?;
+
+                let request = wallet::import_descriptors_request(&descriptor, checksum, label, rescan);
+                let results: Vec<wallet::ImportDescriptorResult> = rpc.call("importdescriptors", &[request])?;
+                match results.into_iter().find(|result| !result.success) {
+                    Some(result) => Err(Error::DescriptorImportError(format!("{:?}", result.error))),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Export the private key of `address`. Descriptor wallets can't export single keys, so the key
+    /// is looked up in, or derived from, the wallet's private descriptors.
+    async fn dump_key(&self, address: &Address) -> Result<PrivateKey, Error> {
+        let wallet_type = self.wallet_type().await?;
+        let rpc = self.rpc.get().await?;
+        match wallet_type {
+            WalletType::Legacy => Ok(rpc.dump_private_key(address)?),
+            WalletType::Descriptor => {
+                let address_info: serde_json::Value =
+                    rpc.call("getaddressinfo", &[serde_json::to_value(address.to_string())?])?;
+                let public_key = address_info["pubkey"]
+                    .as_str()
+                    .and_then(|public_key| PublicKey::from_str(public_key).ok())
+                    .ok_or(Error::MissingPublicKey)?;
+                let key_path = address_info["hdkeypath"]
+                    .as_str()
+                    .and_then(|key_path| DerivationPath::from_str(key_path).ok());
+
+                let descriptors: wallet::ListDescriptorsResult =
+                    rpc.call("listdescriptors", &[serde_json::to_value(true)?])?;
+                let private_key = wallet::find_private_key(
+                    &secp256k1::Secp256k1::new(),
+                    &descriptors,
+                    &public_key,
+                    key_path.as_ref().map(|key_path| key_path.as_ref()),
+                )
+                .ok_or(Error::MissingPrivateKey)?;
+                Ok(PrivateKey {
+                    network: self.network,
+                    ..private_key
+                })
+            }
+        }
+    }
+
     pub async fn wait_for_rescan(&self) -> Result<(), Error> {
         loop {
-            let wallet_info = self.rpc.get_wallet_info()?;
+            let wallet_info = self.rpc.get().await?.get_wallet_info()?;
             match wallet_info.scanning {
                 Some(ScanningDetails::Scanning { progress, .. }) => {
                     info!("Scanning progress: {progress}");
@@ -615,6 +788,28 @@ impl BitcoinCore {
     }
 }
 
+/// Poll `is_seen` every `poll_interval` until it returns true, failing with `Error::MempoolTimeout`
+/// once `timeout` has elapsed.
+pub(crate) async fn poll_until_seen<F, R>(
+    txid: Txid,
+    timeout_duration: Duration,
+    poll_interval: Duration,
+    is_seen: F,
+) -> Result<(), Error>
+where
+    F: Fn() -> R,
+    R: Future<Output = Result<bool, Error>>,
+{
+    timeout(timeout_duration, async {
+        while !is_seen().await? {
+            sleep(poll_interval).await;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|_| Error::MempoolTimeout(txid))?
+}
+
 /// true if the given indicates that the item was not found in the mempool
 fn err_not_in_mempool(err: &bitcoincore_rpc::Error) -> bool {
     matches!(
@@ -640,12 +835,14 @@ impl BitcoinCoreApi for BitcoinCore {
     /// * `num_confirmations` - minimum for a block to be accepted
     async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, Error> {
         loop {
-            match self.rpc.get_block_hash(height.into()) {
+            let rpc = self.rpc.get().await?;
+            match rpc.get_block_hash(height.into()) {
                 Ok(hash) => {
-                    let info = self.rpc.get_block_info(&hash)?;
+                    let info = rpc.get_block_info(&hash)?;
                     if info.confirmations >= num_confirmations as i32 {
-                        return Ok(self.rpc.get_block(&hash)?);
+                        return Ok(rpc.get_block(&hash)?);
                     } else {
+                        drop(rpc);
                         sleep(RETRY_DURATION).await;
                         continue;
                     }
@@ -654,6 +851,7 @@ impl BitcoinCoreApi for BitcoinCore {
                     if BitcoinRpcError::from(err.clone()) == BitcoinRpcError::RpcInvalidParameter =>
                 {
                     // block does not exist yet
+                    drop(rpc);
                     sleep(RETRY_DURATION).await;
                     continue;
                 }
@@ -664,23 +862,27 @@ impl BitcoinCoreApi for BitcoinCore {
 
     /// Get the tip of the main chain as reported by Bitcoin core.
     async fn get_block_count(&self) -> Result<u64, Error> {
-        Ok(self.rpc.get_block_count()?)
+        Ok(self.rpc.get().await?.get_block_count()?)
     }
 
     /// Get wallet balance.
-    fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error> {
+    async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error> {
         Ok(self
             .rpc
+            .get()
+            .await?
             .get_balance(min_confirmations.map(|x| x.try_into().unwrap_or_default()), None)?)
     }
 
     /// List the transaction in the wallet. `max_count` sets a limit on the amount of transactions returned.
     /// If none is provided, [`DEFAULT_MAX_TX_COUNT`] is used, which is an arbitrarily picked big number to
     /// effectively return all transactions.
-    fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error> {
+    async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error> {
         // If no `max_count` is specified to the rpc call, bitcoin core only returns 10 items.
         Ok(self
             .rpc
+            .get()
+            .await?
             .list_transactions(None, max_count.or(Some(DEFAULT_MAX_TX_COUNT)), None, None)?)
     }
 
@@ -691,7 +893,9 @@ impl BitcoinCoreApi for BitcoinCore {
     /// * `txid` - transaction ID
     /// * `block_hash` - hash of the block tx is stored in
     async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error> {
-        Ok(serialize(&self.rpc.get_raw_transaction(txid, Some(block_hash))?))
+        Ok(serialize(
+            &self.rpc.get().await?.get_raw_transaction(txid, Some(block_hash))?,
+        ))
     }
 
     /// Get the raw transaction identified by `Txid` and stored
@@ -701,7 +905,7 @@ impl BitcoinCoreApi for BitcoinCore {
     /// * `txid` - transaction ID
     /// * `block_hash` - hash of the block tx is stored in
     async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, Error> {
-        Ok(self.rpc.get_raw_transaction(txid, block_hash.as_ref())?)
+        Ok(self.rpc.get().await?.get_raw_transaction(txid, block_hash.as_ref())?)
     }
 
     /// Get the merkle proof which can be used to validate transaction inclusion.
@@ -710,7 +914,7 @@ impl BitcoinCoreApi for BitcoinCore {
     /// * `txid` - transaction ID
     /// * `block_hash` - hash of the block tx is stored in
     async fn get_proof(&self, txid: Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error> {
-        Ok(self.rpc.get_tx_out_proof(&[txid], Some(block_hash))?)
+        Ok(self.rpc.get().await?.get_tx_out_proof(&[txid], Some(block_hash))?)
     }
 
     /// Get the block hash for a given height.
@@ -718,7 +922,8 @@ impl BitcoinCoreApi for BitcoinCore {
     /// # Arguments
     /// * `height` - block height
     async fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
-        match self.rpc.get_block_hash(height.into()) {
+        let result = self.rpc.get().await?.get_block_hash(height.into());
+        match result {
             Ok(block_hash) => Ok(block_hash),
             Err(BitcoinError::JsonRpc(JsonRpcError::Rpc(err)))
                 if BitcoinRpcError::from(err.clone()) == BitcoinRpcError::RpcInvalidParameter =>
@@ -732,75 +937,73 @@ impl BitcoinCoreApi for BitcoinCore {
 
     /// Gets a new address from the wallet
     async fn get_new_address(&self) -> Result<Address, Error> {
-        Ok(self.rpc.get_new_address(None, Some(AddressType::Bech32))?)
+        Ok(self.rpc.get().await?.get_new_address(None, Some(AddressType::Bech32))?)
     }
 
     /// Gets a new public key for an address in the wallet
     async fn get_new_public_key(&self) -> Result<PublicKey, Error> {
-        let address = self
-            .rpc
-            .get_new_address(Some(DERIVATION_KEY_LABEL), Some(AddressType::Bech32))?;
-        let address_info = self.rpc.get_address_info(&address)?;
+        let rpc = self.rpc.get().await?;
+        let address = rpc.get_new_address(Some(DERIVATION_KEY_LABEL), Some(AddressType::Bech32))?;
+        let address_info = rpc.get_address_info(&address)?;
         let public_key = address_info.pubkey.ok_or(Error::MissingPublicKey)?;
         Ok(public_key)
     }
 
-    fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error> {
+    async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error> {
         let address = Address::p2wpkh(public_key, self.network).map_err(ConversionError::from)?;
-        Ok(self.rpc.dump_private_key(&address)?)
+        self.dump_key(&address).await
     }
 
-    fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error> {
-        Ok(self
-            .rpc
-            .import_private_key(private_key, Some(DERIVATION_KEY_LABEL), Some(false))?)
+    async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error> {
+        self.import_key(private_key, Some(DERIVATION_KEY_LABEL), false).await
     }
 
     /// Derive and import the private key for the master public key and public secret
     async fn add_new_deposit_key(&self, public_key: PublicKey, secret_key: Vec<u8>) -> Result<(), Error> {
         let address = Address::p2wpkh(&public_key, self.network).map_err(ConversionError::from)?;
-        let private_key = self.rpc.dump_private_key(&address)?;
+        let private_key = self.dump_key(&address).await?;
         let deposit_secret_key =
             addr::calculate_deposit_secret_key(private_key.inner, SecretKey::from_slice(&secret_key)?)?;
-        self.rpc.import_private_key(
+        self.import_key(
             &PrivateKey {
                 compressed: private_key.compressed,
                 network: self.network,
                 inner: deposit_secret_key,
             },
             Some(DEPOSIT_LABEL),
-            // rescan true by default
-            Some(false),
-        )?;
-        Ok(())
+            false,
+        )
+        .await
     }
 
     async fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
-        Ok(self.rpc.get_best_block_hash()?)
+        Ok(self.rpc.get().await?.get_best_block_hash()?)
     }
 
     async fn get_pruned_height(&self) -> Result<u64, Error> {
-        Ok(self.rpc.get_blockchain_info()?.prune_height.unwrap_or(0))
+        Ok(self.rpc.get().await?.get_blockchain_info()?.prune_height.unwrap_or(0))
     }
 
     async fn get_block(&self, hash: &BlockHash) -> Result<Block, Error> {
-        Ok(self.rpc.get_block(hash)?)
+        Ok(self.rpc.get().await?.get_block(hash)?)
     }
 
     async fn get_block_header(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
-        Ok(self.rpc.get_block_header(hash)?)
+        Ok(self.rpc.get().await?.get_block_header(hash)?)
     }
 
     /// Get the transactions that are currently in the mempool. Since `impl trait` is not
-    /// allowed within trait method, we have to use trait objects.
+    /// allowed within trait method, we have to use trait objects. The iterator holds on to a
+    /// connection until it is dropped.
     async fn get_mempool_transactions<'a>(
         &'a self,
     ) -> Result<Box<dyn Iterator<Item = Result<Transaction, Error>> + Send + 'a>, Error> {
+        let rpc = self.rpc.get().await?;
         // get txids from the mempool
-        let txids = self.rpc.get_raw_mempool()?;
+        let txids = rpc.get_raw_mempool()?;
         // map txid to the actual Transaction structs
         let iterator = txids.into_iter().filter_map(move |txid| {
-            match self.rpc.get_raw_transaction(&txid, None) {
+            match rpc.get_raw_transaction(&txid, None) {
                 Ok(x) => Some(Ok(x)),
                 Err(e) if err_not_in_mempool(&e) => None, // not in mempool anymore, so filter out
                 Err(e) => Some(Err(e.into())),            // unknown error, propagate to user
@@ -821,7 +1024,8 @@ impl BitcoinCoreApi for BitcoinCore {
         num_confirmations: u32,
     ) -> Result<TransactionMetadata, Error> {
         let (block_height, block_hash, fee) = retry(get_exponential_backoff(), || async {
-            Ok(match self.rpc.get_transaction(&txid, None) {
+            let result = self.rpc.get().await?.get_transaction(&txid, None);
+            Ok(match result {
                 Ok(GetTransactionResult {
                     info:
                         WalletTxInfo {
@@ -860,9 +1064,13 @@ impl BitcoinCoreApi for BitcoinCore {
     }
 
     async fn bump_fee(&self, txid: &Txid, address: Address, fee_rate: SatPerVbyte) -> Result<Txid, Error> {
+        if !self.replaceable {
+            // the replacement would be rejected by the mempool
+            return Err(Error::ReplaceByFeeDisabled);
+        }
         let (raw_tx, return_to_self_address) = self
             .with_wallet_inner(false, || async {
-                let mut existing_transaction = self.rpc.get_raw_transaction(txid, None)?;
+                let mut existing_transaction = self.rpc.get().await?.get_raw_transaction(txid, None)?;
 
                 let return_to_self = existing_transaction
                     .extract_return_to_self_address(&address.payload)?
@@ -885,15 +1093,16 @@ impl BitcoinCoreApi for BitcoinCore {
             .await?;
 
         let txid = self
-            .with_wallet_inner(false, || async { Ok(self.rpc.send_raw_transaction(&tx.transaction)?) })
+            .with_wallet_inner(false, || async {
+                Ok(self.rpc.get().await?.send_raw_transaction(&tx.transaction)?)
+            })
             .await?;
 
         #[cfg(feature = "regtest-manual-mining")]
         if self.auto_mine {
             log::debug!("Auto-mining!");
 
-            self.rpc
-                .generate_to_address(1, &self.rpc.get_new_address(None, Some(AddressType::Bech32))?)?;
+            self.mine_block().await?;
         }
 
         Ok(txid)
@@ -953,20 +1162,21 @@ impl BitcoinCoreApi for BitcoinCore {
         };
 
         self.with_retry_on_timeout(|| async {
-            if self.rpc.list_wallets()?.contains(wallet_name) {
+            let rpc = self.rpc.get().await?;
+            if rpc.list_wallets()?.contains(wallet_name) {
                 // already loaded - nothing to do
                 info!("Wallet {wallet_name} already loaded");
-            } else if self.rpc.list_wallet_dir()?.contains(wallet_name) {
+            } else if rpc.list_wallet_dir()?.contains(wallet_name) {
                 // wallet exists but is not loaded
                 info!("Loading wallet {wallet_name}...");
-                let result = self.rpc.load_wallet(wallet_name)?;
+                let result = rpc.load_wallet(wallet_name)?;
                 if let Some(warning) = result.warning {
                     warn!("Received error while loading wallet {wallet_name}: {warning}");
                 }
             } else {
                 info!("Creating wallet {wallet_name}...");
                 // wallet does not exist, create
-                let result = self.rpc.create_wallet(wallet_name, None, None, None, None)?;
+                let result = rpc.create_wallet(wallet_name, None, None, None, None)?;
                 if let Some(warning) = result.warning {
                     if !warning.is_empty() {
                         warn!("Received warning while creating wallet {wallet_name}: {warning}");
@@ -984,12 +1194,14 @@ impl BitcoinCoreApi for BitcoinCore {
         // have been imported
         self.wait_for_rescan().await?;
 
-        match self
+        let result = self
             .rpc
+            .get()
+            .await?
             .rescan_blockchain(Some(start_height), Some(end_height))
             .map(|_| ())
-            .map_err(Into::<Error>::into)
-        {
+            .map_err(Into::<Error>::into);
+        match result {
             Err(e) if e.is_transport_error() => {
                 // we assume that if we get a transport error, it's because the
                 // rescan timed out. We just wait for it to complete
@@ -1024,7 +1236,7 @@ impl BitcoinCoreApi for BitcoinCore {
                     self.electrs_client.get_tx_merkle_block_proof(&transaction.txid),
                 )
                 .await?;
-                self.rpc.call(
+                self.rpc.get().await?.call(
                     "importprunedfunds",
                     &[serde_json::to_value(raw_tx)?, serde_json::to_value(raw_merkle_proof)?],
                 )?;
@@ -1034,20 +1246,21 @@ impl BitcoinCoreApi for BitcoinCore {
     }
 
     /// Get the number of unspent transaction outputs.
-    fn get_utxo_count(&self) -> Result<usize, Error> {
-        Ok(self.rpc.list_unspent(None, None, None, None, None)?.len())
+    async fn get_utxo_count(&self) -> Result<usize, Error> {
+        Ok(self.rpc.get().await?.list_unspent(None, None, None, None, None)?.len())
     }
 
     async fn is_in_mempool(&self, txid: Txid) -> Result<bool, Error> {
-        let get_tx_result = self.rpc.get_transaction(&txid, None)?;
+        let get_tx_result = self.rpc.get().await?.get_transaction(&txid, None)?;
         Ok(get_tx_result.info.confirmations == 0)
     }
 
     async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, Error> {
         // unfortunately we need both of these rpc results. The result of the second call
         // is not a parsed tx, but rather a GetTransactionResult.
-        let tx = self.rpc.get_raw_transaction(&txid, None)?;
-        let get_tx_result = self.rpc.get_transaction(&txid, None)?;
+        let rpc = self.rpc.get().await?;
+        let tx = rpc.get_raw_transaction(&txid, None)?;
+        let get_tx_result = rpc.get_transaction(&txid, None)?;
 
         // to get from weight to vsize we divide by 4, but round up by first adding 3
         // Note that we can not rely on tx.get_size() since it doesn't 'discount' witness bytes
@@ -1071,6 +1284,34 @@ impl BitcoinCoreApi for BitcoinCore {
         let fee_rate = fee.checked_div(vsize).ok_or(Error::ArithmeticError)?;
         Ok(SatPerVbyte(fee_rate.try_into()?))
     }
+
+    async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, Error> {
+        let fee_estimation = match self.fee_estimation {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        let result: json::EstimateSmartFeeResult = self
+            .rpc
+            .get()
+            .await?
+            .call("estimatesmartfee", &fee_estimation.estimate_smart_fee_args())?;
+        Ok(fee_estimation.fee_rate(&result))
+    }
+
+    async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), Error> {
+        poll_until_seen(txid, timeout, self.mempool_poll_interval, || async {
+            let rpc = self.rpc.get().await?;
+            match rpc.call::<serde_json::Value>("getmempoolentry", &[serde_json::to_value(txid)?]) {
+                Ok(_) => Ok(true),
+                // it may have been mined already
+                Err(err) if err_not_in_mempool(&err) => Ok(rpc
+                    .get_transaction(&txid, None)
+                    .map_or(false, |tx| tx.info.confirmations > 0)),
+                Err(err) => Err(err.into()),
+            }
+        })
+        .await
+    }
 }
 
 /// Extension trait for transaction, adding methods to help to match the Transaction to Replace/Redeem requests
@@ -1153,6 +1394,7 @@ impl TransactionExt for Transaction {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use bitcoincore_rpc::bitcoin::hashes::{hex::FromHex, sha256::Hash as Sha256Hash, Hash};
 
     #[test]
@@ -1165,4 +1407,55 @@ mod tests {
 
         assert_eq!(expected, script_hash);
     }
+
+    #[test]
+    fn test_create_raw_transaction_args_signal_replaceability() {
+        let address = "bcrt1qjvmc5dtm4qxgtug8faa5jdtqrq8pf6kp5ce5vr".to_string();
+        let request_id = H256::from_low_u64_be(1);
+
+        let args =
+            create_raw_transaction_args(address.clone(), Amount::from_sat(100_000), Some(request_id), true).unwrap();
+        assert_eq!(args[1][&address], serde_json::json!(0.001));
+        assert_eq!(args[1]["data"], serde_json::json!(request_id.to_hex()));
+        assert_eq!(args[3], serde_json::json!(true));
+
+        let args = create_raw_transaction_args(address, Amount::from_sat(100_000), None, false).unwrap();
+        assert!(args[1].get("data").is_none());
+        assert_eq!(args[3], serde_json::json!(false));
+    }
+
+    #[test]
+    fn test_builder_does_not_signal_replaceability_by_default() {
+        let address = "bcrt1qjvmc5dtm4qxgtug8faa5jdtqrq8pf6kp5ce5vr".to_string();
+        let builder = BitcoinCoreBuilder::new("http://localhost:18443".to_string());
+
+        let args =
+            create_raw_transaction_args(address.clone(), Amount::from_sat(100_000), None, builder.replaceable).unwrap();
+        assert_eq!(args[3], serde_json::json!(false));
+
+        let builder = builder.set_replaceable(true);
+        let args = create_raw_transaction_args(address, Amount::from_sat(100_000), None, builder.replaceable).unwrap();
+        assert_eq!(args[3], serde_json::json!(true));
+    }
+
+    #[tokio::test]
+    async fn test_poll_until_seen() {
+        let txid = Txid::all_zeros();
+        let polls = std::sync::atomic::AtomicU32::new(0);
+        // the mock reports the transaction on the third poll
+        let is_seen = || async { Ok(polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) >= 2) };
+
+        poll_until_seen(txid, Duration::from_secs(5), Duration::from_millis(10), is_seen)
+            .await
+            .unwrap();
+        assert_eq!(polls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        assert!(matches!(
+            poll_until_seen(txid, Duration::from_millis(50), Duration::from_millis(10), || async {
+                Ok(false)
+            })
+            .await,
+            Err(Error::MempoolTimeout(x)) if x == txid
+        ));
+    }
 }
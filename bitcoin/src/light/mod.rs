@@ -109,12 +109,15 @@ impl BitcoinCoreApi for BitcoinLight {
         Ok(self.electrs.get_blocks_tip_height().await?.into())
     }
 
-    fn get_balance(&self, _min_confirmations: Option<u32>) -> Result<Amount, BitcoinError> {
+    async fn get_balance(&self, _min_confirmations: Option<u32>) -> Result<Amount, BitcoinError> {
         // TODO: implement
         Ok(Default::default())
     }
 
-    fn list_transactions(&self, _max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError> {
+    async fn list_transactions(
+        &self,
+        _max_count: Option<usize>,
+    ) -> Result<Vec<json::ListTransactionResult>, BitcoinError> {
         // TODO: implement
         Ok(Default::default())
     }
@@ -147,11 +150,11 @@ impl BitcoinCoreApi for BitcoinLight {
         Ok(self.private_key.public_key(&self.secp_ctx))
     }
 
-    fn dump_derivation_key(&self, _public_key: &PublicKey) -> Result<PrivateKey, BitcoinError> {
+    async fn dump_derivation_key(&self, _public_key: &PublicKey) -> Result<PrivateKey, BitcoinError> {
         Ok(self.private_key)
     }
 
-    fn import_derivation_key(&self, _private_key: &PrivateKey) -> Result<(), BitcoinError> {
+    async fn import_derivation_key(&self, _private_key: &PrivateKey) -> Result<(), BitcoinError> {
         // nothing to do
         Ok(())
     }
@@ -273,7 +276,7 @@ impl BitcoinCoreApi for BitcoinLight {
         Ok(())
     }
 
-    fn get_utxo_count(&self) -> Result<usize, BitcoinError> {
+    async fn get_utxo_count(&self) -> Result<usize, BitcoinError> {
         // TODO: implement
         Ok(Default::default())
     }
@@ -303,4 +306,17 @@ impl BitcoinCoreApi for BitcoinLight {
         let fee_rate = fee.checked_div(vsize).ok_or(BitcoinError::ArithmeticError)?;
         Ok(SatPerVbyte(fee_rate))
     }
+
+    async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, BitcoinError> {
+        // the light client has no node to estimate with
+        Ok(None)
+    }
+
+    async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), BitcoinError> {
+        poll_until_seen(txid, timeout, DEFAULT_MEMPOOL_POLL_INTERVAL, || async {
+            Ok(self.is_in_mempool(txid).await?
+                || matches!(self.electrs.get_tx_info(&txid).await, Ok(info) if info.confirmations > 0))
+        })
+        .await
+    }
 }
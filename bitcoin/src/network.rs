@@ -0,0 +1,121 @@
+use crate::{Error, Network};
+use std::{fmt, str::FromStr};
+
+/// Bitcoin network to operate on, which addresses are encoded and validated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitcoinNetwork {
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl BitcoinNetwork {
+    /// Parse the `chain` reported by `getblockchaininfo`.
+    pub(crate) fn from_chain(chain: &str) -> Result<Self, Error> {
+        match chain {
+            "main" => Ok(BitcoinNetwork::Mainnet),
+            "test" => Ok(BitcoinNetwork::Testnet),
+            "signet" => Ok(BitcoinNetwork::Signet),
+            "regtest" => Ok(BitcoinNetwork::Regtest),
+            _ => Err(Error::InvalidBitcoinNetwork),
+        }
+    }
+
+    /// Returns `Error::NetworkMismatch` unless `actual` is this network.
+    pub(crate) fn verify(self, actual: BitcoinNetwork) -> Result<(), Error> {
+        if self == actual {
+            Ok(())
+        } else {
+            Err(Error::NetworkMismatch(self, actual))
+        }
+    }
+}
+
+impl FromStr for BitcoinNetwork {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "mainnet" => Ok(BitcoinNetwork::Mainnet),
+            "testnet" => Ok(BitcoinNetwork::Testnet),
+            "signet" => Ok(BitcoinNetwork::Signet),
+            "regtest" => Ok(BitcoinNetwork::Regtest),
+            _ => Err("Could not parse input as BitcoinNetwork".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for BitcoinNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcoinNetwork::Mainnet => write!(f, "mainnet"),
+            BitcoinNetwork::Testnet => write!(f, "testnet"),
+            BitcoinNetwork::Signet => write!(f, "signet"),
+            BitcoinNetwork::Regtest => write!(f, "regtest"),
+        }
+    }
+}
+
+impl From<BitcoinNetwork> for Network {
+    fn from(network: BitcoinNetwork) -> Self {
+        match network {
+            BitcoinNetwork::Mainnet => Network::Bitcoin,
+            BitcoinNetwork::Testnet => Network::Testnet,
+            BitcoinNetwork::Signet => Network::Signet,
+            BitcoinNetwork::Regtest => Network::Regtest,
+        }
+    }
+}
+
+impl From<Network> for BitcoinNetwork {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => BitcoinNetwork::Mainnet,
+            Network::Testnet => BitcoinNetwork::Testnet,
+            Network::Signet => BitcoinNetwork::Signet,
+            Network::Regtest => BitcoinNetwork::Regtest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETWORKS: [BitcoinNetwork; 4] = [
+        BitcoinNetwork::Mainnet,
+        BitcoinNetwork::Testnet,
+        BitcoinNetwork::Signet,
+        BitcoinNetwork::Regtest,
+    ];
+
+    #[test]
+    fn test_parse_bitcoin_network() {
+        for network in NETWORKS {
+            assert_eq!(network.to_string().parse::<BitcoinNetwork>().unwrap(), network);
+            assert_eq!(BitcoinNetwork::from(Network::from(network)), network);
+        }
+        assert!("main".parse::<BitcoinNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_bitcoin_network_from_chain() {
+        assert_eq!(BitcoinNetwork::from_chain("main").unwrap(), BitcoinNetwork::Mainnet);
+        assert_eq!(BitcoinNetwork::from_chain("test").unwrap(), BitcoinNetwork::Testnet);
+        assert_eq!(BitcoinNetwork::from_chain("signet").unwrap(), BitcoinNetwork::Signet);
+        assert_eq!(BitcoinNetwork::from_chain("regtest").unwrap(), BitcoinNetwork::Regtest);
+        assert!(matches!(
+            BitcoinNetwork::from_chain("testnet4"),
+            Err(Error::InvalidBitcoinNetwork)
+        ));
+    }
+
+    #[test]
+    fn test_verify_bitcoin_network() {
+        assert!(BitcoinNetwork::Signet.verify(BitcoinNetwork::Signet).is_ok());
+        assert!(matches!(
+            BitcoinNetwork::Signet.verify(BitcoinNetwork::Testnet),
+            Err(Error::NetworkMismatch(BitcoinNetwork::Signet, BitcoinNetwork::Testnet))
+        ));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    json, Address, Amount, BitcoinCoreApi, Block, BlockHash, BlockHeader, Error, Network, PrivateKey, PublicKey,
+    SatPerVbyte, Transaction, TransactionMetadata, Txid, H256,
+};
+use async_trait::async_trait;
+use log::info;
+use std::{sync::Arc, time::Duration};
+
+/// Wraps a bitcoin client so that it never writes to the wallet: payments, fee bumps and key
+/// imports are only logged and fail with `Error::ObserverMode`, while all reads are forwarded.
+/// Wallets are not created or loaded either, so they must already be loaded in bitcoind for the
+/// wallet queries to succeed.
+pub struct ObserverBitcoin(Arc<dyn BitcoinCoreApi + Send + Sync>);
+
+impl ObserverBitcoin {
+    pub fn new(inner: Arc<dyn BitcoinCoreApi + Send + Sync>) -> Self {
+        Self(inner)
+    }
+}
+
+fn intercept<T>(action: String) -> Result<T, Error> {
+    info!("Observer mode: would {}", action);
+    Err(Error::ObserverMode(action))
+}
+
+#[async_trait]
+impl BitcoinCoreApi for ObserverBitcoin {
+    fn network(&self) -> Network {
+        self.0.network()
+    }
+
+    async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, Error> {
+        self.0.wait_for_block(height, num_confirmations).await
+    }
+
+    async fn get_block_count(&self) -> Result<u64, Error> {
+        self.0.get_block_count().await
+    }
+
+    async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error> {
+        self.0.get_balance(min_confirmations).await
+    }
+
+    async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error> {
+        self.0.list_transactions(max_count).await
+    }
+
+    async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error> {
+        self.0.get_raw_tx(txid, block_hash).await
+    }
+
+    async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, Error> {
+        self.0.get_transaction(txid, block_hash).await
+    }
+
+    async fn get_proof(&self, txid: Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error> {
+        self.0.get_proof(txid, block_hash).await
+    }
+
+    async fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
+        self.0.get_block_hash(height).await
+    }
+
+    async fn get_new_address(&self) -> Result<Address, Error> {
+        intercept("generate a new address".to_string())
+    }
+
+    async fn get_new_public_key(&self) -> Result<PublicKey, Error> {
+        intercept("generate a new public key".to_string())
+    }
+
+    async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error> {
+        self.0.dump_derivation_key(public_key).await
+    }
+
+    async fn import_derivation_key(&self, _private_key: &PrivateKey) -> Result<(), Error> {
+        intercept("import a derivation key".to_string())
+    }
+
+    async fn add_new_deposit_key(&self, public_key: PublicKey, _secret_key: Vec<u8>) -> Result<(), Error> {
+        intercept(format!("import the deposit key of {}", public_key))
+    }
+
+    async fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
+        self.0.get_best_block_hash().await
+    }
+
+    async fn get_pruned_height(&self) -> Result<u64, Error> {
+        self.0.get_pruned_height().await
+    }
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<Block, Error> {
+        self.0.get_block(hash).await
+    }
+
+    async fn get_block_header(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
+        self.0.get_block_header(hash).await
+    }
+
+    async fn get_mempool_transactions<'a>(
+        &'a self,
+    ) -> Result<Box<dyn Iterator<Item = Result<Transaction, Error>> + Send + 'a>, Error> {
+        self.0.get_mempool_transactions().await
+    }
+
+    async fn wait_for_transaction_metadata(
+        &self,
+        txid: Txid,
+        num_confirmations: u32,
+    ) -> Result<TransactionMetadata, Error> {
+        self.0.wait_for_transaction_metadata(txid, num_confirmations).await
+    }
+
+    async fn bump_fee(&self, txid: &Txid, _address: Address, fee_rate: SatPerVbyte) -> Result<Txid, Error> {
+        intercept(format!("bump the fee of {} to {} sat/vByte", txid, fee_rate.0))
+    }
+
+    async fn create_and_send_transaction(
+        &self,
+        address: Address,
+        sat: u64,
+        _fee_rate: SatPerVbyte,
+        request_id: Option<H256>,
+    ) -> Result<Txid, Error> {
+        intercept(format!(
+            "send {} satoshi to {} for request {:?}",
+            sat, address, request_id
+        ))
+    }
+
+    async fn send_to_address(
+        &self,
+        address: Address,
+        sat: u64,
+        request_id: Option<H256>,
+        _fee_rate: SatPerVbyte,
+        _num_confirmations: u32,
+    ) -> Result<TransactionMetadata, Error> {
+        intercept(format!(
+            "send {} satoshi to {} for request {:?}",
+            sat, address, request_id
+        ))
+    }
+
+    async fn create_or_load_wallet(&self) -> Result<(), Error> {
+        info!("Observer mode: would create or load the wallet");
+        Ok(())
+    }
+
+    async fn rescan_blockchain(&self, start_height: usize, end_height: usize) -> Result<(), Error> {
+        intercept(format!("rescan the blocks {} to {}", start_height, end_height))
+    }
+
+    async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), Error> {
+        intercept(format!("rescan {} addresses", addresses.len()))
+    }
+
+    async fn get_utxo_count(&self) -> Result<usize, Error> {
+        self.0.get_utxo_count().await
+    }
+
+    async fn is_in_mempool(&self, txid: Txid) -> Result<bool, Error> {
+        self.0.is_in_mempool(txid).await
+    }
+
+    async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, Error> {
+        self.0.fee_rate(txid).await
+    }
+
+    async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, Error> {
+        self.0.estimate_fee_rate().await
+    }
+
+    async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), Error> {
+        self.0.wait_for_transaction_in_mempool(txid, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mockall::mock! {
+        Bitcoin {}
+
+        #[async_trait]
+        trait BitcoinCoreApi {
+            fn network(&self) -> Network;
+            async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, Error>;
+            async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, Error>;
+            async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, Error>;
+            async fn get_block_count(&self) -> Result<u64, Error>;
+            async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error>;
+            async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, Error>;
+            async fn get_proof(&self, txid: Txid, block_hash: &BlockHash) -> Result<Vec<u8>, Error>;
+            async fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error>;
+            async fn get_new_address(&self) -> Result<Address, Error>;
+            async fn get_new_public_key(&self) -> Result<PublicKey, Error>;
+            async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, Error>;
+            async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), Error>;
+            async fn add_new_deposit_key(
+                &self,
+                public_key: PublicKey,
+                secret_key: Vec<u8>,
+            ) -> Result<(), Error>;
+            async fn get_best_block_hash(&self) -> Result<BlockHash, Error>;
+            async fn get_pruned_height(&self) -> Result<u64, Error>;
+            async fn get_block(&self, hash: &BlockHash) -> Result<Block, Error>;
+            async fn get_block_header(&self, hash: &BlockHash) -> Result<BlockHeader, Error>;
+            async fn get_mempool_transactions<'a>(
+                &'a self,
+            ) -> Result<Box<dyn Iterator<Item = Result<Transaction, Error>> + Send + 'a>, Error>;
+            async fn wait_for_transaction_metadata(
+                &self,
+                txid: Txid,
+                num_confirmations: u32,
+            ) -> Result<TransactionMetadata, Error>;
+            async fn create_and_send_transaction(
+                &self,
+                address: Address,
+                sat: u64,
+                fee_rate: SatPerVbyte,
+                request_id: Option<H256>,
+            ) -> Result<Txid, Error>;
+            async fn send_to_address(
+                &self,
+                address: Address,
+                sat: u64,
+                request_id: Option<H256>,
+                fee_rate: SatPerVbyte,
+                num_confirmations: u32,
+            ) -> Result<TransactionMetadata, Error>;
+            async fn create_or_load_wallet(&self) -> Result<(), Error>;
+            async fn rescan_blockchain(&self, start_height: usize, end_height: usize) -> Result<(), Error>;
+            async fn rescan_electrs_for_addresses(
+                &self,
+                addresses: Vec<Address>,
+            ) -> Result<(), Error>;
+            async fn get_utxo_count(&self) -> Result<usize, Error>;
+            async fn bump_fee(
+                &self,
+                txid: &Txid,
+                address: Address,
+                fee_rate: SatPerVbyte,
+            ) -> Result<Txid, Error>;
+            async fn is_in_mempool(&self, txid: Txid) -> Result<bool, Error>;
+            async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, Error>;
+            async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, Error>;
+            async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), Error>;
+        }
+    }
+
+    #[tokio::test]
+    async fn should_forward_reads_and_intercept_writes() {
+        // the inner client has no expectations for writes, so it panics if they are forwarded
+        let mut inner = MockBitcoin::default();
+        inner.expect_get_block_count().times(1).returning(|| Ok(101));
+        inner.expect_get_utxo_count().times(1).returning(|| Ok(3));
+        let observer = ObserverBitcoin::new(Arc::new(inner));
+
+        assert_eq!(observer.get_block_count().await.unwrap(), 101);
+        assert_eq!(observer.get_utxo_count().await.unwrap(), 3);
+
+        // the wallet must already be loaded
+        assert!(observer.create_or_load_wallet().await.is_ok());
+        assert!(matches!(
+            observer.get_new_public_key().await,
+            Err(Error::ObserverMode(action)) if action == "generate a new public key"
+        ));
+        assert!(matches!(
+            observer.rescan_blockchain(1, 10).await,
+            Err(Error::ObserverMode(action)) if action == "rescan the blocks 1 to 10"
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::Error;
+use std::{ops::Deref, sync::Mutex, time::Duration};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::timeout,
+};
+
+/// Connections to bitcoin-core that RPC calls are spread over. A single connection handles one
+/// call at a time, so independent calls would otherwise wait for each other.
+pub(crate) struct ClientPool<C = crate::Client> {
+    idle: Mutex<Vec<C>>,
+    /// one permit per idle connection, so that callers wait without blocking the executor
+    permits: Semaphore,
+    /// how long a call may wait for a connection to become idle
+    acquire_timeout: Duration,
+}
+
+impl<C> ClientPool<C> {
+    pub(crate) fn new(clients: Vec<C>, acquire_timeout: Duration) -> Self {
+        Self {
+            permits: Semaphore::new(clients.len()),
+            idle: Mutex::new(clients),
+            acquire_timeout,
+        }
+    }
+
+    /// Take an idle connection, waiting for one to be returned if all are in use. The connection
+    /// is returned to the pool once the guard is dropped, so it should not be held across long waits.
+    pub(crate) async fn get(&self) -> Result<PooledClient<'_, C>, Error> {
+        let permit = timeout(self.acquire_timeout, self.permits.acquire())
+            .await
+            .map_err(|_| Error::ConnectionPoolTimeout)?
+            .expect("the semaphore is never closed");
+        let client = self
+            .idle
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("a permit guarantees an idle client");
+        Ok(PooledClient {
+            pool: self,
+            client: Some(client),
+            _permit: permit,
+        })
+    }
+}
+
+/// A connection taken from the pool, which is returned once dropped.
+pub(crate) struct PooledClient<'a, C> {
+    pool: &'a ClientPool<C>,
+    client: Option<C>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<C> Deref for PooledClient<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.client.as_ref().expect("only taken on drop")
+    }
+}
+
+impl<C> Drop for PooledClient<'_, C> {
+    fn drop(&mut self) {
+        // the client is back in the pool before the permit is released
+        if let Some(client) = self.client.take() {
+            self.pool.idle.lock().unwrap().push(client);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitcoinError, RpcApi};
+    use serde_json::Value;
+    use std::{
+        collections::HashSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    /// Client that takes a while to respond with its id, tracking the number of concurrent calls.
+    struct MockClient {
+        id: usize,
+        active: Arc<AtomicUsize>,
+        max_active: Arc<AtomicUsize>,
+    }
+
+    impl RpcApi for MockClient {
+        fn call<T: for<'a> serde::de::Deserialize<'a>>(&self, _cmd: &str, _args: &[Value]) -> Result<T, BitcoinError> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(serde_json::from_value(Value::from(self.id))?)
+        }
+    }
+
+    fn mock_pool(size: usize, acquire_timeout: Duration) -> (Arc<ClientPool<MockClient>>, Arc<AtomicUsize>) {
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let clients = (0..size)
+            .map(|id| MockClient {
+                id,
+                active: active.clone(),
+                max_active: max_active.clone(),
+            })
+            .collect();
+        (Arc::new(ClientPool::new(clients, acquire_timeout)), max_active)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 6)]
+    async fn test_concurrent_calls_use_multiple_connections() {
+        let (pool, max_active) = mock_pool(3, Duration::from_secs(5));
+
+        let calls: Vec<_> = (0..6)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.get().await.unwrap().call::<usize>("getblockcount", &[]).unwrap() })
+            })
+            .collect();
+        let used: HashSet<_> = futures::future::try_join_all(calls)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect();
+
+        assert!(used.len() > 1);
+        assert!(max_active.load(Ordering::SeqCst) > 1);
+        // never more calls at once than there are connections
+        assert!(max_active.load(Ordering::SeqCst) <= 3);
+        assert_eq!(pool.idle.lock().unwrap().len(), 3);
+        assert_eq!(pool.permits.available_permits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_times_out_when_all_connections_are_busy() {
+        let (pool, _) = mock_pool(1, Duration::from_millis(10));
+
+        let busy = pool.get().await.unwrap();
+        assert!(matches!(pool.get().await, Err(Error::ConnectionPoolTimeout)));
+        drop(busy);
+        assert_eq!(
+            pool.get().await.unwrap().call::<usize>("getblockcount", &[]).unwrap(),
+            0
+        );
+    }
+}
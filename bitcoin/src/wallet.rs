@@ -0,0 +1,203 @@
+use crate::{
+    secp256k1::{Secp256k1, Signing},
+    util::bip32::{ChildNumber, ExtendedPrivKey},
+    PrivateKey, PublicKey,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{fmt, str::FromStr};
+
+/// Kind of Bitcoin Core wallet, which determines the RPCs available to import and export keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletType {
+    /// Berkeley DB wallet, with `importprivkey` and `dumpprivkey`
+    Legacy,
+    /// Descriptor wallet, the default since Bitcoin Core 23, with `importdescriptors`
+    Descriptor,
+}
+
+impl fmt::Display for WalletType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletType::Legacy => write!(f, "legacy"),
+            WalletType::Descriptor => write!(f, "descriptor"),
+        }
+    }
+}
+
+impl WalletType {
+    /// Detect the type from the result of `getwalletinfo`. Versions before Bitcoin Core 0.21 don't
+    /// report `descriptors`, and only have legacy wallets.
+    pub(crate) fn from_wallet_info(wallet_info: &Value) -> Self {
+        if wallet_info["descriptors"].as_bool().unwrap_or(false) {
+            WalletType::Descriptor
+        } else {
+            WalletType::Legacy
+        }
+    }
+}
+
+/// Descriptor for the p2wpkh address of `private_key`, as used for vault addresses.
+pub(crate) fn private_key_descriptor(private_key: &PrivateKey) -> String {
+    format!("wpkh({})", private_key.to_wif())
+}
+
+/// Request of `importdescriptors` for a single descriptor with its `checksum`. Without a rescan,
+/// only transactions from now on are tracked.
+pub(crate) fn import_descriptors_request(descriptor: &str, checksum: &str, label: Option<&str>, rescan: bool) -> Value {
+    let mut request = json!({
+        "desc": format!("{}#{}", descriptor, checksum),
+        "timestamp": if rescan { json!(0) } else { json!("now") },
+    });
+    if let Some(label) = label {
+        request["label"] = json!(label);
+    }
+    json!([request])
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct ImportDescriptorResult {
+    pub success: bool,
+    pub error: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct ListDescriptorsResult {
+    pub descriptors: Vec<DescriptorInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct DescriptorInfo {
+    pub desc: String,
+}
+
+/// Key expressions of `descriptor`, e.g. `[d34db33f/84']tprv.../0/*` for
+/// `wpkh([d34db33f/84']tprv.../0/*)#checksum`.
+fn key_expressions(descriptor: &str) -> impl Iterator<Item = &str> {
+    let descriptor = descriptor.split('#').next().unwrap_or_default();
+    let start = descriptor.rfind('(').map_or(0, |index| index + 1);
+    let end = descriptor[start..]
+        .find(')')
+        .map_or(descriptor.len(), |index| start + index);
+    descriptor[start..end].split(',')
+}
+
+/// Find the private key of `public_key` in the private descriptors returned by `listdescriptors
+/// true`: either a key imported on its own, or one derived from an extended key at `key_path`,
+/// the `hdkeypath` reported by `getaddressinfo`.
+pub(crate) fn find_private_key<C: Signing>(
+    secp: &Secp256k1<C>,
+    descriptors: &ListDescriptorsResult,
+    public_key: &PublicKey,
+    key_path: Option<&[ChildNumber]>,
+) -> Option<PrivateKey> {
+    descriptors
+        .descriptors
+        .iter()
+        .flat_map(|descriptor| key_expressions(&descriptor.desc))
+        .filter_map(|expression| {
+            let (origin, key) = match expression.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                Some((origin, key)) => (origin.split_once('/').map_or("", |(_, path)| path), key),
+                None => ("", expression),
+            };
+            let key = key.split('/').next().unwrap_or_default();
+            if let Ok(private_key) = PrivateKey::from_wif(key) {
+                return Some(private_key);
+            }
+            // extended keys are derived from where their origin ends, keys without origin are masters
+            let extended_key = ExtendedPrivKey::from_str(key).ok()?;
+            let origin = origin
+                .split('/')
+                .filter(|step| !step.is_empty())
+                .map(ChildNumber::from_str)
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+            let path = key_path?.strip_prefix(origin.as_slice())?;
+            Some(extended_key.derive_priv(secp, &path).ok()?.to_priv())
+        })
+        .find(|private_key| private_key.public_key(secp) == *public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{secp256k1::SecretKey, util::bip32::DerivationPath, Network};
+
+    #[test]
+    fn test_wallet_type_from_wallet_info() {
+        let legacy = json!({ "walletname": "vault", "walletversion": 169900, "keypoolsize": 1000 });
+        assert_eq!(WalletType::from_wallet_info(&legacy), WalletType::Legacy);
+
+        let legacy = json!({ "walletname": "vault", "walletversion": 169900, "descriptors": false });
+        assert_eq!(WalletType::from_wallet_info(&legacy), WalletType::Legacy);
+
+        let descriptor = json!({ "walletname": "vault", "walletversion": 169900, "descriptors": true });
+        assert_eq!(WalletType::from_wallet_info(&descriptor), WalletType::Descriptor);
+    }
+
+    #[test]
+    fn test_import_descriptors_request() {
+        let private_key = PrivateKey::new(SecretKey::from_slice(&[1; 32]).unwrap(), Network::Regtest);
+        let descriptor = private_key_descriptor(&private_key);
+        assert_eq!(descriptor, format!("wpkh({})", private_key.to_wif()));
+
+        assert_eq!(
+            import_descriptors_request(&descriptor, "ytxdu4ws", Some("deposit"), false),
+            json!([{
+                "desc": format!("{}#ytxdu4ws", descriptor),
+                "timestamp": "now",
+                "label": "deposit",
+            }])
+        );
+        assert_eq!(
+            import_descriptors_request(&descriptor, "ytxdu4ws", None, true),
+            json!([{
+                "desc": format!("{}#ytxdu4ws", descriptor),
+                "timestamp": 0,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_find_private_key() {
+        let secp = Secp256k1::new();
+        let master = ExtendedPrivKey::new_master(Network::Regtest, &[2; 32]).unwrap();
+        let account_path = DerivationPath::from_str("m/84'/1'/0'").unwrap();
+        let account = master.derive_priv(&secp, &account_path).unwrap();
+        let key_path = DerivationPath::from_str("m/84'/1'/0'/0/3").unwrap();
+        let derived = master.derive_priv(&secp, &key_path).unwrap().to_priv();
+        let imported = PrivateKey::new(SecretKey::from_slice(&[1; 32]).unwrap(), Network::Regtest);
+        let descriptors = |descs: Vec<String>| ListDescriptorsResult {
+            descriptors: descs.into_iter().map(|desc| DescriptorInfo { desc }).collect(),
+        };
+
+        // keys of the wallet's own descriptors are derived from the master key at the key path
+        let wallet = descriptors(vec![
+            format!("pkh({}/44'/1'/0'/0/*)#checksum", master),
+            format!("wpkh({}/84'/1'/0'/0/*)#checksum", master),
+            format!("{}#checksum", private_key_descriptor(&imported)),
+        ]);
+        let find = |public_key: &PrivateKey, key_path: Option<&DerivationPath>| {
+            find_private_key(
+                &secp,
+                &wallet,
+                &public_key.public_key(&secp),
+                key_path.map(|path| path.as_ref()),
+            )
+        };
+        assert_eq!(find(&derived, Some(&key_path)), Some(derived));
+        assert_eq!(find(&derived, None), None);
+        assert_eq!(find(&imported, None), Some(imported));
+
+        // extended keys with an origin are derived from the end of the origin
+        let wallet = descriptors(vec![format!(
+            "wpkh([{}/84h/1h/0h]{}/0/*)#checksum",
+            master.fingerprint(&secp),
+            account
+        )]);
+        assert_eq!(
+            find_private_key(&secp, &wallet, &derived.public_key(&secp), Some(key_path.as_ref())),
+            Some(derived)
+        );
+    }
+}
@@ -8,8 +8,8 @@ use jsonrpc_http_server::{
 use kv::*;
 use parity_scale_codec::{Decode, Encode};
 use runtime::{
-    AccountId, CollateralBalancesPallet, CurrencyId, Error as RuntimeError, InterBtcParachain, RuntimeCurrencyInfo,
-    TryFromSymbol, VaultRegistryPallet,
+    AccountId, CollateralBalancesPallet, CurrencyArg, CurrencyId, Error as RuntimeError, InterBtcParachain,
+    RuntimeCurrencyInfo, VaultRegistryPallet,
 };
 use serde::{Deserialize, Deserializer};
 use std::{net::SocketAddr, time::Duration};
@@ -148,7 +148,7 @@ async fn ensure_funding_allowed(
     };
     let currency_ids: Result<Vec<_>, _> = account_allowances
         .iter()
-        .map(|x| CurrencyId::try_from_symbol(x.symbol.clone()))
+        .map(|x| x.symbol.parse::<CurrencyArg>()?.resolve())
         .collect();
     for currency_id in currency_ids?.iter() {
         let free_balance = parachain_rpc
@@ -221,7 +221,7 @@ async fn atomic_faucet_funding(
 
     let mut transfers = vec![];
     for AllowanceAmount { symbol, amount } in amounts.iter() {
-        let currency_id = CurrencyId::try_from_symbol(symbol.clone())?;
+        let currency_id = symbol.parse::<CurrencyArg>()?.resolve()?;
         log::info!(
             "AccountId: {}, Currency: {:?} Type: {:?}, Amount: {}",
             account_id,
@@ -319,9 +319,9 @@ mod tests {
     use crate::{error::Error, Allowance, AllowanceAmount, AllowanceConfig};
     use futures::{future::join_all, TryFutureExt};
     use runtime::{
+        CurrencyArg,
         CurrencyId::{self},
-        Error as RuntimeError, InterBtcParachain, OracleKey, RuntimeCurrencyInfo, Token, TryFromSymbol, VaultId, KBTC,
-        KINT, KSM,
+        Error as RuntimeError, InterBtcParachain, OracleKey, RuntimeCurrencyInfo, Token, VaultId, KBTC, KINT, KSM,
     };
     use std::sync::Arc;
 
@@ -378,7 +378,7 @@ mod tests {
         provider: &InterBtcParachain,
     ) -> Vec<(u128, CurrencyId)> {
         join_all(allowance_vec.iter().map(|x| {
-            let currency_id = CurrencyId::try_from_symbol(x.symbol.clone()).unwrap();
+            let currency_id = x.symbol.parse::<CurrencyArg>().unwrap().resolve().unwrap();
             provider
                 .get_free_balance_for_id(account_id.clone(), currency_id.clone())
                 .map_ok(move |balance| (balance, currency_id.clone()))
@@ -12,7 +12,7 @@ use futures::future::join_all;
 use git_version::git_version;
 use runtime::{
     cli::{parse_duration_ms, ProviderUserOpts},
-    CurrencyId, FixedU128, InterBtcParachain, InterBtcSigner, OracleKey, OraclePallet, ShutdownSender, TryFromSymbol,
+    CurrencyArg, FixedU128, InterBtcParachain, InterBtcSigner, OracleKey, OraclePallet, ShutdownSender,
 };
 use std::{path::PathBuf, time::Duration};
 use tokio::{join, time::sleep};
@@ -108,28 +108,40 @@ async fn submit_bitcoin_fees(parachain_rpc: &InterBtcParachain, maybe_bitcoin_fe
     Ok(())
 }
 
-async fn submit_exchange_rate(
+async fn submit_exchange_rates(
     parachain_rpc: &InterBtcParachain,
-    currency_pair_and_price: &CurrencyPairAndPrice<Currency>,
+    currency_pairs_and_prices: &[CurrencyPairAndPrice<Currency>],
     currency_store: &CurrencyStore<String>,
 ) -> Result<(), Error> {
-    log::info!(
-        "Attempting to set exchange rate: {} ({})",
-        currency_pair_and_price,
-        chrono::offset::Local::now()
-    );
+    let mut values = Vec::with_capacity(currency_pairs_and_prices.len());
+    for currency_pair_and_price in currency_pairs_and_prices {
+        log::info!(
+            "Attempting to set exchange rate: {} ({})",
+            currency_pair_and_price,
+            chrono::offset::Local::now()
+        );
 
-    let currency_id =
-        CurrencyId::try_from_symbol(currency_pair_and_price.pair.quote.symbol()).map_err(Error::RuntimeError)?;
-    let key = OracleKey::ExchangeRate(currency_id);
-    let exchange_rate = currency_pair_and_price.exchange_rate(currency_store)?;
-    parachain_rpc.feed_values(vec![(key, exchange_rate)]).await?;
+        let currency_id = currency_pair_and_price
+            .pair
+            .quote
+            .symbol()
+            .parse::<CurrencyArg>()
+            .and_then(|currency| currency.resolve())
+            .map_err(Error::RuntimeError)?;
+        let key = OracleKey::ExchangeRate(currency_id);
+        let exchange_rate = currency_pair_and_price.exchange_rate(currency_store)?;
+        values.push((key, exchange_rate));
+    }
 
-    log::info!(
-        "Successfully set exchange rate: {} ({})",
-        currency_pair_and_price,
-        chrono::offset::Local::now()
-    );
+    parachain_rpc.feed_values(values).await?;
+
+    for currency_pair_and_price in currency_pairs_and_prices {
+        log::info!(
+            "Successfully set exchange rate: {} ({})",
+            currency_pair_and_price,
+            chrono::offset::Local::now()
+        );
+    }
 
     Ok(())
 }
@@ -210,14 +222,9 @@ async fn _main() -> Result<(), Error> {
             retry_notify(
                 get_exponential_backoff(),
                 || async {
-                    join_all(prices.iter().map(|currency_pair_and_price| {
-                        submit_exchange_rate(&parachain_rpc, currency_pair_and_price, currency_store)
-                    }))
-                    .await
-                    .into_iter() // turn vec<result> into result
-                    .find(|x| x.is_err())
-                    .transpose()
-                    .map_err(Into::into)
+                    submit_exchange_rates(&parachain_rpc, &prices, currency_store)
+                        .await
+                        .map_err(Into::into)
                 },
                 |err, _| log::error!("Error: {}", err),
             )
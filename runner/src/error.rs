@@ -4,7 +4,9 @@ use backoff::Error as BackoffError;
 use codec::Error as CodecError;
 use nix::Error as OsError;
 use reqwest::Error as ReqwestError;
-use std::io::Error as IoError;
+use semver::Version;
+use sp_core::H256;
+use std::{io::Error as IoError, time::Duration};
 use subxt::Error as SubxtError;
 use thiserror::Error;
 use url::ParseError as UrlParseError;
@@ -27,16 +29,49 @@ pub enum Error {
     IntegerConversionError,
     #[error("A client release has not been downloaded")]
     NoDownloadedRelease,
+    #[error("No current client release set on-chain")]
+    NoClientRelease,
+    #[error("No previous client release to roll back to")]
+    NoPreviousRelease,
     #[error("A child process is already running")]
     ChildProcessExists,
     #[error("Failed to terminate child process")]
     ProcessTerminationFailure,
     #[error("Failed to parse the client-type CLI argument")]
     ClientTypeParsingError,
+    #[error("Failed to parse the on-client-failure CLI argument")]
+    FailurePolicyParsingError,
+    #[error("Invalid client environment variable: {0}")]
+    InvalidClientEnv(String),
     #[error("Failed to derive the release name of the vault")]
     ClientNameDerivationError,
-    #[error("Incorrect Checksum")]
-    IncorrectChecksum,
+    #[error("Client checksum mismatch: expected {expected:#x}, got {actual:#x}")]
+    HashMismatch { expected: H256, actual: H256 },
+    #[error("Client restarted {restarts} times within {window:?}, giving up")]
+    TooManyRestarts { restarts: u32, window: Duration },
+    #[error("Insufficient disk space: {needed} bytes needed, {available} bytes available")]
+    InsufficientDiskSpace { needed: u64, available: u64 },
+    #[error("Downloaded release is not an executable, check the release URL")]
+    NotAnExecutable,
+    #[error("Invalid release signature")]
+    InvalidSignature,
+    #[error("Failed to read the client version: {0}")]
+    ClientVersionError(String),
+    #[error("Could not connect to the parachain after {attempts} attempts in {elapsed:?}: {last_error}")]
+    ConnectionTimeout {
+        attempts: u32,
+        elapsed: Duration,
+        last_error: String,
+    },
+    #[error("Release version {new} is older than the running version {current}, use --allow-downgrade to run it")]
+    DowngradeBlocked { current: Version, new: Version },
+}
+
+impl Error {
+    /// Whether the error was returned by the RPC client, e.g. because the connection was lost
+    pub fn is_rpc_error(&self) -> bool {
+        matches!(self, Error::SubxtError(SubxtError::Rpc(_)))
+    }
 }
 
 impl<E: Into<Error> + Sized> From<BackoffError<E>> for Error {
@@ -0,0 +1,243 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::Child,
+    thread::{self, JoinHandle},
+};
+
+/// Size of the buffer used to forward client output. Output is written to disk as it is read, so
+/// a chatty client can't make the runner buffer an unbounded amount of it.
+const FORWARD_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Where to store the client output, and how much of it to keep.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub dir: PathBuf,
+    /// Size after which a log file is rotated
+    pub max_file_size: u64,
+    /// Number of rotated files to keep, in addition to the current one
+    pub max_files: usize,
+}
+
+/// Append-only log file that is rotated to `<name>.1`, `<name>.2`, ... when it grows too large.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_file_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            max_file_size,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let rotated_path = self.rotated_path(index);
+                if rotated_path.exists() {
+                    fs::rename(rotated_path, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+        let len = self.file.write(buf)?;
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Writer that starts every line with a prefix, so that the output of several clients can be told apart.
+pub struct PrefixedWriter<W> {
+    prefix: Vec<u8>,
+    inner: W,
+    at_line_start: bool,
+}
+
+impl<W: Write> PrefixedWriter<W> {
+    pub fn new(prefix: String, inner: W) -> Self {
+        Self {
+            prefix: prefix.into_bytes(),
+            inner,
+            at_line_start: true,
+        }
+    }
+}
+
+impl<W: Write> Write for PrefixedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in buf.split_inclusive(|byte| *byte == b'\n') {
+            if self.at_line_start {
+                self.inner.write_all(&self.prefix)?;
+            }
+            self.inner.write_all(line)?;
+            self.at_line_start = line.ends_with(b"\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Copy `reader` to `writer` on a separate thread, until `reader` is closed.
+fn forward(mut reader: impl Read + Send + 'static, mut writer: impl Write + Send + 'static) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; FORWARD_BUFFER_SIZE];
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    log::warn!("Failed to read client output: {}", err);
+                    break;
+                }
+            };
+            if let Err(err) = writer.write_all(&buf[..len]).and_then(|_| writer.flush()) {
+                log::warn!("Failed to write client output to log file: {}", err);
+                break;
+            }
+        }
+    })
+}
+
+/// Write the piped stdout and stderr of `child` to `stdout.log` and `stderr.log` in the log directory.
+pub fn capture_output(child: &mut Child, config: &LogConfig) -> io::Result<Vec<JoinHandle<()>>> {
+    fs::create_dir_all(&config.dir)?;
+    let open = |name: &str| RotatingFile::open(config.dir.join(name), config.max_file_size, config.max_files);
+
+    let mut handles = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        handles.push(forward(stdout, open("stdout.log")?));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        handles.push(forward(stderr, open("stderr.log")?));
+    }
+    Ok(handles)
+}
+
+/// Print the piped stdout and stderr of `child` to the terminal, with every line starting with `prefix`.
+pub fn prefix_output(child: &mut Child, prefix: &str) -> Vec<JoinHandle<()>> {
+    let mut handles = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        handles.push(forward(stdout, PrefixedWriter::new(prefix.to_string(), io::stdout())));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        handles.push(forward(stderr, PrefixedWriter::new(prefix.to_string(), io::stderr())));
+    }
+    handles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        path::Path,
+        process::{Command, Stdio},
+    };
+    use tempdir::TempDir;
+
+    fn log_file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_child_output_is_written_to_log_dir() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let config = LogConfig {
+            dir: tmp.path().join("logs"),
+            max_file_size: 1024,
+            max_files: 2,
+        };
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("echo out; echo err >&2")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let handles = capture_output(&mut child, &config).unwrap();
+        child.wait().unwrap();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(config.dir.join("stdout.log")).unwrap(), "out\n");
+        assert_eq!(fs::read_to_string(config.dir.join("stderr.log")).unwrap(), "err\n");
+    }
+
+    #[test]
+    fn test_prefixed_writer_prefixes_every_line() {
+        let mut writer = PrefixedWriter::new("[vault] ".to_string(), Vec::new());
+        // lines may be split across writes
+        for chunk in ["first\nsec", "ond\n", "third"] {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(writer.inner).unwrap(),
+            "[vault] first\n[vault] second\n[vault] third"
+        );
+    }
+
+    #[test]
+    fn test_log_file_is_rotated() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mut file = RotatingFile::open(tmp.path().join("stdout.log"), 10, 2).unwrap();
+        for line in ["aaaaaaaa\n", "bbbbbbbb\n", "cccccccc\n", "dddddddd\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        // Only the two most recent rotated files are kept
+        assert_eq!(
+            log_file_names(tmp.path()),
+            vec!["stdout.log", "stdout.log.1", "stdout.log.2"]
+        );
+        assert_eq!(fs::read_to_string(tmp.path().join("stdout.log")).unwrap(), "dddddddd\n");
+        assert_eq!(fs::read_to_string(tmp.path().join("stdout.log.1")).unwrap(), "cccccccc\n");
+        assert_eq!(fs::read_to_string(tmp.path().join("stdout.log.2")).unwrap(), "bbbbbbbb\n");
+    }
+}
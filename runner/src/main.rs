@@ -1,52 +1,246 @@
 mod error;
+mod logs;
 mod runner;
+mod status;
 
 use clap::Parser;
 
 use error::Error;
 
-use futures::{FutureExt, TryFutureExt};
-use runner::ClientType;
+use futures::StreamExt;
+use runner::{ClientEnv, ClientType, FailurePolicy, RunnerExt};
 use signal_hook::consts::*;
 use signal_hook_tokio::Signals;
-use std::{fmt::Debug, path::PathBuf};
+use sp_core::{crypto::Ss58Codec, sr25519};
+use std::{
+    convert::TryInto,
+    fmt::Debug,
+    fs,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use crate::runner::{retry_with_log_async, subxt_api, Runner};
+use crate::runner::{connect_retry_config, connect_with_backoff, subxt_api, Runner};
 
 #[derive(Parser, Debug, Clone)]
 #[clap(version, author, about, trailing_var_arg = true)]
 pub struct Opts {
-    /// Client to run, one of: vault, oracle, faucet. Default is `vault`.
-    #[clap(long, default_value = "vault")]
-    pub client_type: ClientType,
+    /// Clients to run, comma-separated, any of: vault, oracle, faucet. Default is `vault`. Each client is
+    /// downloaded and restarted independently, using a subdirectory of `--download-path` and `--log-dir`
+    /// named after it if more than one is given.
+    #[clap(long = "client-type", value_delimiter = ',', default_value = "vault")]
+    pub client_types: Vec<ClientType>,
+
+    /// What to do when one of the clients fails fatally: `isolate` keeps the other clients running,
+    /// `restart-all` restarts all of them.
+    #[clap(long, default_value = "isolate")]
+    pub on_client_failure: FailurePolicy,
 
     /// Parachain websocket URL.
     #[clap(long)]
     pub parachain_ws: String,
 
+    /// Interval in seconds between checks for a new client release. Must be nonzero.
+    #[clap(long, default_value = "6", value_parser = clap::value_parser!(u64).range(1..))]
+    pub poll_interval_secs: u64,
+
+    /// Delay in milliseconds before retrying to connect to the parachain on startup. Doubled after every
+    /// failed attempt, up to `--connect-retry-max-ms`.
+    #[clap(long, default_value = "1000")]
+    pub connect_retry_base_ms: u64,
+
+    /// Upper bound in milliseconds of the delay between attempts to connect to the parachain on startup.
+    #[clap(long, default_value = "60000")]
+    pub connect_retry_max_ms: u64,
+
+    /// Seconds after which the runner gives up connecting to the parachain on startup, and exits.
+    #[clap(long, default_value = "600")]
+    pub connect_timeout_secs: u64,
+
     /// Download path for the client executable.
     #[clap(long, default_value = ".")]
     pub download_path: PathBuf,
 
+    /// Gateway used to download releases with an `ipfs://<cid>` URI.
+    #[clap(long, default_value = "https://ipfs.io/ipfs/")]
+    pub ipfs_gateway: String,
+
+    /// Number of times a failed client executable download is retried, with exponential backoff.
+    #[clap(long, default_value = "5")]
+    pub max_download_retries: u32,
+
+    /// Free disk space (in MB) that must remain on the download path's filesystem after writing the client
+    /// executable. Downloads that would exceed it are aborted.
+    #[clap(long, default_value = "100")]
+    pub disk_space_margin_mb: u64,
+
+    /// Seconds to wait for the client to exit after sending it a `SIGTERM`, before it is sent a `SIGKILL`.
+    #[clap(long, default_value = "30")]
+    pub shutdown_grace_period_secs: u64,
+
+    /// Number of client restarts within `--restart-window-secs` after which restarts are delayed exponentially.
+    #[clap(long, default_value = "5")]
+    pub max_restarts: u32,
+
+    /// Number of client crashes within `--restart-window-secs` after which the runner gives up. A client that
+    /// exits successfully is not restarted.
+    #[clap(long, default_value = "11")]
+    pub max_crashes: u32,
+
+    /// Upper bound in seconds of the delay between restarts of a crashing client.
+    #[clap(long, default_value = "32")]
+    pub max_restart_delay_secs: u64,
+
+    /// Length in seconds of the window in which client restarts are counted.
+    #[clap(long, default_value = "300")]
+    pub restart_window_secs: u64,
+
+    /// Port to serve the runner status on (as JSON, at `/status`). The status server is disabled if not set.
+    /// If several clients are run, `/status` reports all of them and `/status/<client>` a single one.
+    #[clap(long)]
+    pub status_port: Option<u16>,
+
+    /// sr25519 public key (SS58 or hex-encoded) that client releases must be signed with. If set, the
+    /// detached signature at `<release uri>.sig` is verified before running a downloaded release.
+    #[clap(long, value_parser = parse_signing_key)]
+    pub release_signing_key: Option<sr25519::Public>,
+
+    /// Allow updating the client to a release with a lower version (as reported by `<client> --version`) than
+    /// the one currently run. Such updates are refused by default, to guard against accidental rollbacks.
+    #[clap(long)]
+    pub allow_downgrade: bool,
+
+    /// Directory to write the client's stdout and stderr to, as `stdout.log` and `stderr.log`. If not set, the
+    /// client output is printed to the terminal.
+    #[clap(long)]
+    pub log_dir: Option<PathBuf>,
+
+    /// Size in MB after which a client log file is rotated.
+    #[clap(long, default_value = "100")]
+    pub log_max_size_mb: u64,
+
+    /// Number of rotated client log files to keep.
+    #[clap(long, default_value = "5")]
+    pub log_max_files: usize,
+
+    /// Number of previously run releases to keep on disk. On `SIGUSR1`, the client is rolled back to the most
+    /// recent one without downloading it again. Older releases are removed.
+    #[clap(long, default_value = "1")]
+    pub keep_releases: usize,
+
+    /// Only check that the current release can be read from the parachain, downloaded and verified (into a
+    /// temporary directory), then exit without running the client.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Environment variable to set for the client, as `KEY=VALUE`. Use `KEY=@<path>` to read the value from a
+    /// file when the client is started, e.g. to keep secrets out of the process list. Can be repeated.
+    #[clap(long)]
+    pub client_env: Vec<ClientEnv>,
+
     /// CLI arguments to pass to the client executable.
     pub client_args: Vec<String>,
 }
 
+impl Opts {
+    /// Options of the runner of `client_type`. If several clients are run, their files are kept apart in
+    /// subdirectories named after them.
+    fn for_client(&self, client_type: &ClientType) -> Opts {
+        if self.client_types.len() <= 1 {
+            return self.clone();
+        }
+        let subdir = client_type.to_string();
+        Opts {
+            download_path: self.download_path.join(&subdir),
+            log_dir: self.log_dir.as_ref().map(|dir| dir.join(&subdir)),
+            ..self.clone()
+        }
+    }
+}
+
+fn parse_signing_key(src: &str) -> Result<sr25519::Public, String> {
+    if let Ok(public) = sr25519::Public::from_ss58check(src) {
+        return Ok(public);
+    }
+    let raw: [u8; 32] = hex::decode(src.strip_prefix("0x").unwrap_or(src))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "expected an SS58 address or a 32 byte hex-encoded public key".to_string())?;
+    Ok(sr25519::Public::from_raw(raw))
+}
+
+/// Roll all clients back to their previous release whenever a `SIGUSR1` is received.
+async fn request_rollbacks(mut signals: Signals, rollback_handles: Vec<Arc<AtomicBool>>) {
+    while signals.next().await.is_some() {
+        log::info!("Received SIGUSR1, rolling back to the previous release");
+        for rollback_requested in &rollback_handles {
+            rollback_requested.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     env_logger::init_from_env(
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log::LevelFilter::Info.as_str()),
     );
     let opts: Opts = Opts::parse();
-    let rpc_client = retry_with_log_async(
-        || subxt_api(&opts.parachain_ws).into_future().boxed(),
-        "Error fetching executable".to_string(),
+    let rpc_client = connect_with_backoff(
+        &opts.parachain_ws,
+        || subxt_api(&opts.parachain_ws),
+        connect_retry_config(
+            Duration::from_millis(opts.connect_retry_base_ms),
+            Duration::from_millis(opts.connect_retry_max_ms),
+            Duration::from_secs(opts.connect_timeout_secs),
+        ),
     )
     .await?;
     log::info!("Connected to the parachain");
 
-    let runner = Runner::new(rpc_client, opts);
+    if opts.dry_run {
+        let download_path = std::env::temp_dir().join(format!("runner-dry-run-{}", process::id()));
+        let dry_run_opts = Opts {
+            download_path: download_path.clone(),
+            ..opts.clone()
+        };
+        let mut result = Ok(());
+        for client_type in opts.client_types {
+            let mut runner = Runner::new(rpc_client.clone(), client_type, dry_run_opts.clone());
+            result = Runner::dry_run(&mut runner).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        if let Err(err) = fs::remove_dir_all(&download_path) {
+            log::warn!("Failed to remove {:?}: {}", download_path, err);
+        }
+        return result;
+    }
+
+    let runners: Vec<_> = opts
+        .client_types
+        .iter()
+        .map(|client_type| Runner::new(rpc_client.clone(), client_type.clone(), opts.clone()))
+        .collect();
+    let rollback_handles = runners.iter().map(Runner::rollback_handle).collect();
+    tokio::spawn(request_rollbacks(Signals::new(&[SIGUSR1])?, rollback_handles));
+    if let Some(port) = opts.status_port {
+        let statuses = runners
+            .iter()
+            .map(|runner| (runner.client_type().to_string(), runner.status()))
+            .collect();
+        tokio::spawn(status::serve_status(statuses, port));
+    }
+    let runners = runners
+        .into_iter()
+        .map(|runner| Box::new(runner) as Box<dyn RunnerExt + Send>)
+        .collect();
     let shutdown_signals = Signals::new(&[SIGHUP, SIGTERM, SIGINT, SIGQUIT])?;
-    Runner::run(Box::new(runner), shutdown_signals).await?;
+    Runner::run(runners, opts.on_client_failure, shutdown_signals).await?;
     Ok(())
 }
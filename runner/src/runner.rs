@@ -1,35 +1,58 @@
-use crate::{error::Error, Opts};
-use backoff::{retry, Error as BackoffError, ExponentialBackoff};
-use bytes::Bytes;
+use crate::{
+    error::Error,
+    logs::{self, LogConfig},
+    status::{RunnerStatus, SharedStatus},
+    Opts,
+};
+use backoff::{backoff::Backoff, retry, Error as BackoffError, ExponentialBackoff};
+use bytes::{Bytes, BytesMut};
 use codec::Decode;
-use futures::{future::BoxFuture, FutureExt, StreamExt, TryFutureExt};
+use futures::{
+    future::{join_all, BoxFuture, Future},
+    FutureExt, StreamExt, TryFutureExt,
+};
 use nix::{
-    sys::signal::{self, Signal},
+    sys::{
+        signal::{self, Signal},
+        statvfs::statvfs,
+    },
     unistd::Pid,
 };
+use rand::Rng;
 use reqwest::Url;
+use semver::Version;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use signal_hook_tokio::SignalsInfo;
-use sp_core::{hexdisplay::AsBytesRef, H256};
+use sp_core::{hexdisplay::AsBytesRef, sr25519, Pair, H256};
 use std::{
+    collections::VecDeque,
     convert::TryInto,
     fmt::{Debug, Display},
+    ffi::OsStr,
     fs::{self, OpenOptions},
-    io::Write,
+    io::{self, Read},
+    iter,
     os::unix::prelude::OpenOptionsExt,
-    path::PathBuf,
-    process::{Child, Command, Stdio},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
     str::{self, FromStr},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use subxt::{dynamic::Value, OnlineClient, PolkadotConfig};
+use tokio::sync::watch;
 
 use async_trait::async_trait;
 
 /// Type of the client to run.
 /// Also used as the name of the downloaded executable.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClientType {
     Vault,
     Oracle,
@@ -61,14 +84,158 @@ impl Display for ClientType {
     }
 }
 
-/// Pallet in the parachain where the client release is assumed to be stored
+impl ClientType {
+    /// Key of this client type in the release storage maps.
+    pub fn release_storage_key(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+/// What the runner does when one of several supervised clients fails fatally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Keep the other clients running
+    Isolate,
+    /// Stop all clients and start them again
+    RestartAll,
+}
+
+impl FromStr for FailurePolicy {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let policy = match s {
+            "isolate" => FailurePolicy::Isolate,
+            "restart-all" => FailurePolicy::RestartAll,
+            _ => return Err(Error::FailurePolicyParsingError),
+        };
+        Ok(policy)
+    }
+}
+
+/// Environment variable to set for the client, given as `KEY=VALUE`, or as `KEY=@<path>` to read the value
+/// from a file when the client is started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientEnv {
+    pub key: String,
+    pub value: ClientEnvValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientEnvValue {
+    Literal(String),
+    File(PathBuf),
+}
+
+impl FromStr for ClientEnv {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| Error::InvalidClientEnv(format!("expected KEY=VALUE, got `{}`", s)))?;
+        if key.is_empty() || key.contains('\0') {
+            return Err(Error::InvalidClientEnv(format!("invalid variable name in `{}`", s)));
+        }
+        let value = match value.strip_prefix('@') {
+            Some("") => return Err(Error::InvalidClientEnv(format!("missing file path in `{}`", s))),
+            Some(path) => ClientEnvValue::File(PathBuf::from(path)),
+            None => ClientEnvValue::Literal(value.to_string()),
+        };
+        Ok(ClientEnv {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+impl ClientEnv {
+    /// The variable's value, read from its file if it is given as `@<path>`.
+    pub fn resolve(&self) -> Result<String, Error> {
+        match &self.value {
+            ClientEnvValue::Literal(value) => Ok(value.clone()),
+            ClientEnvValue::File(path) => {
+                let value = fs::read_to_string(path).map_err(|err| {
+                    Error::InvalidClientEnv(format!("failed to read {} from {:?}: {}", self.key, path, err))
+                })?;
+                // files usually end with a newline that is not part of the value
+                Ok(value.strip_suffix('\n').unwrap_or(&value).to_string())
+            }
+        }
+    }
+}
+
+/// Reason for stopping the supervised clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StopReason {
+    Shutdown,
+    Restart,
+}
+
+/// Pallet in the parachain where the client releases are assumed to be stored
 pub const PARACHAIN_MODULE: &str = "ClientsInfo";
 
 /// Storage item in the Pallet where the client release is assumed to be stored
 pub const CURRENT_RELEASES_STORAGE_ITEM: &str = "CurrentClientReleases";
 
-/// Parachain block time
-pub const BLOCK_TIME: Duration = Duration::from_secs(6);
+/// Storage item in the Pallet where the upcoming client release is assumed to be stored
+pub const PENDING_RELEASES_STORAGE_ITEM: &str = "PendingClientReleases";
+
+/// Which of the client's releases to read from the parachain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseKind {
+    /// The release the client should be running
+    Current,
+    /// The upcoming release, that becomes current once it is enacted
+    Pending,
+}
+
+impl ReleaseKind {
+    fn storage_item(self) -> &'static str {
+        match self {
+            ReleaseKind::Current => CURRENT_RELEASES_STORAGE_ITEM,
+            ReleaseKind::Pending => PENDING_RELEASES_STORAGE_ITEM,
+        }
+    }
+}
+
+/// Directory (relative to the download path) where the pending release is prefetched to
+pub const STAGING_DIR: &str = "staging";
+
+/// Subdirectory of the download path where previously run releases are kept for rollbacks
+pub const PREVIOUS_RELEASES_DIR: &str = "previous";
+
+/// File in `PREVIOUS_RELEASES_DIR` listing the kept releases, most recent first, so that they can still
+/// be rolled back to after a runner restart
+pub const PREVIOUS_RELEASES_INDEX: &str = "releases.json";
+
+/// File in the download path holding the checksum of the release that was rolled back from, so that it is
+/// not updated to again after a runner restart
+pub const ROLLED_BACK_RELEASE_FILE: &str = "rolled-back-release";
+
+/// File in the download path holding the version of the client that was last run, so that downgrades
+/// are also detected across runner restarts
+pub const LAST_RUN_VERSION_FILE: &str = "last-run-version";
+
+/// File in the download path describing the release that was last run, so that it can be run again
+/// after a runner restart if the current release is a blocked downgrade
+pub const LAST_RUN_RELEASE_FILE: &str = "last-run-release.json";
+
+/// Maximum time a client may take to print its version
+pub const CLIENT_VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extension of executables that are still being written to disk
+pub const PARTIAL_DOWNLOAD_EXTENSION: &str = "part";
+
+/// URI scheme of releases that are pinned on IPFS, of the form `ipfs://<cid>[/<path>]`
+pub const IPFS_URI_PREFIX: &str = "ipfs://";
+
+/// Suffix appended to a release URI to get the URI of its detached signature
+pub const SIGNATURE_URI_SUFFIX: &str = ".sig";
+
+/// Maximum random delay added to the poll interval, as a fraction of it. Spreads out the storage
+/// queries of runners that were started at the same time.
+pub const POLL_JITTER: f64 = 0.1;
 
 /// Timeout used by the retry utilities: One minute
 pub const RETRY_TIMEOUT: Duration = Duration::from_millis(60_000);
@@ -79,6 +246,18 @@ pub const RETRY_INTERVAL: Duration = Duration::from_millis(1_000);
 /// Multiplier for the interval in retry utilities: Constant interval retry
 pub const RETRY_MULTIPLIER: f64 = 1.0;
 
+/// Magic bytes that ELF executables, the format of the client releases on Linux, start with
+pub const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+
+/// Interval at which a terminated child process is polled to check whether it has exited
+pub const CHILD_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Delay before the first restart that exceeds the crash-loop threshold, doubled on every further restart
+pub const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// Multiplier for the interval between binary download attempts
+pub const DOWNLOAD_RETRY_MULTIPLIER: f64 = 2.0;
+
 /// Data type assumed to be used by the parachain to store the client release.
 /// If this type is different from the on-chain one, decoding will fail.
 #[derive(Decode, Default, Eq, PartialEq, Debug, Clone)]
@@ -91,7 +270,7 @@ pub struct ClientRelease {
 }
 
 /// Wrapper around `ClientRelease`, which includes details for running the executable.
-#[derive(Default, Eq, PartialEq, Debug, Clone)]
+#[derive(Default, Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadedRelease {
     /// The SHA256 checksum of the client binary.
     pub checksum: H256,
@@ -99,6 +278,106 @@ pub struct DownloadedRelease {
     pub path: PathBuf,
     /// Name of the executable
     pub bin_name: String,
+    /// URI the executable was downloaded from
+    pub uri: String,
+}
+
+/// Version of the client executable at `path`, as reported by `<path> --version`. Clients print their name
+/// followed by the version, e.g. `vault 1.21.0`.
+pub async fn client_version(path: &Path) -> Result<Version, Error> {
+    let output = tokio::process::Command::new(path)
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(CLIENT_VERSION_TIMEOUT, output)
+        .await
+        .map_err(|_| {
+            Error::ClientVersionError(format!(
+                "{:?} --version timed out after {:?}",
+                path, CLIENT_VERSION_TIMEOUT
+            ))
+        })??;
+    if !output.status.success() {
+        return Err(Error::ClientVersionError(format!(
+            "{:?} --version exited with {}",
+            path, output.status
+        )));
+    }
+    parse_client_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_client_version(output: &str) -> Result<Version, Error> {
+    let version = output
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().last())
+        .ok_or_else(|| Error::ClientVersionError("empty --version output".to_string()))?;
+    Version::parse(version.strip_prefix('v').unwrap_or(version))
+        .map_err(|err| Error::ClientVersionError(format!("invalid version `{}`: {}", version, err)))
+}
+
+/// Version recorded in `LAST_RUN_VERSION_FILE` under `download_path`, if any.
+fn read_last_run_version(download_path: &Path) -> Option<Version> {
+    let path = download_path.join(LAST_RUN_VERSION_FILE);
+    let version = fs::read_to_string(&path).ok()?;
+    match Version::parse(version.trim()) {
+        Ok(version) => Some(version),
+        Err(err) => {
+            log::warn!("Ignoring invalid version in {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Release recorded in `LAST_RUN_RELEASE_FILE` under `download_path`, if its executable still exists.
+fn read_last_run_release(download_path: &Path) -> Option<DownloadedRelease> {
+    let path = download_path.join(LAST_RUN_RELEASE_FILE);
+    let contents = fs::read(&path).ok()?;
+    match serde_json::from_slice::<DownloadedRelease>(&contents) {
+        Ok(release) => Some(release).filter(|release| release.path.exists()),
+        Err(err) => {
+            log::warn!("Ignoring invalid last run release in {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Releases in the `PREVIOUS_RELEASES_INDEX` under `download_path` whose executables still exist.
+fn read_previous_releases(download_path: &Path) -> VecDeque<DownloadedRelease> {
+    let path = download_path.join(PREVIOUS_RELEASES_DIR).join(PREVIOUS_RELEASES_INDEX);
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(_) => return VecDeque::new(),
+    };
+    match serde_json::from_slice::<VecDeque<DownloadedRelease>>(&contents) {
+        Ok(releases) => releases.into_iter().filter(|release| release.path.exists()).collect(),
+        Err(err) => {
+            log::warn!("Ignoring invalid list of previous releases in {:?}: {}", path, err);
+            VecDeque::new()
+        }
+    }
+}
+
+/// Replace the `PREVIOUS_RELEASES_INDEX` under `download_path` with `releases`. Failures are only logged,
+/// since they merely prevent rollbacks after a restart.
+fn write_previous_releases<'a>(download_path: &Path, releases: impl Iterator<Item = &'a DownloadedRelease>) {
+    let releases: Vec<_> = releases.collect();
+    let result = serde_json::to_vec(&releases)
+        .map_err(io::Error::from)
+        .and_then(|contents| {
+            let dir = download_path.join(PREVIOUS_RELEASES_DIR);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(PREVIOUS_RELEASES_INDEX), contents)
+        });
+    if let Err(err) = result {
+        log::warn!("Failed to record the previous releases: {}", err);
+    }
+}
+
+/// Checksum recorded in `ROLLED_BACK_RELEASE_FILE` under `download_path`, if any.
+fn read_rolled_back_release(download_path: &Path) -> Option<H256> {
+    let checksum = fs::read_to_string(download_path.join(ROLLED_BACK_RELEASE_FILE)).ok()?;
+    H256::from_str(checksum.trim()).ok()
 }
 
 fn sha256sum(bytes: &[u8]) -> Vec<u8> {
@@ -107,6 +386,241 @@ fn sha256sum(bytes: &[u8]) -> Vec<u8> {
     hasher.result().as_slice().to_vec()
 }
 
+/// Path of the temporary file that `path` is written to before being moved into place.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(".");
+    partial_path.push(PARTIAL_DOWNLOAD_EXTENSION);
+    partial_path.into()
+}
+
+/// Write the contents of `reader` to an executable file at `path`, creating its directory if needed.
+///
+/// The data is first written to a `.part` file next to `path`, which is only renamed once fully
+/// written. Since the rename is atomic, a crash can never leave a truncated executable at `path`.
+fn write_executable(path: &Path, mut reader: impl Read) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let partial_path = partial_path(path);
+    let written = OpenOptions::new()
+        .read(true)
+        .write(true)
+        // Make the binary executable.
+        // The set permissions are: -rwx------
+        .mode(0o700)
+        .create(true)
+        .truncate(true)
+        .open(&partial_path)
+        .and_then(|mut file| {
+            io::copy(&mut reader, &mut file)?;
+            file.sync_all()
+        });
+    if let Err(err) = written {
+        let _ = fs::remove_file(&partial_path);
+        return Err(err.into());
+    }
+    fs::rename(&partial_path, path)?;
+    Ok(())
+}
+
+/// Check that `signature` is a valid sr25519 signature of `bytes` by `signing_key`. The signature may be
+/// given either as 64 raw bytes or hex-encoded.
+fn verify_signature(bytes: &[u8], signature: &[u8], signing_key: &sr25519::Public) -> Result<(), Error> {
+    let signature = parse_signature(signature).ok_or(Error::InvalidSignature)?;
+    if !sr25519::Pair::verify(&signature, bytes, signing_key) {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+fn parse_signature(signature: &[u8]) -> Option<sr25519::Signature> {
+    if signature.len() == 64 {
+        return sr25519::Signature::from_slice(signature);
+    }
+    let encoded = str::from_utf8(signature).ok()?.trim();
+    let decoded = hex::decode(encoded.strip_prefix("0x").unwrap_or(encoded)).ok()?;
+    sr25519::Signature::from_slice(&decoded)
+}
+
+/// Rewrite `ipfs://` release URIs to `gateway`, leaving all other URIs unchanged.
+fn resolve_release_uri(uri: &str, gateway: &str) -> String {
+    match uri.strip_prefix(IPFS_URI_PREFIX) {
+        Some(ipfs_path) => format!("{}/{}", gateway.trim_end_matches('/'), ipfs_path),
+        None => uri.to_string(),
+    }
+}
+
+/// Add a random delay of up to `POLL_JITTER` times the `interval` to it.
+fn with_jitter(interval: Duration) -> Duration {
+    interval.mul_f64(1.0 + rand::thread_rng().gen_range(0.0..=POLL_JITTER))
+}
+
+/// Check that the SHA256 checksum of `bytes` matches the on-chain `expected` checksum.
+fn verify_checksum(bytes: &[u8], expected: &H256) -> Result<(), Error> {
+    let actual = H256::from_slice(&sha256sum(bytes));
+    if actual != *expected {
+        return Err(Error::HashMismatch {
+            expected: *expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Check that the SHA256 checksum of the file at `path` matches the `expected` checksum.
+fn verify_file_checksum(path: &Path, expected: &H256) -> Result<(), Error> {
+    verify_checksum(&fs::read(path)?, expected)
+}
+
+/// Remove the files in `dir` that were left behind by interrupted downloads.
+fn remove_partial_downloads(dir: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some(OsStr::new(PARTIAL_DOWNLOAD_EXTENSION)) {
+            log::info!("Removing partially downloaded file {:?}", path);
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the directory of the release with `checksum`, after its executable at `path` was moved or
+/// removed. Directories that aren't release directories, or that aren't empty, are kept.
+fn remove_release_dir(path: &Path, checksum: &H256) {
+    if let Some(release_dir) = path.parent() {
+        if release_dir.file_name() == Some(OsStr::new(&format!("{:x}", checksum))) {
+            let _ = fs::remove_dir(release_dir);
+        }
+    }
+}
+
+/// Send a `SIGTERM` to the child process (via the underlying `kill` system call), retrying for `RETRY_TIMEOUT`.
+fn send_sigterm(child_proc: &Child) -> Result<(), Error> {
+    let _ = retry_with_log(
+        || {
+            Ok(signal::kill(
+                Pid::from_raw(child_proc.id().try_into().map_err(|_| Error::IntegerConversionError)?),
+                Signal::SIGTERM,
+            ))
+        },
+        "Failed to kill child process".to_string(),
+    )
+    .map_err(|_| Error::ProcessTerminationFailure)?;
+    Ok(())
+}
+
+/// Whether the child process has exited, sending it a `SIGKILL` if it is still running past `deadline`.
+fn exited_or_killed(child_proc: &mut Child, deadline: Instant, grace_period: Duration) -> Result<bool, Error> {
+    if child_proc.try_wait()?.is_some() {
+        return Ok(true);
+    }
+    if Instant::now() < deadline {
+        return Ok(false);
+    }
+    log::warn!(
+        "Client process (pid: {}) did not exit within {:?}, sending SIGKILL",
+        child_proc.id(),
+        grace_period
+    );
+    child_proc.kill()?;
+    Ok(true)
+}
+
+/// Wait for the terminated child process to be reaped, returning its pid.
+fn reap(child_proc: &mut Child) -> u32 {
+    match child_proc.wait() {
+        Ok(exit_status) => log::info!(
+            "Terminated client process (pid: {}) with exit status {}",
+            child_proc.id(),
+            exit_status
+        ),
+        Err(error) => log::warn!("Client process termination error: {}", error),
+    };
+    child_proc.id()
+}
+
+/// Fail with `Error::InsufficientDiskSpace` unless `size` bytes and the safety `margin` fit in `available`.
+fn check_fits(size: u64, margin: u64, available: u64) -> Result<(), Error> {
+    let needed = size.saturating_add(margin);
+    if available < needed {
+        return Err(Error::InsufficientDiskSpace { needed, available });
+    }
+    Ok(())
+}
+
+/// Check that `bytes` are an executable for this platform, rather than e.g. the HTML error page of a
+/// misconfigured release URL. Only ELF executables are recognized, so the check is skipped elsewhere.
+fn verify_executable_format(bytes: &[u8]) -> Result<(), Error> {
+    if cfg!(target_os = "linux") && !bytes.starts_with(&ELF_MAGIC) {
+        return Err(Error::NotAnExecutable);
+    }
+    Ok(())
+}
+
+/// A scheduled restart of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Restart {
+    /// Number of the restart within the crash-loop window, starting at 1
+    pub attempt: u32,
+    /// How long to wait before restarting
+    pub delay: Duration,
+}
+
+/// Keeps track of recent client restarts, in order to detect crash loops.
+#[derive(Debug, Clone)]
+pub struct RestartTracker {
+    restarts: VecDeque<Instant>,
+    max_restarts: u32,
+    max_crashes: u32,
+    max_delay: Duration,
+    window: Duration,
+}
+
+impl RestartTracker {
+    pub fn new(max_restarts: u32, max_crashes: u32, max_delay: Duration, window: Duration) -> Self {
+        Self {
+            restarts: VecDeque::new(),
+            max_restarts,
+            max_crashes,
+            max_delay,
+            window,
+        }
+    }
+
+    /// Record a restart at `now` and return when to perform it.
+    /// The first `max_restarts` restarts within the window happen immediately, further ones are delayed
+    /// exponentially up to `max_delay`. After `max_crashes` restarts within the window
+    /// `Error::TooManyRestarts` is returned.
+    pub fn record_restart(&mut self, now: Instant) -> Result<Restart, Error> {
+        while let Some(oldest) = self.restarts.front() {
+            if now.saturating_duration_since(*oldest) <= self.window {
+                break;
+            }
+            self.restarts.pop_front();
+        }
+        self.restarts.push_back(now);
+
+        let restarts = self.restarts.len() as u32;
+        if restarts > self.max_crashes {
+            return Err(Error::TooManyRestarts {
+                restarts,
+                window: self.window,
+            });
+        }
+        let delay = match restarts.saturating_sub(self.max_restarts) {
+            0 => Duration::ZERO,
+            excess => RESTART_BACKOFF_BASE
+                .checked_mul(2u32.saturating_pow(excess - 1))
+                .map_or(self.max_delay, |delay| delay.min(self.max_delay)),
+        };
+        Ok(Restart {
+            attempt: restarts,
+            delay,
+        })
+    }
+}
+
 /// Per-network manager of the client executable
 pub struct Runner {
     /// `subxt` api to the parachain
@@ -115,33 +629,119 @@ pub struct Runner {
     child_proc: Option<Child>,
     /// Details about the currently run release
     downloaded_release: Option<DownloadedRelease>,
+    /// Details about the prefetched pending release, if any
+    staged_release: Option<DownloadedRelease>,
+    /// Releases that were run before the current one, most recent first
+    previous_releases: VecDeque<DownloadedRelease>,
+    /// Checksum of the release that was rolled back from, which is not updated to again
+    rolled_back_release: Option<H256>,
+    /// Set to roll back to the previous release on the next poll
+    rollback_requested: Arc<AtomicBool>,
+    /// How the last child process exited, if it exited on its own
+    last_exit_status: Option<ExitStatus>,
+    /// Recent restarts of the child process
+    restart_tracker: RestartTracker,
+    /// Status reported by the status server, kept in sync with the fields above
+    status: SharedStatus,
+    /// Client run by this runner
+    client_type: ClientType,
     /// Runner CLI arguments
     opts: Opts,
 }
 
 impl Runner {
-    pub fn new(subxt_api: OnlineClient<PolkadotConfig>, opts: Opts) -> Self {
+    pub fn new(subxt_api: OnlineClient<PolkadotConfig>, client_type: ClientType, opts: Opts) -> Self {
+        let opts = opts.for_client(&client_type);
         Self {
             subxt_api,
+            client_type,
             child_proc: None,
             downloaded_release: None,
+            staged_release: None,
+            previous_releases: read_previous_releases(&opts.download_path),
+            rolled_back_release: read_rolled_back_release(&opts.download_path),
+            rollback_requested: Arc::new(AtomicBool::new(false)),
+            last_exit_status: None,
+            restart_tracker: RestartTracker::new(
+                opts.max_restarts,
+                opts.max_crashes,
+                Duration::from_secs(opts.max_restart_delay_secs),
+                Duration::from_secs(opts.restart_window_secs),
+            ),
+            status: SharedStatus::default(),
             opts,
         }
     }
 
-    fn try_load_downloaded_binary(runner: &mut impl RunnerExt, release: &ClientRelease) -> Result<(), Error> {
-        let (bin_name, bin_path) = runner.get_bin_path(&release.uri)?;
+    /// Handle to the runner status, for serving it over HTTP
+    pub fn status(&self) -> SharedStatus {
+        self.status.clone()
+    }
+
+    /// Persist the downloaded release in `LAST_RUN_RELEASE_FILE`, and its version in `LAST_RUN_VERSION_FILE`
+    /// in the background. Failures are only logged, since the client is already running.
+    fn record_last_run_release(&self) {
+        let release = match self.downloaded_release.clone() {
+            Some(release) => release,
+            None => {
+                log::warn!("Failed to record the last run release: {}", Error::NoDownloadedRelease);
+                return;
+            }
+        };
+        let download_path = self.opts.download_path.clone();
+        let result = serde_json::to_vec(&release)
+            .map_err(io::Error::from)
+            .and_then(|contents| fs::write(download_path.join(LAST_RUN_RELEASE_FILE), contents));
+        if let Err(err) = result {
+            log::warn!("Failed to record the last run release: {}", err);
+        }
+        tokio::spawn(async move {
+            let result = client_version(&release.path).await.and_then(|version| {
+                fs::write(download_path.join(LAST_RUN_VERSION_FILE), version.to_string()).map_err(Into::into)
+            });
+            if let Err(err) = result {
+                log::warn!("Failed to record the version of the client: {}", err);
+            }
+        });
+    }
+
+    fn log_config(&self) -> Option<LogConfig> {
+        self.opts.log_dir.clone().map(|dir| LogConfig {
+            dir,
+            max_file_size: self.opts.log_max_size_mb.saturating_mul(1024 * 1024),
+            max_files: self.opts.log_max_files,
+        })
+    }
+
+    /// Flag that makes the runner roll back to the previous release on its next poll, once set.
+    pub fn rollback_handle(&self) -> Arc<AtomicBool> {
+        self.rollback_requested.clone()
+    }
+
+    fn update_status(&self, update: impl FnOnce(&mut RunnerStatus)) {
+        let mut status = self.status.write().unwrap_or_else(|err| err.into_inner());
+        update(&mut status);
+    }
+
+    async fn try_load_downloaded_binary(runner: &mut impl RunnerExt, release: &ClientRelease) -> Result<(), Error> {
+        let (bin_name, bin_path) = runner.get_bin_path(release)?;
         let file_content = fs::read(bin_path.clone()).map_err(|_| Error::NoDownloadedRelease)?;
         let checksum = H256::from_slice(&sha256sum(&file_content));
 
         if checksum != release.checksum {
-            return Err(Error::IncorrectChecksum);
+            return Err(Error::HashMismatch {
+                expected: release.checksum,
+                actual: checksum,
+            });
         }
+        // The binary may have been downloaded before a signing key was configured
+        Runner::verify_release_signature(runner, &release.uri, &file_content).await?;
 
         let downloaded_release = DownloadedRelease {
             checksum,
             path: bin_path.clone(),
             bin_name,
+            uri: release.uri.clone(),
         };
 
         runner.set_downloaded_release(Some(downloaded_release));
@@ -163,46 +763,231 @@ impl Runner {
             }
         }
 
-        let (bin_name, bin_path) = runner.get_bin_path(&release.uri)?;
+        let (bin_name, bin_path) = runner.get_bin_path(&release)?;
+
+        if let Some(staged_release) = runner.staged_release() {
+            runner.set_staged_release(None);
+            if staged_release.checksum.eq(&release.checksum) {
+                match Runner::promote_staged_release(&staged_release, &bin_path) {
+                    Ok(()) => {
+                        log::info!("Using prefetched release, skipping download");
+                        let downloaded_release = DownloadedRelease {
+                            checksum: release.checksum,
+                            path: bin_path,
+                            bin_name,
+                            uri: release.uri,
+                        };
+                        runner.set_downloaded_release(Some(downloaded_release.clone()));
+                        return Ok(downloaded_release);
+                    }
+                    Err(err) => log::warn!("Failed to use prefetched release, downloading it again: {}", err),
+                }
+            } else if let Err(err) = fs::remove_file(&staged_release.path) {
+                log::warn!("Failed to remove outdated prefetched release: {}", err);
+            }
+        }
+
         log::info!("Downloading {} at: {:?}", bin_name, bin_path);
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            // Make the binary executable.
-            // The set permissions are: -rwx------
-            .mode(0o700)
-            .create(true)
-            .open(bin_path.clone())?;
+        let bytes = Runner::get_request_bytes_with_retry(runner, &release.uri, download_retry_config()).await?;
 
-        let bytes = retry_with_log_async(
-            || runner.get_request_bytes(release.uri.clone()).into_future().boxed(),
-            "Error fetching executable".to_string(),
-        )
-        .await?;
+        // Verify the download before anything is written to disk, so that a corrupted
+        // or tampered binary is never made executable.
+        let verified = match verify_checksum(&bytes, &release.checksum).and_then(|_| verify_executable_format(&bytes)) {
+            Ok(()) => Runner::verify_release_signature(runner, &release.uri, &bytes).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = verified {
+            if bin_path.exists() {
+                fs::remove_file(&bin_path)?;
+                remove_release_dir(&bin_path, &release.checksum);
+            }
+            return Err(err);
+        }
 
-        file.write_all(&bytes)?;
-        file.sync_all()?;
+        Runner::check_disk_space(runner, bytes.len())?;
+        write_executable(&bin_path, &bytes[..])?;
 
         let downloaded_release = DownloadedRelease {
             checksum: release.checksum,
             path: bin_path,
             bin_name,
+            uri: release.uri,
         };
         runner.set_downloaded_release(Some(downloaded_release.clone()));
         Ok(downloaded_release)
     }
 
-    fn get_bin_path(runner: &impl RunnerExt, uri: &str) -> Result<(String, PathBuf), Error> {
+    /// If a release signing key is configured, check the detached signature published at
+    /// `<uri>.sig` against it.
+    async fn verify_release_signature(runner: &impl RunnerExt, uri: &str, bytes: &[u8]) -> Result<(), Error> {
+        let signing_key = match runner.release_signing_key() {
+            Some(signing_key) => signing_key,
+            None => return Ok(()),
+        };
+        let signature_uri = format!("{}{}", uri, SIGNATURE_URI_SUFFIX);
+        let signature = Runner::get_request_bytes_with_retry(runner, &signature_uri, download_retry_config()).await?;
+        verify_signature(bytes, &signature, &signing_key)
+    }
+
+    /// Ensure there is room for a `size` bytes executable in the download path, on top of the
+    /// configured safety margin.
+    fn check_disk_space(runner: &impl RunnerExt, size: usize) -> Result<(), Error> {
+        let available = runner.available_disk_space(runner.download_path())?;
+        check_fits(size as u64, runner.disk_space_margin(), available)
+    }
+
+    /// Fetch `url`, retrying up to `max_download_retries` times with exponential backoff.
+    async fn get_request_bytes_with_retry(
+        runner: &impl RunnerExt,
+        url: &str,
+        backoff: ExponentialBackoff,
+    ) -> Result<Bytes, Error> {
+        backoff::future::retry_notify(
+            LimitedRetries::new(backoff, runner.max_download_retries()),
+            || {
+                runner.get_request_bytes(url.to_string()).map_err(|err| match err {
+                    // retrying won't free up disk space
+                    err @ Error::InsufficientDiskSpace { .. } => BackoffError::Permanent(err),
+                    err => BackoffError::Transient(err),
+                })
+            },
+            |err, delay| {
+                log::warn!(
+                    "Error fetching executable from {}: {}. Retrying in {:?}...",
+                    url,
+                    err,
+                    delay
+                )
+            },
+        )
+        .await
+    }
+
+    /// Move a prefetched release into its final location, after re-verifying its checksum.
+    fn promote_staged_release(staged_release: &DownloadedRelease, bin_path: &Path) -> Result<(), Error> {
+        if let Err(err) = verify_file_checksum(&staged_release.path, &staged_release.checksum) {
+            let _ = fs::remove_file(&staged_release.path);
+            return Err(err);
+        }
+        if let Some(release_dir) = bin_path.parent() {
+            fs::create_dir_all(release_dir)?;
+        }
+        fs::rename(&staged_release.path, bin_path)?;
+        Ok(())
+    }
+
+    async fn prefetch_pending(runner: &mut impl RunnerExt) -> Result<(), Error> {
+        let pending_release = match runner.try_get_release(ReleaseKind::Pending).await? {
+            Some(release) => release,
+            None => return Ok(()),
+        };
+
+        let is_downloaded = runner
+            .downloaded_release()
+            .as_ref()
+            .map_or(false, |release| release.checksum.eq(&pending_release.checksum));
+        if !is_downloaded {
+            Runner::stage_release(runner, pending_release).await?;
+        }
+        Ok(())
+    }
+
+    /// Download `release` to the staging directory, unless it is already there. A staged release is moved
+    /// to the download path instead of being downloaded again once it becomes current.
+    async fn stage_release(runner: &mut impl RunnerExt, release: ClientRelease) -> Result<DownloadedRelease, Error> {
+        let staged_release = runner.staged_release();
+        if let Some(staged_release) = &staged_release {
+            if staged_release.checksum.eq(&release.checksum) {
+                return Ok(staged_release.clone());
+            }
+        }
+
+        let (bin_name, _) = runner.get_bin_path(&release)?;
+        let staging_dir = runner.download_path().join(STAGING_DIR);
+        fs::create_dir_all(&staging_dir)?;
+        let staged_path = staging_dir.join(&bin_name);
+        log::info!("Prefetching release {} at: {:?}", bin_name, staged_path);
+
+        // Not retried here, since prefetching is attempted again on the next tick
+        let bytes = runner.get_request_bytes(release.uri.clone()).await?;
+        verify_checksum(&bytes, &release.checksum)?;
+        verify_executable_format(&bytes)?;
+        Runner::verify_release_signature(runner, &release.uri, &bytes).await?;
+
+        if let Some(outdated_release) = staged_release {
+            let _ = fs::remove_file(&outdated_release.path);
+        }
+        Runner::check_disk_space(runner, bytes.len())?;
+        write_executable(&staged_path, &bytes[..])?;
+
+        let staged_release = DownloadedRelease {
+            checksum: release.checksum,
+            path: staged_path,
+            bin_name,
+            uri: release.uri,
+        };
+        runner.set_staged_release(Some(staged_release.clone()));
+        Ok(staged_release)
+    }
+
+    /// Return `Error::DowngradeBlocked` if `release` is older than the client that was last run, unless
+    /// downgrades are allowed. The release is prefetched in order to ask it for its version.
+    async fn check_downgrade(runner: &mut impl RunnerExt, release: &ClientRelease) -> Result<(), Error> {
+        if runner.allow_downgrade() {
+            return Ok(());
+        }
+        let current = match read_last_run_version(runner.download_path()) {
+            Some(version) => Ok(version),
+            None => match runner.downloaded_release().as_ref().map(|release| release.path.clone()) {
+                Some(current_path) => client_version(&current_path).await,
+                None => return Ok(()),
+            },
+        };
+        let new_path = Runner::stage_release(runner, release.clone()).await?.path;
+
+        match (current, client_version(&new_path).await) {
+            (Ok(current), Ok(new)) if new < current => Err(Error::DowngradeBlocked { current, new }),
+            (Ok(_), Ok(_)) => Ok(()),
+            (Err(err), _) | (_, Err(err)) => {
+                log::warn!("Could not compare the client versions, allowing the update: {}", err);
+                Ok(())
+            }
+        }
+    }
+
+    fn get_bin_path(runner: &impl RunnerExt, release: &ClientRelease) -> Result<(String, PathBuf), Error> {
         // Remove any trailing slashes from the release URI
-        let parsed_uri = Url::parse(uri.trim_end_matches('/'))?;
-        let bin_name = parsed_uri
-            .path_segments()
-            .and_then(|segments| segments.last())
-            .and_then(|name| if name.is_empty() { None } else { Some(name) })
-            .ok_or(Error::ClientNameDerivationError)?;
+        let uri = release.uri.trim_end_matches('/');
+        let bin_name = match uri.strip_prefix(IPFS_URI_PREFIX) {
+            Some(ipfs_path) => {
+                let mut segments = ipfs_path.split('/');
+                let cid = segments.next().filter(|cid| !cid.is_empty());
+                match (cid, segments.last()) {
+                    // The CID points to a directory that contains the executable
+                    (Some(_), Some(name)) if !name.is_empty() => name.to_string(),
+                    // A bare CID is not a meaningful file name
+                    (Some(cid), None) => format!("{}-{}", runner.client_type(), cid),
+                    _ => return Err(Error::ClientNameDerivationError),
+                }
+            }
+            None => {
+                let parsed_uri = Url::parse(uri)?;
+                parsed_uri
+                    .path_segments()
+                    .and_then(|segments| segments.last())
+                    .and_then(|name| if name.is_empty() { None } else { Some(name) })
+                    .ok_or(Error::ClientNameDerivationError)?
+                    .to_string()
+            }
+        };
 
-        let bin_path = runner.download_path().join(bin_name);
-        Ok((bin_name.to_string(), bin_path))
+        // Each release is stored in a directory named after its checksum, so that releases published
+        // under the same name never overwrite each other
+        let bin_path = runner
+            .download_path()
+            .join(format!("{:x}", release.checksum))
+            .join(&bin_name);
+        Ok((bin_name, bin_path))
     }
 
     fn delete_downloaded_release(runner: &mut impl RunnerExt) -> Result<(), Error> {
@@ -214,12 +999,81 @@ impl Runner {
             "Failed to remove old release".to_string(),
         )?;
 
+        // Leftovers of downloads that were interrupted by a crash, next to the executables
+        // in the release directories
+        for entry in fs::read_dir(runner.download_path())? {
+            let path = entry?.path();
+            if path.is_dir() {
+                remove_partial_downloads(&path)?;
+            }
+        }
+        remove_partial_downloads(runner.download_path())?;
+        remove_release_dir(&release.path, &release.checksum);
+
+        runner.set_downloaded_release(None);
+        Ok(())
+    }
+
+    fn retire_downloaded_release(runner: &mut impl RunnerExt) -> Result<(), Error> {
+        let keep_releases = runner.keep_releases();
+        if keep_releases == 0 {
+            return runner.delete_downloaded_release();
+        }
+        let release = runner.downloaded_release().clone().ok_or(Error::NoDownloadedRelease)?;
+
+        // Named after the checksum, since releases are usually published under the same name
+        let download_path = runner.download_path().to_path_buf();
+        let previous_dir = download_path.join(PREVIOUS_RELEASES_DIR);
+        fs::create_dir_all(&previous_dir)?;
+        let previous_path = previous_dir.join(format!("{:x}-{}", release.checksum, release.bin_name));
+        log::info!("Keeping old release for rollbacks, at {:?}", previous_path);
+        fs::rename(&release.path, &previous_path)?;
+        remove_release_dir(&release.path, &release.checksum);
+
+        let previous_releases = runner.previous_releases();
+        previous_releases.retain(|previous_release| previous_release.checksum != release.checksum);
+        previous_releases.push_front(DownloadedRelease {
+            path: previous_path,
+            ..release
+        });
+        let pruned = previous_releases.split_off(keep_releases.min(previous_releases.len()));
+        for pruned_release in pruned {
+            log::info!("Removing old release, with path {:?}", pruned_release.path);
+            if let Err(err) = fs::remove_file(&pruned_release.path) {
+                log::warn!("Failed to remove old release: {}", err);
+            }
+        }
+        write_previous_releases(&download_path, previous_releases.iter());
+
         runner.set_downloaded_release(None);
         Ok(())
     }
 
-    fn terminate_proc_and_wait(runner: &mut impl RunnerExt) -> Result<u32, Error> {
+    async fn rollback(runner: &mut impl RunnerExt) -> Result<(), Error> {
+        let previous_release = runner.previous_releases().pop_front().ok_or(Error::NoPreviousRelease)?;
+        log::warn!(
+            "Rolling back to the previous release {} (checksum: {:#x})",
+            previous_release.uri,
+            previous_release.checksum
+        );
+        runner.terminate_proc_and_wait().await?;
+        if let Some(current_release) = runner.downloaded_release().clone() {
+            runner.set_rolled_back_release(Some(current_release.checksum));
+            runner.delete_downloaded_release()?;
+        }
+        // Listed first, to be run again after a restart while the rolled back release is still current
+        let download_path = runner.download_path().to_path_buf();
+        write_previous_releases(
+            &download_path,
+            iter::once(&previous_release).chain(runner.previous_releases().iter()),
+        );
+        runner.set_downloaded_release(Some(previous_release));
+        runner.run_binary()
+    }
+
+    async fn terminate_proc_and_wait(runner: &mut impl RunnerExt) -> Result<u32, Error> {
         log::info!("Trying to terminate child process...");
+        let grace_period = runner.shutdown_grace_period();
         let child_proc = match runner.child_proc().as_mut() {
             Some(x) => x,
             None => {
@@ -228,54 +1082,100 @@ impl Runner {
             }
         };
 
-        let _ = retry_with_log(
-            || {
-                Ok(signal::kill(
-                    Pid::from_raw(child_proc.id().try_into().map_err(|_| Error::IntegerConversionError)?),
-                    Signal::SIGTERM,
-                ))
-            },
-            "Failed to kill child process".to_string(),
-        )
-        .map_err(|_| Error::ProcessTerminationFailure)?;
-
-        match child_proc.wait() {
-            Ok(exit_status) => log::info!(
-                "Terminated client process (pid: {}) with exit status {}",
-                child_proc.id(),
-                exit_status
-            ),
-            Err(error) => log::warn!("Client process termination error: {}", error),
-        };
-        let pid = child_proc.id();
+        send_sigterm(child_proc)?;
+        // Give the client time to shut down cleanly (e.g. to release its Bitcoin wallet),
+        // and only force-kill it if it doesn't exit within the grace period.
+        let deadline = Instant::now() + grace_period;
+        while !exited_or_killed(child_proc, deadline, grace_period)? {
+            tokio::time::sleep(CHILD_EXIT_POLL_INTERVAL).await;
+        }
+        let pid = reap(child_proc);
         runner.set_child_proc(None);
         Ok(pid)
     }
 
-    async fn try_get_release<T: RunnerExt + StorageReader>(runner: &T) -> Result<Option<ClientRelease>, Error> {
-        retry_with_log_async(
-            || {
-                runner
-                    .read_chain_storage::<ClientRelease>(runner.subxt_api())
-                    .into_future()
-                    .boxed()
-            },
-            "Error fetching executable".to_string(),
+    /// `terminate_proc_and_wait` for when the runner is dropped, and can't await the child's exit.
+    fn terminate_proc_and_wait_blocking(&mut self) -> Result<u32, Error> {
+        let grace_period = self.shutdown_grace_period();
+        let child_proc = match self.child_proc.as_mut() {
+            Some(x) => x,
+            None => return Ok(0),
+        };
+
+        send_sigterm(child_proc)?;
+        let deadline = Instant::now() + grace_period;
+        while !exited_or_killed(child_proc, deadline, grace_period)? {
+            thread::sleep(CHILD_EXIT_POLL_INTERVAL);
+        }
+        let pid = reap(child_proc);
+        self.child_proc = None;
+        Ok(pid)
+    }
+
+    async fn try_get_release<T: RunnerExt + StorageReader + Send>(
+        runner: &mut T,
+        kind: ReleaseKind,
+    ) -> Result<Option<ClientRelease>, Error> {
+        let storage_item = kind.storage_item();
+        // Each attempt may have to replace the connection, which needs exclusive access to the runner
+        let runner = tokio::sync::Mutex::new(runner);
+        retry_with_log_async(
+            || {
+                async {
+                    let mut runner = runner.lock().await;
+                    match runner.read_chain_storage::<ClientRelease>(storage_item).await {
+                        // The websocket client can't recover from a dropped connection on its own
+                        Err(err) if err.is_rpc_error() => {
+                            log::warn!("Lost connection to the parachain: {}", err);
+                            runner.reconnect().await?;
+                            runner.read_chain_storage::<ClientRelease>(storage_item).await
+                        }
+                        result => result,
+                    }
+                }
+                .boxed()
+            },
+            "Error fetching executable".to_string(),
         )
         .await
     }
 
+    async fn reconnect(runner: &mut Runner, mut backoff: ExponentialBackoff) -> Result<(), Error> {
+        let url = runner.parachain_url();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            log::info!("Reconnecting to the parachain at {} (attempt {})...", url, attempt);
+            match subxt_api(&url).await {
+                Ok(subxt_api) => {
+                    log::info!("Reconnected to the parachain");
+                    runner.subxt_api = subxt_api;
+                    return Ok(());
+                }
+                Err(err) => match backoff.next_backoff() {
+                    Some(delay) => {
+                        log::warn!("Failed to reconnect: {}. Retrying in {:?}...", err, delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                    // give up, so that the auto-updater keeps supervising the client in the meantime
+                    None => return Err(err),
+                },
+            }
+        }
+    }
+
     /// Read parachain storage via an RPC call, and decode the result
     async fn read_chain_storage<T: 'static + Decode + Debug>(
         client_type: &ClientType,
         subxt_api: &OnlineClient<PolkadotConfig>,
+        storage_item: &str,
     ) -> Result<Option<T>, Error> {
         // Based on the implementation of `subxt_api.storage().fetch(...)`, but with decoding for a custom type. Source:
         // https://github.com/paritytech/subxt/blob/99cea97f817ee0a6fee642ff22f867822d9557f6/subxt/src/storage/storage_client.rs#L142
         let storage_address = subxt::dynamic::storage(
             PARACHAIN_MODULE,
-            CURRENT_RELEASES_STORAGE_ITEM,
-            vec![Value::from_bytes(client_type.to_string().as_bytes())],
+            storage_item,
+            vec![Value::from_bytes(client_type.release_storage_key())],
         );
         let lookup_bytes = subxt::storage::utils::storage_address_bytes(&storage_address, &subxt_api.metadata())?;
         let enc_res = subxt_api
@@ -289,20 +1189,78 @@ impl Runner {
             .map_err(Into::into)
     }
 
-    /// Run the auto-updater while concurrently listening for termination signals.
-    pub async fn run(mut runner: Box<dyn RunnerExt + Send>, mut shutdown_signals: SignalsInfo) -> Result<(), Error> {
-        tokio::select! {
-            _ = shutdown_signals.next() => {
-                runner.terminate_proc_and_wait()?;
+    /// Run the auto-updaters of all clients while concurrently listening for termination signals. When a
+    /// client fails fatally, `policy` decides whether the other clients keep running or are restarted with it.
+    pub async fn run(
+        mut runners: Vec<Box<dyn RunnerExt + Send>>,
+        policy: FailurePolicy,
+        mut shutdown_signals: SignalsInfo,
+    ) -> Result<(), Error> {
+        loop {
+            let (stop_tx, _) = watch::channel(None);
+            let mut clients = Box::pin(join_all(
+                runners
+                    .iter_mut()
+                    .map(|runner| Runner::supervise(runner.as_mut(), policy, &stop_tx)),
+            ));
+            let results = tokio::select! {
+                _ = shutdown_signals.next() => {
+                    stop_tx.send_replace(Some(StopReason::Shutdown));
+                    clients.await
+                }
+                results = &mut clients => results,
+            };
+            results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+            match *stop_tx.borrow() {
+                Some(StopReason::Restart) => log::info!("Restarting all clients"),
+                // all clients have stopped, either on a signal or because they failed
+                _ => return Ok(()),
             }
-            result = runner.as_mut().auto_update() => {
+            tokio::time::sleep(RESTART_BACKOFF_BASE).await;
+        }
+    }
+
+    /// Run the auto-updater of a single client until it fails or the clients are stopped.
+    async fn supervise(
+        runner: &mut (dyn RunnerExt + Send),
+        policy: FailurePolicy,
+        stop_tx: &watch::Sender<Option<StopReason>>,
+    ) -> Result<(), Error> {
+        let client_type = runner.client_type();
+        let mut stop_rx = stop_tx.subscribe();
+        tokio::select! {
+            _ = stop_rx.changed() => {}
+            result = runner.auto_update() => {
                 match result {
-                    Ok(_) => log::error!("Auto-updater unexpectedly terminated."),
-                    Err(e) => log::error!("Runner error: {}", e),
+                    Ok(_) => log::error!("[{}] Auto-updater unexpectedly terminated.", client_type),
+                    Err(e) => log::error!("[{}] Runner error: {}", client_type, e),
+                }
+                if policy == FailurePolicy::RestartAll {
+                    // a shutdown takes precedence over restarting
+                    stop_tx.send_if_modified(|reason| {
+                        let stop = reason.is_none();
+                        if stop {
+                            *reason = Some(StopReason::Restart);
+                        }
+                        stop
+                    });
                 }
-                runner.terminate_proc_and_wait()?;
             }
         };
+        runner.terminate_proc_and_wait().await
+    }
+
+    /// Read the current release and download and verify it, without running the client.
+    pub async fn dry_run(runner: &mut impl RunnerExt) -> Result<(), Error> {
+        fs::create_dir_all(runner.download_path())?;
+        let release = runner
+            .try_get_release(ReleaseKind::Current)
+            .await?
+            .ok_or(Error::NoClientRelease)?;
+        log::info!("Current release: {} (checksum: {:#x})", release.uri, release.checksum);
+        runner.download_binary(release).await?;
+        log::info!("Dry run succeeded: the release was downloaded and verified");
         Ok(())
     }
 
@@ -311,34 +1269,86 @@ impl Runner {
         fs::create_dir_all(&runner.download_path())?;
 
         let release = runner
-            .try_get_release()
+            .try_get_release(ReleaseKind::Current)
             .await?
             .expect("No current client release set on-chain.");
 
-        if runner.try_load_downloaded_binary(&release).is_err() {
+        if runner.rolled_back_release() == Some(release.checksum) && !runner.previous_releases().is_empty() {
+            // restarted after a rollback: keep running the release that was rolled back to, which is
+            // listed first, until a different release is published
+            let previous_release = runner.previous_releases().pop_front().ok_or(Error::NoPreviousRelease)?;
+            log::warn!(
+                "Release {} was rolled back, running the previous release {} instead",
+                release.uri,
+                previous_release.uri
+            );
+            runner.set_downloaded_release(Some(previous_release));
+        } else if runner.try_load_downloaded_binary(&release).await.is_err() {
             // something went wrong while loading the binary: it is outdated,
             // non-existent, or something else went wrong. In all of these
-            // case, try to download the newest binary
-            runner.download_binary(release).await?;
+            // case, try to download the newest binary. The release may have
+            // been downgraded while the runner was stopped.
+            match runner.check_downgrade(&release).await {
+                Err(err @ Error::DowngradeBlocked { .. }) => {
+                    log::error!("Not updating the client: {}", err);
+                    // Keep running the last run client, as if the downgrade was published while running it
+                    let last_run_release = read_last_run_release(runner.download_path()).ok_or(err)?;
+                    log::warn!("Running the last run release {} instead", last_run_release.uri);
+                    runner.set_downloaded_release(Some(last_run_release));
+                }
+                result => {
+                    result?;
+                    runner.download_binary(release).await?;
+                }
+            }
         }
 
         runner.run_binary()?;
 
         loop {
-            runner.maybe_restart_client()?;
-            if let Some(new_release) = runner.try_get_release().await? {
+            runner.maybe_restart_client().await?;
+            if runner.take_rollback_request() {
+                match runner.rollback().await {
+                    Err(Error::NoPreviousRelease) => log::warn!("Not rolling back: {}", Error::NoPreviousRelease),
+                    result => result?,
+                }
+            }
+            let new_release = match runner.try_get_release(ReleaseKind::Current).await {
+                Ok(new_release) => new_release,
+                Err(err) => {
+                    // Keep restarting the client until the parachain can be reached again
+                    log::error!("Failed to fetch the current client release: {}", err);
+                    tokio::time::sleep(with_jitter(runner.poll_interval())).await;
+                    continue;
+                }
+            };
+            if let Some(new_release) = new_release {
                 let maybe_downloaded_release = runner.downloaded_release();
                 let downloaded_release = maybe_downloaded_release.as_ref().ok_or(Error::NoDownloadedRelease)?;
                 if new_release.checksum != downloaded_release.checksum {
+                    if runner.rolled_back_release() == Some(new_release.checksum) {
+                        // Keep running the previous release until a different one is published
+                        tokio::time::sleep(with_jitter(runner.poll_interval())).await;
+                        continue;
+                    }
+                    // Checked before stopping the client, which keeps running if the release is rejected
+                    match runner.check_downgrade(&new_release).await {
+                        Err(err @ Error::DowngradeBlocked { .. }) => {
+                            log::error!("Not updating the client: {}", err);
+                            tokio::time::sleep(with_jitter(runner.poll_interval())).await;
+                            continue;
+                        }
+                        result => result?,
+                    }
                     log::info!("Found new client release, updating...");
 
                     // Wait for child process to finish completely.
                     // To ensure there can't be two client processes using the same resources (such as the Bitcoin
                     // wallet for vaults).
-                    runner.terminate_proc_and_wait()?;
+                    runner.terminate_proc_and_wait().await?;
 
-                    // Delete old release
-                    runner.delete_downloaded_release()?;
+                    // Keep the old release for rollbacks, or delete it
+                    runner.retire_downloaded_release()?;
 
                     // Download new release
                     runner.download_binary(new_release).await?;
@@ -347,12 +1357,23 @@ impl Runner {
                     runner.run_binary()?;
                 }
             }
-            tokio::time::sleep(BLOCK_TIME).await;
+            // Download the upcoming release ahead of time, to minimize the downtime when it becomes current
+            if let Err(err) = runner.prefetch_pending().await {
+                log::warn!("Failed to prefetch pending client release: {}", err);
+            }
+            tokio::time::sleep(with_jitter(runner.poll_interval())).await;
         }
     }
 
-    fn maybe_restart_client(runner: &mut impl RunnerExt) -> Result<(), Error> {
+    async fn maybe_restart_client(runner: &mut impl RunnerExt) -> Result<(), Error> {
         if !runner.check_child_proc_alive()? {
+            if runner.last_exit_status().map_or(false, |status| status.success()) {
+                // the client chose to stop, so restarting it would likely be futile
+                return Ok(());
+            }
+            let restart = runner.record_restart()?;
+            log::warn!("Restarting client (attempt {}) in {:?}", restart.attempt, restart.delay);
+            tokio::time::sleep(restart.delay).await;
             runner.run_binary()?;
         }
         Ok(())
@@ -366,6 +1387,7 @@ impl Runner {
                 Some(status) => {
                     log::info!("Child exited with: {status}");
                     runner.set_child_proc(None);
+                    runner.set_last_exit_status(Some(status));
                     return Ok(false);
                 }
                 None => {
@@ -376,13 +1398,25 @@ impl Runner {
         Ok(false)
     }
 
-    fn run_binary(runner: &mut impl RunnerExt, stdout_mode: impl Into<Stdio>) -> Result<Child, Error> {
+    fn run_binary(
+        runner: &mut impl RunnerExt,
+        stdout_mode: impl Into<Stdio>,
+        stderr_mode: impl Into<Stdio>,
+    ) -> Result<Child, Error> {
         if runner.child_proc().is_some() {
             return Err(Error::ChildProcessExists);
         }
         let downloaded_release = runner.downloaded_release().as_ref().ok_or(Error::NoDownloadedRelease)?;
+        // the binary may have been replaced on disk since it was downloaded
+        verify_file_checksum(&downloaded_release.path, &downloaded_release.checksum)?;
         let mut command = Command::new(downloaded_release.path.as_os_str());
-        command.args(runner.client_args().clone()).stdout(stdout_mode);
+        for env in runner.client_env() {
+            command.env(&env.key, env.resolve()?);
+        }
+        command
+            .args(runner.client_args().clone())
+            .stdout(stdout_mode)
+            .stderr(stderr_mode);
         let child = retry_with_log(
             || command.spawn().map_err(Into::into),
             "Failed to spawn child process".to_string(),
@@ -398,7 +1432,7 @@ impl Drop for Runner {
             .check_child_proc_alive()
             .expect("Failed to check child process status")
         {
-            if let Err(e) = self.terminate_proc_and_wait() {
+            if let Err(e) = self.terminate_proc_and_wait_blocking() {
                 log::warn!("Failed to terminate child process: {}", e);
             }
         }
@@ -409,38 +1443,86 @@ impl Drop for Runner {
 pub trait RunnerExt {
     fn subxt_api(&self) -> &OnlineClient<PolkadotConfig>;
     fn client_args(&self) -> &Vec<String>;
+    /// Environment variables to set for the client, in addition to the runner's own
+    fn client_env(&self) -> &Vec<ClientEnv>;
+    fn max_download_retries(&self) -> u32;
+    /// Interval between queries for a new release
+    fn poll_interval(&self) -> Duration;
+    fn shutdown_grace_period(&self) -> Duration;
+    /// Free space (in bytes) to leave on the download path's filesystem after writing an executable
+    fn disk_space_margin(&self) -> u64;
+    /// Key that releases must be signed with, if signature verification is enabled
+    fn release_signing_key(&self) -> Option<sr25519::Public>;
+    /// Whether the client may be updated to a release with a lower version
+    fn allow_downgrade(&self) -> bool;
+    /// Space (in bytes) available to unprivileged users on the filesystem containing `path`
+    fn available_disk_space(&self, path: &Path) -> Result<u64, Error>;
     fn child_proc(&mut self) -> &mut Option<Child>;
     fn set_child_proc(&mut self, child_proc: Option<Child>);
     fn downloaded_release(&self) -> &Option<DownloadedRelease>;
     fn set_downloaded_release(&mut self, downloaded_release: Option<DownloadedRelease>);
+    fn staged_release(&self) -> Option<DownloadedRelease>;
+    fn set_staged_release(&mut self, staged_release: Option<DownloadedRelease>);
     fn download_path(&self) -> &PathBuf;
     fn parachain_url(&self) -> String;
     fn client_type(&self) -> ClientType;
-    /// Read the current client release from the parachain, retrying for `RETRY_TIMEOUT` if there is a network error.
-    async fn try_get_release(&self) -> Result<Option<ClientRelease>, Error>;
-    /// Download the client binary and make it executable, retrying for `RETRY_TIMEOUT` if there is a network error.
+    /// Read the `kind` client release from the parachain, reconnecting if the connection to the parachain was
+    /// lost.
+    async fn try_get_release(&mut self, kind: ReleaseKind) -> Result<Option<ClientRelease>, Error>;
+    /// Replace the parachain connection with a new one, retrying with exponential backoff until it succeeds.
+    async fn reconnect(&mut self) -> Result<(), Error>;
+    /// Download the client binary and make it executable, retrying up to `max_download_retries` times if there is
+    /// a network error. If the release has already been prefetched, the staged binary is used instead.
     async fn download_binary(&mut self, release: ClientRelease) -> Result<(), Error>;
-    /// Convert a release URI (e.g. a GitHub link) to an executable name and OS path (after download)
-    fn get_bin_path(&self, uri: &str) -> Result<(String, PathBuf), Error>;
+    /// Download the pending client release into the staging directory, if it hasn't been downloaded yet.
+    async fn prefetch_pending(&mut self) -> Result<(), Error>;
+    /// Fail with `Error::DowngradeBlocked` if updating to `release` would downgrade the client.
+    async fn check_downgrade(&mut self, release: &ClientRelease) -> Result<(), Error>;
+    /// Convert a release URI (e.g. a GitHub link) to an executable name, and the release to the OS path of the
+    /// executable (after download)
+    fn get_bin_path(&self, release: &ClientRelease) -> Result<(String, PathBuf), Error>;
     /// Remove downloaded release from the file system. This is only supposed to occur _after_ the client process
     /// has been killed. In case of failure, removing is retried for `RETRY_TIMEOUT`.
     fn delete_downloaded_release(&mut self) -> Result<(), Error>;
+    /// Keep the downloaded release for rollbacks, moving it out of the way of the next download, and remove the
+    /// previous releases beyond `--keep-releases`. The release is deleted right away if none are kept.
+    fn retire_downloaded_release(&mut self) -> Result<(), Error>;
+    /// Number of previous releases kept for rollbacks
+    fn keep_releases(&self) -> usize;
+    /// Releases that were run before the current one, most recent first
+    fn previous_releases(&mut self) -> &mut VecDeque<DownloadedRelease>;
+    /// Checksum of the release that was rolled back from, if any
+    fn rolled_back_release(&self) -> Option<H256>;
+    fn set_rolled_back_release(&mut self, checksum: Option<H256>);
+    /// Whether a rollback was requested since the last call
+    fn take_rollback_request(&mut self) -> bool;
+    /// Stop the client and run the most recent previous release instead, without downloading it again. The
+    /// current release is deleted.
+    async fn rollback(&mut self) -> Result<(), Error>;
     /// Spawn a the client as a child process with the CLI arguments set in the `Runner`, retrying for
     /// `RETRY_TIMEOUT`.
     fn run_binary(&mut self) -> Result<(), Error>;
     /// Send a `SIGTERM` to the child process (via the underlying `kill` system call).
     /// If `kill` returns an error code, the operation is retried for `RETRY_TIMEOUT`.
-    fn terminate_proc_and_wait(&mut self) -> Result<(), Error>;
+    /// If the child has not exited after `shutdown_grace_period`, it is sent a `SIGKILL`.
+    async fn terminate_proc_and_wait(&mut self) -> Result<(), Error>;
     /// Get the client release executable, as `Bytes`
     async fn get_request_bytes(&self, url: String) -> Result<Bytes, Error>;
     /// Main loop, checks the parachain for new releases and updates the client accordingly.
     fn auto_update(&mut self) -> BoxFuture<'_, Result<(), Error>>;
     /// Returns whether the child is alive and sets the `runner.child` field to `None` if not.
     fn check_child_proc_alive(&mut self) -> Result<bool, Error>;
-    /// If the child process crashed, start it again
-    fn maybe_restart_client(&mut self) -> Result<(), Error>;
-    /// If a client binary exists on disk, load it.
-    fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error>;
+    /// How the last child process exited, if it exited on its own
+    fn last_exit_status(&self) -> Option<ExitStatus>;
+    fn set_last_exit_status(&mut self, status: Option<ExitStatus>);
+    /// If the child process crashed, start it again. A client that exited successfully is not restarted.
+    async fn maybe_restart_client(&mut self) -> Result<(), Error>;
+    /// Record a restart of the child process, returning when to perform it.
+    /// Errors if the client is restarted too often.
+    fn record_restart(&mut self) -> Result<Restart, Error>;
+    /// If a client binary exists on disk, load it. Its checksum, and its signature if a release signing key
+    /// is configured, are verified first.
+    async fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error>;
 }
 
 #[async_trait]
@@ -453,11 +1535,55 @@ impl RunnerExt for Runner {
         &self.opts.client_args
     }
 
+    fn client_env(&self) -> &Vec<ClientEnv> {
+        &self.opts.client_env
+    }
+
+    fn max_download_retries(&self) -> u32 {
+        self.opts.max_download_retries
+    }
+
+    fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.opts.poll_interval_secs)
+    }
+
+    fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.opts.shutdown_grace_period_secs)
+    }
+
+    fn disk_space_margin(&self) -> u64 {
+        self.opts.disk_space_margin_mb.saturating_mul(1024 * 1024)
+    }
+
+    fn release_signing_key(&self) -> Option<sr25519::Public> {
+        self.opts.release_signing_key
+    }
+
+    fn allow_downgrade(&self) -> bool {
+        self.opts.allow_downgrade
+    }
+
+    fn available_disk_space(&self, path: &Path) -> Result<u64, Error> {
+        let stats = statvfs(path)?;
+        // the `statvfs` field types are platform-dependent
+        #[allow(clippy::useless_conversion)]
+        let available = u64::from(stats.blocks_available()).saturating_mul(u64::from(stats.fragment_size()));
+        Ok(available)
+    }
+
     fn child_proc(&mut self) -> &mut Option<Child> {
         &mut self.child_proc
     }
 
     fn set_child_proc(&mut self, child_proc: Option<Child>) {
+        if child_proc.is_some() {
+            self.last_exit_status = None;
+        }
+        let child_pid = child_proc.as_ref().map(Child::id);
+        self.update_status(|status| {
+            status.child_pid = child_pid;
+            status.child_started_at = child_pid.map(|_| Instant::now());
+        });
         self.child_proc = child_proc;
     }
 
@@ -466,9 +1592,19 @@ impl RunnerExt for Runner {
     }
 
     fn set_downloaded_release(&mut self, downloaded_release: Option<DownloadedRelease>) {
+        self.update_status(|status| status.downloaded_release = downloaded_release.clone());
         self.downloaded_release = downloaded_release;
     }
 
+    fn staged_release(&self) -> Option<DownloadedRelease> {
+        self.staged_release.clone()
+    }
+
+    fn set_staged_release(&mut self, staged_release: Option<DownloadedRelease>) {
+        self.update_status(|status| status.staged_release = staged_release.clone());
+        self.staged_release = staged_release;
+    }
+
     fn download_path(&self) -> &PathBuf {
         &self.opts.download_path
     }
@@ -478,16 +1614,39 @@ impl RunnerExt for Runner {
     }
 
     fn client_type(&self) -> ClientType {
-        self.opts.client_type.clone()
+        self.client_type.clone()
+    }
+
+    async fn try_get_release(&mut self, kind: ReleaseKind) -> Result<Option<ClientRelease>, Error> {
+        Runner::try_get_release(self, kind).await
     }
 
-    async fn try_get_release(&self) -> Result<Option<ClientRelease>, Error> {
-        Runner::try_get_release(self).await
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        Runner::reconnect(self, reconnect_retry_config()).await
     }
 
     fn run_binary(&mut self) -> Result<(), Error> {
-        let child = Runner::run_binary(self, Stdio::inherit())?;
-        self.child_proc = Some(child);
+        let child = match self.log_config() {
+            Some(log_config) => {
+                let mut child = Runner::run_binary(self, Stdio::piped(), Stdio::piped())?;
+                if let Err(err) = logs::capture_output(&mut child, &log_config) {
+                    // Nothing would supervise (or drain the output of) the client, so don't leave it running
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(err.into());
+                }
+                child
+            }
+            // tell the output of the clients apart if they share the terminal
+            None if self.opts.client_types.len() > 1 => {
+                let mut child = Runner::run_binary(self, Stdio::piped(), Stdio::piped())?;
+                logs::prefix_output(&mut child, &format!("[{}] ", self.client_type));
+                child
+            }
+            None => Runner::run_binary(self, Stdio::inherit(), Stdio::inherit())?,
+        };
+        self.set_child_proc(Some(child));
+        self.record_last_run_release();
         Ok(())
     }
 
@@ -496,8 +1655,16 @@ impl RunnerExt for Runner {
         Ok(())
     }
 
-    fn get_bin_path(&self, uri: &str) -> Result<(String, PathBuf), Error> {
-        Runner::get_bin_path(self, uri)
+    async fn prefetch_pending(&mut self) -> Result<(), Error> {
+        Runner::prefetch_pending(self).await
+    }
+
+    async fn check_downgrade(&mut self, release: &ClientRelease) -> Result<(), Error> {
+        Runner::check_downgrade(self, release).await
+    }
+
+    fn get_bin_path(&self, release: &ClientRelease) -> Result<(String, PathBuf), Error> {
+        Runner::get_bin_path(self, release)
     }
 
     fn delete_downloaded_release(&mut self) -> Result<(), Error> {
@@ -505,16 +1672,68 @@ impl RunnerExt for Runner {
         Ok(())
     }
 
-    fn terminate_proc_and_wait(&mut self) -> Result<(), Error> {
-        Runner::terminate_proc_and_wait(self)?;
+    fn retire_downloaded_release(&mut self) -> Result<(), Error> {
+        Runner::retire_downloaded_release(self)
+    }
+
+    fn keep_releases(&self) -> usize {
+        self.opts.keep_releases
+    }
+
+    fn previous_releases(&mut self) -> &mut VecDeque<DownloadedRelease> {
+        &mut self.previous_releases
+    }
+
+    fn rolled_back_release(&self) -> Option<H256> {
+        self.rolled_back_release
+    }
+
+    fn set_rolled_back_release(&mut self, checksum: Option<H256>) {
+        self.rolled_back_release = checksum;
+        let path = self.opts.download_path.join(ROLLED_BACK_RELEASE_FILE);
+        let result = match checksum {
+            Some(checksum) => fs::write(path, format!("{:x}", checksum)),
+            None => fs::remove_file(path).or_else(|err| match err.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(err),
+            }),
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to record the rolled back release: {}", err);
+        }
+    }
+
+    fn take_rollback_request(&mut self) -> bool {
+        self.rollback_requested.swap(false, Ordering::SeqCst)
+    }
+
+    async fn rollback(&mut self) -> Result<(), Error> {
+        Runner::rollback(self).await
+    }
+
+    async fn terminate_proc_and_wait(&mut self) -> Result<(), Error> {
+        Runner::terminate_proc_and_wait(self).await?;
         Ok(())
     }
 
     // Declaring as a static method would highly complicate mocking
     async fn get_request_bytes(&self, url: String) -> Result<Bytes, Error> {
+        let url = resolve_release_uri(&url, &self.opts.ipfs_gateway);
         log::info!("Fetching executable from {}", url);
-        let response = reqwest::get(url.clone()).await?;
-        Ok(response.bytes().await?)
+        let mut response = reqwest::get(url.clone()).await?.error_for_status()?;
+        // Give up as soon as the release can't fit in the download path, rather than buffering
+        // all of it first. `Content-Length` is only a hint, so the streamed size is capped too.
+        let available = self.available_disk_space(self.download_path())?;
+        let margin = self.disk_space_margin();
+        if let Some(size) = response.content_length() {
+            check_fits(size, margin, available)?;
+        }
+        let mut bytes = BytesMut::new();
+        while let Some(chunk) = response.chunk().await? {
+            check_fits((bytes.len() + chunk.len()) as u64, margin, available)?;
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes.freeze())
     }
 
     fn auto_update(&mut self) -> BoxFuture<'_, Result<(), Error>> {
@@ -525,30 +1744,37 @@ impl RunnerExt for Runner {
         Runner::check_child_proc_alive(self)
     }
 
-    fn maybe_restart_client(&mut self) -> Result<(), Error> {
-        Runner::maybe_restart_client(self)
+    fn last_exit_status(&self) -> Option<ExitStatus> {
+        self.last_exit_status
+    }
+
+    fn set_last_exit_status(&mut self, status: Option<ExitStatus>) {
+        self.last_exit_status = status;
+    }
+
+    async fn maybe_restart_client(&mut self) -> Result<(), Error> {
+        Runner::maybe_restart_client(self).await
+    }
+
+    fn record_restart(&mut self) -> Result<Restart, Error> {
+        self.restart_tracker.record_restart(Instant::now())
     }
 
-    fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error> {
-        Runner::try_load_downloaded_binary(self, release)
+    async fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error> {
+        Runner::try_load_downloaded_binary(self, release).await
     }
 }
 
 #[async_trait]
 pub trait StorageReader {
-    async fn read_chain_storage<T: 'static + Decode + Debug>(
-        &self,
-        subxt_api: &OnlineClient<PolkadotConfig>,
-    ) -> Result<Option<T>, Error>;
+    /// Read `storage_item` of the client type's release from the parachain, using the runner's connection.
+    async fn read_chain_storage<T: 'static + Decode + Debug>(&self, storage_item: &str) -> Result<Option<T>, Error>;
 }
 
 #[async_trait]
 impl StorageReader for Runner {
-    async fn read_chain_storage<T: 'static + Decode + Debug>(
-        &self,
-        subxt_api: &OnlineClient<PolkadotConfig>,
-    ) -> Result<Option<T>, Error> {
-        Runner::read_chain_storage(&self.client_type(), subxt_api).await
+    async fn read_chain_storage<T: 'static + Decode + Debug>(&self, storage_item: &str) -> Result<Option<T>, Error> {
+        Runner::read_chain_storage(&self.client_type(), self.subxt_api(), storage_item).await
     }
 }
 
@@ -565,6 +1791,28 @@ pub fn custom_retry_config() -> ExponentialBackoff {
     }
 }
 
+/// Backoff used between binary download attempts. The number of attempts is bounded by
+/// `max_download_retries` rather than by the elapsed time.
+pub fn download_retry_config() -> ExponentialBackoff {
+    ExponentialBackoff {
+        initial_interval: RETRY_INTERVAL,
+        max_elapsed_time: None,
+        multiplier: DOWNLOAD_RETRY_MULTIPLIER,
+        ..ExponentialBackoff::default()
+    }
+}
+
+/// Backoff used between reconnection attempts to the parachain, which give up after `RETRY_TIMEOUT`.
+pub fn reconnect_retry_config() -> ExponentialBackoff {
+    ExponentialBackoff {
+        initial_interval: RETRY_INTERVAL,
+        max_interval: RETRY_TIMEOUT,
+        max_elapsed_time: Some(RETRY_TIMEOUT),
+        multiplier: DOWNLOAD_RETRY_MULTIPLIER,
+        ..ExponentialBackoff::default()
+    }
+}
+
 pub fn retry_with_log<T, F>(mut f: F, log_msg: String) -> Result<T, Error>
 where
     F: FnMut() -> Result<T, Error>,
@@ -593,6 +1841,94 @@ where
     .map_err(Into::into)
 }
 
+/// Wraps `backoff` to give up after `max_retries` retries.
+pub struct LimitedRetries<B> {
+    backoff: B,
+    max_retries: u32,
+    retries: u32,
+}
+
+impl<B> LimitedRetries<B> {
+    pub fn new(backoff: B, max_retries: u32) -> Self {
+        Self {
+            backoff,
+            max_retries,
+            retries: 0,
+        }
+    }
+}
+
+impl<B: Backoff> Backoff for LimitedRetries<B> {
+    fn next_backoff(&mut self) -> Option<Duration> {
+        if self.retries >= self.max_retries {
+            return None;
+        }
+        self.retries += 1;
+        self.backoff.next_backoff()
+    }
+
+    fn reset(&mut self) {
+        self.retries = 0;
+        self.backoff.reset();
+    }
+}
+
+/// Backoff used between the attempts to connect to the parachain on startup, giving up once
+/// `max_elapsed_time` has passed.
+pub fn connect_retry_config(
+    initial_interval: Duration,
+    max_interval: Duration,
+    max_elapsed_time: Duration,
+) -> ExponentialBackoff {
+    ExponentialBackoff {
+        initial_interval,
+        current_interval: initial_interval,
+        max_interval,
+        max_elapsed_time: Some(max_elapsed_time),
+        multiplier: DOWNLOAD_RETRY_MULTIPLIER,
+        ..ExponentialBackoff::default()
+    }
+}
+
+/// Call `connect` until it succeeds, waiting between the attempts as given by `backoff`.
+pub async fn connect_with_backoff<T, F, Fut>(
+    url: &str,
+    mut connect: F,
+    mut backoff: ExponentialBackoff,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        log::info!(
+            "Connecting to the parachain at {} (attempt {}, {:?} elapsed)...",
+            url,
+            attempt,
+            start.elapsed()
+        );
+        match connect().await {
+            Ok(client) => return Ok(client),
+            Err(err) => match backoff.next_backoff() {
+                Some(delay) => {
+                    log::warn!("Failed to connect: {}. Retrying in {:?}...", err, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                None => {
+                    return Err(Error::ConnectionTimeout {
+                        attempts: attempt,
+                        elapsed: start.elapsed(),
+                        last_error: err.to_string(),
+                    })
+                }
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use async_trait::async_trait;
@@ -612,6 +1948,7 @@ mod tests {
         path::PathBuf,
         process::{Child, Command, Stdio},
         str::FromStr,
+        sync::{Arc, Mutex},
         thread,
     };
 
@@ -622,6 +1959,8 @@ mod tests {
 
     use super::*;
 
+    use clap::Parser;
+    use subxt::{error::RpcError, Error as SubxtError};
     use sysinfo::{Pid, System, SystemExt};
 
     macro_rules! assert_err {
@@ -641,32 +1980,52 @@ mod tests {
         pub trait RunnerExt {
             fn subxt_api(&self) -> &OnlineClient<PolkadotConfig>;
             fn client_args(&self) -> &Vec<String>;
+            fn client_env(&self) -> &Vec<ClientEnv>;
+            fn max_download_retries(&self) -> u32;
+            fn poll_interval(&self) -> Duration;
+            fn shutdown_grace_period(&self) -> Duration;
+            fn disk_space_margin(&self) -> u64;
+            fn release_signing_key(&self) -> Option<sr25519::Public>;
+            fn allow_downgrade(&self) -> bool;
+            fn available_disk_space(&self, path: &Path) -> Result<u64, Error>;
             fn child_proc(&mut self) -> &mut Option<Child>;
             fn set_child_proc(&mut self, child_proc: Option<Child>);
             fn downloaded_release(&self) -> &Option<DownloadedRelease>;
             fn set_downloaded_release(&mut self, downloaded_release: Option<DownloadedRelease>);
+            fn staged_release(&self) -> Option<DownloadedRelease>;
+            fn set_staged_release(&mut self, staged_release: Option<DownloadedRelease>);
             fn download_path(&self) -> &PathBuf;
             fn parachain_url(&self) -> String;
             fn client_type(&self) -> ClientType;
-            async fn try_get_release(&self) -> Result<Option<ClientRelease>, Error>;
+            async fn try_get_release(&mut self, kind: ReleaseKind) -> Result<Option<ClientRelease>, Error>;
+            async fn reconnect(&mut self) -> Result<(), Error>;
             async fn download_binary(&mut self, release: ClientRelease) -> Result<(), Error>;
-            fn get_bin_path(&self, uri: &str) -> Result<(String, PathBuf), Error>;
+            async fn prefetch_pending(&mut self) -> Result<(), Error>;
+            async fn check_downgrade(&mut self, release: &ClientRelease) -> Result<(), Error>;
+            fn get_bin_path(&self, release: &ClientRelease) -> Result<(String, PathBuf), Error>;
             fn delete_downloaded_release(&mut self) -> Result<(), Error>;
+            fn retire_downloaded_release(&mut self) -> Result<(), Error>;
+            fn keep_releases(&self) -> usize;
+            fn previous_releases(&mut self) -> &mut VecDeque<DownloadedRelease>;
+            fn rolled_back_release(&self) -> Option<H256>;
+            fn set_rolled_back_release(&mut self, checksum: Option<H256>);
+            fn take_rollback_request(&mut self) -> bool;
+            async fn rollback(&mut self) -> Result<(), Error>;
             fn run_binary(&mut self) -> Result<(), Error>;
-            fn terminate_proc_and_wait(&mut self) -> Result<(), Error>;
+            async fn terminate_proc_and_wait(&mut self) -> Result<(), Error>;
             async fn get_request_bytes(&self, url: String) -> Result<Bytes, Error>;
             fn auto_update(&mut self) ->  BoxFuture<'static, Result<(), Error>>;
             fn check_child_proc_alive(&mut self) -> Result<bool, Error>;
-            fn maybe_restart_client(&mut self) -> Result<(), Error>;
-            fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error>;
+            fn last_exit_status(&self) -> Option<ExitStatus>;
+            fn set_last_exit_status(&mut self, status: Option<ExitStatus>);
+            async fn maybe_restart_client(&mut self) -> Result<(), Error>;
+            fn record_restart(&mut self) -> Result<Restart, Error>;
+            async fn try_load_downloaded_binary(&mut self, release: &ClientRelease) -> Result<(), Error>;
         }
 
         #[async_trait]
         pub trait StorageReader {
-            async fn read_chain_storage<T: 'static + Decode + Debug>(
-                &self,
-                subxt_api: &OnlineClient<PolkadotConfig>,
-            ) -> Result<Option<T>, Error>;
+            async fn read_chain_storage<T: 'static + Decode + Debug>(&self, storage_item: &str) -> Result<Option<T>, Error>;
         }
     }
 
@@ -681,7 +2040,7 @@ mod tests {
         let client_release = ClientRelease {
             uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
                 .to_string(),
-            checksum: H256::default(),
+            checksum: H256::from_slice(&sha256sum(&ELF_MAGIC)),
         };
 
         runner
@@ -689,9 +2048,15 @@ mod tests {
             .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
         runner
             .expect_get_request_bytes()
-            .returning(|_| Ok(Bytes::from_static(&[1, 2, 3, 4])));
+            .returning(|_| Ok(Bytes::from_static(&ELF_MAGIC)));
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_release_signing_key().return_const(None);
         runner.expect_downloaded_release().return_const(None);
+        runner.expect_staged_release().return_const(None);
         runner.expect_set_downloaded_release().return_const(());
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_disk_space_margin().return_const(0u64);
+        runner.expect_available_disk_space().returning(|_| Ok(u64::MAX));
 
         let downloaded_release = Runner::download_binary(&mut runner, client_release.clone())
             .await
@@ -701,7 +2066,8 @@ mod tests {
             DownloadedRelease {
                 checksum: client_release.checksum,
                 path: mock_path.clone(),
-                bin_name: mock_bin_name
+                bin_name: mock_bin_name,
+                uri: client_release.uri.clone(),
             }
         );
 
@@ -720,96 +2086,1084 @@ mod tests {
         );
 
         let file_content = fs::read(mock_path.clone()).unwrap();
-        assert_eq!(file_content, vec![1, 2, 3, 4]);
+        assert_eq!(file_content, ELF_MAGIC.to_vec());
     }
 
     #[tokio::test]
-    async fn test_runner_binary_is_not_redownloaded() {
+    async fn test_runner_download_binary_checks_checksum() {
         let mut runner = MockRunner::default();
-        let downloaded_release = DownloadedRelease::default();
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mock_path = tmp.path().join("vault-standalone-metadata");
+        let moved_mock_path = mock_path.clone();
+
+        let client_release = ClientRelease {
+            uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                .to_string(),
+            checksum: H256::from_slice(&sha256sum(&[1, 2, 3, 4])),
+        };
 
         runner
-            .expect_downloaded_release()
-            .return_const(Some(downloaded_release.clone()));
+            .expect_get_bin_path()
+            .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
+        // The mirror serves different bytes than the ones the on-chain checksum was computed for
+        runner
+            .expect_get_request_bytes()
+            .returning(|_| Ok(Bytes::from_static(&[5, 6, 7, 8])));
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_release_signing_key().return_const(None);
+        runner.expect_downloaded_release().return_const(None);
+        runner.expect_staged_release().return_const(None);
+        runner
+            .expect_set_downloaded_release()
+            .times(0)
+            .returning(|_| panic!("Unexpected call"));
 
-        // No release should be set in the runner
+        assert_err!(
+            Runner::download_binary(&mut runner, client_release).await,
+            Error::HashMismatch { .. }
+        );
+        // No executable must be left behind
+        assert_eq!(mock_path.exists(), false);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_runner_download_binary_rejects_non_executable() {
+        let mut runner = MockRunner::default();
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mock_path = tmp.path().join("vault-standalone-metadata");
+        let moved_mock_path = mock_path.clone();
+
+        // A misconfigured release URL serving an error page, whose checksum was registered on-chain
+        let error_page: &[u8] = b"<!DOCTYPE html><html><body>404 Not Found</body></html>";
+        let client_release = ClientRelease {
+            uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                .to_string(),
+            checksum: H256::from_slice(&sha256sum(error_page)),
+        };
+
+        runner
+            .expect_get_bin_path()
+            .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
+        runner
+            .expect_get_request_bytes()
+            .returning(move |_| Ok(Bytes::from_static(error_page)));
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_release_signing_key().return_const(None);
+        runner.expect_downloaded_release().return_const(None);
+        runner.expect_staged_release().return_const(None);
         runner
             .expect_set_downloaded_release()
             .times(0)
             .returning(|_| panic!("Unexpected call"));
 
-        // The latest on-chain release matches the currently downloaded one
-        let new_downloaded_release = Runner::download_binary(&mut runner, ClientRelease::default())
-            .await
-            .unwrap();
+        assert_err!(
+            Runner::download_binary(&mut runner, client_release).await,
+            Error::NotAnExecutable
+        );
+        assert_eq!(mock_path.exists(), false);
+    }
 
-        assert_eq!(downloaded_release, new_downloaded_release);
+    #[tokio::test]
+    async fn test_runner_download_binary_checks_disk_space() {
+        let mut runner = MockRunner::default();
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mock_path = tmp.path().join("vault-standalone-metadata");
+        let moved_mock_path = mock_path.clone();
+
+        let client_release = ClientRelease {
+            uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                .to_string(),
+            checksum: H256::from_slice(&sha256sum(&ELF_MAGIC)),
+        };
+
+        runner
+            .expect_get_bin_path()
+            .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
+        runner
+            .expect_get_request_bytes()
+            .returning(|_| Ok(Bytes::from_static(&ELF_MAGIC)));
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_release_signing_key().return_const(None);
+        runner.expect_downloaded_release().return_const(None);
+        runner.expect_staged_release().return_const(None);
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_disk_space_margin().return_const(100u64);
+        // Fake an almost full filesystem
+        runner.expect_available_disk_space().returning(|_| Ok(64));
+
+        match Runner::download_binary(&mut runner, client_release).await {
+            Err(Error::InsufficientDiskSpace { needed, available }) => {
+                assert_eq!(needed, 104);
+                assert_eq!(available, 64);
+            }
+            other => panic!("expected InsufficientDiskSpace, got {:?}", other),
+        }
+        assert_eq!(mock_path.exists(), false);
+    }
+
+    #[tokio::test]
+    async fn test_runner_download_binary_does_not_retry_oversized_release() {
+        let mut runner = MockRunner::default();
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mock_path = tmp.path().join("vault-standalone-metadata");
+        let moved_mock_path = mock_path.clone();
+
+        let client_release = ClientRelease {
+            uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                .to_string(),
+            checksum: H256::from_slice(&sha256sum(&ELF_MAGIC)),
+        };
+
+        runner
+            .expect_get_bin_path()
+            .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
+        // The release is rejected while it is being fetched
+        runner.expect_get_request_bytes().once().returning(|_| {
+            Err(Error::InsufficientDiskSpace {
+                needed: 104,
+                available: 64,
+            })
+        });
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_downloaded_release().return_const(None);
+        runner.expect_staged_release().return_const(None);
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+
+        assert_err!(
+            Runner::download_binary(&mut runner, client_release).await,
+            Error::InsufficientDiskSpace {
+                needed: 104,
+                available: 64
+            }
+        );
+        assert_eq!(mock_path.exists(), false);
+    }
+
+    #[tokio::test]
+    async fn test_runner_download_binary_checks_signature() {
+        let signing_pair = sr25519::Pair::from_seed(&[1; 32]);
+        let binary = ELF_MAGIC;
+        let valid_signature = signing_pair.sign(&binary);
+        let tampered_signature = signing_pair.sign(&[5, 6, 7, 8]);
+
+        for (signature, is_valid) in [(valid_signature, true), (tampered_signature, false)] {
+            let mut runner = MockRunner::default();
+            let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+            let mock_path = tmp.path().join("vault-standalone-metadata");
+            let moved_mock_path = mock_path.clone();
+
+            let client_release = ClientRelease {
+                uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                    .to_string(),
+                checksum: H256::from_slice(&sha256sum(&binary)),
+            };
+
+            runner
+                .expect_get_bin_path()
+                .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_mock_path.clone())));
+            runner.expect_get_request_bytes().returning(move |url| {
+                if url.ends_with(SIGNATURE_URI_SUFFIX) {
+                    Ok(Bytes::copy_from_slice(signature.as_ref()))
+                } else {
+                    Ok(Bytes::copy_from_slice(&binary))
+                }
+            });
+            runner.expect_max_download_retries().return_const(5u32);
+            runner
+                .expect_release_signing_key()
+                .return_const(Some(signing_pair.public()));
+            runner.expect_downloaded_release().return_const(None);
+            runner.expect_staged_release().return_const(None);
+            runner.expect_set_downloaded_release().return_const(());
+            runner.expect_download_path().return_const(tmp.path().to_path_buf());
+            runner.expect_disk_space_margin().return_const(0u64);
+            runner.expect_available_disk_space().returning(|_| Ok(u64::MAX));
+
+            let result = Runner::download_binary(&mut runner, client_release).await;
+            if is_valid {
+                assert!(result.is_ok());
+                assert_eq!(fs::read(&mock_path).unwrap(), binary.to_vec());
+            } else {
+                assert_err!(result, Error::InvalidSignature);
+                assert_eq!(mock_path.exists(), false);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let signing_pair = sr25519::Pair::from_seed(&[1; 32]);
+        let other_pair = sr25519::Pair::from_seed(&[2; 32]);
+        let signature = signing_pair.sign(&[1, 2, 3, 4]);
+
+        assert!(verify_signature(&[1, 2, 3, 4], signature.as_ref(), &signing_pair.public()).is_ok());
+        // hex-encoded signatures are accepted as well
+        let hex_signature = format!("0x{}\n", hex::encode(signature));
+        assert!(verify_signature(&[1, 2, 3, 4], hex_signature.as_bytes(), &signing_pair.public()).is_ok());
+
+        assert_err!(
+            verify_signature(&[1, 2, 3, 5], signature.as_ref(), &signing_pair.public()),
+            Error::InvalidSignature
+        );
+        assert_err!(
+            verify_signature(&[1, 2, 3, 4], signature.as_ref(), &other_pair.public()),
+            Error::InvalidSignature
+        );
+        assert_err!(
+            verify_signature(&[1, 2, 3, 4], b"not a signature", &signing_pair.public()),
+            Error::InvalidSignature
+        );
+    }
+
+    fn test_retry_config() -> ExponentialBackoff {
+        ExponentialBackoff {
+            initial_interval: Duration::from_millis(1),
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runner_download_is_retried() {
+        let mut runner = MockRunner::default();
+        let attempts = Arc::new(Mutex::new(0));
+        let moved_attempts = attempts.clone();
+        runner.expect_max_download_retries().return_const(5u32);
+        runner.expect_get_request_bytes().returning(move |_| {
+            let mut attempts = moved_attempts.lock().unwrap();
+            *attempts += 1;
+            // The first two attempts fail
+            if *attempts <= 2 {
+                Err(Error::NoDownloadedRelease)
+            } else {
+                Ok(Bytes::from_static(&[1, 2, 3, 4]))
+            }
+        });
+
+        let bytes = Runner::get_request_bytes_with_retry(&runner, "https://example.com/vault", test_retry_config())
+            .await
+            .unwrap();
+        assert_eq!(bytes, Bytes::from_static(&[1, 2, 3, 4]));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_runner_download_gives_up_after_max_retries() {
+        let mut runner = MockRunner::default();
+        runner.expect_max_download_retries().return_const(2u32);
+        runner
+            .expect_get_request_bytes()
+            .times(3)
+            .returning(|_| Err(Error::NoDownloadedRelease));
+
+        assert_err!(
+            Runner::get_request_bytes_with_retry(&runner, "https://example.com/vault", test_retry_config()).await,
+            Error::NoDownloadedRelease
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_backoff_grows_and_gives_up() {
+        let mut backoff = ExponentialBackoff {
+            randomization_factor: 0.0,
+            ..connect_retry_config(
+                Duration::from_millis(10),
+                Duration::from_millis(40),
+                Duration::from_secs(60),
+            )
+        };
+        let delays: Vec<_> = (0..4).map(|_| backoff.next_backoff().unwrap()).collect();
+        assert_eq!(delays, [10, 20, 40, 40].map(Duration::from_millis));
+
+        let attempts = Arc::new(Mutex::new(0));
+        let moved_attempts = attempts.clone();
+        let backoff = connect_retry_config(
+            Duration::from_millis(1),
+            Duration::from_millis(5),
+            Duration::from_millis(50),
+        );
+        let result: Result<(), _> = connect_with_backoff(
+            "ws://localhost:9944",
+            || {
+                *moved_attempts.lock().unwrap() += 1;
+                async { Err(Error::NoClientRelease) }
+            },
+            backoff,
+        )
+        .await;
+
+        let attempts = *attempts.lock().unwrap();
+        assert!(attempts > 1);
+        match result {
+            Err(Error::ConnectionTimeout {
+                attempts: reported,
+                last_error,
+                ..
+            }) => {
+                assert_eq!(reported, attempts);
+                assert_eq!(last_error, Error::NoClientRelease.to_string());
+            }
+            result => panic!("expected a connection timeout, got {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runner_prefetched_release_is_not_redownloaded() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let bin_path = tmp.path().join("vault-standalone-metadata");
+        let moved_bin_path = bin_path.clone();
+        let mut runner = MockRunner::default();
+
+        let release = ClientRelease {
+            uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/vault-standalone-metadata"
+                .to_string(),
+            checksum: H256::from_slice(&sha256sum(&ELF_MAGIC)),
+        };
+        let pending_release = release.clone();
+
+        let staged_release = Arc::new(Mutex::new(None));
+        let (staged_release_getter, staged_release_setter) = (staged_release.clone(), staged_release.clone());
+
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner
+            .expect_get_bin_path()
+            .returning(move |_| Ok(("vault-standalone-metadata".to_string(), moved_bin_path.clone())));
+        runner
+            .expect_try_get_release()
+            .returning(move |_| Ok(Some(pending_release.clone())));
+        runner.expect_downloaded_release().return_const(None);
+        runner.expect_set_downloaded_release().return_const(());
+        runner.expect_disk_space_margin().return_const(0u64);
+        runner.expect_available_disk_space().returning(|_| Ok(u64::MAX));
+        runner.expect_release_signing_key().return_const(None);
+        runner
+            .expect_staged_release()
+            .returning(move || staged_release_getter.lock().unwrap().clone());
+        runner
+            .expect_set_staged_release()
+            .returning(move |release| *staged_release_setter.lock().unwrap() = release);
+        // Only prefetching the pending release may hit the network
+        runner
+            .expect_get_request_bytes()
+            .times(1)
+            .returning(|_| Ok(Bytes::from_static(&ELF_MAGIC)));
+
+        Runner::prefetch_pending(&mut runner).await.unwrap();
+        let staged_path = staged_release.lock().unwrap().clone().unwrap().path;
+        assert_eq!(fs::read(&staged_path).unwrap(), ELF_MAGIC.to_vec());
+
+        // The pending release becomes the current one
+        let downloaded_release = Runner::download_binary(&mut runner, release).await.unwrap();
+        assert_eq!(downloaded_release.path, bin_path);
+        assert_eq!(fs::read(&bin_path).unwrap(), ELF_MAGIC.to_vec());
+        assert_eq!(staged_path.exists(), false);
+    }
+
+    #[tokio::test]
+    async fn test_runner_dry_run_does_not_spawn_client() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mut runner = MockRunner::default();
+        runner.expect_download_path().return_const(tmp.path().join("dry-run"));
+        runner
+            .expect_try_get_release()
+            .once()
+            .returning(|_| Ok(Some(ClientRelease::default())));
+        runner.expect_download_binary().once().returning(|_| Ok(()));
+        runner.expect_run_binary().never();
+
+        Runner::dry_run(&mut runner).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_runner_dry_run_fails_without_release() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mut runner = MockRunner::default();
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_try_get_release().returning(|_| Ok(None));
+        runner.expect_download_binary().never();
+
+        assert_err!(Runner::dry_run(&mut runner).await, Error::NoClientRelease);
+    }
+
+    #[tokio::test]
+    async fn test_runner_binary_is_not_redownloaded() {
+        let mut runner = MockRunner::default();
+        let downloaded_release = DownloadedRelease::default();
+
+        runner
+            .expect_downloaded_release()
+            .return_const(Some(downloaded_release.clone()));
+
+        // No release should be set in the runner
+        runner
+            .expect_set_downloaded_release()
+            .times(0)
+            .returning(|_| panic!("Unexpected call"));
+
+        // The latest on-chain release matches the currently downloaded one
+        let new_downloaded_release = Runner::download_binary(&mut runner, ClientRelease::default())
+            .await
+            .unwrap();
+
+        assert_eq!(downloaded_release, new_downloaded_release);
+    }
+
+    #[tokio::test]
+    async fn test_runner_get_bin_path() {
+        let clients = [ClientType::Vault, ClientType::Oracle, ClientType::Faucet];
+        let mock_path = PathBuf::from_str("./mock_download_dir").unwrap();
+        for client in clients {
+            let mut runner = MockRunner::default();
+            runner.expect_download_path().return_const(mock_path.clone());
+            runner.expect_client_type().return_const(client.clone());
+            let release = ClientRelease {
+                uri: "https://github.com/interlay/interbtc-clients/releases/download/1.17.2/vault-standalone-metadata"
+                    .to_string(),
+                checksum: H256::from_low_u64_be(10),
+            };
+            let (bin_name, bin_path) = Runner::get_bin_path(&runner, &release).unwrap();
+            assert_eq!(bin_name, "vault-standalone-metadata");
+            assert_eq!(
+                bin_path,
+                mock_path
+                    .join("000000000000000000000000000000000000000000000000000000000000000a")
+                    .join(bin_name)
+            );
+        }
+    }
+
+    #[test]
+    fn test_client_type_release_storage_key() {
+        for (name, client_type) in [
+            ("vault", ClientType::Vault),
+            ("oracle", ClientType::Oracle),
+            ("faucet", ClientType::Faucet),
+        ] {
+            assert_eq!(ClientType::from_str(name).unwrap().to_string(), name);
+            assert_eq!(client_type.release_storage_key(), name.as_bytes().to_vec());
+        }
+        assert_err!(ClientType::from_str("relayer"), Error::ClientTypeParsingError);
+
+        // `vault` is the default client type
+        let opts = Opts::try_parse_from(["runner", "--parachain-ws", "ws://localhost:9944"]).unwrap();
+        assert_eq!(opts.client_types, vec![ClientType::Vault]);
+        assert_eq!(opts.on_client_failure, FailurePolicy::Isolate);
+    }
+
+    #[test]
+    fn test_runner_opts_for_multiple_clients() {
+        let opts = Opts::try_parse_from([
+            "runner",
+            "--parachain-ws",
+            "ws://localhost:9944",
+            "--client-type",
+            "vault,oracle",
+            "--log-dir",
+            "logs",
+            "--on-client-failure",
+            "restart-all",
+        ])
+        .unwrap();
+        assert_eq!(opts.client_types, vec![ClientType::Vault, ClientType::Oracle]);
+        assert_eq!(opts.on_client_failure, FailurePolicy::RestartAll);
+
+        // The clients don't share their files
+        let oracle_opts = opts.for_client(&ClientType::Oracle);
+        assert_eq!(oracle_opts.download_path, PathBuf::from("./oracle"));
+        assert_eq!(oracle_opts.log_dir, Some(PathBuf::from("logs/oracle")));
+
+        assert!(Opts::try_parse_from([
+            "runner",
+            "--parachain-ws",
+            "ws://localhost:9944",
+            "--on-client-failure",
+            "ignore",
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_runner_releases_with_the_same_name_coexist() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let binaries: [&'static [u8]; 2] = [b"\x7fELF old", b"\x7fELF new"];
+
+        let mut bin_paths = vec![];
+        for (version, binary) in ["1.15.0", "1.16.0"].into_iter().zip(binaries) {
+            let release = ClientRelease {
+                uri: format!(
+                    "https://github.com/interlay/interbtc-clients/releases/download/{}/vault-standalone-metadata",
+                    version
+                ),
+                checksum: H256::from_slice(&sha256sum(binary)),
+            };
+            let mut runner = MockRunner::default();
+            runner.expect_download_path().return_const(tmp.path().to_path_buf());
+            runner.expect_client_type().return_const(ClientType::Vault);
+            let (bin_name, bin_path) = Runner::get_bin_path(&runner, &release).unwrap();
+
+            let moved_bin_path = bin_path.clone();
+            runner
+                .expect_get_bin_path()
+                .returning(move |_| Ok((bin_name.clone(), moved_bin_path.clone())));
+            runner
+                .expect_get_request_bytes()
+                .returning(move |_| Ok(Bytes::from_static(binary)));
+            runner.expect_max_download_retries().return_const(5u32);
+            runner.expect_release_signing_key().return_const(None);
+            runner.expect_downloaded_release().return_const(None);
+            runner.expect_staged_release().return_const(None);
+            runner.expect_set_downloaded_release().return_const(());
+            runner.expect_disk_space_margin().return_const(0u64);
+            runner.expect_available_disk_space().returning(|_| Ok(u64::MAX));
+
+            let downloaded_release = Runner::download_binary(&mut runner, release).await.unwrap();
+            assert_eq!(downloaded_release.path, bin_path);
+            bin_paths.push(bin_path);
+        }
+
+        assert_eq!(bin_paths[0].file_name(), bin_paths[1].file_name());
+        assert_ne!(bin_paths[0], bin_paths[1]);
+        for (bin_path, binary) in bin_paths.iter().zip(binaries) {
+            assert_eq!(fs::read(bin_path).unwrap(), binary);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runner_get_bin_path_for_ipfs_release() {
+        let mock_path = PathBuf::from_str("./mock_download_dir").unwrap();
+        let mut runner = MockRunner::default();
+        runner.expect_download_path().return_const(mock_path.clone());
+        runner.expect_client_type().return_const(ClientType::Vault);
+
+        let release = |uri: &str| ClientRelease {
+            uri: uri.to_string(),
+            checksum: H256::default(),
+        };
+
+        let (bin_name, bin_path) = Runner::get_bin_path(
+            &runner,
+            &release("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"),
+        )
+        .unwrap();
+        assert_eq!(
+            bin_name,
+            "vault-bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+        );
+        assert_eq!(
+            bin_path,
+            mock_path.join(format!("{:x}", H256::default())).join(bin_name)
+        );
+
+        let (bin_name, _) = Runner::get_bin_path(
+            &runner,
+            &release("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi/vault-standalone-metadata"),
+        )
+        .unwrap();
+        assert_eq!(bin_name, "vault-standalone-metadata");
+
+        assert_err!(Runner::get_bin_path(&runner, &release("ipfs://")), Error::ClientNameDerivationError);
+    }
+
+    #[test]
+    fn test_resolve_release_uri() {
+        assert_eq!(
+            resolve_release_uri(
+                "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi/vault",
+                "https://ipfs.io/ipfs/"
+            ),
+            "https://ipfs.io/ipfs/bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi/vault"
+        );
+        assert_eq!(
+            resolve_release_uri(
+                "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+                "http://localhost:8080/ipfs"
+            ),
+            "http://localhost:8080/ipfs/bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+        );
+
+        let https_uri = "https://github.com/interlay/interbtc-clients/releases/download/1.17.2/vault-standalone-metadata";
+        assert_eq!(resolve_release_uri(https_uri, "https://ipfs.io/ipfs/"), https_uri);
+    }
+
+    #[tokio::test]
+    async fn test_runner_delete_downloaded_release() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        // Create dummy file in its release directory
+        let release_dir = tmp.path().join(format!("{:x}", H256::default()));
+        fs::create_dir(&release_dir).unwrap();
+        let mock_path = release_dir.join("mock_file");
+        File::create(mock_path.clone()).unwrap();
+
+        let mut runner = MockRunner::default();
+        let downloaded_release = DownloadedRelease {
+            checksum: H256::default(),
+            path: mock_path.clone(),
+            bin_name: String::default(),
+            uri: String::default(),
+        };
+        runner
+            .expect_downloaded_release()
+            .return_const(Some(downloaded_release));
+        runner.expect_set_downloaded_release().return_const(());
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+
+        // Leftover of an interrupted download
+        let partial_path = release_dir.join("mock_file.part");
+        File::create(partial_path.clone()).unwrap();
+
+        Runner::delete_downloaded_release(&mut runner).unwrap();
+        assert_eq!(mock_path.exists(), false);
+        assert_eq!(partial_path.exists(), false);
+        assert_eq!(release_dir.exists(), false);
+    }
+
+    #[tokio::test]
+    async fn test_runner_keeps_previous_release_for_rollback() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let release = |name: &str, content: &[u8]| {
+            let path = tmp.path().join(name);
+            fs::write(&path, content).unwrap();
+            DownloadedRelease {
+                checksum: H256::from_slice(&sha256sum(content)),
+                path,
+                bin_name: "vault".to_string(),
+                uri: format!("https://example.com/{}/vault", name),
+            }
+        };
+        let oldest_release = release("oldest", b"oldest");
+        let old_release = release("vault", b"old");
+
+        // Upgrade from the old release, which replaces the oldest one kept
+        let mut runner = MockRunner::default();
+        runner.expect_keep_releases().return_const(1usize);
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner
+            .expect_downloaded_release()
+            .return_const(Some(old_release.clone()));
+        runner
+            .expect_previous_releases()
+            .return_var(VecDeque::from([oldest_release.clone()]));
+        runner
+            .expect_set_downloaded_release()
+            .withf(|release| release.is_none())
+            .once()
+            .return_const(());
+
+        Runner::retire_downloaded_release(&mut runner).unwrap();
+        let previous_releases = runner.previous_releases().clone();
+        assert_eq!(previous_releases.len(), 1);
+        let previous_release = previous_releases[0].clone();
+        assert_eq!(previous_release.checksum, old_release.checksum);
+        assert_eq!(
+            previous_release.path.parent().unwrap(),
+            tmp.path().join(PREVIOUS_RELEASES_DIR)
+        );
+        assert_eq!(fs::read(&previous_release.path).unwrap(), b"old");
+        assert_eq!(old_release.path.exists(), false);
+        assert_eq!(oldest_release.path.exists(), false);
+        // still known after a restart
+        assert_eq!(read_previous_releases(tmp.path()), previous_releases);
+
+        // The new release is bad, so roll back to the old one
+        let new_release = release("vault", b"new");
+        let new_checksum = new_release.checksum;
+        let mut runner = MockRunner::default();
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_previous_releases().return_var(previous_releases);
+        runner.expect_downloaded_release().return_const(Some(new_release));
+        runner.expect_terminate_proc_and_wait().once().returning(|| Ok(()));
+        runner
+            .expect_set_rolled_back_release()
+            .withf(move |checksum| *checksum == Some(new_checksum))
+            .once()
+            .return_const(());
+        runner.expect_delete_downloaded_release().once().returning(|| Ok(()));
+        let moved_previous_release = previous_release.clone();
+        runner
+            .expect_set_downloaded_release()
+            .withf(move |release| release.as_ref() == Some(&moved_previous_release))
+            .once()
+            .return_const(());
+        runner.expect_run_binary().once().returning(|| Ok(()));
+
+        Runner::rollback(&mut runner).await.unwrap();
+        assert!(runner.previous_releases().is_empty());
+        assert_eq!(fs::read(&previous_release.path).unwrap(), b"old");
+        // the release that was rolled back to is run again after a restart
+        assert_eq!(
+            read_previous_releases(tmp.path()),
+            VecDeque::from([previous_release.clone()])
+        );
+        assert_err!(Runner::rollback(&mut runner).await, Error::NoPreviousRelease);
+    }
+
+    /// Reader that fails after yielding its contents, like a dropped connection.
+    struct InterruptedReader(&'static [u8]);
+
+    impl Read for InterruptedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection dropped"));
+            }
+            let len = buf.len().min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runner_interrupted_write_keeps_binary_intact() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let bin_path = tmp.path().join("vault-standalone-metadata");
+        fs::write(&bin_path, [1, 2, 3, 4]).unwrap();
+
+        assert_err!(
+            write_executable(&bin_path, InterruptedReader(&[5, 6])),
+            Error::IoError(_)
+        );
+        assert_eq!(fs::read(&bin_path).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(partial_path(&bin_path).exists(), false);
+
+        write_executable(&bin_path, &[5, 6, 7, 8][..]).unwrap();
+        assert_eq!(fs::read(&bin_path).unwrap(), vec![5, 6, 7, 8]);
+        assert_eq!(partial_path(&bin_path).exists(), false);
+    }
+
+    #[tokio::test]
+    async fn test_runner_terminate_proc_and_wait() {
+        // spawn long-running child process
+        let mut runner = MockRunner::default();
+        runner
+            .expect_child_proc()
+            .returning(|| Some(Command::new("sleep").arg("100").spawn().unwrap()));
+        runner.expect_set_child_proc().return_const(());
+        runner
+            .expect_shutdown_grace_period()
+            .return_const(Duration::from_secs(10));
+        let pid = Runner::terminate_proc_and_wait(&mut runner).await.unwrap();
+        let pid_i32: i32 = pid.try_into().unwrap();
+        let s = System::new_all();
+        // Get all running processes
+        let processes = s.processes();
+        // Get the child process based on its pid
+        let child_process = processes.get(&Pid::from(pid_i32));
+
+        assert_eq!(child_process.is_none(), true);
+    }
+
+    #[tokio::test]
+    async fn test_runner_terminate_proc_kills_after_grace_period() {
+        // spawn a child process that ignores `SIGTERM`
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg("trap '' TERM; exec sleep 100")
+            .spawn()
+            .unwrap();
+        // Wait for the trap to be installed
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let child = Mutex::new(Some(child));
+
+        let mut runner = MockRunner::default();
+        runner
+            .expect_child_proc()
+            .returning(move || child.lock().unwrap().take());
+        runner.expect_set_child_proc().return_const(());
+        runner
+            .expect_shutdown_grace_period()
+            .return_const(Duration::from_millis(500));
+
+        let start = Instant::now();
+        let pid = Runner::terminate_proc_and_wait(&mut runner).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        let pid_i32: i32 = pid.try_into().unwrap();
+        let s = System::new_all();
+        assert_eq!(s.processes().get(&Pid::from(pid_i32)).is_none(), true);
+    }
+
+    #[tokio::test]
+    async fn test_runner_run_binary_with_retry() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+
+        let mock_executable_path = tmp.path().join("print_cli_input");
+        {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .mode(0o700)
+                .create(true)
+                .open(mock_executable_path.clone())
+                .unwrap();
+
+            // Script that prints CLI input to stdout
+            file.write_all(b"#!/bin/bash\necho $@").unwrap();
+
+            file.sync_all().unwrap();
+            // drop `file` here to close it and avoid `ExecutableFileBusy` errors
+        }
+
+        let mut runner = MockRunner::default();
+        let mock_vault_args: Vec<String> = vec![
+            "--bitcoin-rpc-url",
+            "http://localhost:18443",
+            "--bitcoin-rpc-user",
+            "rpcuser",
+            "--bitcoin-rpc-pass",
+            "rpcpassword",
+            "--keyfile",
+            "keyfile.json",
+            "--keyname",
+            "0xa81f76187f1e5d2059f67439c4242a92a5cd66a409579db73f156c6e2aae5102",
+            "--faucet-url",
+            "http://localhost:3033",
+            "--auto-register=KSM=faucet",
+            "--btc-parachain-url",
+            "ws://localhost:9944",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let mock_downloaded_release = DownloadedRelease {
+            checksum: H256::from_slice(&sha256sum(b"#!/bin/bash\necho $@")),
+            path: mock_executable_path.clone(),
+            bin_name: String::default(),
+            uri: String::default(),
+        };
+        runner.expect_child_proc().return_var(None);
+        runner
+            .expect_downloaded_release()
+            .return_const(Some(mock_downloaded_release));
+        runner.expect_client_args().return_const(mock_vault_args.clone());
+        runner.expect_client_env().return_const(vec![]);
+        runner.expect_set_child_proc().return_const(());
+        let child = Runner::run_binary(&mut runner, Stdio::piped(), Stdio::inherit()).unwrap();
+
+        let output = child.wait_with_output().unwrap();
+
+        let mut expected_output = mock_vault_args.join(" ");
+        expected_output.push('\n');
+        assert_eq!(output.stdout, expected_output.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_runner_run_binary_sets_client_env() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let script = b"#!/bin/bash\necho \"$RUST_LOG $SECRET\"";
+        let mock_executable_path = tmp.path().join("print_env");
+        {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .mode(0o700)
+                .create(true)
+                .open(mock_executable_path.clone())
+                .unwrap();
+            file.write_all(script).unwrap();
+            file.sync_all().unwrap();
+        }
+        let secret_path = tmp.path().join("secret");
+        fs::write(&secret_path, "hunter2\n").unwrap();
+
+        let mut runner = MockRunner::default();
+        runner.expect_child_proc().return_var(None);
+        runner.expect_downloaded_release().return_const(Some(DownloadedRelease {
+            checksum: H256::from_slice(&sha256sum(script)),
+            path: mock_executable_path,
+            bin_name: String::default(),
+            uri: String::default(),
+        }));
+        runner.expect_client_args().return_const(vec![]);
+        runner.expect_client_env().return_const(vec![
+            ClientEnv::from_str("RUST_LOG=info,vault=debug").unwrap(),
+            ClientEnv::from_str(&format!("SECRET=@{}", secret_path.display())).unwrap(),
+        ]);
+
+        let child = Runner::run_binary(&mut runner, Stdio::piped(), Stdio::inherit()).unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(output.stdout, b"info,vault=debug hunter2\n");
+    }
+
+    #[test]
+    fn test_client_env_parsing() {
+        assert_eq!(
+            ClientEnv::from_str("RUST_LOG=a=b").unwrap(),
+            ClientEnv {
+                key: "RUST_LOG".to_string(),
+                value: ClientEnvValue::Literal("a=b".to_string()),
+            }
+        );
+        assert_eq!(
+            ClientEnv::from_str("EMPTY=").unwrap().value,
+            ClientEnvValue::Literal(String::new())
+        );
+        assert_eq!(
+            ClientEnv::from_str("SECRET=@/run/secrets/key").unwrap().value,
+            ClientEnvValue::File(PathBuf::from("/run/secrets/key"))
+        );
+        for malformed in ["RUST_LOG", "=info", "SECRET=@"] {
+            assert_err!(ClientEnv::from_str(malformed), Error::InvalidClientEnv(_));
+        }
+        assert_err!(
+            ClientEnv::from_str("SECRET=@/nonexistent/secret").unwrap().resolve(),
+            Error::InvalidClientEnv(_)
+        );
+    }
+
+    fn write_version_script(path: &Path, version: &str) {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .mode(0o700)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(format!("#!/bin/sh\necho vault {}", version).as_bytes())
+            .unwrap();
+        file.sync_all().unwrap();
+    }
+
+    fn mock_runner_with_versions(tmp: &TempDir, current: &str, new: &str) -> (MockRunner, ClientRelease) {
+        let current_path = tmp.path().join("vault-current");
+        let new_path = tmp.path().join("vault-new");
+        write_version_script(&current_path, current);
+        write_version_script(&new_path, new);
+        let new_release = ClientRelease {
+            uri: "https://example.com/vault-new".to_string(),
+            checksum: H256::from_low_u64_be(2),
+        };
+
+        let mut runner = MockRunner::default();
+        runner.expect_allow_downgrade().return_const(false);
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_downloaded_release().return_const(Some(DownloadedRelease {
+            checksum: H256::from_low_u64_be(1),
+            path: current_path,
+            bin_name: "vault-current".to_string(),
+            uri: "https://example.com/vault-current".to_string(),
+        }));
+        // The new release was already prefetched
+        runner.expect_staged_release().return_const(Some(DownloadedRelease {
+            checksum: new_release.checksum,
+            path: new_path,
+            bin_name: "vault-new".to_string(),
+            uri: new_release.uri.clone(),
+        }));
+        (runner, new_release)
+    }
+
+    #[tokio::test]
+    async fn test_runner_blocks_downgrade() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let (mut runner, new_release) = mock_runner_with_versions(&tmp, "1.21.0", "1.20.3");
+
+        match Runner::check_downgrade(&mut runner, &new_release).await {
+            Err(Error::DowngradeBlocked { current, new }) => {
+                assert_eq!(current, Version::new(1, 21, 0));
+                assert_eq!(new, Version::new(1, 20, 3));
+            }
+            result => panic!("expected a blocked downgrade, got {:?}", result),
+        }
     }
 
     #[tokio::test]
-    async fn test_runner_get_bin_path() {
-        let clients = [ClientType::Vault, ClientType::Oracle, ClientType::Faucet];
-        let mock_path = PathBuf::from_str("./mock_download_dir").unwrap();
-        for client in clients {
-            let mut runner = MockRunner::default();
-            runner.expect_download_path().return_const(mock_path.clone());
-            runner.expect_client_type().return_const(client.clone());
-            let (bin_name, bin_path) = Runner::get_bin_path(
-                &runner,
-                "https://github.com/interlay/interbtc-clients/releases/download/1.17.2/vault-standalone-metadata",
-            )
-            .unwrap();
-            assert_eq!(bin_name, "vault-standalone-metadata");
-            assert_eq!(bin_path, mock_path.join(bin_name));
+    async fn test_runner_blocks_downgrade_below_last_run_version() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let (mut runner, new_release) = mock_runner_with_versions(&tmp, "1.21.0", "1.21.5");
+        // A newer client was run before the runner restarted
+        fs::write(tmp.path().join(LAST_RUN_VERSION_FILE), "1.22.0").unwrap();
+
+        match Runner::check_downgrade(&mut runner, &new_release).await {
+            Err(Error::DowngradeBlocked { current, new }) => {
+                assert_eq!(current, Version::new(1, 22, 0));
+                assert_eq!(new, Version::new(1, 21, 5));
+            }
+            result => panic!("expected a blocked downgrade, got {:?}", result),
         }
     }
 
     #[tokio::test]
-    async fn test_runner_delete_downloaded_release() {
+    async fn test_runner_keeps_last_run_release_on_blocked_downgrade_at_startup() {
         let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
-        // Create dummy file
-        let mock_path = tmp.path().join("mock_file");
-        File::create(mock_path.clone()).unwrap();
-
-        let mut runner = MockRunner::default();
-        let downloaded_release = DownloadedRelease {
-            checksum: H256::default(),
-            path: mock_path.clone(),
-            bin_name: String::default(),
+        let last_run_path = tmp.path().join("vault-current");
+        write_version_script(&last_run_path, "1.22.0");
+        let last_run_release = DownloadedRelease {
+            checksum: H256::from_low_u64_be(1),
+            path: last_run_path,
+            bin_name: "vault-current".to_string(),
+            uri: "https://example.com/vault-current".to_string(),
         };
+        fs::write(
+            tmp.path().join(LAST_RUN_RELEASE_FILE),
+            serde_json::to_vec(&last_run_release).unwrap(),
+        )
+        .unwrap();
+        let mut runner = MockRunner::default();
+
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
         runner
-            .expect_downloaded_release()
-            .return_const(Some(downloaded_release));
-        runner.expect_set_downloaded_release().return_const(());
+            .expect_try_get_release()
+            .once()
+            .returning(|_| Ok(Some(ClientRelease::default())));
+        runner.expect_rolled_back_release().return_const(None);
+        runner
+            .expect_try_load_downloaded_binary()
+            .returning(|_| Err(Error::NoDownloadedRelease));
+        runner.expect_check_downgrade().once().returning(|_| {
+            Err(Error::DowngradeBlocked {
+                current: Version::new(1, 22, 0),
+                new: Version::new(1, 21, 5),
+            })
+        });
+        runner.expect_download_binary().never();
+        runner
+            .expect_set_downloaded_release()
+            .withf(move |release| release.as_ref() == Some(&last_run_release))
+            .once()
+            .return_const(());
 
-        Runner::delete_downloaded_release(&mut runner).unwrap();
-        assert_eq!(mock_path.exists(), false);
+        // return arbitrary error to terminate the `auto_update` function
+        runner
+            .expect_run_binary()
+            .once()
+            .returning(|| Err(Error::ProcessTerminationFailure));
+
+        assert_err!(Runner::auto_update(&mut runner).await, Error::ProcessTerminationFailure);
     }
 
     #[tokio::test]
-    async fn test_runner_terminate_proc_and_wait() {
-        // spawn long-running child process
+    async fn test_runner_allows_upgrade() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let (mut runner, new_release) = mock_runner_with_versions(&tmp, "1.21.0", "1.22.0");
+
+        Runner::check_downgrade(&mut runner, &new_release).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_runner_allows_downgrade_if_enabled() {
         let mut runner = MockRunner::default();
-        runner
-            .expect_child_proc()
-            .returning(|| Some(Command::new("sleep").arg("100").spawn().unwrap()));
-        runner.expect_set_child_proc().return_const(());
-        let pid = Runner::terminate_proc_and_wait(&mut runner).unwrap();
-        let pid_i32: i32 = pid.try_into().unwrap();
-        let s = System::new_all();
-        // Get all running processes
-        let processes = s.processes();
-        // Get the child process based on its pid
-        let child_process = processes.get(&Pid::from(pid_i32));
+        runner.expect_allow_downgrade().return_const(true);
+        runner.expect_downloaded_release().never();
 
-        assert_eq!(child_process.is_none(), true);
+        Runner::check_downgrade(&mut runner, &ClientRelease::default())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_client_version() {
+        assert_eq!(parse_client_version("vault 1.21.0\n").unwrap(), Version::new(1, 21, 0));
+        assert_eq!(parse_client_version("oracle v1.2.3").unwrap(), Version::new(1, 2, 3));
+        assert_err!(parse_client_version(""), Error::ClientVersionError(_));
+        assert_err!(parse_client_version("vault unknown"), Error::ClientVersionError(_));
     }
 
     #[tokio::test]
-    async fn test_runner_run_binary_with_retry() {
+    async fn test_runner_run_binary_checks_checksum() {
         let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
 
-        let mock_executable_path = tmp.path().join("print_cli_input");
+        let mock_executable_path = tmp.path().join("client");
         {
             let mut file = OpenOptions::new()
                 .read(true)
@@ -818,59 +3172,33 @@ mod tests {
                 .create(true)
                 .open(mock_executable_path.clone())
                 .unwrap();
-
-            // Script that prints CLI input to stdout
-            file.write_all(b"#!/bin/bash\necho $@").unwrap();
-
+            file.write_all(b"#!/bin/bash\necho tampered").unwrap();
             file.sync_all().unwrap();
-            // drop `file` here to close it and avoid `ExecutableFileBusy` errors
         }
 
         let mut runner = MockRunner::default();
-        let mock_vault_args: Vec<String> = vec![
-            "--bitcoin-rpc-url",
-            "http://localhost:18443",
-            "--bitcoin-rpc-user",
-            "rpcuser",
-            "--bitcoin-rpc-pass",
-            "rpcpassword",
-            "--keyfile",
-            "keyfile.json",
-            "--keyname",
-            "0xa81f76187f1e5d2059f67439c4242a92a5cd66a409579db73f156c6e2aae5102",
-            "--faucet-url",
-            "http://localhost:3033",
-            "--auto-register=KSM=faucet",
-            "--btc-parachain-url",
-            "ws://localhost:9944",
-        ]
-        .iter()
-        .map(|s| s.to_string())
-        .collect();
-
         let mock_downloaded_release = DownloadedRelease {
-            checksum: H256::default(),
-            path: mock_executable_path.clone(),
+            checksum: H256::from_slice(&sha256sum(b"#!/bin/bash\necho $@")),
+            path: mock_executable_path,
             bin_name: String::default(),
+            uri: String::default(),
         };
         runner.expect_child_proc().return_var(None);
         runner
             .expect_downloaded_release()
             .return_const(Some(mock_downloaded_release));
-        runner.expect_client_args().return_const(mock_vault_args.clone());
-        runner.expect_set_child_proc().return_const(());
-        let child = Runner::run_binary(&mut runner, Stdio::piped()).unwrap();
-
-        let output = child.wait_with_output().unwrap();
+        runner.expect_client_args().return_const(vec![]);
 
-        let mut expected_output = mock_vault_args.join(" ");
-        expected_output.push('\n');
-        assert_eq!(output.stdout, expected_output.as_bytes());
+        assert_err!(
+            Runner::run_binary(&mut runner, Stdio::piped(), Stdio::inherit()),
+            Error::HashMismatch { .. }
+        );
     }
 
     #[tokio::test]
     async fn test_runner_terminate_child_proc_on_signal() {
         let mut runner = MockRunner::default();
+        runner.expect_client_type().return_const(ClientType::Vault);
         runner.expect_terminate_proc_and_wait().once().returning(|| Ok(()));
         runner.expect_auto_update().returning(|| {
             Box::pin(async {
@@ -879,7 +3207,11 @@ mod tests {
             })
         });
         let shutdown_signals = Signals::new(&[SIGHUP, SIGTERM, SIGINT, SIGQUIT]).unwrap();
-        let task = tokio::spawn(Runner::run(Box::new(runner), shutdown_signals));
+        let task = tokio::spawn(Runner::run(
+            vec![Box::new(runner)],
+            FailurePolicy::Isolate,
+            shutdown_signals,
+        ));
         // Wait for the signals iterator to be polled
         // This `sleep` is based on the test case in `signal-hook-tokio` itself:
         // https://github.com/vorner/signal-hook/blob/a9e5ca5e46c9c8e6de89ff1b3ce63c5ff89cd708/signal-hook-tokio/tests/tests.rs#L50
@@ -891,6 +3223,7 @@ mod tests {
     #[tokio::test]
     async fn test_runner_terminate_child_proc_on_crash() {
         let mut runner = MockRunner::default();
+        runner.expect_client_type().return_const(ClientType::Vault);
         // Assume the auto-updater crashes
         runner.expect_auto_update().returning(|| {
             // return an arbitrary error
@@ -900,25 +3233,294 @@ mod tests {
         runner.expect_terminate_proc_and_wait().once().returning(|| Ok(()));
 
         let shutdown_signals = Signals::new(&[]).unwrap();
-        let task = tokio::spawn(Runner::run(Box::new(runner), shutdown_signals));
+        let task = tokio::spawn(Runner::run(
+            vec![Box::new(runner)],
+            FailurePolicy::Isolate,
+            shutdown_signals,
+        ));
         task.await.unwrap().unwrap();
     }
 
+    fn mock_crashing_client() -> MockRunner {
+        let mut runner = MockRunner::default();
+        runner.expect_client_type().return_const(ClientType::Oracle);
+        runner
+            .expect_auto_update()
+            .returning(|| Box::pin(async { Err(Error::ProcessTerminationFailure) }));
+        runner
+    }
+
+    #[tokio::test]
+    async fn test_runner_isolates_crashed_client() {
+        let mut crashing = mock_crashing_client();
+        crashing.expect_terminate_proc_and_wait().once().returning(|| Ok(()));
+        let mut healthy = MockRunner::default();
+        healthy.expect_client_type().return_const(ClientType::Vault);
+        healthy.expect_auto_update().returning(|| {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(())
+            })
+        });
+        // Each client is terminated only when its own auto-updater stops
+        let healthy_terminated = Arc::new(Mutex::new(None));
+        let terminated = healthy_terminated.clone();
+        let started = Instant::now();
+        healthy.expect_terminate_proc_and_wait().once().returning(move || {
+            *terminated.lock().unwrap() = Some(started.elapsed());
+            Ok(())
+        });
+
+        let shutdown_signals = Signals::new(&[]).unwrap();
+        Runner::run(
+            vec![Box::new(crashing), Box::new(healthy)],
+            FailurePolicy::Isolate,
+            shutdown_signals,
+        )
+        .await
+        .unwrap();
+
+        // The healthy client kept running after the other one crashed
+        assert!(healthy_terminated.lock().unwrap().unwrap() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_runner_restarts_all_clients_on_crash() {
+        let mut crashing = mock_crashing_client();
+        crashing.expect_terminate_proc_and_wait().times(2).returning(|| Ok(()));
+        let mut healthy = MockRunner::default();
+        healthy.expect_client_type().return_const(ClientType::Vault);
+        let healthy_starts = Arc::new(Mutex::new(0));
+        let starts = healthy_starts.clone();
+        healthy.expect_auto_update().returning(move || {
+            *starts.lock().unwrap() += 1;
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(100_000)).await;
+                Ok(())
+            })
+        });
+        healthy.expect_terminate_proc_and_wait().returning(|| Ok(()));
+
+        let shutdown_signals = Signals::new(&[]).unwrap();
+        // Stop after the clients were restarted once
+        let _ = tokio::time::timeout(
+            RESTART_BACKOFF_BASE + Duration::from_millis(500),
+            Runner::run(
+                vec![Box::new(crashing), Box::new(healthy)],
+                FailurePolicy::RestartAll,
+                shutdown_signals,
+            ),
+        )
+        .await;
+
+        // The healthy client was stopped and started again along with the crashed one
+        assert_eq!(*healthy_starts.lock().unwrap(), 2);
+    }
+
     #[tokio::test]
     async fn test_runner_child_restarts_if_crashed() {
         let mut runner = MockRunner::default();
         runner.expect_check_child_proc_alive().returning(|| Ok(false));
+        runner.expect_last_exit_status().return_const(None);
+        runner.expect_record_restart().returning(|| {
+            Ok(Restart {
+                attempt: 1,
+                delay: Duration::ZERO,
+            })
+        });
 
         // The test passes as long as `run_binary` is called
         runner.expect_run_binary().once().returning(|| Ok(()));
-        Runner::maybe_restart_client(&mut runner).unwrap();
+        Runner::maybe_restart_client(&mut runner).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_runner_crash_looping_child_restarts_with_backoff() {
+        let mut runner = MockRunner::default();
+        // The child exits immediately after every start
+        runner.expect_check_child_proc_alive().returning(|| Ok(false));
+        runner.expect_last_exit_status().return_const(None);
+        runner.expect_record_restart().once().returning(|| {
+            Ok(Restart {
+                attempt: 6,
+                delay: Duration::from_millis(200),
+            })
+        });
+        runner.expect_run_binary().once().returning(|| Ok(()));
+
+        let start = Instant::now();
+        Runner::maybe_restart_client(&mut runner).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_runner_crash_loop_gives_up() {
+        let mut runner = MockRunner::default();
+        runner.expect_check_child_proc_alive().returning(|| Ok(false));
+        runner.expect_last_exit_status().return_const(None);
+        runner.expect_record_restart().returning(|| {
+            Err(Error::TooManyRestarts {
+                restarts: 10,
+                window: Duration::from_secs(60),
+            })
+        });
+        runner.expect_run_binary().never();
+
+        assert_err!(
+            Runner::maybe_restart_client(&mut runner).await,
+            Error::TooManyRestarts { .. }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runner_failing_child_backs_off_then_gives_up() {
+        let mut runner = MockRunner::default();
+        let mut child = Command::new("sh").arg("-c").arg("exit 1").spawn().unwrap();
+        let exit_status = child.wait().unwrap();
+        // The client exits with code 1 after every start
+        runner.expect_check_child_proc_alive().returning(|| Ok(false));
+        runner.expect_last_exit_status().return_const(Some(exit_status));
+        let mut tracker = RestartTracker::new(1, 4, Duration::from_secs(2), Duration::from_secs(60));
+        let now = Instant::now();
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let recorded_delays = delays.clone();
+        runner.expect_record_restart().returning(move || {
+            let restart = tracker.record_restart(now)?;
+            recorded_delays.lock().unwrap().push(restart.delay);
+            // don't actually wait in the test
+            Ok(Restart {
+                delay: Duration::ZERO,
+                ..restart
+            })
+        });
+        runner.expect_run_binary().times(4).returning(|| Ok(()));
+
+        for _ in 0..4 {
+            Runner::maybe_restart_client(&mut runner).await.unwrap();
+        }
+        assert_err!(
+            Runner::maybe_restart_client(&mut runner).await,
+            Error::TooManyRestarts { restarts: 5, .. }
+        );
+        // The delay doubles after the first restart, up to the maximum delay
+        assert_eq!(
+            *delays.lock().unwrap(),
+            vec![
+                Duration::ZERO,
+                RESTART_BACKOFF_BASE,
+                RESTART_BACKOFF_BASE * 2,
+                Duration::from_secs(2)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runner_does_not_restart_cleanly_exited_child() {
+        let mut runner = MockRunner::default();
+        let mut child = Command::new("sh").arg("-c").arg("exit 0").spawn().unwrap();
+        let exit_status = child.wait().unwrap();
+        runner.expect_check_child_proc_alive().returning(|| Ok(false));
+        runner.expect_last_exit_status().return_const(Some(exit_status));
+        runner.expect_record_restart().never();
+        runner.expect_run_binary().never();
+
+        Runner::maybe_restart_client(&mut runner).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_runner_reconnects_on_connection_loss() {
+        let mut runner = MockRunner::default();
+        let disconnected = Arc::new(Mutex::new(true));
+        let (disconnected_getter, disconnected_setter) = (disconnected.clone(), disconnected.clone());
+
+        runner
+            .expect_read_chain_storage::<ClientRelease>()
+            .times(2)
+            .returning(move |_| {
+                if *disconnected_getter.lock().unwrap() {
+                    // The RPC node went away
+                    Err(Error::SubxtError(SubxtError::Rpc(RpcError::SubscriptionDropped)))
+                } else {
+                    Ok(Some(ClientRelease::default()))
+                }
+            });
+        runner.expect_reconnect().once().returning(move || {
+            *disconnected_setter.lock().unwrap() = false;
+            Ok(())
+        });
+
+        let release = Runner::try_get_release(&mut runner, ReleaseKind::Current)
+            .await
+            .unwrap();
+        assert_eq!(release, Some(ClientRelease::default()));
+    }
+
+    #[test]
+    fn test_poll_interval_jitter_is_bounded() {
+        let interval = Duration::from_secs(6);
+        for _ in 0..1000 {
+            let delay = with_jitter(interval);
+            assert!(delay >= interval);
+            assert!(delay <= interval.mul_f64(1.0 + POLL_JITTER));
+        }
+    }
+
+    #[test]
+    fn test_poll_interval_cli_flag() {
+        let opts = Opts::try_parse_from(["runner", "--parachain-ws", "ws://localhost:9944"]).unwrap();
+        assert_eq!(opts.poll_interval_secs, 6);
+
+        let opts = Opts::try_parse_from([
+            "runner",
+            "--parachain-ws",
+            "ws://localhost:9944",
+            "--poll-interval-secs",
+            "12",
+        ])
+        .unwrap();
+        assert_eq!(opts.poll_interval_secs, 12);
+
+        assert!(Opts::try_parse_from([
+            "runner",
+            "--parachain-ws",
+            "ws://localhost:9944",
+            "--poll-interval-secs",
+            "0",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_restart_tracker_backs_off() {
+        let window = Duration::from_secs(60);
+        let mut tracker = RestartTracker::new(2, 8, Duration::from_secs(60), window);
+        let now = Instant::now();
+
+        assert_eq!(tracker.record_restart(now).unwrap().delay, Duration::ZERO);
+        assert_eq!(tracker.record_restart(now).unwrap().delay, Duration::ZERO);
+        for step in 0..6 {
+            let restart = tracker.record_restart(now).unwrap();
+            assert_eq!(restart.attempt, step + 3);
+            assert_eq!(restart.delay, RESTART_BACKOFF_BASE * 2u32.pow(step));
+        }
+        assert_err!(tracker.record_restart(now), Error::TooManyRestarts { .. });
+
+        // Restarts outside of the window are forgotten
+        assert_eq!(
+            tracker.record_restart(now + window * 2).unwrap(),
+            Restart {
+                attempt: 1,
+                delay: Duration::ZERO
+            }
+        );
     }
 
     #[tokio::test]
     async fn test_runner_terminate_child_process_does_not_throw() {
         let mut runner = MockRunner::default();
         runner.expect_child_proc().return_var(None);
-        assert_eq!(Runner::terminate_proc_and_wait(&mut runner).unwrap(), 0);
+        runner.expect_shutdown_grace_period().return_const(Duration::from_secs(10));
+        assert_eq!(Runner::terminate_proc_and_wait(&mut runner).await.unwrap(), 0);
     }
 
     #[tokio::test]
@@ -933,7 +3535,7 @@ mod tests {
         });
 
         assert_err!(
-            Runner::try_load_downloaded_binary(&mut runner, &Default::default()),
+            Runner::try_load_downloaded_binary(&mut runner, &Default::default()).await,
             Error::NoDownloadedRelease
         );
     }
@@ -974,7 +3576,50 @@ mod tests {
             ]),
             ..Default::default()
         };
-        Runner::try_load_downloaded_binary(&mut runner, &release).unwrap();
+        runner.expect_release_signing_key().return_const(None);
+        Runner::try_load_downloaded_binary(&mut runner, &release).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_try_load_downloaded_binary_checks_signature() {
+        let signing_pair = sr25519::Pair::from_seed(&[1; 32]);
+        let binary = ELF_MAGIC;
+        let valid_signature = signing_pair.sign(&binary);
+        let tampered_signature = signing_pair.sign(&[5, 6, 7, 8]);
+
+        for (signature, is_valid) in [(valid_signature, true), (tampered_signature, false)] {
+            let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+            let mock_path = tmp.path().join("client");
+            fs::write(&mock_path, binary).unwrap();
+            let mut runner = MockRunner::default();
+
+            runner
+                .expect_get_bin_path()
+                .returning(move |_| Ok(("client".to_string(), mock_path.clone())));
+            runner
+                .expect_get_request_bytes()
+                .withf(|url| url.ends_with(SIGNATURE_URI_SUFFIX))
+                .returning(move |_| Ok(Bytes::copy_from_slice(signature.as_ref())));
+            runner.expect_max_download_retries().return_const(5u32);
+            runner
+                .expect_release_signing_key()
+                .return_const(Some(signing_pair.public()));
+            runner
+                .expect_set_downloaded_release()
+                .times(is_valid as usize)
+                .return_const(());
+
+            let release = ClientRelease {
+                uri: "https://github.com/interlay/interbtc-clients/releases/download/1.15.0/client".to_string(),
+                checksum: H256::from_slice(&sha256sum(&binary)),
+            };
+            let result = Runner::try_load_downloaded_binary(&mut runner, &release).await;
+            if is_valid {
+                assert!(result.is_ok());
+            } else {
+                assert_err!(result, Error::InvalidSignature);
+            }
+        }
     }
 
     #[tokio::test]
@@ -1000,8 +3645,8 @@ mod tests {
             .returning(move |_| Ok(("client".to_string(), moved_mock_path.clone())));
 
         assert_err!(
-            Runner::try_load_downloaded_binary(&mut runner, &Default::default()),
-            Error::IncorrectChecksum
+            Runner::try_load_downloaded_binary(&mut runner, &Default::default()).await,
+            Error::HashMismatch { .. }
         );
     }
 
@@ -1012,14 +3657,19 @@ mod tests {
         let mut runner = MockRunner::default();
 
         runner.expect_download_path().return_const(mock_path.clone());
-        runner
-            .expect_try_load_downloaded_binary()
-            .returning(|_| Err(Error::IncorrectChecksum));
+        runner.expect_try_load_downloaded_binary().returning(|_| {
+            Err(Error::HashMismatch {
+                expected: H256::from_low_u64_be(1),
+                actual: H256::from_low_u64_be(2),
+            })
+        });
 
         runner
             .expect_try_get_release()
             .once()
-            .returning(|| Ok(Some(ClientRelease::default())));
+            .returning(|_| Ok(Some(ClientRelease::default())));
+        runner.expect_rolled_back_release().return_const(None);
+        runner.expect_check_downgrade().once().returning(|_| Ok(()));
         runner.expect_download_binary().once().returning(|release| {
             assert_eq!(release, ClientRelease::default());
             Ok(())
@@ -1048,7 +3698,9 @@ mod tests {
         runner
             .expect_try_get_release()
             .once()
-            .returning(|| Ok(Some(ClientRelease::default())));
+            .returning(|_| Ok(Some(ClientRelease::default())));
+        runner.expect_rolled_back_release().return_const(None);
+        runner.expect_check_downgrade().once().returning(|_| Ok(()));
         runner.expect_download_binary().once().returning(|release| {
             assert_eq!(release, ClientRelease::default());
             Ok(())
@@ -1063,6 +3715,46 @@ mod tests {
         assert_err!(Runner::auto_update(&mut runner).await, Error::ProcessTerminationFailure);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_runner_restarts_client_while_disconnected() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let mut runner = MockRunner::default();
+        let fetches = Arc::new(Mutex::new(0));
+        let restarts = Arc::new(Mutex::new(0));
+        let moved_restarts = restarts.clone();
+
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_try_load_downloaded_binary().returning(|_| Ok(()));
+        runner.expect_rolled_back_release().return_const(None);
+        runner.expect_run_binary().once().returning(|| Ok(()));
+        runner.expect_take_rollback_request().return_const(false);
+        runner.expect_poll_interval().return_const(Duration::from_secs(6));
+        runner.expect_try_get_release().returning(move |_| {
+            let mut fetches = fetches.lock().unwrap();
+            *fetches += 1;
+            if *fetches == 1 {
+                Ok(Some(ClientRelease::default()))
+            } else {
+                // reconnecting to the parachain gave up
+                Err(Error::SubxtError(SubxtError::Rpc(RpcError::SubscriptionDropped)))
+            }
+        });
+        runner.expect_download_binary().never();
+        runner.expect_maybe_restart_client().returning(move || {
+            let mut restarts = moved_restarts.lock().unwrap();
+            *restarts += 1;
+            if *restarts < 3 {
+                Ok(())
+            } else {
+                // return arbitrary error to terminate the `auto_update` function
+                Err(Error::ProcessTerminationFailure)
+            }
+        });
+
+        assert_err!(Runner::auto_update(&mut runner).await, Error::ProcessTerminationFailure);
+        assert_eq!(*restarts.lock().unwrap(), 3);
+    }
+
     #[tokio::test]
     async fn test_runner_different_checksum_prompts_download() {
         let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
@@ -1074,18 +3766,22 @@ mod tests {
         runner.expect_run_binary().once().returning(|| Ok(()));
         runner.expect_maybe_restart_client().once().returning(|| Ok(()));
 
-        runner.expect_try_get_release().times(2).returning(|| {
+        runner.expect_try_get_release().times(2).returning(|_| {
             Ok(Some(ClientRelease {
                 uri: Default::default(),
                 checksum: H256::from_low_u64_be(10),
             }))
         });
+        runner.expect_take_rollback_request().return_const(false);
+        runner.expect_rolled_back_release().return_const(None);
+        runner.expect_check_downgrade().once().returning(|_| Ok(()));
         runner
             .expect_downloaded_release()
             .once()
             .return_const(Some(DownloadedRelease {
                 path: Default::default(),
                 bin_name: Default::default(),
+                uri: Default::default(),
                 checksum: H256::from_low_u64_be(11),
             }));
 
@@ -1096,4 +3792,48 @@ mod tests {
 
         assert_err!(Runner::auto_update(&mut runner).await, Error::ProcessTerminationFailure);
     }
+
+    #[tokio::test]
+    async fn test_runner_keeps_running_rolled_back_to_release_after_restart() {
+        let tmp = TempDir::new("runner-tests").expect("failed to create tempdir");
+        let rolled_back_checksum = H256::from_low_u64_be(10);
+        let previous_release = DownloadedRelease {
+            checksum: H256::from_low_u64_be(9),
+            path: tmp.path().join(PREVIOUS_RELEASES_DIR).join("vault"),
+            bin_name: "vault".to_string(),
+            uri: "https://example.com/9/vault".to_string(),
+        };
+        let mut runner = MockRunner::default();
+
+        runner.expect_download_path().return_const(tmp.path().to_path_buf());
+        runner.expect_try_get_release().once().returning(move |_| {
+            Ok(Some(ClientRelease {
+                uri: Default::default(),
+                checksum: rolled_back_checksum,
+            }))
+        });
+        runner
+            .expect_rolled_back_release()
+            .return_const(Some(rolled_back_checksum));
+        runner
+            .expect_previous_releases()
+            .return_var(VecDeque::from([previous_release.clone()]));
+        // not downloaded again
+        runner.expect_try_load_downloaded_binary().never();
+        runner.expect_download_binary().never();
+        runner
+            .expect_set_downloaded_release()
+            .withf(move |release| release.as_ref() == Some(&previous_release))
+            .once()
+            .return_const(());
+
+        // return arbitrary error to terminate the `auto_update` function
+        runner
+            .expect_run_binary()
+            .once()
+            .returning(|| Err(Error::ProcessTerminationFailure));
+
+        assert_err!(Runner::auto_update(&mut runner).await, Error::ProcessTerminationFailure);
+        assert!(runner.previous_releases().is_empty());
+    }
 }
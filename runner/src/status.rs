@@ -0,0 +1,179 @@
+use crate::runner::DownloadedRelease;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+use warp::{Filter, Rejection, Reply};
+
+/// Runner state that is reported by the status endpoint. Updated by the `Runner` whenever
+/// the client process or one of the releases changes.
+#[derive(Debug, Default)]
+pub struct RunnerStatus {
+    pub downloaded_release: Option<DownloadedRelease>,
+    pub child_pid: Option<u32>,
+    pub child_started_at: Option<Instant>,
+    pub staged_release: Option<DownloadedRelease>,
+}
+
+pub type SharedStatus = Arc<RwLock<RunnerStatus>>;
+
+/// Statuses of all supervised clients, by client type.
+pub type ClientStatuses = BTreeMap<String, SharedStatus>;
+
+/// JSON body served at `/status/<client>`, and at `/status` if a single client is run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct StatusResponse {
+    /// URI of the release the client was started from
+    pub uri: Option<String>,
+    /// SHA256 checksum of the running client executable
+    pub checksum: Option<H256>,
+    /// PID of the client process, if it is running
+    pub pid: Option<u32>,
+    /// Seconds since the client process was started
+    pub uptime_secs: Option<u64>,
+    /// Whether the pending release has already been downloaded
+    pub pending_release_prefetched: bool,
+}
+
+impl From<&RunnerStatus> for StatusResponse {
+    fn from(status: &RunnerStatus) -> Self {
+        Self {
+            uri: status.downloaded_release.as_ref().map(|release| release.uri.clone()),
+            checksum: status.downloaded_release.as_ref().map(|release| release.checksum),
+            pid: status.child_pid,
+            uptime_secs: status.child_started_at.map(|started_at| started_at.elapsed().as_secs()),
+            pending_release_prefetched: status.staged_release.is_some(),
+        }
+    }
+}
+
+fn read_status(status: &SharedStatus) -> StatusResponse {
+    // the status is only ever overwritten as a whole, so it is still consistent if a writer panicked
+    let status = status.read().unwrap_or_else(|err| err.into_inner());
+    StatusResponse::from(&*status)
+}
+
+/// Serves the status of each client at `/status/<client>`. At `/status`, the status of the client is
+/// served if only one is run, otherwise the statuses of all clients keyed by client type.
+pub fn status_route(statuses: ClientStatuses) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let statuses = Arc::new(statuses);
+    let all_statuses = statuses.clone();
+    let all = warp::path!("status").and(warp::get()).map(move || {
+        match all_statuses.values().collect::<Vec<_>>().as_slice() {
+            [status] => warp::reply::json(&read_status(status)),
+            _ => warp::reply::json(
+                &all_statuses
+                    .iter()
+                    .map(|(client, status)| (client, read_status(status)))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+        }
+    });
+    let client = warp::path!("status" / String)
+        .and(warp::get())
+        .and_then(move |client: String| {
+            let status = statuses.get(&client).map(read_status);
+            async move {
+                status
+                    .map(|status| warp::reply::json(&status))
+                    .ok_or_else(warp::reject::not_found)
+            }
+        });
+    all.or(client)
+}
+
+/// Serve the status of the clients on `http://localhost:{port}/status`.
+pub async fn serve_status(statuses: ClientStatuses, port: u16) {
+    log::info!("Starting status server at http://{}:{}/status", Ipv4Addr::LOCALHOST, port);
+    warp::serve(status_route(statuses))
+        .run(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn serve(statuses: ClientStatuses) -> SocketAddr {
+        let (addr, server) =
+            warp::serve(status_route(statuses)).bind_ephemeral(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0));
+        tokio::spawn(server);
+        addr
+    }
+
+    async fn get_json(url: &str) -> serde_json::Value {
+        let body = reqwest::get(url).await.unwrap().text().await.unwrap();
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_status_server_reports_runner_state() {
+        let status = SharedStatus::default();
+        let addr = serve(BTreeMap::from([("vault".to_string(), status.clone())]));
+
+        let url = format!("http://{}/status", addr);
+        // the status of a single client is also served under its name
+        assert_eq!(get_json(&url).await, get_json(&format!("{}/vault", url)).await);
+        let body = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "uri": null,
+                "checksum": null,
+                "pid": null,
+                "uptime_secs": null,
+                "pending_release_prefetched": false,
+            })
+        );
+
+        {
+            let mut status = status.write().unwrap();
+            status.downloaded_release = Some(DownloadedRelease {
+                checksum: H256::from_low_u64_be(1),
+                path: PathBuf::from("vault"),
+                bin_name: "vault".to_string(),
+                uri: "https://example.com/vault".to_string(),
+            });
+            status.child_pid = Some(42);
+            status.child_started_at = Some(Instant::now());
+            status.staged_release = status.downloaded_release.clone();
+        }
+
+        let body = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<StatusResponse>(&body).unwrap(),
+            StatusResponse {
+                uri: Some("https://example.com/vault".to_string()),
+                checksum: Some(H256::from_low_u64_be(1)),
+                pid: Some(42),
+                uptime_secs: Some(0),
+                pending_release_prefetched: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_server_reports_every_client() {
+        let vault = SharedStatus::default();
+        vault.write().unwrap().child_pid = Some(1);
+        let oracle = SharedStatus::default();
+        oracle.write().unwrap().child_pid = Some(2);
+        let addr = serve(BTreeMap::from([
+            ("vault".to_string(), vault),
+            ("oracle".to_string(), oracle),
+        ]));
+
+        let statuses = get_json(&format!("http://{}/status", addr)).await;
+        assert_eq!(statuses["vault"]["pid"], 1);
+        assert_eq!(statuses["oracle"]["pid"], 2);
+        assert_eq!(get_json(&format!("http://{}/status/oracle", addr)).await["pid"], 2);
+
+        let response = reqwest::get(format!("http://{}/status/faucet", addr)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,13 +1,15 @@
-use crate::{BtcAddress, H160};
+use crate::{BtcAddress, H160, H256};
 use bitcoin::{
     Address, ConversionError, Hash, Network, Payload, PubkeyHash, Script, ScriptHash, WPubkeyHash, WScriptHash,
+    WitnessVersion,
 };
+use std::str::FromStr;
 
 pub trait PartialAddress: Sized + Eq + PartialOrd {
     /// Decode the `PartialAddress` from the `Payload` type.
     ///
     /// # Arguments
-    /// * `payload` - Bitcoin payload (P2PKH, P2SH, P2WPKH)
+    /// * `payload` - Bitcoin payload (P2PKH, P2SH, P2WPKH, P2TR where supported)
     fn from_payload(payload: Payload) -> Result<Self, ConversionError>;
 
     /// Encode the `PartialAddress` into the `Payload` type.
@@ -22,8 +24,30 @@ pub trait PartialAddress: Sized + Eq + PartialOrd {
     /// Encode the `PartialAddress` as an address that the bitcoin rpc can use.
     ///
     /// # Arguments
+    /// * `network` - network to prefix, e.g. the configured `BitcoinNetwork`
+    fn to_address(&self, network: impl Into<Network>) -> Result<Address, ConversionError>;
+
+    /// Encode the `PartialAddress` as a human-readable address string.
+    ///
+    /// # Arguments
     /// * `network` - network to prefix
-    fn to_address(&self, network: Network) -> Result<Address, ConversionError>;
+    fn to_address_string(&self, network: impl Into<Network>) -> Result<String, ConversionError> {
+        Ok(self.to_address(network)?.to_string())
+    }
+
+    /// Decode the `PartialAddress` from a human-readable address string.
+    ///
+    /// # Arguments
+    /// * `address` - Bitcoin address string
+    /// * `network` - network the address is expected to be valid for
+    fn from_address_string(address: &str, network: impl Into<Network>) -> Result<Self, ConversionError> {
+        let network = network.into();
+        let address = Address::from_str(address)?;
+        if !address.is_valid_for_network(network) {
+            return Err(ConversionError::InvalidNetwork(network, address.network));
+        }
+        Self::from_address(address)
+    }
 }
 
 impl PartialAddress for BtcAddress {
@@ -31,13 +55,21 @@ impl PartialAddress for BtcAddress {
         match payload {
             Payload::PubkeyHash(hash) => Ok(Self::P2PKH(H160::from(hash.as_hash().into_inner()))),
             Payload::ScriptHash(hash) => Ok(Self::P2SH(H160::from(hash.as_hash().into_inner()))),
-            Payload::WitnessProgram { version: _, program } => {
+            Payload::WitnessProgram {
+                version: WitnessVersion::V0,
+                program,
+            } => {
                 if program.len() == 20 {
                     Ok(Self::P2WPKHv0(H160::from_slice(program.as_slice())))
                 } else {
                     Err(ConversionError::InvalidPayload)
                 }
             }
+            // the on-chain address type has no taproot (or later) variant, so refuse
+            // rather than silently reinterpreting the program as a v0 output
+            Payload::WitnessProgram { version, .. } => {
+                Err(ConversionError::UnsupportedWitnessVersion(version.to_num()))
+            }
         }
     }
 
@@ -56,9 +88,55 @@ impl PartialAddress for BtcAddress {
         Self::from_payload(address.payload)
     }
 
-    fn to_address(&self, network: Network) -> Result<Address, ConversionError> {
+    fn to_address(&self, network: impl Into<Network>) -> Result<Address, ConversionError> {
         let payload = self.to_payload()?;
-        Ok(Address { payload, network })
+        Ok(Address {
+            payload,
+            network: network.into(),
+        })
+    }
+}
+
+/// A taproot (witness v1) output, by its 32-byte tweaked x-only output key. The parachain's
+/// `BtcAddress` has no taproot variant, so this can be used for bitcoin payments and key
+/// tracking, but not in calls to the parachain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct P2trAddress(pub H256);
+
+impl PartialAddress for P2trAddress {
+    fn from_payload(payload: Payload) -> Result<Self, ConversionError> {
+        match payload {
+            Payload::WitnessProgram {
+                version: WitnessVersion::V1,
+                program,
+            } if program.len() == 32 => Ok(Self(H256::from_slice(program.as_slice()))),
+            Payload::WitnessProgram {
+                version: WitnessVersion::V1,
+                ..
+            } => Err(ConversionError::InvalidPayload),
+            Payload::WitnessProgram { version, .. } => {
+                Err(ConversionError::UnsupportedWitnessVersion(version.to_num()))
+            }
+            _ => Err(ConversionError::InvalidFormat),
+        }
+    }
+
+    fn to_payload(&self) -> Result<Payload, ConversionError> {
+        Ok(Payload::WitnessProgram {
+            version: WitnessVersion::V1,
+            program: self.0.as_bytes().to_vec(),
+        })
+    }
+
+    fn from_address(address: Address) -> Result<Self, ConversionError> {
+        Self::from_payload(address.payload)
+    }
+
+    fn to_address(&self, network: impl Into<Network>) -> Result<Address, ConversionError> {
+        Ok(Address {
+            payload: self.to_payload()?,
+            network: network.into(),
+        })
     }
 }
 
@@ -75,9 +153,9 @@ impl PartialAddress for Payload {
         Ok(address.payload)
     }
 
-    fn to_address(&self, network: Network) -> Result<Address, ConversionError> {
+    fn to_address(&self, network: impl Into<Network>) -> Result<Address, ConversionError> {
         Ok(Address {
-            network,
+            network: network.into(),
             payload: self.clone(),
         })
     }
@@ -86,7 +164,7 @@ impl PartialAddress for Payload {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::str::FromStr;
+    use bitcoin::BitcoinNetwork;
 
     #[test]
     fn test_encode_and_decode_payload() {
@@ -100,4 +178,118 @@ mod tests {
                 .to_string()
         );
     }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki#test-vectors-for-v1-and-higher
+    const TAPROOT_ADDRESSES: [&str; 2] = [
+        "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+    ];
+
+    #[test]
+    fn test_encode_and_decode_taproot_payload() {
+        for addr in TAPROOT_ADDRESSES {
+            let address = Address::from_str(addr).unwrap();
+            let network = address.network;
+            let payload = Payload::from_address(address).unwrap();
+            assert!(matches!(
+                payload,
+                Payload::WitnessProgram {
+                    version: WitnessVersion::V1,
+                    ref program,
+                } if program.len() == 32
+            ));
+            assert_eq!(addr, payload.to_address(network).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_encode_and_decode_p2tr_address() {
+        for addr in TAPROOT_ADDRESSES {
+            let network = Address::from_str(addr).unwrap().network;
+            let p2tr = P2trAddress::from_address_string(addr, network).unwrap();
+            assert_eq!(p2tr.to_address_string(network).unwrap(), addr);
+            assert_eq!(P2trAddress::from_payload(p2tr.to_payload().unwrap()).unwrap(), p2tr);
+        }
+        // BIP-341 key path spending: the output key of the first `scriptPubKey` test vector
+        let output_key = H256::from_str("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343").unwrap();
+        assert_eq!(
+            P2trAddress(output_key).to_address_string(Network::Bitcoin).unwrap(),
+            "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5"
+        );
+
+        // not taproot outputs
+        assert!(matches!(
+            P2trAddress::from_address_string("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Bitcoin),
+            Err(ConversionError::UnsupportedWitnessVersion(0))
+        ));
+        assert!(matches!(
+            P2trAddress::from_payload(Payload::WitnessProgram {
+                version: WitnessVersion::V1,
+                program: vec![0; 20],
+            }),
+            Err(ConversionError::InvalidPayload)
+        ));
+    }
+
+    #[test]
+    fn test_reject_taproot_btc_address() {
+        for addr in TAPROOT_ADDRESSES {
+            assert!(matches!(
+                BtcAddress::from_address(Address::from_str(addr).unwrap()),
+                Err(ConversionError::UnsupportedWitnessVersion(1))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reject_malformed_bech32m() {
+        // witness v1 program with a bech32 (rather than bech32m) checksum
+        assert!(Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd").is_err());
+    }
+
+    #[test]
+    fn test_address_string_round_trip() {
+        let addresses = [
+            BtcAddress::P2PKH(H160::from_low_u64_be(1)),
+            BtcAddress::P2SH(H160::from_low_u64_be(2)),
+            BtcAddress::P2WPKHv0(H160::from_low_u64_be(3)),
+        ];
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            for address in addresses {
+                let encoded = address.to_address_string(network).unwrap();
+                assert_eq!(BtcAddress::from_address_string(&encoded, network).unwrap(), address);
+            }
+        }
+    }
+
+    #[test]
+    fn test_regtest_and_signet_address_round_trip() {
+        let cases = [
+            (BitcoinNetwork::Regtest, "bcrt1q6v2c7q7uv8vu6xle2k9ryfj3y3fuuy4rqnl50f"),
+            // signet shares its prefixes with testnet
+            (BitcoinNetwork::Signet, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            (BitcoinNetwork::Signet, "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"),
+        ];
+        for (network, encoded) in cases {
+            let address = BtcAddress::from_address_string(encoded, network).unwrap();
+            assert_eq!(address.to_address_string(network).unwrap(), encoded);
+            assert_eq!(address.to_address(network).unwrap().network, Network::from(network));
+        }
+    }
+
+    #[test]
+    fn test_address_string_network_mismatch() {
+        let address = BtcAddress::P2WPKHv0(H160::from_low_u64_be(1));
+        let encoded = address.to_address_string(Network::Testnet).unwrap();
+        assert!(matches!(
+            BtcAddress::from_address_string(&encoded, Network::Bitcoin),
+            Err(ConversionError::InvalidNetwork(Network::Bitcoin, Network::Testnet))
+        ));
+
+        let encoded = address.to_address_string(BitcoinNetwork::Regtest).unwrap();
+        assert!(matches!(
+            BtcAddress::from_address_string(&encoded, BitcoinNetwork::Signet),
+            Err(ConversionError::InvalidNetwork(Network::Signet, Network::Regtest))
+        ));
+    }
 }
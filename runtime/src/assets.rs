@@ -1,20 +1,139 @@
-use crate::{types::*, AssetMetadata, Error};
+use crate::{types::*, AssetMetadata, Error, UtilFuncs};
 use lazy_static::lazy_static;
 use primitives::{CurrencyId, CurrencyInfo};
 use std::{
     collections::BTreeMap,
-    sync::{Mutex, MutexGuard},
+    env, fmt,
+    str::FromStr,
+    sync::{Mutex, MutexGuard, RwLock},
+    time::{Duration, Instant},
 };
+use tokio::time::sleep;
+
+// how long to remember that an asset is not registered before querying the chain again
+const MISSING_ASSET_TTL: Duration = Duration::from_secs(60);
+
+/// Environment variable with additional symbol aliases, as comma-separated `ALIAS=SYMBOL` pairs (e.g.
+/// `WBTC=KBTC`), which take precedence over the default ones
+pub const SYMBOL_ALIASES_ENV: &str = "CURRENCY_SYMBOL_ALIASES";
+
+// symbols that operators commonly use for the hardcoded currencies
+const DEFAULT_SYMBOL_ALIASES: [(&str, &str); 2] = [("XBTC", "IBTC"), ("INTERBTC", "IBTC")];
 
 lazy_static! {
     // NOTE: restrict access to the lock to ensure that no async code yields while holding the mutex
     static ref ASSET_REGISTRY: Mutex<AssetRegistry> = Mutex::new(AssetRegistry::default());
+    static ref SYMBOL_ALIASES: RwLock<SymbolAliases> = RwLock::new(SymbolAliases::from_env());
+}
+
+/// Symbols that resolve to the currency of another symbol, by uppercase alias.
+#[derive(Debug, Clone)]
+struct SymbolAliases(BTreeMap<String, String>);
+
+impl Default for SymbolAliases {
+    fn default() -> Self {
+        Self(
+            DEFAULT_SYMBOL_ALIASES
+                .iter()
+                .map(|(alias, symbol)| (alias.to_string(), symbol.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl SymbolAliases {
+    /// The default symbol aliases, extended with the ones set in `SYMBOL_ALIASES_ENV`.
+    fn from_env() -> Self {
+        let mut aliases = Self::default();
+        if let Ok(src) = env::var(SYMBOL_ALIASES_ENV) {
+            match parse_symbol_aliases(&src) {
+                Ok(extra_aliases) => {
+                    for (alias, symbol) in extra_aliases {
+                        if let Err(err) = aliases.insert(&alias, &symbol) {
+                            log::warn!("Ignoring {}: {}", SYMBOL_ALIASES_ENV, err);
+                        }
+                    }
+                }
+                Err(err) => log::warn!("Ignoring {}: {}", SYMBOL_ALIASES_ENV, err),
+            }
+        }
+        aliases
+    }
+
+    /// Let `alias` resolve to the currency with `symbol`. Aliases may not shadow the symbol of a
+    /// currency, which could then no longer be referred to.
+    fn insert(&mut self, alias: &str, symbol: &str) -> Result<(), Error> {
+        let alias = alias.trim().to_uppercase();
+        if currency_by_symbol(&alias).is_ok() {
+            return Err(Error::SymbolAliasShadowsCurrency(alias));
+        }
+        self.0.insert(alias, symbol.trim().to_uppercase());
+        Ok(())
+    }
+
+    /// The symbol that `uppercase_symbol` is an alias of, or the symbol itself.
+    fn resolve(&self, uppercase_symbol: String) -> String {
+        self.0.get(&uppercase_symbol).cloned().unwrap_or(uppercase_symbol)
+    }
+
+    /// The currency with `symbol`, or else the one it is an alias of. Foreign assets that were
+    /// registered after the alias was added still take precedence over it.
+    fn currency(&self, symbol: &str) -> Result<CurrencyId, Error> {
+        self.currency_in(symbol, &AssetRegistry::snapshot()?)
+    }
+
+    /// Like `currency`, but looks up foreign assets in `registry`.
+    fn currency_in(&self, symbol: &str, registry: &AssetRegistry) -> Result<CurrencyId, Error> {
+        let uppercase_symbol = symbol.trim().to_uppercase();
+        match currency_by_symbol_in(&uppercase_symbol, registry) {
+            Err(Error::AssetNotFound) if self.0.contains_key(&uppercase_symbol) => {
+                currency_by_symbol_in(&self.resolve(uppercase_symbol), registry)
+            }
+            result => result,
+        }
+    }
+}
+
+/// Parse comma-separated `ALIAS=SYMBOL` pairs, case-insensitively.
+pub fn parse_symbol_aliases(src: &str) -> Result<Vec<(String, String)>, Error> {
+    src.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((alias, symbol)) if !alias.trim().is_empty() && !symbol.trim().is_empty() => {
+                Ok((alias.trim().to_uppercase(), symbol.trim().to_uppercase()))
+            }
+            _ => Err(Error::InvalidSymbolAlias(pair.to_string())),
+        })
+        .collect()
+}
+
+/// Let `alias` resolve to the currency with `symbol`, e.g. `XBTC` to `IBTC`. Fails if `alias` is the
+/// symbol of a hardcoded token or a registered foreign asset.
+pub fn add_symbol_alias(alias: &str, symbol: &str) -> Result<(), Error> {
+    SYMBOL_ALIASES
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(alias, symbol)
+}
+
+fn symbol_aliases() -> SymbolAliases {
+    SYMBOL_ALIASES.read().unwrap_or_else(|err| err.into_inner()).clone()
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct AssetRegistry {
     symbol_lookup: BTreeMap<String, u32>,
     metadata_lookup: BTreeMap<u32, AssetMetadata>,
+    // ids that were not found on chain, and when that was checked
+    missing_lookup: BTreeMap<u32, Instant>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CachedAsset {
+    Found(AssetMetadata),
+    Missing,
+    Unknown,
 }
 
 impl AssetRegistry {
@@ -30,9 +149,30 @@ impl AssetRegistry {
         log::info!("Found asset: {}", asset_name);
         self.symbol_lookup.insert(asset_name, foreign_asset_id);
         self.metadata_lookup.insert(foreign_asset_id, asset_metadata);
+        self.missing_lookup.remove(&foreign_asset_id);
         Ok(())
     }
 
+    fn inner_lookup(&self, foreign_asset_id: u32, now: Instant) -> CachedAsset {
+        if let Some(asset_metadata) = self.metadata_lookup.get(&foreign_asset_id) {
+            return CachedAsset::Found(asset_metadata.clone());
+        }
+        match self.missing_lookup.get(&foreign_asset_id) {
+            Some(checked_at) if now.saturating_duration_since(*checked_at) < MISSING_ASSET_TTL => CachedAsset::Missing,
+            _ => CachedAsset::Unknown,
+        }
+    }
+
+    fn inner_mark_missing(&mut self, foreign_asset_id: u32, now: Instant) {
+        self.missing_lookup.insert(foreign_asset_id, now);
+    }
+
+    fn inner_invalidate(&mut self) {
+        self.symbol_lookup.clear();
+        self.metadata_lookup.clear();
+        self.missing_lookup.clear();
+    }
+
     pub(crate) fn insert(foreign_asset_id: u32, asset_metadata: AssetMetadata) -> Result<(), Error> {
         let mut asset_registry = Self::global()?;
         asset_registry.inner_insert(foreign_asset_id, asset_metadata)?;
@@ -48,6 +188,62 @@ impl AssetRegistry {
         Ok(())
     }
 
+    /// Drop all cached assets, including the ones known to be missing.
+    pub fn invalidate() -> Result<(), Error> {
+        Self::global()?.inner_invalidate();
+        Ok(())
+    }
+
+    /// Replace the cached assets with the ones currently registered on chain.
+    pub async fn refresh_now<P: UtilFuncs>(parachain_rpc: &P) -> Result<(), Error> {
+        // fetch before locking, the lock must not be held across the query
+        let assets = parachain_rpc.get_foreign_assets_metadata().await?;
+        let mut asset_registry = Self::global()?;
+        asset_registry.inner_invalidate();
+        for (foreign_asset_id, asset_metadata) in assets {
+            asset_registry.inner_insert(foreign_asset_id, asset_metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Refresh the cached assets every `interval`, only returns on error.
+    pub async fn refresh_periodically<P: UtilFuncs>(parachain_rpc: &P, interval: Duration) -> Result<(), Error> {
+        loop {
+            sleep(interval).await;
+            if let Err(err) = Self::refresh_now(parachain_rpc).await {
+                if err.is_rpc_disconnect_error() {
+                    return Err(err);
+                }
+                log::warn!("Failed to refresh asset registry: {}", err);
+            }
+        }
+    }
+
+    /// Fetch the asset metadata for a foreign asset, querying the chain if it is not cached.
+    /// Assets that are not registered are remembered for a short while to avoid repeated queries.
+    pub async fn get_or_fetch_asset_metadata<P: UtilFuncs>(
+        parachain_rpc: &P,
+        foreign_asset_id: u32,
+    ) -> Result<AssetMetadata, Error> {
+        match Self::global()?.inner_lookup(foreign_asset_id, Instant::now()) {
+            CachedAsset::Found(asset_metadata) => return Ok(asset_metadata),
+            CachedAsset::Missing => return Err(Error::AssetNotFound),
+            CachedAsset::Unknown => {}
+        }
+
+        match parachain_rpc.get_foreign_asset_metadata(foreign_asset_id).await {
+            Ok(asset_metadata) => {
+                Self::insert(foreign_asset_id, asset_metadata.clone())?;
+                Ok(asset_metadata)
+            }
+            Err(Error::AssetNotFound) => {
+                Self::global()?.inner_mark_missing(foreign_asset_id, Instant::now());
+                Err(Error::AssetNotFound)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     /// Fetch the currency for a ticker symbol
     pub fn get_foreign_asset_by_symbol(symbol: String) -> Result<CurrencyId, Error> {
         Self::global()?
@@ -65,6 +261,146 @@ impl AssetRegistry {
             .cloned()
             .ok_or(Error::AssetNotFound)
     }
+
+    /// Copy of the global registry, for resolving many currencies without holding the lock
+    pub fn snapshot() -> Result<Self, Error> {
+        Ok(Self::global()?.clone())
+    }
+}
+
+/// The id in `FOREIGN(<id>)` or `FOREIGNASSET(<id>)`, given in uppercase, i.e. also the `Debug`
+/// format of `CurrencyId::ForeignAsset`, or `None` if `uppercase_src` is not of that form
+pub(crate) fn foreign_asset_id(uppercase_src: &str) -> Option<Result<u32, Error>> {
+    uppercase_src
+        .strip_prefix("FOREIGN(")
+        .or_else(|| uppercase_src.strip_prefix("FOREIGNASSET("))
+        .and_then(|id| id.strip_suffix(')'))
+        .map(|id| id.trim().parse().map_err(|_| Error::InvalidCurrency))
+}
+
+/// Tokens that are known without querying the chain
+const HARDCODED_TOKENS: [TokenSymbol; 6] = [DOT, IBTC, INTR, KSM, KBTC, KINT];
+
+/// Currencies that are known without querying the chain
+fn hardcoded_currency(uppercase_symbol: &str) -> Option<CurrencyId> {
+    HARDCODED_TOKENS
+        .iter()
+        .find(|token| token.symbol() == uppercase_symbol)
+        .map(|token| Token(*token))
+}
+
+/// The hardcoded token or registered foreign asset with `uppercase_symbol`, ignoring aliases.
+fn currency_by_symbol(uppercase_symbol: &str) -> Result<CurrencyId, Error> {
+    match hardcoded_currency(uppercase_symbol) {
+        Some(currency_id) => Ok(currency_id),
+        None => AssetRegistry::get_foreign_asset_by_symbol(uppercase_symbol.to_string()),
+    }
+}
+
+/// Like `currency_by_symbol`, but looks up foreign assets in `registry`.
+fn currency_by_symbol_in(uppercase_symbol: &str, registry: &AssetRegistry) -> Result<CurrencyId, Error> {
+    match hardcoded_currency(uppercase_symbol) {
+        Some(currency_id) => Ok(currency_id),
+        None => registry
+            .symbol_lookup
+            .get(uppercase_symbol)
+            .map(|foreign_asset_id| CurrencyId::ForeignAsset(*foreign_asset_id))
+            .ok_or(Error::AssetNotFound),
+    }
+}
+
+/// A currency given on the command line or in a config file: a token symbol (e.g. `KSM`), one of
+/// the symbol aliases, the symbol of a foreign asset, `FOREIGN(<id>)` or `ForeignAsset(<id>)`, all
+/// case-insensitive. Foreign asset symbols are only known once the asset registry is loaded, so
+/// they are looked up by `resolve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyArg(CurrencyArgInner);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CurrencyArgInner {
+    Id(CurrencyId),
+    ForeignSymbol(String),
+}
+
+impl CurrencyArg {
+    /// The currency, looking up foreign asset symbols in the asset registry.
+    pub fn resolve(&self) -> Result<CurrencyId, Error> {
+        self.resolve_with_registry(&AssetRegistry::snapshot()?)
+    }
+
+    /// Like `resolve`, but looks up foreign asset symbols in `registry`.
+    pub fn resolve_with_registry(&self, registry: &AssetRegistry) -> Result<CurrencyId, Error> {
+        match &self.0 {
+            CurrencyArgInner::Id(currency_id) => Ok(*currency_id),
+            CurrencyArgInner::ForeignSymbol(symbol) => symbol_aliases().currency_in(symbol, registry),
+        }
+    }
+
+    /// The currency if it is known without the asset registry, i.e. a hardcoded token, one of
+    /// its aliases or a foreign asset given by id.
+    pub fn currency_id(&self) -> Option<CurrencyId> {
+        match &self.0 {
+            CurrencyArgInner::Id(currency_id) => Some(*currency_id),
+            CurrencyArgInner::ForeignSymbol(_) => None,
+        }
+    }
+}
+
+impl FromStr for CurrencyArg {
+    type Err = Error;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let uppercase_src = src.trim().to_uppercase();
+        // aliases never shadow hardcoded tokens, but may shadow foreign assets that are registered later,
+        // so those are only resolved once the registry is loaded
+        if let Some(currency_id) = hardcoded_currency(&symbol_aliases().resolve(uppercase_src.clone())) {
+            return Ok(Self(CurrencyArgInner::Id(currency_id)));
+        }
+        let invalid = || {
+            Error::InvalidCurrencyArg(
+                src.to_string(),
+                HARDCODED_TOKENS
+                    .iter()
+                    .map(|token| token.symbol())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
+        match foreign_asset_id(&uppercase_src) {
+            Some(id) => id
+                .map(|id| Self(CurrencyArgInner::Id(ForeignAsset(id))))
+                .map_err(|_| invalid()),
+            None if !uppercase_src.is_empty()
+                && !uppercase_src.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') =>
+            {
+                Ok(Self(CurrencyArgInner::ForeignSymbol(uppercase_src)))
+            }
+            None => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for CurrencyArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            CurrencyArgInner::Id(Token(token)) => write!(f, "{}", token.symbol()),
+            CurrencyArgInner::Id(ForeignAsset(id)) => write!(f, "FOREIGN({})", id),
+            CurrencyArgInner::Id(currency_id) => write!(f, "{:?}", currency_id),
+            CurrencyArgInner::ForeignSymbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+/// Parse any currency like a `CurrencyArg`, looking up foreign asset symbols in `registry`. Foreign
+/// assets given by id must be registered as well.
+pub fn parse_currency_with_registry(src: &str, registry: &AssetRegistry) -> Result<CurrencyId, Error> {
+    match src.parse::<CurrencyArg>()?.resolve_with_registry(registry) {
+        Ok(ForeignAsset(foreign_asset_id)) if !registry.metadata_lookup.contains_key(&foreign_asset_id) => {
+            Err(Error::InvalidCurrency)
+        }
+        Ok(currency_id) => Ok(currency_id),
+        Err(Error::AssetNotFound) => Err(Error::InvalidCurrency),
+        Err(err) => Err(err),
+    }
 }
 
 /// Convert a ticker symbol into a `CurrencyId` at runtime
@@ -73,18 +409,9 @@ pub trait TryFromSymbol: Sized {
 }
 
 impl TryFromSymbol for CurrencyId {
+    /// Resolve `symbol` like a `CurrencyArg`: case-insensitively, or else the symbol it is an alias of.
     fn try_from_symbol(symbol: String) -> Result<Self, Error> {
-        let uppercase_symbol = symbol.to_uppercase();
-        // try hardcoded currencies first
-        match uppercase_symbol.as_str() {
-            id if id == DOT.symbol() => Ok(Token(DOT)),
-            id if id == IBTC.symbol() => Ok(Token(IBTC)),
-            id if id == INTR.symbol() => Ok(Token(INTR)),
-            id if id == KSM.symbol() => Ok(Token(KSM)),
-            id if id == KBTC.symbol() => Ok(Token(KBTC)),
-            id if id == KINT.symbol() => Ok(Token(KINT)),
-            _ => AssetRegistry::get_foreign_asset_by_symbol(uppercase_symbol),
-        }
+        symbol.parse::<CurrencyArg>()?.resolve()
     }
 }
 
@@ -94,6 +421,56 @@ pub trait RuntimeCurrencyInfo {
     fn symbol(&self) -> Result<String, Error>;
     fn decimals(&self) -> Result<u32, Error>;
     fn coingecko_id(&self) -> Result<String, Error>;
+
+    /// Format a raw amount using the currency's decimals, followed by its symbol (e.g. `1.5 KSM`).
+    fn format_amount(&self, raw: u128) -> Result<String, Error> {
+        let decimals = self.decimals()?;
+        let unit = decimals_unit(decimals)?;
+        let fraction = format!("{:0width$}", raw % unit, width = decimals as usize);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            Ok(format!("{} {}", raw / unit, self.symbol()?))
+        } else {
+            Ok(format!("{}.{} {}", raw / unit, fraction, self.symbol()?))
+        }
+    }
+
+    /// Scale a decimal string (e.g. `1.5`) into the raw amount of this currency.
+    /// Fails if the string has more fractional digits than the currency supports.
+    fn parse_amount(&self, src: &str) -> Result<u128, Error> {
+        let decimals = self.decimals()?;
+        let src = src.trim();
+        let (integer_digits, fraction_digits) = src.split_once('.').unwrap_or((src, ""));
+        if (integer_digits.is_empty() && fraction_digits.is_empty())
+            || !integer_digits
+                .chars()
+                .chain(fraction_digits.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(Error::InvalidAmount);
+        }
+        if fraction_digits.len() > decimals as usize {
+            return Err(Error::TooManyDecimals(decimals));
+        }
+
+        let parse_digits = |digits: &str| match digits {
+            "" => Ok(0u128),
+            digits => digits.parse::<u128>().map_err(|_| Error::ArithmeticOverflow),
+        };
+        let integer = parse_digits(integer_digits)?.checked_mul(decimals_unit(decimals)?);
+        // right-pad the fractional digits to the currency's precision
+        let fraction =
+            parse_digits(fraction_digits)?.checked_mul(decimals_unit(decimals - fraction_digits.len() as u32)?);
+        integer
+            .zip(fraction)
+            .and_then(|(integer, fraction)| integer.checked_add(fraction))
+            .ok_or(Error::ArithmeticOverflow)
+    }
+}
+
+/// The raw amount of a single unit with the given number of decimals
+fn decimals_unit(decimals: u32) -> Result<u128, Error> {
+    10u128.checked_pow(decimals).ok_or(Error::ArithmeticOverflow)
 }
 
 impl RuntimeCurrencyInfo for CurrencyId {
@@ -142,7 +519,99 @@ impl RuntimeCurrencyInfo for CurrencyId {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metadata::runtime_types::interbtc_primitives::CustomMetadata;
+    use crate::{metadata::runtime_types::interbtc_primitives::CustomMetadata, AccountId, RuntimeVersion, VaultId};
+    use async_trait::async_trait;
+
+    fn dummy_asset_metadata(symbol: &[u8]) -> AssetMetadata {
+        AssetMetadata {
+            decimals: 10,
+            location: None,
+            name: b"Dummy".to_vec(),
+            symbol: symbol.to_vec(),
+            existential_deposit: 0,
+            additional: CustomMetadata {
+                fee_per_second: 0,
+                coingecko_id: vec![],
+            },
+        }
+    }
+
+    mockall::mock! {
+        Provider {}
+
+        #[async_trait]
+        pub trait UtilFuncs {
+            async fn get_current_chain_height(&self) -> Result<u32, Error>;
+            async fn get_rpc_properties(&self) -> Result<serde_json::Map<String, serde_json::Value>, Error>;
+            async fn runtime_version(&self) -> Result<RuntimeVersion, Error>;
+            fn get_native_currency_id(&self) -> CurrencyId;
+            fn get_account_id(&self) -> &AccountId;
+            fn is_this_vault(&self, vault_id: &VaultId) -> bool;
+            async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, Error>;
+            async fn get_foreign_asset_metadata(&self, id: u32) -> Result<AssetMetadata, Error>;
+            async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), Error>;
+        }
+    }
+
+    #[tokio::test]
+    async fn should_cache_fetched_asset() -> Result<(), Error> {
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_get_foreign_asset_metadata()
+            .withf(|id| *id == 1000)
+            .times(1)
+            .returning(|_| Ok(dummy_asset_metadata(b"FETCHED")));
+
+        for _ in 0..2 {
+            assert_eq!(
+                AssetRegistry::get_or_fetch_asset_metadata(&parachain_rpc, 1000).await?,
+                dummy_asset_metadata(b"FETCHED")
+            );
+        }
+        assert_eq!(CurrencyId::try_from_symbol("FETCHED".to_string())?, ForeignAsset(1000));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_cache_missing_asset() {
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_get_foreign_asset_metadata()
+            .withf(|id| *id == 1001)
+            .times(1)
+            .returning(|_| Err(Error::AssetNotFound));
+
+        for _ in 0..2 {
+            assert!(matches!(
+                AssetRegistry::get_or_fetch_asset_metadata(&parachain_rpc, 1001).await,
+                Err(Error::AssetNotFound)
+            ));
+        }
+    }
+
+    #[test]
+    fn should_expire_missing_asset() {
+        let mut registry = AssetRegistry::default();
+        let now = Instant::now();
+        registry.inner_mark_missing(5, now);
+
+        assert_eq!(registry.inner_lookup(5, now), CachedAsset::Missing);
+        assert_eq!(registry.inner_lookup(5, now + MISSING_ASSET_TTL), CachedAsset::Unknown);
+    }
+
+    #[test]
+    fn should_invalidate_cached_assets() -> Result<(), Error> {
+        let mut registry = AssetRegistry::default();
+        let now = Instant::now();
+        registry.inner_insert(5, dummy_asset_metadata(b"GONE"))?;
+        registry.inner_mark_missing(6, now);
+
+        registry.inner_invalidate();
+        assert_eq!(registry.inner_lookup(5, now), CachedAsset::Unknown);
+        assert_eq!(registry.inner_lookup(6, now), CachedAsset::Unknown);
+        assert!(parse_currency_with_registry("GONE", &registry).is_err());
+        Ok(())
+    }
 
     #[test]
     fn should_store_metadata() -> Result<(), Error> {
@@ -173,6 +642,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn should_convert_token_symbol_case_insensitively() -> Result<(), Error> {
+        for symbol in ["kbtc", "KBTC", "Kbtc", " kBTC "] {
+            assert_eq!(CurrencyId::try_from_symbol(symbol.to_string())?, Token(KBTC));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_convert_symbol_alias() -> Result<(), Error> {
+        assert_eq!(CurrencyId::try_from_symbol("XBTC".to_string())?, Token(IBTC));
+        assert_eq!(CurrencyId::try_from_symbol("xbtc".to_string())?, Token(IBTC));
+        assert_eq!(CurrencyId::try_from_symbol("InterBTC".to_string())?, Token(IBTC));
+
+        // added to a copy, since the aliases are shared by all tests
+        let mut aliases = SymbolAliases::default();
+        for (alias, symbol) in parse_symbol_aliases("wbtc=KBTC, Kusama = ksm,")? {
+            aliases.insert(&alias, &symbol)?;
+        }
+        assert_eq!(aliases.currency("WBTC")?, Token(KBTC));
+        assert_eq!(aliases.currency("kusama")?, Token(KSM));
+        assert!(matches!(
+            CurrencyId::try_from_symbol("WBTC".to_string()),
+            Err(Error::AssetNotFound)
+        ));
+
+        // aliases can't shadow currencies
+        assert!(matches!(
+            aliases.insert("ksm", "KBTC"),
+            Err(Error::SymbolAliasShadowsCurrency(alias)) if alias == "KSM"
+        ));
+        assert_eq!(aliases.currency("KSM")?, Token(KSM));
+
+        assert!(matches!(
+            CurrencyId::try_from_symbol("NOTACURRENCY".to_string()),
+            Err(Error::AssetNotFound)
+        ));
+        assert!(matches!(
+            parse_symbol_aliases("WBTC"),
+            Err(Error::InvalidSymbolAlias(pair)) if pair == "WBTC"
+        ));
+        assert!(matches!(
+            parse_symbol_aliases("=KBTC"),
+            Err(Error::InvalidSymbolAlias(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn should_get_runtime_info_for_token_symbol() -> Result<(), Error> {
         assert_eq!(Token(DOT).name()?, "Polkadot");
@@ -181,6 +698,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn should_format_amount() -> Result<(), Error> {
+        assert_eq!(Token(KSM).format_amount(1_500_000_000_000)?, "1.5 KSM");
+        assert_eq!(Token(KSM).format_amount(1)?, "0.000000000001 KSM");
+        assert_eq!(Token(KSM).format_amount(2_000_000_000_000)?, "2 KSM");
+        assert_eq!(Token(KBTC).format_amount(123_456_789)?, "1.23456789 KBTC");
+        assert_eq!(Token(KBTC).format_amount(0)?, "0 KBTC");
+        Ok(())
+    }
+
+    #[test]
+    fn should_parse_amount() -> Result<(), Error> {
+        assert_eq!(Token(KSM).parse_amount("1.5")?, 1_500_000_000_000);
+        assert_eq!(Token(KSM).parse_amount("0.000000000001")?, 1);
+        assert_eq!(Token(KSM).parse_amount("2")?, 2_000_000_000_000);
+        assert_eq!(Token(KSM).parse_amount(".5")?, 500_000_000_000);
+        assert_eq!(Token(KBTC).parse_amount("1.23456789")?, 123_456_789);
+
+        for raw in [0, 1, 123_456_789, u64::MAX as u128] {
+            let formatted = Token(KBTC).format_amount(raw)?;
+            let amount = formatted.strip_suffix(" KBTC").unwrap();
+            assert_eq!(Token(KBTC).parse_amount(amount)?, raw);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_invalid_amount() {
+        assert!(matches!(
+            Token(KBTC).parse_amount("0.123456789"),
+            Err(Error::TooManyDecimals(8))
+        ));
+        assert!(matches!(Token(KSM).parse_amount(""), Err(Error::InvalidAmount)));
+        assert!(matches!(Token(KSM).parse_amount("."), Err(Error::InvalidAmount)));
+        assert!(matches!(Token(KSM).parse_amount("-1"), Err(Error::InvalidAmount)));
+        assert!(matches!(Token(KSM).parse_amount("1.2.3"), Err(Error::InvalidAmount)));
+        assert!(matches!(
+            Token(KSM).parse_amount(&u128::MAX.to_string()),
+            Err(Error::ArithmeticOverflow)
+        ));
+    }
+
     #[test]
     fn should_convert_foreign_asset() -> Result<(), Error> {
         AssetRegistry::global()?.inner_insert(
@@ -223,4 +782,128 @@ mod tests {
         assert_eq!(ForeignAsset(0).decimals()?, 10);
         Ok(())
     }
+
+    #[test]
+    fn should_parse_foreign_collateral_currency() -> Result<(), Error> {
+        let mut registry = AssetRegistry::default();
+        registry.inner_insert(
+            3,
+            AssetMetadata {
+                decimals: 12,
+                location: None,
+                name: b"Foreign Collateral".to_vec(),
+                symbol: b"FCOL".to_vec(),
+                existential_deposit: 0,
+                additional: CustomMetadata {
+                    fee_per_second: 0,
+                    coingecko_id: vec![],
+                },
+            },
+        )?;
+
+        let parse_collateral = |src: &str| crate::parse_collateral_currency_with_registry(src, &registry);
+        assert_eq!(parse_collateral("KSM")?, Token(KSM));
+        assert_eq!(parse_collateral("kint")?, Token(KINT));
+        assert_eq!(parse_collateral("FOREIGN(3)")?, ForeignAsset(3));
+        assert_eq!(parse_collateral("foreign( 3 )")?, ForeignAsset(3));
+        assert_eq!(parse_collateral("fcol")?, ForeignAsset(3));
+
+        assert!(matches!(parse_collateral("FOREIGN(404)"), Err(Error::InvalidCurrency)));
+        assert!(matches!(
+            parse_collateral("FOREIGN(x)"),
+            Err(Error::InvalidCurrencyArg(..))
+        ));
+        assert!(matches!(parse_collateral("KBTC"), Err(Error::InvalidCurrency)));
+        assert!(matches!(parse_collateral("UNKNOWN"), Err(Error::InvalidCurrency)));
+
+        // foreign assets are only accepted as collateral
+        assert!(matches!(
+            crate::parse_wrapped_currency("FOREIGN(3)"),
+            Err(Error::InvalidCurrency)
+        ));
+        assert!(matches!(
+            crate::parse_native_currency("FOREIGN(3)"),
+            Err(Error::InvalidCurrency)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn should_round_trip_currency_arg() -> Result<(), Error> {
+        for token in HARDCODED_TOKENS {
+            let currency: CurrencyArg = token.symbol().to_lowercase().parse().unwrap();
+            assert_eq!(currency.resolve()?, Token(token));
+            assert_eq!(currency.to_string(), token.symbol());
+            assert_eq!(currency.to_string().parse::<CurrencyArg>().unwrap(), currency);
+        }
+
+        let currency: CurrencyArg = "foreign( 5 )".parse().unwrap();
+        assert_eq!(currency.resolve()?, ForeignAsset(5));
+        assert_eq!(currency.to_string(), "FOREIGN(5)");
+        assert_eq!(currency.to_string().parse::<CurrencyArg>().unwrap(), currency);
+        // as well as the `Debug` format of the currency id
+        let currency: CurrencyArg = format!("{:?}", ForeignAsset(5)).parse().unwrap();
+        assert_eq!(currency.currency_id(), Some(ForeignAsset(5)));
+        assert_eq!(currency.to_string(), "FOREIGN(5)");
+
+        // aliases are displayed as the symbol they stand for
+        assert_eq!("xbtc".parse::<CurrencyArg>().unwrap().to_string(), "IBTC");
+
+        // foreign asset symbols are resolved once the asset is registered, in a local registry since
+        // the global one is shared by all tests
+        let currency: CurrencyArg = "Fargo".parse().unwrap();
+        assert_eq!(currency.to_string(), "FARGO");
+        assert_eq!(currency.to_string().parse::<CurrencyArg>().unwrap(), currency);
+        assert_eq!(currency.currency_id(), None);
+        let mut registry = AssetRegistry::default();
+        assert!(matches!(
+            currency.resolve_with_registry(&registry),
+            Err(Error::AssetNotFound)
+        ));
+        registry.inner_insert(9, dummy_asset_metadata(b"FARGO"))?;
+        assert_eq!(currency.resolve_with_registry(&registry)?, ForeignAsset(9));
+
+        for src in ["", "FOREIGN(x)", "FOREIGN(-1)", "two words"] {
+            let err = src.parse::<CurrencyArg>().unwrap_err().to_string();
+            assert!(err.contains("DOT, IBTC, INTR, KSM, KBTC, KINT"), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_parse_currency_with_registry() -> Result<(), Error> {
+        let mut registry = AssetRegistry::default();
+        registry.inner_insert(
+            7,
+            AssetMetadata {
+                decimals: 18,
+                location: None,
+                name: b"Fresh Collateral".to_vec(),
+                symbol: b"FRESH".to_vec(),
+                existential_deposit: 0,
+                additional: CustomMetadata {
+                    fee_per_second: 0,
+                    coingecko_id: vec![],
+                },
+            },
+        )?;
+
+        assert_eq!(parse_currency_with_registry("ksm", &registry)?, Token(KSM));
+        assert_eq!(parse_currency_with_registry("Fresh", &registry)?, ForeignAsset(7));
+        assert_eq!(parse_currency_with_registry("FOREIGN(7)", &registry)?, ForeignAsset(7));
+        assert_eq!(
+            parse_currency_with_registry("ForeignAsset(7)", &registry)?,
+            ForeignAsset(7)
+        );
+        assert_eq!(parse_currency_with_registry("xbtc", &registry)?, Token(IBTC));
+        assert!(matches!(
+            parse_currency_with_registry("FOREIGN(8)", &registry),
+            Err(Error::InvalidCurrency)
+        ));
+        assert!(matches!(
+            parse_currency_with_registry("FRESH", &AssetRegistry::default()),
+            Err(Error::InvalidCurrency)
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,431 @@
+use crate::{BlockNumber, Error, EventNotification, H256};
+use futures::{stream, Future, Stream, StreamExt};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::time::sleep;
+
+/// Turn a fallible block subscription into a stream of strictly increasing block numbers.
+/// Whenever the subscription fails or ends, `subscribe` is called again. Heights that were
+/// already yielded are skipped, so consumers see each height at most once, even across
+/// resubscriptions. If resubscribing fails, the error is yielded and the next attempt is
+/// made after `resubscribe_delay`.
+pub(crate) fn deduplicated_block_numbers<C, F, S>(
+    subscribe: C,
+    resubscribe_delay: Duration,
+) -> impl Stream<Item = Result<BlockNumber, Error>>
+where
+    C: Fn() -> F,
+    F: Future<Output = Result<S, Error>>,
+    S: Stream<Item = Result<BlockNumber, Error>> + Unpin,
+{
+    deduplicated_blocks(
+        move || {
+            let subscription = subscribe();
+            async move { Ok(subscription.await?.map(|number| number.map(|number| (number, ())))) }
+        },
+        resubscribe_delay,
+    )
+    .map(|block| block.map(|(number, _)| number))
+}
+
+/// Like [`deduplicated_block_numbers`], but each block carries some data (e.g. its events)
+/// that is yielded alongside its number.
+pub(crate) fn deduplicated_blocks<T, C, F, S>(
+    subscribe: C,
+    resubscribe_delay: Duration,
+) -> impl Stream<Item = Result<(BlockNumber, T), Error>>
+where
+    C: Fn() -> F,
+    F: Future<Output = Result<S, Error>>,
+    S: Stream<Item = Result<(BlockNumber, T), Error>> + Unpin,
+{
+    stream::unfold(
+        (subscribe, None::<S>, None::<BlockNumber>, false),
+        move |(subscribe, mut subscription, mut last_seen, mut failed)| async move {
+            loop {
+                let current = match subscription.as_mut() {
+                    Some(current) => current,
+                    None => {
+                        if failed {
+                            sleep(resubscribe_delay).await;
+                        }
+                        match subscribe().await {
+                            Ok(new_subscription) => {
+                                failed = false;
+                                subscription.insert(new_subscription)
+                            }
+                            Err(err) => return Some((Err(err), (subscribe, None, last_seen, true))),
+                        }
+                    }
+                };
+                match current.next().await {
+                    Some(Ok((number, data))) if last_seen.map_or(true, |last_seen| number > last_seen) => {
+                        last_seen = Some(number);
+                        return Some((Ok((number, data)), (subscribe, subscription, last_seen, failed)));
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => {
+                        log::warn!("Block subscription failed, resubscribing: {}", err);
+                        subscription = None;
+                        failed = true;
+                    }
+                    None => {
+                        log::warn!("Block subscription closed, resubscribing");
+                        subscription = None;
+                        failed = true;
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// Keeps track of the best chain since the last finalized block, to detect reorgs.
+#[derive(Debug, Default)]
+pub(crate) struct ReorgTracker {
+    best: BTreeMap<BlockNumber, H256>,
+}
+
+impl ReorgTracker {
+    /// Whether the block is already part of the tracked best chain.
+    pub(crate) fn contains(&self, number: BlockNumber, hash: H256) -> bool {
+        self.best.get(&number) == Some(&hash)
+    }
+
+    /// Whether a block at height `number` with parent `parent_hash` extends the tracked chain. If
+    /// not, its parent has to be imported along with it.
+    pub(crate) fn connects(&self, number: BlockNumber, parent_hash: H256) -> bool {
+        match (number.checked_sub(1), self.best.keys().next()) {
+            (Some(parent_number), Some(&lowest)) if parent_number >= lowest => {
+                self.best.get(&parent_number) == Some(&parent_hash)
+            }
+            // nothing tracked at that height, so there's nothing to compare against
+            _ => true,
+        }
+    }
+
+    /// Make `branch`, given in ascending order and connecting to the tracked chain, the new best
+    /// chain. Returns a `Reorg` if tracked blocks had to be retracted for it.
+    pub(crate) fn import<T>(&mut self, branch: &[(BlockNumber, H256)]) -> Option<EventNotification<T>> {
+        let (&(first, _), &(_, to)) = (branch.first()?, branch.last()?);
+        let from = self.best.values().next_back().copied();
+        let retracted: Vec<_> = self.best.split_off(&first).into_values().collect();
+        self.best.extend(branch.iter().copied());
+        match from {
+            Some(from) if !retracted.is_empty() => Some(EventNotification::Reorg { from, to, retracted }),
+            _ => None,
+        }
+    }
+
+    /// Forget the blocks below the finalized `number`, these can't be reorged anymore.
+    pub(crate) fn finalize(&mut self, number: BlockNumber) {
+        self.best = self.best.split_off(&number);
+    }
+}
+
+/// The last block whose events were processed, persisted in a file so that the events emitted
+/// while the client was down can be replayed after a restart.
+#[derive(Debug, Clone)]
+pub struct BlockCursor {
+    path: PathBuf,
+}
+
+impl BlockCursor {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The stored block, or `None` if no block was processed yet, in which case processing
+    /// resumes at the current block. A corrupt file is treated like a missing one.
+    pub fn load(&self) -> Result<Option<BlockNumber>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        // the trailing newline tells a complete block number from a truncated one
+        match contents.strip_suffix('\n').and_then(|number| number.parse().ok()) {
+            Some(number) => Ok(Some(number)),
+            None => {
+                log::warn!(
+                    "Ignoring corrupt block cursor {}, resuming at the current block",
+                    self.path.display()
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Replace the stored block. The file is synced and then renamed into place, so a crash
+    /// leaves either the previous or the new block.
+    pub fn store(&self, number: BlockNumber) -> Result<(), Error> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        writeln!(file, "{}", number)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Process the events of the live `blocks` in order, storing every processed block in `cursor`.
+/// Blocks that were skipped, since the block in `cursor` or between two live blocks, are fetched
+/// with `fetch_block` and processed first. Blocks that were processed already are ignored, so
+/// the events of every block are processed exactly once. If a skipped block was pruned by the node,
+/// the rest of the gap is skipped with a warning and processing resumes at the live block. Only
+/// returns if `blocks` fails or ends.
+pub(crate) async fn process_blocks_from_cursor<T, S, C, Fut, F, R>(
+    mut blocks: S,
+    cursor: &BlockCursor,
+    fetch_block: C,
+    mut on_event: F,
+) -> Result<(), Error>
+where
+    S: Stream<Item = Result<(BlockNumber, Vec<T>), Error>> + Unpin,
+    C: Fn(BlockNumber) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Error>>,
+    F: FnMut(T) -> R,
+    R: Future<Output = ()>,
+{
+    let mut next = cursor.load()?.map(|last| last.saturating_add(1));
+    while let Some(block) = blocks.next().await {
+        let (number, events) = block?;
+        if let Some(next) = next {
+            if number < next {
+                continue;
+            }
+            if next < number {
+                log::info!("Replaying the events of blocks {} to {}", next, number - 1);
+            }
+            for missed in next..number {
+                let events = match fetch_block(missed).await {
+                    Ok(events) => events,
+                    Err(err) if err.is_pruned_block_error() => {
+                        log::warn!(
+                            "Block {} is no longer available ({}), skipping the events of blocks {} to {}",
+                            missed,
+                            err,
+                            missed,
+                            number - 1
+                        );
+                        break;
+                    }
+                    Err(err) => return Err(err),
+                };
+                for event in events {
+                    on_event(event).await;
+                }
+                cursor.store(missed)?;
+            }
+        }
+        for event in events {
+            on_event(event).await;
+        }
+        cursor.store(number)?;
+        next = Some(number.saturating_add(1));
+    }
+    Err(Error::ChannelClosed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    #[tokio::test]
+    async fn test_block_numbers_are_deduplicated_across_resubscriptions() {
+        // each subscription yields its blocks, then the "websocket" drops
+        let subscriptions = Arc::new(Mutex::new(vec![
+            Ok(vec![Ok(1), Ok(2), Ok(2), Err(Error::ChannelClosed), Ok(9)]),
+            Err(Error::Timeout),
+            Ok(vec![Ok(2), Ok(3)]),
+            Ok(vec![Ok(3), Ok(4)]),
+        ]));
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let blocks = deduplicated_block_numbers(
+            || {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                let next = subscriptions.lock().unwrap().remove(0);
+                async move { next.map(stream::iter) }
+            },
+            Duration::from_millis(1),
+        );
+        let received: Vec<_> = blocks.take(5).collect().await;
+
+        assert!(matches!(
+            received.as_slice(),
+            [Ok(1), Ok(2), Err(Error::Timeout), Ok(3), Ok(4)]
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_events_in_gap_are_replayed_exactly_once() {
+        let tmp_dir = tempdir::TempDir::new("cursor").unwrap();
+        let cursor = BlockCursor::new(tmp_dir.path().join("events.cursor"));
+        assert_eq!(cursor.load().unwrap(), None);
+        // the client was down after processing block 2
+        cursor.store(2).unwrap();
+
+        // every block has a single event, its number
+        let fetched = Arc::new(Mutex::new(vec![]));
+        let fetch_block = |number| {
+            fetched.lock().unwrap().push(number);
+            async move { Ok(vec![number]) }
+        };
+        let process = |live: Vec<BlockNumber>| {
+            let cursor = cursor.clone();
+            let fetch_block = &fetch_block;
+            async move {
+                let mut processed = vec![];
+                let blocks = stream::iter(live.into_iter().map(|number| Ok((number, vec![number]))));
+                let result = process_blocks_from_cursor(blocks, &cursor, fetch_block, |event| {
+                    processed.push(event);
+                    async {}
+                })
+                .await;
+                assert!(matches!(result, Err(Error::ChannelClosed)));
+                processed
+            }
+        };
+
+        // blocks 3 to 5 are replayed before 6, old and duplicate blocks are ignored, and the gap
+        // between 7 and 9 is filled
+        assert_eq!(process(vec![6, 2, 7, 7, 9]).await, vec![3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(*fetched.lock().unwrap(), vec![3, 4, 5, 8]);
+        assert_eq!(cursor.load().unwrap(), Some(9));
+
+        // after a restart, nothing is processed twice
+        assert_eq!(process(vec![9, 10]).await, vec![10]);
+        assert_eq!(cursor.load().unwrap(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_pruned_gap_is_skipped() {
+        let tmp_dir = tempdir::TempDir::new("cursor").unwrap();
+        let cursor = BlockCursor::new(tmp_dir.path().join("events.cursor"));
+        cursor.store(2).unwrap();
+
+        // the node only keeps the state of the blocks from 5 on
+        let fetch_block = |number| async move {
+            match number {
+                0..=4 => Err(Error::BlockNotFound),
+                _ => Ok(vec![number]),
+            }
+        };
+        let mut processed = vec![];
+        let blocks = stream::iter(vec![6, 7].into_iter().map(|number| Ok((number, vec![number]))));
+        let result = process_blocks_from_cursor(blocks, &cursor, fetch_block, |event| {
+            processed.push(event);
+            async {}
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::ChannelClosed)));
+        assert_eq!(processed, vec![6, 7]);
+        assert_eq!(cursor.load().unwrap(), Some(7));
+
+        // other failures are not mistaken for pruning
+        cursor.store(2).unwrap();
+        let blocks = stream::iter(vec![Ok((6, vec![6]))]);
+        let result = process_blocks_from_cursor(
+            blocks,
+            &cursor,
+            |_| async { Err(Error::Timeout) },
+            |_: BlockNumber| async {},
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Timeout)));
+        assert_eq!(cursor.load().unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_block_cursor_round_trip() {
+        let tmp_dir = tempdir::TempDir::new("cursor").unwrap();
+        let path = tmp_dir.path().join("events.cursor");
+        let cursor = BlockCursor::new(&path);
+
+        cursor.store(1234).unwrap();
+        assert_eq!(cursor.load().unwrap(), Some(1234));
+        cursor.store(u32::MAX).unwrap();
+        assert_eq!(BlockCursor::new(&path).load().unwrap(), Some(u32::MAX));
+        // nothing is left behind
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_block_cursor_recovers_from_corrupt_file() {
+        let tmp_dir = tempdir::TempDir::new("cursor").unwrap();
+        let path = tmp_dir.path().join("events.cursor");
+        let cursor = BlockCursor::new(&path);
+        cursor.store(1234).unwrap();
+
+        // truncated, e.g. by a full disk
+        std::fs::write(&path, "12").unwrap();
+        assert_eq!(cursor.load().unwrap(), None);
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(cursor.load().unwrap(), None);
+        std::fs::write(&path, "not a block\n").unwrap();
+        assert_eq!(cursor.load().unwrap(), None);
+        std::fs::write(&path, [0xff, 0xfe, b'\n']).unwrap();
+        assert_eq!(cursor.load().unwrap(), None);
+
+        // and it is overwritten once the next block is processed
+        cursor.store(1235).unwrap();
+        assert_eq!(cursor.load().unwrap(), Some(1235));
+    }
+
+    #[test]
+    fn test_reorg_tracker_detects_reorg() {
+        let hash = |fork: u8, number: BlockNumber| H256::from_low_u64_be(((fork as u64) << 32) | number as u64);
+        let mut tracker = ReorgTracker::default();
+
+        // best chain 1 <- 2 <- 3 on fork 0
+        for number in 1..=3 {
+            assert!(tracker.connects(number, hash(0, number - 1)));
+            assert_eq!(tracker.import::<()>(&[(number, hash(0, number))]), None);
+        }
+        assert!(tracker.contains(3, hash(0, 3)));
+        tracker.finalize(2);
+
+        // fork 1 branches off at 2 and overtakes: its block 4 doesn't connect, neither does 3
+        assert!(!tracker.connects(4, hash(1, 3)));
+        assert!(tracker.connects(3, hash(0, 2)));
+        assert_eq!(
+            tracker.import::<()>(&[(3, hash(1, 3)), (4, hash(1, 4))]),
+            Some(EventNotification::Reorg {
+                from: hash(0, 3),
+                to: hash(1, 4),
+                retracted: vec![hash(0, 3)],
+            })
+        );
+        assert!(!tracker.contains(3, hash(0, 3)));
+        assert!(tracker.contains(3, hash(1, 3)));
+        assert!(tracker.connects(5, hash(1, 4)));
+
+        // a sibling at the same height replaces the current best block
+        assert_eq!(
+            tracker.import::<()>(&[(4, hash(2, 4))]),
+            Some(EventNotification::Reorg {
+                from: hash(1, 4),
+                to: hash(2, 4),
+                retracted: vec![hash(1, 4)],
+            })
+        );
+
+        // blocks below the finalized one are forgotten
+        tracker.finalize(4);
+        assert!(!tracker.contains(3, hash(1, 3)));
+        assert!(tracker.connects(4, hash(9, 3)));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::Error;
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+// timeout of parachain calls without an override (1 minute)
+pub(crate) const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a call to the parachain may take before it is abandoned, by method: `Pallet.call` for
+/// extrinsics, `Pallet.Entry` for storage queries and the RPC method name otherwise.
+#[derive(Debug, Clone)]
+pub(crate) struct CallTimeouts {
+    default: Duration,
+    overrides: Arc<HashMap<String, Duration>>,
+}
+
+impl Default for CallTimeouts {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_CALL_TIMEOUT,
+            overrides: Default::default(),
+        }
+    }
+}
+
+impl CallTimeouts {
+    pub(crate) fn set_default(&mut self, timeout: Duration) {
+        self.default = timeout;
+    }
+
+    pub(crate) fn set(&mut self, method: String, timeout: Duration) {
+        Arc::make_mut(&mut self.overrides).insert(method, timeout);
+    }
+
+    pub(crate) fn get(&self, method: &str) -> Duration {
+        self.overrides.get(method).copied().unwrap_or(self.default)
+    }
+
+    /// Run `call`, failing with `Error::CallTimeout` if `method` does not complete in time. Timed out
+    /// calls are not retried here, that is left to the retry policy of the caller.
+    pub(crate) async fn run<T, F>(&self, method: &str, call: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        let call_timeout = self.get(method);
+        timeout(call_timeout, call).await.map_err(|_| {
+            log::warn!("{} did not complete within {:?}", method, call_timeout);
+            Error::CallTimeout(method.to_string())
+        })?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::RpcError, InterBtcParachain, InterBtcSigner, ShutdownSender, UtilFuncs, VaultRegistryPallet};
+    use codec::Decode;
+    use futures::future;
+    use serde_json::value::RawValue;
+    use sp_keyring::AccountKeyring;
+    use std::{convert::TryFrom, time::Instant};
+    use subxt::{
+        ext::frame_metadata::RuntimeMetadataPrefixed,
+        rpc::{RpcClientT, RpcFuture, RpcSubscription, RuntimeVersion},
+        Metadata, OnlineClient,
+    };
+
+    const RUNTIME_METADATA: &[u8] = include_bytes!("../metadata-parachain-kintsugi-testnet.scale");
+
+    // a node that hangs
+    struct UnresponsiveClient;
+
+    impl RpcClientT for UnresponsiveClient {
+        fn request_raw<'a>(&'a self, _: &'a str, _: Option<Box<RawValue>>) -> RpcFuture<'a, Box<RawValue>> {
+            Box::pin(future::pending::<Result<_, RpcError>>())
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            _: &'a str,
+            _: Option<Box<RawValue>>,
+            _: &'a str,
+        ) -> RpcFuture<'a, RpcSubscription> {
+            Box::pin(future::pending::<Result<_, RpcError>>())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_time_out_unresponsive_call() {
+        let mut timeouts = CallTimeouts::default();
+        timeouts.set_default(Duration::from_millis(200));
+        timeouts.set("system_health".to_string(), Duration::from_millis(10));
+        let client = &UnresponsiveClient;
+        let request = |method: &'static str| {
+            timeouts.run(method, async move {
+                Ok(client
+                    .request_raw(method, None)
+                    .await
+                    .map_err(crate::SubxtError::from)?)
+            })
+        };
+
+        let start = Instant::now();
+        assert!(matches!(
+            request("system_health").await,
+            Err(Error::CallTimeout(method)) if method == "system_health"
+        ));
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        let start = Instant::now();
+        assert!(matches!(
+            request("system_syncState").await,
+            Err(Error::CallTimeout(method)) if method == "system_syncState"
+        ));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // calls that complete in time are unaffected
+        assert_eq!(timeouts.run("system_health", async { Ok(1) }).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_time_out_parachain_getters() {
+        // the metadata is known up front, so connecting doesn't need the node
+        let metadata =
+            Metadata::try_from(RuntimeMetadataPrefixed::decode(&mut &RUNTIME_METADATA[..]).unwrap()).unwrap();
+        let runtime_version = RuntimeVersion {
+            spec_version: 0,
+            transaction_version: 0,
+            other: Default::default(),
+        };
+        let api = OnlineClient::from_rpc_client_with(
+            Default::default(),
+            runtime_version,
+            metadata,
+            Arc::new(UnresponsiveClient),
+        );
+        let parachain_rpc = InterBtcParachain::from_online_client(
+            api,
+            InterBtcSigner::new(AccountKeyring::Alice.pair()),
+            ShutdownSender::new(),
+        )
+        .unwrap()
+        .with_call_timeout(Duration::from_millis(10));
+
+        assert!(matches!(
+            parachain_rpc.get_rpc_properties().await,
+            Err(Error::CallTimeout(method)) if method == "system_properties"
+        ));
+        assert!(matches!(
+            parachain_rpc.get_all_vaults().await,
+            Err(Error::CallTimeout(method)) if method == "VaultRegistry.Vaults"
+        ));
+    }
+}
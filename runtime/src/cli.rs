@@ -1,12 +1,20 @@
 use crate::{
     error::{Error, KeyLoadingError},
     rpc::ShutdownSender,
-    InterBtcParachain, InterBtcSigner,
+    tls, Balance, InterBtcParachain, InterBtcSigner,
 };
 use clap::Parser;
+use rustls::ClientConfig;
 use sp_keyring::AccountKeyring;
-use std::{collections::HashMap, num::ParseIntError, str::FromStr, time::Duration};
-use subxt::ext::sp_core::{sr25519::Pair, Pair as _};
+use std::{
+    collections::HashMap,
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use subxt::ext::sp_core::{crypto::Ss58Codec, sr25519::Pair, Pair as _};
 
 #[derive(Parser, Debug, Clone)]
 pub struct ProviderUserOpts {
@@ -23,17 +31,45 @@ pub struct ProviderUserOpts {
     /// The name of the account from the keyfile to use.
     #[clap(long, conflicts_with = "keyring", requires = "keyfile")]
     pub keyname: Option<String>,
+
+    /// Path to a file containing the password of the account in the keyfile, if it has one.
+    /// A trailing newline is ignored.
+    #[clap(long, conflicts_with = "keyring", requires = "keyfile")]
+    pub keyfile_password_file: Option<PathBuf>,
+
+    /// Path to a file containing the BIP39 mnemonic of the account, mutually exclusive with keyring and keyfile.
+    /// The account name used for wallet selection is its SS58 address.
+    #[clap(long, conflicts_with_all(["keyring", "keyfile"]))]
+    pub mnemonic_file: Option<PathBuf>,
+
+    /// Derivation path to apply to the mnemonic, e.g. `//vault//0`.
+    #[clap(long, requires = "mnemonic_file")]
+    pub derivation_path: Option<String>,
 }
 
 impl ProviderUserOpts {
     /// Get the key pair and the username, the latter of which is used for wallet selection.
     pub fn get_key_pair(&self) -> Result<(Pair, String), Error> {
         // load parachain credentials
-        let (pair, user_name) = match (self.keyfile.as_ref(), self.keyname.as_ref(), &self.keyring) {
-            (Some(file_path), Some(keyname), None) => {
-                (get_credentials_from_file(file_path, keyname)?, keyname.to_string())
+        let (pair, user_name) = match (
+            self.keyfile.as_ref(),
+            self.keyname.as_ref(),
+            &self.keyring,
+            self.mnemonic_file.as_ref(),
+        ) {
+            (Some(file_path), Some(keyname), None, None) => {
+                let password = self.keyfile_password_file.as_deref().map(read_password).transpose()?;
+                (
+                    get_credentials_from_file(file_path, keyname, password.as_deref())?,
+                    keyname.to_string(),
+                )
+            }
+            (None, None, Some(keyring), None) => (keyring.pair(), format!("{}", keyring)),
+            (None, None, None, Some(mnemonic_file)) => {
+                let pair = get_credentials_from_mnemonic_file(mnemonic_file, self.derivation_path.as_deref())?;
+                let user_name = pair.public().to_ss58check();
+                (pair, user_name)
             }
-            (None, None, Some(keyring)) => (keyring.pair(), format!("{}", keyring)),
             _ => {
                 // should never occur, due to clap constraints
                 return Err(Error::KeyringArgumentError);
@@ -49,15 +85,47 @@ impl ProviderUserOpts {
 ///
 /// * `file_path` - path to the json file containing the credentials
 /// * `keyname` - name of the key to get
-fn get_credentials_from_file(file_path: &str, keyname: &str) -> Result<Pair, KeyLoadingError> {
+/// * `password` - password of the key, if any
+fn get_credentials_from_file(file_path: &str, keyname: &str, password: Option<&str>) -> Result<Pair, KeyLoadingError> {
     let file = std::fs::File::open(file_path)?;
     let reader = std::io::BufReader::new(file);
     let map: HashMap<String, String> = serde_json::from_reader(reader)?;
     let pair_str = map.get(keyname).ok_or(KeyLoadingError::KeyNotFound)?;
-    let pair = Pair::from_string(pair_str, None).map_err(KeyLoadingError::SecretStringError)?;
+    let pair = Pair::from_string(pair_str, password).map_err(KeyLoadingError::SecretStringError)?;
     Ok(pair)
 }
 
+/// Derives the key pair from the BIP39 mnemonic in the given file
+///
+/// # Arguments
+///
+/// * `file_path` - path to the file containing the mnemonic
+/// * `derivation_path` - derivation path to apply to the mnemonic, e.g. `//vault//0`
+fn get_credentials_from_mnemonic_file(
+    file_path: &Path,
+    derivation_path: Option<&str>,
+) -> Result<Pair, KeyLoadingError> {
+    let mnemonic = std::fs::read_to_string(file_path)?;
+    let mnemonic = mnemonic.trim();
+    // checked separately, since the secret URI below also accepts seeds and dev accounts
+    Pair::from_phrase(mnemonic, None).map_err(|err| KeyLoadingError::InvalidMnemonic(format!("{:?}", err)))?;
+
+    let derivation_path = derivation_path.unwrap_or_default();
+    if !derivation_path.is_empty() && !derivation_path.starts_with('/') {
+        return Err(KeyLoadingError::InvalidDerivationPath(derivation_path.to_string()));
+    }
+    Pair::from_string(&format!("{}{}", mnemonic, derivation_path), None)
+        .map_err(|_| KeyLoadingError::InvalidDerivationPath(derivation_path.to_string()))
+}
+
+/// Reads the keyfile password, without the trailing newline that most editors add.
+fn read_password(path: &Path) -> Result<String, KeyLoadingError> {
+    let password =
+        std::fs::read_to_string(path).map_err(|err| KeyLoadingError::PasswordFile(path.display().to_string(), err))?;
+    let password = password.strip_suffix('\n').unwrap_or(&password);
+    Ok(password.strip_suffix('\r').unwrap_or(password).to_string())
+}
+
 pub fn parse_account_keyring(src: &str) -> Result<AccountKeyring, Error> {
     AccountKeyring::from_str(src).map_err(|_| Error::KeyringAccountParsingError)
 }
@@ -70,9 +138,19 @@ pub fn parse_duration_minutes(src: &str) -> Result<Duration, ParseIntError> {
     Ok(Duration::from_secs(src.parse::<u64>()? * 60))
 }
 
+/// Parse a `METHOD=MILLISECONDS` call timeout override.
+fn parse_call_timeout(src: &str) -> Result<(String, Duration), String> {
+    let (method, timeout) = src
+        .split_once('=')
+        .ok_or_else(|| format!("invalid METHOD=MILLISECONDS: no `=` found in `{}`", src))?;
+    let timeout = parse_duration_ms(timeout).map_err(|err| format!("invalid timeout in `{}`: {}", src, err))?;
+    Ok((method.to_string(), timeout))
+}
+
 #[derive(Parser, Debug, Clone)]
 pub struct ConnectionOpts {
-    /// Parachain websocket URL.
+    /// Parachain websocket URL. Multiple comma-separated URLs may be given,
+    /// in which case the connection fails over between them.
     #[cfg_attr(
         feature = "parachain-metadata-interlay-testnet",
         clap(long, default_value = "wss://staging.interlay-dev.interlay.io:443/parachain")
@@ -95,6 +173,18 @@ pub struct ConnectionOpts {
     #[clap(long, value_parser = parse_duration_ms, default_value = "60000")]
     pub btc_parachain_connection_timeout_ms: Duration,
 
+    /// Client certificate (PEM) to present to the parachain node, for mutual TLS.
+    #[clap(long, requires = "parachain_tls_key")]
+    pub parachain_tls_cert: Option<PathBuf>,
+
+    /// Private key (PEM) of the parachain client certificate.
+    #[clap(long, requires = "parachain_tls_cert")]
+    pub parachain_tls_key: Option<PathBuf>,
+
+    /// CA certificate (PEM) to verify the parachain node with, instead of the system roots.
+    #[clap(long)]
+    pub parachain_ca: Option<PathBuf>,
+
     /// Maximum number of concurrent requests
     #[clap(long)]
     pub max_concurrent_requests: Option<usize>,
@@ -102,9 +192,45 @@ pub struct ConnectionOpts {
     /// Maximum notification capacity for each subscription
     #[clap(long)]
     pub max_notifs_per_subscription: Option<usize>,
+
+    /// Only warn, rather than refusing to connect, if the runtime of the parachain node
+    /// does not match the metadata this client was compiled against.
+    #[clap(long)]
+    pub allow_runtime_mismatch: bool,
+
+    /// Tip, in planck of the native currency, to add to every submitted transaction so that
+    /// it is prioritized when the parachain is congested.
+    #[clap(long, default_value = "0")]
+    pub tx_tip: Balance,
+
+    /// Number of blocks after which a submitted transaction that has not been included expires,
+    /// rounded up to a power of two. Zero makes transactions valid forever.
+    #[clap(long, default_value = "64")]
+    pub tx_lifetime_blocks: u64,
+
+    /// Timeout in milliseconds after which a call to the parachain is abandoned, so that a hung
+    /// node cannot stall the client. Failed calls are retried independently of this.
+    #[clap(long, value_parser = parse_duration_ms, default_value = "60000")]
+    pub call_timeout_ms: Duration,
+
+    /// Timeout of a single call as `METHOD=MILLISECONDS`, overriding `--call-timeout-ms`. The
+    /// method is `Pallet.call` for transactions, `Pallet.Entry` for storage queries or the name
+    /// of an RPC method, e.g. `Oracle.feed_values=120000`. May be given multiple times.
+    #[clap(long = "call-timeout", value_parser = parse_call_timeout)]
+    pub call_timeouts: Vec<(String, Duration)>,
 }
 
 impl ConnectionOpts {
+    /// TLS configuration for the parachain connection, `None` if no TLS options are set.
+    pub fn tls_client_config(&self) -> Result<Option<Arc<ClientConfig>>, Error> {
+        Ok(tls::load_client_config(
+            self.parachain_tls_cert.as_deref(),
+            self.parachain_tls_key.as_deref(),
+            self.parachain_ca.as_deref(),
+        )?
+        .map(Arc::new))
+    }
+
     pub async fn try_connect(
         &self,
         signer: InterBtcSigner,
@@ -113,11 +239,140 @@ impl ConnectionOpts {
         InterBtcParachain::from_url_and_config_with_retry(
             &self.btc_parachain_url,
             signer,
+            self.tls_client_config()?,
             self.max_concurrent_requests,
             self.max_notifs_per_subscription,
             self.btc_parachain_connection_timeout_ms,
+            self.allow_runtime_mismatch,
             shutdown_tx,
         )
         .await
+        .map(|parachain_rpc| self.configure(parachain_rpc))
+    }
+
+    /// Apply the tip, transaction lifetime and call timeouts to `parachain_rpc`.
+    pub fn configure(&self, parachain_rpc: InterBtcParachain) -> InterBtcParachain {
+        let parachain_rpc = parachain_rpc
+            .with_tx_tip(self.tx_tip)
+            .with_tx_lifetime(self.tx_lifetime_blocks)
+            .with_call_timeout(self.call_timeout_ms);
+        self.call_timeouts
+            .iter()
+            .fold(parachain_rpc, |parachain_rpc, (method, timeout)| {
+                parachain_rpc.with_call_timeout_for(method.clone(), *timeout)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_get_key_pair_with_password_file() {
+        let dir = std::env::temp_dir().join(format!("keyfile-password-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keyfile = dir.join("keyfile.json");
+        std::fs::write(&keyfile, r#"{ "vault": "//Alice" }"#).unwrap();
+        let password_file = dir.join("password");
+        std::fs::write(&password_file, "hunter2\n").unwrap();
+
+        let opts = ProviderUserOpts::try_parse_from([
+            "vault",
+            "--keyfile",
+            keyfile.to_str().unwrap(),
+            "--keyname",
+            "vault",
+            "--keyfile-password-file",
+            password_file.to_str().unwrap(),
+        ])
+        .unwrap();
+        let (pair, user_name) = opts.get_key_pair().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(user_name, "vault");
+        // the trailing newline is not part of the password
+        assert_eq!(
+            pair.public(),
+            Pair::from_string("//Alice", Some("hunter2")).unwrap().public()
+        );
+        assert_ne!(pair.public(), Pair::from_string("//Alice", None).unwrap().public());
+    }
+
+    const DEV_PHRASE: &str = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+
+    fn mnemonic_key_pair(mnemonic: &str, derivation_path: Option<&str>) -> Result<(Pair, String), Error> {
+        let mnemonic_file = std::env::temp_dir().join(format!(
+            "mnemonic-{}-{}",
+            std::process::id(),
+            derivation_path.unwrap_or_default().replace('/', "_")
+        ));
+        std::fs::write(&mnemonic_file, format!("{}\n", mnemonic)).unwrap();
+        let mut args = vec![
+            "vault".to_string(),
+            "--mnemonic-file".to_string(),
+            mnemonic_file.display().to_string(),
+        ];
+        if let Some(derivation_path) = derivation_path {
+            args.extend(["--derivation-path".to_string(), derivation_path.to_string()]);
+        }
+        let result = ProviderUserOpts::try_parse_from(args).unwrap().get_key_pair();
+        std::fs::remove_file(&mnemonic_file).unwrap();
+        result
+    }
+
+    #[test]
+    fn should_derive_key_pair_from_mnemonic() {
+        // the dev accounts are derived from the dev phrase
+        let (pair, user_name) = mnemonic_key_pair(DEV_PHRASE, Some("//Alice")).unwrap();
+        assert_eq!(pair.public(), AccountKeyring::Alice.public());
+        assert_eq!(user_name, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+
+        let (pair, _) = mnemonic_key_pair(DEV_PHRASE, Some("//vault//0")).unwrap();
+        assert_ne!(pair.public(), AccountKeyring::Alice.public());
+
+        let (pair, _) = mnemonic_key_pair(DEV_PHRASE, None).unwrap();
+        assert_eq!(pair.public(), Pair::from_phrase(DEV_PHRASE, None).unwrap().0.public());
+    }
+
+    #[test]
+    fn should_reject_invalid_mnemonic() {
+        // the valid mnemonic ends with "about"
+        let invalid_checksum = ["abandon"; 12].join(" ");
+        assert!(matches!(
+            mnemonic_key_pair(&invalid_checksum, None),
+            Err(Error::KeyLoadingFailure(KeyLoadingError::InvalidMnemonic(_)))
+        ));
+        // dev accounts are not mnemonics
+        assert!(matches!(
+            mnemonic_key_pair("//Alice", None),
+            Err(Error::KeyLoadingFailure(KeyLoadingError::InvalidMnemonic(_)))
+        ));
+        assert!(matches!(
+            mnemonic_key_pair(DEV_PHRASE, Some("vault")),
+            Err(Error::KeyLoadingFailure(KeyLoadingError::InvalidDerivationPath(_)))
+        ));
+    }
+
+    #[test]
+    fn should_reject_password_file_with_keyring() {
+        assert!(ProviderUserOpts::try_parse_from([
+            "vault",
+            "--keyring",
+            "alice",
+            "--keyfile-password-file",
+            "password",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn should_parse_call_timeout() {
+        assert_eq!(
+            parse_call_timeout("Oracle.feed_values=120000").unwrap(),
+            ("Oracle.feed_values".to_string(), Duration::from_secs(120))
+        );
+        assert!(parse_call_timeout("Oracle.feed_values").is_err());
+        assert!(parse_call_timeout("Oracle.feed_values=2m").is_err());
     }
 }
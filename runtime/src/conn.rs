@@ -1,50 +1,578 @@
-use crate::{error::JsonRpseeError, Error};
+use crate::{
+    error::{JsonRpseeError, RpcError},
+    tls::new_tls_websocket_client,
+    Error,
+};
+use futures::{
+    future::BoxFuture,
+    stream::{self, StreamExt},
+    FutureExt,
+};
 use jsonrpsee::{core::client::Client as WsClient, ws_client::WsClientBuilder};
-use std::time::Duration;
+use rustls::ClientConfig;
+use serde_json::value::RawValue;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+use subxt::rpc::{RpcClientT, RpcFuture, RpcSubscription};
 use tokio::time::{sleep, timeout};
 
 const RETRY_TIMEOUT: Duration = Duration::from_millis(1000);
+const MAX_RETRY_TIMEOUT: Duration = Duration::from_secs(30);
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
 
+// number of recent notifications remembered per subscription to drop re-deliveries
+const RECENT_NOTIFICATIONS: usize = 16;
+
+// subscriptions that submit an extrinsic, which must not be repeated on a new connection
+const SUBMITTING_SUBSCRIPTIONS: [&str; 1] = ["author_submitAndWatchExtrinsic"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connected,
+    Reconnecting,
+    Disconnected,
+}
+
+/// The state of a client's reconnecting connection, for metrics.
+#[derive(Debug)]
+pub(crate) struct ConnectionState {
+    status: RwLock<ConnectionStatus>,
+    reconnect_count: AtomicU64,
+}
+
+impl ConnectionState {
+    fn new(status: ConnectionStatus) -> Self {
+        Self {
+            status: RwLock::new(status),
+            reconnect_count: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn status(&self) -> ConnectionStatus {
+        *self.status.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn set_status(&self, status: ConnectionStatus) {
+        *self.status.write().unwrap_or_else(|err| err.into_inner()) = status;
+    }
+
+    /// The number of times the connection was re-established after being lost.
+    pub(crate) fn reconnect_count(&self) -> u64 {
+        self.reconnect_count.load(Ordering::SeqCst)
+    }
+}
+
+/// Whether the error means that the websocket connection was lost.
+pub(crate) fn is_disconnect_error(err: &RpcError) -> bool {
+    match err {
+        RpcError::ClientError(e) => match e.downcast_ref::<JsonRpseeError>() {
+            Some(e) => matches!(e, JsonRpseeError::RestartNeeded(_)),
+            None => {
+                log::error!("Failed to downcast RPC error; this is a bug please file an issue");
+                false
+            }
+        },
+        RpcError::SubscriptionDropped => true,
+    }
+}
+
+/// Split a comma-separated list of websocket URLs.
+pub(crate) fn parse_endpoints(urls: &str) -> Vec<&str> {
+    urls.split(',').map(str::trim).filter(|url| !url.is_empty()).collect()
+}
+
+/// The endpoints a single client connects to. The client stays on the endpoint it is connected
+/// to, and only moves on to the next one once that connection fails.
+#[derive(Debug)]
+pub(crate) struct Endpoints {
+    urls: Vec<String>,
+    // index of the endpoint of the current connection, `None` before connecting
+    active: Mutex<Option<usize>>,
+}
+
+impl Endpoints {
+    pub(crate) fn new(urls: &str) -> Self {
+        Self {
+            urls: parse_endpoints(urls).into_iter().map(str::to_string).collect(),
+            active: Mutex::new(None),
+        }
+    }
+
+    /// The endpoint of the current connection.
+    pub(crate) fn active(&self) -> Option<String> {
+        let active = *self.active.lock().unwrap_or_else(|err| err.into_inner());
+        active.map(|index| self.urls[index].clone())
+    }
+
+    /// Connect to the first endpoint or, if this replaces a lost connection, to the one after
+    /// the endpoint of that connection.
+    async fn connect<T, F, R>(&self, connect: F) -> Result<T, Error>
+    where
+        F: Fn(&str) -> R,
+        R: Future<Output = Result<T, Error>>,
+    {
+        let urls: Vec<_> = self.urls.iter().map(String::as_str).collect();
+        let start = self
+            .active
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .map_or(0, |index| index + 1);
+        let (index, rpc) = connect_with_failover(&urls, start, connect).await?;
+        log::info!("Connected to {}!", urls[index]);
+        *self.active.lock().unwrap_or_else(|err| err.into_inner()) = Some(index);
+        Ok(rpc)
+    }
+}
+
 pub(crate) async fn new_websocket_client(
     url: &str,
+    tls_config: Option<Arc<ClientConfig>>,
     max_concurrent_requests: Option<usize>,
     max_notifs_per_subscription: Option<usize>,
 ) -> Result<WsClient, Error> {
+    let max_concurrent_requests = max_concurrent_requests.unwrap_or(1024);
+    let max_notifs_per_subscription = max_notifs_per_subscription.unwrap_or(256);
+    if let Some(tls_config) = tls_config {
+        return timeout(
+            CONNECTION_TIMEOUT,
+            new_tls_websocket_client(url, tls_config, max_concurrent_requests, max_notifs_per_subscription),
+        )
+        .await?;
+    }
     let ws_client = WsClientBuilder::default()
         .connection_timeout(CONNECTION_TIMEOUT)
-        .max_concurrent_requests(max_concurrent_requests.unwrap_or(1024))
-        .max_notifs_per_subscription(max_notifs_per_subscription.unwrap_or(256))
+        .max_concurrent_requests(max_concurrent_requests)
+        .max_notifs_per_subscription(max_notifs_per_subscription)
         .build(url)
         .await?;
     Ok(ws_client)
 }
 
+/// Connect to one of the `endpoints`, which are tried round-robin so that a lost node is
+/// failed over.
 pub(crate) async fn new_websocket_client_with_retry(
-    url: &str,
+    endpoints: &Endpoints,
+    tls_config: Option<Arc<ClientConfig>>,
     max_concurrent_requests: Option<usize>,
     max_notifs_per_subscription: Option<usize>,
     connection_timeout: Duration,
 ) -> Result<WsClient, Error> {
     log::info!("Connecting to the btc-parachain...");
-    timeout(connection_timeout, async move {
-        loop {
-            match new_websocket_client(url, max_concurrent_requests, max_notifs_per_subscription).await {
+    timeout(
+        connection_timeout,
+        endpoints.connect(|url| {
+            new_websocket_client(
+                url,
+                tls_config.clone(),
+                max_concurrent_requests,
+                max_notifs_per_subscription,
+            )
+        }),
+    )
+    .await?
+}
+
+/// Try the endpoints in turn from `start`, backing off after each full round of failures.
+/// Returns the index of the endpoint that was connected to.
+async fn connect_with_failover<T, F, R>(endpoints: &[&str], start: usize, connect: F) -> Result<(usize, T), Error>
+where
+    F: Fn(&str) -> R,
+    R: Future<Output = Result<T, Error>>,
+{
+    if endpoints.is_empty() {
+        return Err(Error::NoParachainEndpoints);
+    }
+    let mut backoff = RETRY_TIMEOUT;
+    loop {
+        for offset in 0..endpoints.len() {
+            let index = (start + offset) % endpoints.len();
+            match connect(endpoints[index]).await {
                 Err(err) if err.is_ws_invalid_url_error() => {
                     return Err(err);
                 }
                 Err(Error::JsonRpseeError(JsonRpseeError::Transport(err))) => {
-                    log::trace!("could not connect to parachain: {}", err);
-                    sleep(RETRY_TIMEOUT).await;
-                    continue;
-                }
-                Ok(rpc) => {
-                    log::info!("Connected!");
-                    return Ok(rpc);
+                    if endpoints.len() > 1 {
+                        log::warn!(
+                            "could not connect to parachain at {}: {}, failing over to {}",
+                            endpoints[index],
+                            err,
+                            endpoints[(index + 1) % endpoints.len()]
+                        );
+                    } else {
+                        log::trace!("could not connect to parachain: {}", err);
+                    }
                 }
+                Ok(rpc) => return Ok((index, rpc)),
                 Err(err) => return Err(err),
             }
         }
-    })
-    .await?
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RETRY_TIMEOUT);
+    }
+}
+
+type Connector<C> = Arc<dyn Fn() -> BoxFuture<'static, Result<C, Error>> + Send + Sync>;
+
+/// An [`RpcClientT`] that replaces its client when the connection is lost. Subscriptions are
+/// re-established on the new client, so consumers see a continuous stream. Requests and extrinsic
+/// submissions fail with the disconnect instead, since they may already have been handled by the
+/// node and repeating them is up to the caller.
+pub(crate) struct ReconnectingRpcClient<C> {
+    connect: Connector<C>,
+    // the current client, and how often it has been replaced
+    state: Arc<tokio::sync::RwLock<(u64, Arc<C>)>>,
+    connection: Arc<ConnectionState>,
+}
+
+impl<C> Clone for ReconnectingRpcClient<C> {
+    fn clone(&self) -> Self {
+        Self {
+            connect: self.connect.clone(),
+            state: self.state.clone(),
+            connection: self.connection.clone(),
+        }
+    }
+}
+
+impl<C: RpcClientT> ReconnectingRpcClient<C> {
+    pub(crate) async fn new<F, R>(connect: F) -> Result<Self, Error>
+    where
+        F: Fn() -> R + Send + Sync + 'static,
+        R: Future<Output = Result<C, Error>> + Send + 'static,
+    {
+        let connect: Connector<C> = Arc::new(move || connect().boxed());
+        let client = connect().await?;
+        Ok(Self {
+            connect,
+            state: Arc::new(tokio::sync::RwLock::new((0, Arc::new(client)))),
+            connection: Arc::new(ConnectionState::new(ConnectionStatus::Connected)),
+        })
+    }
+
+    pub(crate) fn connection_state(&self) -> Arc<ConnectionState> {
+        self.connection.clone()
+    }
+
+    async fn current(&self) -> (u64, Arc<C>) {
+        let state = self.state.read().await;
+        (state.0, state.1.clone())
+    }
+
+    /// Replace the client of the given generation, unless another caller already did so.
+    async fn reconnect(&self, generation: u64) -> Result<(u64, Arc<C>), RpcError> {
+        let mut state = self.state.write().await;
+        if state.0 == generation {
+            log::warn!("Lost connection to the parachain, reconnecting...");
+            self.connection.set_status(ConnectionStatus::Reconnecting);
+            let client = (self.connect)().await.map_err(|err| {
+                self.connection.set_status(ConnectionStatus::Disconnected);
+                // still a disconnect, so callers fall back to restarting
+                RpcError::ClientError(Box::new(JsonRpseeError::RestartNeeded(err.to_string())))
+            })?;
+            *state = (generation + 1, Arc::new(client));
+            self.connection.reconnect_count.fetch_add(1, Ordering::SeqCst);
+            self.connection.set_status(ConnectionStatus::Connected);
+        }
+        Ok((state.0, state.1.clone()))
+    }
+}
+
+impl<C: RpcClientT> RpcClientT for ReconnectingRpcClient<C> {
+    fn request_raw<'a>(&'a self, method: &'a str, params: Option<Box<RawValue>>) -> RpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            let (generation, client) = self.current().await;
+            let result = client.request_raw(method, params).await;
+            if matches!(&result, Err(err) if is_disconnect_error(err)) {
+                // reconnect for the next call, but leave it to the caller to repeat this one
+                self.reconnect(generation).await?;
+            }
+            result
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        Box::pin(async move {
+            let (mut generation, mut client) = self.current().await;
+            if SUBMITTING_SUBSCRIPTIONS.contains(&sub) {
+                let result = client.subscribe_raw(sub, params, unsub).await;
+                if matches!(&result, Err(err) if is_disconnect_error(err)) {
+                    self.reconnect(generation).await?;
+                }
+                return result;
+            }
+            let inner = match client.subscribe_raw(sub, params.clone(), unsub).await {
+                Err(err) if is_disconnect_error(&err) => {
+                    (generation, client) = self.reconnect(generation).await?;
+                    client.subscribe_raw(sub, params.clone(), unsub).await?
+                }
+                result => result?,
+            };
+            Ok(RestoringSubscription {
+                client: self.clone(),
+                generation,
+                sub: sub.to_string(),
+                params,
+                unsub: unsub.to_string(),
+                inner: Some(inner),
+                recent: VecDeque::with_capacity(RECENT_NOTIFICATIONS),
+            }
+            .into_stream())
+        })
+    }
+}
+
+/// A subscription that is re-established on a new client when the connection drops.
+struct RestoringSubscription<C> {
+    client: ReconnectingRpcClient<C>,
+    generation: u64,
+    sub: String,
+    params: Option<Box<RawValue>>,
+    unsub: String,
+    // `None` once the subscription could not be restored
+    inner: Option<RpcSubscription>,
+    recent: VecDeque<String>,
+}
+
+impl<C: RpcClientT> RestoringSubscription<C> {
+    async fn restore(&mut self) -> Result<(), RpcError> {
+        let (generation, client) = self.client.reconnect(self.generation).await?;
+        self.inner = Some(
+            client
+                .subscribe_raw(&self.sub, self.params.clone(), &self.unsub)
+                .await?,
+        );
+        self.generation = generation;
+        log::info!("Restored subscription {}", self.sub);
+        Ok(())
+    }
+
+    /// Whether the notification was delivered before, e.g. the current head is
+    /// sent again when resubscribing.
+    fn is_redelivery(&mut self, notification: &RawValue) -> bool {
+        if self.recent.iter().any(|recent| recent == notification.get()) {
+            return true;
+        }
+        if self.recent.len() == RECENT_NOTIFICATIONS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(notification.get().to_string());
+        false
+    }
+
+    fn into_stream(self) -> RpcSubscription {
+        stream::unfold(self, |mut subscription| async move {
+            loop {
+                match subscription.inner.as_mut()?.next().await {
+                    Some(Ok(notification)) if subscription.is_redelivery(&notification) => continue,
+                    Some(Ok(notification)) => return Some((Ok(notification), subscription)),
+                    Some(Err(err)) if !is_disconnect_error(&err) => return Some((Err(err), subscription)),
+                    // the connection was lost, which may also just end the stream
+                    Some(Err(_)) | None => {}
+                }
+                if let Err(err) = subscription.restore().await {
+                    subscription.inner = None;
+                    return Some((Err(err), subscription));
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn transport_error() -> Error {
+        Error::JsonRpseeError(JsonRpseeError::Transport(anyhow::anyhow!("connection refused")))
+    }
+
+    #[test]
+    fn should_parse_endpoints() {
+        assert_eq!(
+            parse_endpoints("ws://a:9944, ws://b:9944,,"),
+            vec!["ws://a:9944", "ws://b:9944"]
+        );
+        assert_eq!(parse_endpoints("ws://a:9944"), vec!["ws://a:9944"]);
+    }
+
+    #[tokio::test]
+    async fn should_fail_over_to_second_endpoint() {
+        let attempts = Mutex::new(vec![]);
+        let (index, connected) = connect_with_failover(&["ws://down:9944", "ws://up:9944"], 0, |url| {
+            attempts.lock().unwrap().push(url.to_string());
+            let url = url.to_string();
+            async move {
+                match url.as_str() {
+                    "ws://up:9944" => Ok(url),
+                    _ => Err(transport_error()),
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(index, 1);
+        assert_eq!(connected, "ws://up:9944");
+        assert_eq!(*attempts.lock().unwrap(), vec!["ws://down:9944", "ws://up:9944"]);
+    }
+
+    #[tokio::test]
+    async fn should_stay_on_endpoint_until_it_fails() {
+        let endpoints = Endpoints::new("ws://a:9944,ws://b:9944");
+        let other_endpoints = Endpoints::new("ws://a:9944,ws://b:9944");
+        let connect = |url: &str| {
+            let url = url.to_string();
+            async move { Ok::<_, Error>(url) }
+        };
+        assert_eq!(endpoints.active(), None);
+
+        assert_eq!(endpoints.connect(connect).await.unwrap(), "ws://a:9944");
+        // other clients are not affected by this one's connections
+        assert_eq!(other_endpoints.connect(connect).await.unwrap(), "ws://a:9944");
+        assert_eq!(endpoints.active().as_deref(), Some("ws://a:9944"));
+
+        // reconnecting after the connection was lost moves on to the next endpoint
+        assert_eq!(endpoints.connect(connect).await.unwrap(), "ws://b:9944");
+        assert_eq!(endpoints.active().as_deref(), Some("ws://b:9944"));
+        assert_eq!(other_endpoints.active().as_deref(), Some("ws://a:9944"));
+        assert_eq!(endpoints.connect(connect).await.unwrap(), "ws://a:9944");
+    }
+
+    #[tokio::test]
+    async fn should_start_after_previous_endpoint() {
+        let (index, _) = connect_with_failover(&["ws://a:9944", "ws://b:9944"], 3, |url| {
+            let url = url.to_string();
+            async move { Ok::<_, Error>(url) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(index, 1);
+    }
+
+    struct MockClient {
+        // `None` drops the connection
+        notifications: Vec<Option<&'static str>>,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl RpcClientT for MockClient {
+        fn request_raw<'a>(&'a self, _: &'a str, _: Option<Box<RawValue>>) -> RpcFuture<'a, Box<RawValue>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Err(RpcError::SubscriptionDropped) })
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            _: &'a str,
+            _: Option<Box<RawValue>>,
+            _: &'a str,
+        ) -> RpcFuture<'a, RpcSubscription> {
+            let notifications = self.notifications.clone();
+            Box::pin(async move {
+                let subscription: RpcSubscription = stream::iter(notifications)
+                    .map(|notification| match notification {
+                        Some(value) => Ok(RawValue::from_string(value.to_string()).unwrap()),
+                        None => Err(RpcError::ClientError(Box::new(JsonRpseeError::RestartNeeded(
+                            "connection dropped".to_string(),
+                        )))),
+                    })
+                    .boxed();
+                Ok(subscription)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_restore_subscription_after_disconnect() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let client = ReconnectingRpcClient::new({
+            let connections = connections.clone();
+            move || {
+                let notifications = match connections.fetch_add(1, Ordering::SeqCst) {
+                    0 => vec![Some("1"), Some("2"), None],
+                    // the last notification is re-delivered after resubscribing
+                    _ => vec![Some("2"), Some("3")],
+                };
+                async move {
+                    Ok(MockClient {
+                        notifications,
+                        requests: Default::default(),
+                    })
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let notifications: Vec<_> = client
+            .subscribe_raw("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
+            .await
+            .unwrap()
+            .take(3)
+            .map(|notification| notification.unwrap().get().to_string())
+            .collect()
+            .await;
+
+        assert_eq!(notifications, vec!["1", "2", "3"]);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert_eq!(client.connection_state().reconnect_count(), 1);
+        assert_eq!(client.connection_state().status(), ConnectionStatus::Connected);
+    }
+
+    #[tokio::test]
+    async fn should_not_replay_requests_or_submissions_after_disconnect() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = ReconnectingRpcClient::new({
+            let connections = connections.clone();
+            let requests = requests.clone();
+            move || {
+                connections.fetch_add(1, Ordering::SeqCst);
+                let requests = requests.clone();
+                async move {
+                    Ok(MockClient {
+                        notifications: vec![Some("1"), None],
+                        requests,
+                    })
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        // the request fails with the disconnect, but the next call uses a new connection
+        assert!(matches!(
+            client.request_raw("author_submitExtrinsic", None).await,
+            Err(err) if is_disconnect_error(&err)
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // the submission is not repeated on a new connection
+        let notifications: Vec<_> = client
+            .subscribe_raw("author_submitAndWatchExtrinsic", None, "author_unwatchExtrinsic")
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].as_ref().unwrap().get(), "1");
+        assert!(matches!(&notifications[1], Err(err) if is_disconnect_error(err)));
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,115 @@
+use crate::{Balance, CurrencyId, Error, FixedPointNumber, FixedU128};
+use sp_arithmetic::traits::CheckedDiv;
+
+/// Convert `amount` of `from` into `to`. `exchange_rate` gives the oracle rate of a currency other than
+/// the `wrapped` one, in its smallest unit per satoshi. Conversions between two such currencies are
+/// routed through the wrapped currency. Every step rounds down, the same way the chain does.
+pub fn convert(
+    amount: Balance,
+    from: CurrencyId,
+    to: CurrencyId,
+    wrapped: CurrencyId,
+    exchange_rate: impl Fn(CurrencyId) -> Result<FixedU128, Error>,
+) -> Result<Balance, Error> {
+    if from == to {
+        return Ok(amount);
+    }
+    let wrapped_amount = if from == wrapped {
+        amount
+    } else {
+        collateral_to_wrapped(amount, exchange_rate(from)?)?
+    };
+    if to == wrapped {
+        Ok(wrapped_amount)
+    } else {
+        wrapped_to_collateral(wrapped_amount, exchange_rate(to)?)
+    }
+}
+
+/// Convert a wrapped `amount` at `exchange_rate`, like `oracle_wrappedToCollateral`.
+pub fn wrapped_to_collateral(amount: Balance, exchange_rate: FixedU128) -> Result<Balance, Error> {
+    exchange_rate.checked_mul_int(amount).ok_or(Error::ArithmeticOverflow)
+}
+
+/// Convert `amount` at `exchange_rate` into the wrapped currency, like `oracle_collateralToWrapped`.
+pub fn collateral_to_wrapped(amount: Balance, exchange_rate: FixedU128) -> Result<Balance, Error> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    FixedU128::checked_from_integer(amount)
+        .and_then(|amount| amount.checked_div(&exchange_rate))
+        // truncate the fractional part
+        .map(|wrapped_amount| wrapped_amount.into_inner() / FixedU128::accuracy())
+        .ok_or(Error::ArithmeticOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Token, DOT, KBTC, KSM};
+
+    // 1 KBTC = 2000 KSM, with 8 and 12 decimals respectively
+    fn ksm_rate() -> FixedU128 {
+        FixedU128::saturating_from_integer(20_000_000u128)
+    }
+
+    // 1 KBTC = 5000 DOT, with 8 and 10 decimals respectively
+    fn dot_rate() -> FixedU128 {
+        FixedU128::saturating_from_integer(500_000u128)
+    }
+
+    fn exchange_rate(currency_id: CurrencyId) -> Result<FixedU128, Error> {
+        match currency_id {
+            Token(KSM) => Ok(ksm_rate()),
+            Token(DOT) => Ok(dot_rate()),
+            _ => Err(Error::InvalidCurrency),
+        }
+    }
+
+    #[test]
+    fn should_convert_directly() -> Result<(), Error> {
+        let one_kbtc = 100_000_000;
+        let ksm = convert(one_kbtc, Token(KBTC), Token(KSM), Token(KBTC), exchange_rate)?;
+        assert_eq!(ksm, 2_000_000_000_000_000);
+        assert_eq!(
+            convert(ksm, Token(KSM), Token(KBTC), Token(KBTC), exchange_rate)?,
+            one_kbtc
+        );
+        assert_eq!(convert(42, Token(KSM), Token(KSM), Token(KBTC), exchange_rate)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn should_convert_through_wrapped_currency() -> Result<(), Error> {
+        // 1 KSM is 50_000 satoshi, which is 2.5 DOT
+        let one_ksm = 1_000_000_000_000;
+        assert_eq!(
+            convert(one_ksm, Token(KSM), Token(DOT), Token(KBTC), exchange_rate)?,
+            25_000_000_000
+        );
+        assert_eq!(
+            convert(25_000_000_000, Token(DOT), Token(KSM), Token(KBTC), exchange_rate)?,
+            one_ksm
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn should_round_down() -> Result<(), Error> {
+        let rate = FixedU128::saturating_from_rational(3, 2);
+        assert_eq!(wrapped_to_collateral(3, rate)?, 4);
+        assert_eq!(collateral_to_wrapped(5, rate)?, 3);
+        assert_eq!(collateral_to_wrapped(0, FixedU128::from_inner(0))?, 0);
+        assert!(matches!(
+            collateral_to_wrapped(1, FixedU128::from_inner(0)),
+            Err(Error::ArithmeticOverflow)
+        ));
+        // the fraction of a satoshi is lost before converting into DOT
+        let one_planck_short = 1_000_000_000_000 - 1;
+        assert_eq!(
+            convert(one_planck_short, Token(KSM), Token(DOT), Token(KBTC), exchange_rate)?,
+            49_999 * 500_000
+        );
+        Ok(())
+    }
+}
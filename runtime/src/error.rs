@@ -1,6 +1,9 @@
 pub use jsonrpsee::core::Error as JsonRpseeError;
 
-use crate::{types::*, BTC_RELAY_MODULE, ISSUE_MODULE, SYSTEM_MODULE, VAULT_REGISTRY_MODULE};
+use crate::{
+    conn::is_disconnect_error, types::*, FixedPointNumber, BTC_RELAY_MODULE, ISSUE_MODULE, SYSTEM_MODULE,
+    VAULT_REGISTRY_MODULE,
+};
 use codec::Error as CodecError;
 use jsonrpsee::{
     client_transport::ws::WsHandshakeError,
@@ -8,10 +11,17 @@ use jsonrpsee::{
 };
 use prometheus::Error as PrometheusError;
 use serde_json::Error as SerdeJsonError;
-use std::{array::TryFromSliceError, fmt::Debug, io::Error as IoError, num::TryFromIntError, str::Utf8Error};
+use std::{
+    array::TryFromSliceError,
+    fmt::{self, Debug, Display},
+    io::Error as IoError,
+    num::TryFromIntError,
+    str::Utf8Error,
+};
 use subxt::{
     error::{DispatchError, ModuleError, TransactionError},
-    ext::sp_core::crypto::SecretStringError,
+    ext::{sp_core::crypto::SecretStringError, sp_runtime},
+    Metadata,
 };
 use thiserror::Error;
 use tokio::time::error::Elapsed;
@@ -47,12 +57,34 @@ pub enum Error {
     BlockHashNotFound,
     #[error("Transaction is invalid: {0}")]
     InvalidTransaction(String),
+    #[error("No pending transaction with nonce {0}")]
+    TransactionNotPending(u32),
+    #[error("Replacement tip {0} must be higher than the current tip {1}")]
+    ReplacementTipTooLow(Balance, Balance),
     #[error("Request has timed out")]
     Timeout,
+    #[error("Call to {0} timed out")]
+    CallTimeout(String),
+    #[error("Timed out waiting for Bitcoin confirmations")]
+    ConfirmationTimeout,
+    #[error("Timed out waiting for the parachain node to sync")]
+    SyncTimeout,
+    #[error("Invalid sync state reported by the node: {0}")]
+    InvalidSyncState(String),
+    #[error("Circuit breaker for {0} is open after repeated failures")]
+    CircuitOpen(String),
+    #[error("Shutdown was signaled while retrying")]
+    Shutdown,
     #[error("Block is not in the relay main chain")]
     BlockNotInRelayMainChain,
     #[error("Invalid currency")]
     InvalidCurrency,
+    #[error("Invalid currency `{0}`, expected one of {1}, a foreign asset symbol or FOREIGN(<id>)")]
+    InvalidCurrencyArg(String, String),
+    #[error("Invalid currency symbol alias {0}, expected ALIAS=SYMBOL")]
+    InvalidSymbolAlias(String),
+    #[error("Currency symbol alias {0} is the symbol of a currency")]
+    SymbolAliasShadowsCurrency(String),
     #[error("Invalid keyring arguments")]
     KeyringArgumentError,
     #[error("Failed to parse keyring account")]
@@ -61,6 +93,30 @@ pub enum Error {
     StorageItemNotFound,
     #[error("Insufficient funds")]
     InsufficientFunds,
+    #[error("Only {0} tokens can be replaced, less than the minimum of {1}")]
+    ReplaceAmountTooLow(Balance, Balance),
+    #[error("Collateralization after withdrawal {:.4} is below {:.4}", .0.to_float(), .1.to_float())]
+    WouldBreachThreshold(FixedU128, FixedU128),
+    #[error("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[error("Could not parse amount")]
+    InvalidAmount,
+    #[error("TLS configuration error: {0}")]
+    TlsConfig(String),
+    #[error("No parachain endpoints configured")]
+    NoParachainEndpoints,
+    #[error("Could not parse fee estimate: {0}")]
+    InvalidFeeEstimate(String),
+    #[error("Could not parse weight: {0}")]
+    InvalidWeight(String),
+    #[error("Call {0} weighs {1}, more than the maximum batch weight of {2}")]
+    CallTooHeavy(usize, u64, u64),
+    #[error("Failed to feed values for {0:?}: {1}")]
+    FeedValuesFailed(Vec<OracleKey>, Box<Error>),
+    #[error("Not submitting {0} in observer mode")]
+    ObserverMode(String),
+    #[error("Amount has more than {0} decimals")]
+    TooManyDecimals(u32),
     #[error("Currency not found")]
     CurrencyNotFound,
     #[error("Client does not support spec_version: expected {0}..{1}, got {2}")]
@@ -75,7 +131,7 @@ pub enum Error {
     Serialize(#[from] TryFromSliceError),
     #[error("Error converting: {0}")]
     Convert(#[from] TryFromIntError),
-    #[error("Subxt runtime error: {0}")]
+    #[error("Subxt runtime error: {}", display_subxt_error(.0))]
     SubxtRuntimeError(#[from] SubxtError),
     #[error("Error decoding: {0}")]
     CodecError(#[from] CodecError),
@@ -105,6 +161,15 @@ impl Error {
         )
     }
 
+    /// The names of a failed dispatch, if this is a module error. Subxt resolves these against the
+    /// metadata of the connected chain.
+    pub fn module_error(&self) -> Option<DecodedModuleError> {
+        match self {
+            Error::SubxtRuntimeError(err) => DecodedModuleError::from_subxt_error(err),
+            _ => None,
+        }
+    }
+
     pub fn is_duplicate_block(&self) -> bool {
         self.is_module_err(BTC_RELAY_MODULE, &format!("{:?}", BtcRelayPalletError::DuplicateBlock))
     }
@@ -151,6 +216,14 @@ impl Error {
         })
     }
 
+    /// Whether the transaction was rejected because its nonce is either
+    /// already used (stale) or not yet valid (future).
+    pub fn is_invalid_nonce(&self) -> bool {
+        self.is_invalid_transaction()
+            .map(|data| data.contains(STALE_TX_MESSAGE) || data.contains(FUTURE_TX_MESSAGE))
+            .unwrap_or(false)
+    }
+
     pub fn is_pool_too_low_priority(&self) -> Option<()> {
         self.map_custom_error(|custom_error| {
             if custom_error.code() == POOL_TOO_LOW_PRIORITY {
@@ -163,16 +236,7 @@ impl Error {
 
     pub fn is_rpc_disconnect_error(&self) -> bool {
         match self {
-            Error::SubxtRuntimeError(SubxtError::Rpc(RpcError::ClientError(e))) => {
-                match e.downcast_ref::<JsonRpseeError>() {
-                    Some(e) => matches!(e, JsonRpseeError::RestartNeeded(_)),
-                    None => {
-                        log::error!("Failed to downcast RPC error; this is a bug please file an issue");
-                        return false;
-                    }
-                }
-            }
-            Error::SubxtRuntimeError(SubxtError::Rpc(RpcError::SubscriptionDropped)) => true,
+            Error::SubxtRuntimeError(SubxtError::Rpc(err)) => is_disconnect_error(err),
             _ => false,
         }
     }
@@ -207,11 +271,92 @@ impl Error {
         false
     }
 
+    /// Whether the block or its state is no longer available, e.g. since the node pruned it.
+    pub fn is_pruned_block_error(&self) -> bool {
+        matches!(self, Error::BlockNotFound)
+            || self
+                .map_custom_error(|custom_error| custom_error.message().contains(STATE_DISCARDED_MESSAGE).then_some(()))
+                .is_some()
+    }
+
     pub fn is_parachain_shutdown_error(&self) -> bool {
         self.is_module_err(SYSTEM_MODULE, &format!("{:?}", SystemPalletError::CallFiltered))
     }
 }
 
+/// A `DispatchError::Module` resolved against the runtime metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedModuleError {
+    pub pallet_index: u8,
+    pub error_index: u8,
+    pub pallet: String,
+    pub error: String,
+    pub docs: Vec<String>,
+}
+
+impl DecodedModuleError {
+    /// Look up the error with the given indices in `metadata`, which should be that of the chain
+    /// the error was reported by, since indices differ between runtimes.
+    pub fn decode(metadata: &Metadata, pallet_index: u8, error_index: u8) -> Option<Self> {
+        let details = metadata.error(pallet_index, error_index).ok()?;
+        Some(Self {
+            pallet_index,
+            error_index,
+            pallet: details.pallet().to_string(),
+            error: details.error().to_string(),
+            docs: details.description().to_vec(),
+        })
+    }
+
+    fn from_subxt_error(err: &SubxtError) -> Option<Self> {
+        match err {
+            SubxtError::Runtime(DispatchError::Module(ModuleError {
+                pallet,
+                error,
+                description,
+                error_data,
+            })) => Some(Self {
+                pallet_index: error_data.pallet_index,
+                error_index: error_data.error_index(),
+                pallet: pallet.clone(),
+                error: error.clone(),
+                docs: description.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DecodedModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.pallet, self.error)?;
+        if !self.docs.is_empty() {
+            write!(f, " ({})", self.docs.join(" ").trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe the outcome of a failed `system_dryRun`, naming module errors where possible.
+pub(crate) fn describe_dry_run_error(metadata: &Metadata, dispatch_error: &sp_runtime::DispatchError) -> String {
+    match dispatch_error {
+        sp_runtime::DispatchError::Module(sp_runtime::ModuleError { index, error, .. }) => {
+            match DecodedModuleError::decode(metadata, *index, error[0]) {
+                Some(decoded) => decoded.to_string(),
+                None => format!("{:?}", dispatch_error),
+            }
+        }
+        _ => format!("{:?}", dispatch_error),
+    }
+}
+
+fn display_subxt_error(err: &SubxtError) -> String {
+    match DecodedModuleError::from_subxt_error(err) {
+        Some(decoded) => decoded.to_string(),
+        None => err.to_string(),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum KeyLoadingError {
     #[error("Key not found in file")]
@@ -222,9 +367,67 @@ pub enum KeyLoadingError {
     IoError(#[from] IoError),
     #[error("Invalid secret string: {0:?}")]
     SecretStringError(SecretStringError),
+    #[error("Failed to read the keyfile password from {0}: {1}")]
+    PasswordFile(String, IoError),
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("Invalid derivation path: `{0}`")]
+    InvalidDerivationPath(String),
 }
 
 // https://github.com/paritytech/substrate/blob/e60597dff0aa7ffad623be2cc6edd94c7dc51edd/client/rpc-api/src/author/error.rs#L80
 const BASE_ERROR: i32 = 1000;
 const POOL_INVALID_TX: i32 = BASE_ERROR + 10;
 const POOL_TOO_LOW_PRIORITY: i32 = POOL_INVALID_TX + 4;
+
+// https://github.com/paritytech/substrate/blob/e60597dff0aa7ffad623be2cc6edd94c7dc51edd/primitives/runtime/src/transaction_validity.rs#L113
+const STALE_TX_MESSAGE: &str = "Transaction is outdated";
+const FUTURE_TX_MESSAGE: &str = "Transaction will be valid in the future";
+// reported by the state RPCs for blocks whose state was pruned
+const STATE_DISCARDED_MESSAGE: &str = "State already discarded";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::{Decode, Encode};
+    use std::convert::TryFrom;
+    use subxt::ext::frame_metadata::RuntimeMetadataPrefixed;
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parachain-metadata-interlay")] {
+            const RUNTIME_METADATA: &[u8] = include_bytes!("../metadata-parachain-interlay.scale");
+        } else if #[cfg(feature = "parachain-metadata-kintsugi")] {
+            const RUNTIME_METADATA: &[u8] = include_bytes!("../metadata-parachain-kintsugi.scale");
+        } else if #[cfg(feature = "parachain-metadata-interlay-testnet")] {
+            const RUNTIME_METADATA: &[u8] = include_bytes!("../metadata-parachain-interlay-testnet.scale");
+        } else if #[cfg(feature = "parachain-metadata-kintsugi-testnet")] {
+            const RUNTIME_METADATA: &[u8] = include_bytes!("../metadata-parachain-kintsugi-testnet.scale");
+        }
+    }
+
+    fn runtime_metadata() -> Metadata {
+        Metadata::try_from(RuntimeMetadataPrefixed::decode(&mut &RUNTIME_METADATA[..]).unwrap()).unwrap()
+    }
+
+    fn pallet_index(name: &str) -> u8 {
+        runtime_metadata().pallet(name).unwrap().index()
+    }
+
+    #[test]
+    fn should_decode_module_error_from_metadata() {
+        let pallet_index = pallet_index(VAULT_REGISTRY_MODULE);
+        let error_index = VaultRegistryPalletError::InsufficientCollateral.encode()[0];
+
+        let decoded = DecodedModuleError::decode(&runtime_metadata(), pallet_index, error_index).unwrap();
+        assert_eq!(decoded.pallet_index, pallet_index);
+        assert_eq!(decoded.error_index, error_index);
+        assert_eq!(decoded.pallet, VAULT_REGISTRY_MODULE);
+        assert_eq!(decoded.error, "InsufficientCollateral");
+        assert!(decoded.to_string().starts_with("VaultRegistry::InsufficientCollateral"));
+    }
+
+    #[test]
+    fn should_not_decode_unknown_module_error() {
+        assert_eq!(DecodedModuleError::decode(&runtime_metadata(), u8::MAX, u8::MAX), None);
+    }
+}
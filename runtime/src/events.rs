@@ -0,0 +1,132 @@
+use crate::{Error, SubxtError};
+use codec::Decode;
+use subxt::events::{EventDetails, StaticEvent};
+
+/// An undecoded event as found in a block.
+pub(crate) trait RawEvent {
+    fn pallet_name(&self) -> &str;
+    fn variant_name(&self) -> &str;
+    fn field_bytes(&self) -> &[u8];
+}
+
+impl RawEvent for EventDetails {
+    fn pallet_name(&self) -> &str {
+        EventDetails::pallet_name(self)
+    }
+
+    fn variant_name(&self) -> &str {
+        EventDetails::variant_name(self)
+    }
+
+    fn field_bytes(&self) -> &[u8] {
+        EventDetails::field_bytes(self)
+    }
+}
+
+/// Decode all events of type `T`, skipping events emitted by other pallets or of other variants.
+/// Events that could not be read or decoded are returned as errors so the caller can report them.
+pub(crate) fn decode_events<T, E, I>(events: I) -> Vec<Result<T, Error>>
+where
+    T: StaticEvent + Decode,
+    E: RawEvent,
+    I: IntoIterator<Item = Result<E, SubxtError>>,
+{
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            Ok(event) if event.pallet_name() == T::PALLET && event.variant_name() == T::EVENT => {
+                Some(T::decode(&mut event.field_bytes()).map_err(Error::from))
+            }
+            Ok(_) => None,
+            Err(err) => Some(Err(err.into())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use codec::Encode;
+
+    struct SyntheticEvent {
+        pallet: &'static str,
+        variant: &'static str,
+        bytes: Vec<u8>,
+    }
+
+    impl RawEvent for SyntheticEvent {
+        fn pallet_name(&self) -> &str {
+            self.pallet
+        }
+
+        fn variant_name(&self) -> &str {
+            self.variant
+        }
+
+        fn field_bytes(&self) -> &[u8] {
+            &self.bytes
+        }
+    }
+
+    fn dummy_request_issue_event() -> RequestIssueEvent {
+        RequestIssueEvent {
+            issue_id: H256::from_low_u64_be(1),
+            requester: AccountId::new([1; 32]),
+            amount: 1000,
+            fee: 10,
+            griefing_collateral: 100,
+            vault_id: VaultId::new(AccountId::new([2; 32]), Token(KSM), Token(KBTC)),
+            vault_address: BtcAddress::P2WPKHv0(H160::from_low_u64_be(3)),
+            vault_public_key: BtcPublicKey { 0: [4; 33] },
+        }
+    }
+
+    #[test]
+    fn should_filter_and_decode_request_issue_events() {
+        let event = dummy_request_issue_event();
+        let events = vec![
+            Ok(SyntheticEvent {
+                pallet: "Issue",
+                variant: "ExecuteIssue",
+                bytes: vec![0; 4],
+            }),
+            Ok(SyntheticEvent {
+                pallet: "Redeem",
+                variant: "RequestIssue",
+                bytes: event.encode(),
+            }),
+            Ok(SyntheticEvent {
+                pallet: "Issue",
+                variant: "RequestIssue",
+                bytes: event.encode(),
+            }),
+        ];
+
+        let decoded = decode_events::<RequestIssueEvent, _, _>(events);
+        assert_eq!(decoded.len(), 1);
+        let decoded = decoded.into_iter().next().unwrap().unwrap();
+        assert_eq!(decoded.issue_id, event.issue_id);
+        assert_eq!(decoded.vault_id, event.vault_id);
+        assert_eq!(decoded.amount, event.amount);
+        assert_eq!(decoded.vault_address, event.vault_address);
+    }
+
+    #[test]
+    fn should_report_undecodable_events() {
+        let events = vec![
+            Ok(SyntheticEvent {
+                pallet: "Issue",
+                variant: "RequestIssue",
+                bytes: vec![0; 4],
+            }),
+            Err(SubxtError::Other("dropped".to_string())),
+        ];
+
+        let decoded = decode_events::<RequestIssueEvent, _, _>(events);
+        assert!(matches!(
+            decoded.as_slice(),
+            [Err(Error::CodecError(_)), Err(Error::SubxtRuntimeError(_))]
+        ));
+    }
+}
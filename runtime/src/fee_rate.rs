@@ -0,0 +1,118 @@
+use crate::{Error, FixedU128};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+pub(crate) type FeeRateUpdateSender = broadcast::Sender<FixedU128>;
+pub type FeeRateUpdateReceiver = broadcast::Receiver<FixedU128>;
+
+/// Broadcasts fee rate updates and remembers the latest one for late subscribers.
+#[derive(Clone)]
+pub(crate) struct FeeRateUpdates {
+    tx: FeeRateUpdateSender,
+    latest: Arc<RwLock<Option<FixedU128>>>,
+}
+
+impl FeeRateUpdates {
+    pub(crate) fn new() -> Self {
+        // low capacity channel since we generally only care about the newest value, so it's ok
+        // if we miss an event
+        let (tx, _) = broadcast::channel(2);
+        Self {
+            tx,
+            latest: Default::default(),
+        }
+    }
+
+    pub(crate) fn send(&self, fee_rate: FixedU128) {
+        *self.latest.write().unwrap() = Some(fee_rate);
+        // an error only means that nobody is subscribed right now
+        let _ = self.tx.send(fee_rate);
+    }
+
+    /// Plain receiver that only sees updates sent after subscribing
+    pub(crate) fn receiver(&self) -> FeeRateUpdateReceiver {
+        self.tx.subscribe()
+    }
+
+    pub(crate) fn subscribe(&self) -> FeeRateSubscription {
+        let receiver = self.tx.subscribe();
+        FeeRateSubscription {
+            // read after subscribing, so that no update can be missed in between
+            pending: *self.latest.read().unwrap(),
+            latest: self.latest.clone(),
+            receiver,
+        }
+    }
+}
+
+/// Independent receiver of fee rate updates. Starts with the latest known fee rate, if any, and
+/// skips ahead to the latest fee rate if it falls behind.
+pub struct FeeRateSubscription {
+    pending: Option<FixedU128>,
+    latest: Arc<RwLock<Option<FixedU128>>>,
+    receiver: FeeRateUpdateReceiver,
+}
+
+impl FeeRateSubscription {
+    pub async fn recv(&mut self) -> Result<FixedU128, Error> {
+        if let Some(fee_rate) = self.pending.take() {
+            return Ok(fee_rate);
+        }
+        match self.receiver.recv().await {
+            Ok(fee_rate) => Ok(fee_rate),
+            Err(RecvError::Lagged(skipped)) => {
+                log::debug!("Skipped {} fee rate updates, using the latest one", skipped);
+                // the buffered updates are outdated, so only wait for updates after the latest one
+                self.receiver = self.receiver.resubscribe();
+                match *self.latest.read().unwrap() {
+                    Some(fee_rate) => Ok(fee_rate),
+                    None => Err(Error::ChannelClosed),
+                }
+            }
+            Err(RecvError::Closed) => Err(Error::ChannelClosed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedPointNumber;
+
+    fn fee_rate(n: u128) -> FixedU128 {
+        FixedU128::saturating_from_integer(n)
+    }
+
+    #[tokio::test]
+    async fn test_all_subscribers_observe_updates() {
+        let updates = FeeRateUpdates::new();
+        let mut first = updates.subscribe();
+        let mut second = updates.subscribe();
+
+        updates.send(fee_rate(1));
+        assert_eq!(first.recv().await.unwrap(), fee_rate(1));
+        assert_eq!(second.recv().await.unwrap(), fee_rate(1));
+
+        // late subscribers start with the current value
+        let mut late = updates.subscribe();
+        assert_eq!(late.recv().await.unwrap(), fee_rate(1));
+
+        updates.send(fee_rate(2));
+        for subscription in [&mut first, &mut second, &mut late] {
+            assert_eq!(subscription.recv().await.unwrap(), fee_rate(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_skips_to_latest() {
+        let updates = FeeRateUpdates::new();
+        let mut subscription = updates.subscribe();
+        for n in 1..=5 {
+            updates.send(fee_rate(n));
+        }
+        assert_eq!(subscription.recv().await.unwrap(), fee_rate(5));
+
+        updates.send(fee_rate(6));
+        assert_eq!(subscription.recv().await.unwrap(), fee_rate(6));
+    }
+}
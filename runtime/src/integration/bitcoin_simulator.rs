@@ -324,10 +324,13 @@ impl BitcoinCoreApi for MockBitcoinCore {
             sleep(Duration::from_secs(1)).await;
         }
     }
-    fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError> {
+    async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError> {
         Ok(Amount::ZERO)
     }
-    fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError> {
+    async fn list_transactions(
+        &self,
+        max_count: Option<usize>,
+    ) -> Result<Vec<json::ListTransactionResult>, BitcoinError> {
         Ok(vec![])
     }
     async fn get_block_count(&self) -> Result<u64, BitcoinError> {
@@ -389,10 +392,10 @@ impl BitcoinCoreApi for MockBitcoinCore {
         let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key);
         Ok(PublicKey::new(public_key))
     }
-    fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError> {
+    async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError> {
         todo!()
     }
-    fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError> {
+    async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError> {
         todo!()
     }
     async fn add_new_deposit_key(&self, _public_key: PublicKey, _secret_key: Vec<u8>) -> Result<(), BitcoinError> {
@@ -493,7 +496,7 @@ impl BitcoinCoreApi for MockBitcoinCore {
     async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), BitcoinError> {
         Ok(())
     }
-    fn get_utxo_count(&self) -> Result<usize, BitcoinError> {
+    async fn get_utxo_count(&self) -> Result<usize, BitcoinError> {
         Ok(0)
     }
 
@@ -508,4 +511,12 @@ impl BitcoinCoreApi for MockBitcoinCore {
     async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, BitcoinError> {
         unimplemented!()
     }
+
+    async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, BitcoinError> {
+        Ok(None)
+    }
+
+    async fn wait_for_transaction_in_mempool(&self, _txid: Txid, _timeout: Duration) -> Result<(), BitcoinError> {
+        Ok(())
+    }
 }
@@ -105,7 +105,7 @@ pub async fn setup_provider(client: SubxtClient, key: AccountKeyring) -> InterBt
     let shutdown_tx = crate::ShutdownSender::new();
 
     let json_client: JsonRpcClient = client.into();
-    InterBtcParachain::new(json_client, signer, shutdown_tx)
+    InterBtcParachain::new(json_client, signer, false, shutdown_tx)
         .await
         .expect("Error creating parachain_rpc")
 }
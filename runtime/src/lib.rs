@@ -4,11 +4,20 @@ pub mod cli;
 
 mod addr;
 mod assets;
+mod blocks;
+mod call_timeout;
 mod conn;
+mod conversion;
 mod error;
+mod events;
+mod fee_rate;
+mod metrics;
+mod pending_tx;
 mod retry;
 mod rpc;
 mod shutdown;
+mod thresholds;
+mod tls;
 
 pub mod types;
 
@@ -27,24 +36,33 @@ use subxt::{
     Config,
 };
 
-pub use addr::PartialAddress;
-pub use assets::{AssetRegistry, RuntimeCurrencyInfo, TryFromSymbol};
-pub use error::{Error, SubxtError};
+pub use addr::{P2trAddress, PartialAddress};
+pub use assets::{
+    add_symbol_alias, parse_currency_with_registry, parse_symbol_aliases, AssetRegistry, CurrencyArg,
+    RuntimeCurrencyInfo, TryFromSymbol, SYMBOL_ALIASES_ENV,
+};
+pub use blocks::BlockCursor;
+pub use conn::ConnectionStatus;
+pub use conversion::{collateral_to_wrapped, convert, wrapped_to_collateral};
+pub use error::{DecodedModuleError, Error, SubxtError};
+pub use fee_rate::{FeeRateSubscription, FeeRateUpdateReceiver};
+pub use metrics::register_runtime_metrics;
+pub use pending_tx::PendingTransaction;
 pub use primitives::CurrencyInfo;
 pub use prometheus;
-pub use retry::{notify_retry, RetryPolicy};
+pub use retry::{notify_retry, notify_retry_until_shutdown, CircuitBreaker, CircuitState, RetryConfig, RetryPolicy};
 #[cfg(feature = "testing-utils")]
 pub use rpc::SudoPallet;
 pub use rpc::{
-    BtcRelayPallet, CollateralBalancesPallet, FeePallet, FeeRateUpdateReceiver, InterBtcParachain, IssuePallet,
-    OraclePallet, RedeemPallet, ReplacePallet, SecurityPallet, TimestampPallet, UtilFuncs, VaultRegistryPallet,
-    DEFAULT_SPEC_NAME, SS58_PREFIX,
+    BtcRelayPallet, CollateralBalancesPallet, FeePallet, InterBtcParachain, IssuePallet, OraclePallet, RedeemPallet,
+    ReplacePallet, SecurityPallet, TimestampPallet, UtilFuncs, VaultRegistryPallet, DEFAULT_SPEC_NAME, SS58_PREFIX,
 };
-pub use shutdown::{ShutdownReceiver, ShutdownSender};
+pub use shutdown::{ShutdownReason, ShutdownReceiver, ShutdownSender};
 pub use sp_arithmetic::{traits as FixedPointTraits, FixedI128, FixedPointNumber, FixedU128};
 pub use std::collections::btree_set::BTreeSet;
 use std::time::Duration;
 pub use subxt::ext::sp_core::{self, crypto::Ss58Codec, sr25519::Pair};
+pub use thresholds::Thresholds;
 pub use types::*;
 
 pub const TX_FEES: u128 = 2000000000;
@@ -140,25 +158,28 @@ impl Config for InterBtcRuntime {
 }
 
 pub fn parse_collateral_currency(src: &str) -> Result<CurrencyId, Error> {
-    match src.to_uppercase().as_str() {
-        id if id == KSM.symbol() => Ok(Token(KSM)),
-        id if id == DOT.symbol() => Ok(Token(DOT)),
-        x => parse_native_currency(x),
+    parse_collateral_currency_with_registry(src, &AssetRegistry::snapshot()?)
+}
+
+/// Like `parse_collateral_currency`, but looks up foreign assets in `registry`. Foreign assets
+/// are only accepted as collateral, not as the native or wrapped currency.
+pub fn parse_collateral_currency_with_registry(src: &str, registry: &AssetRegistry) -> Result<CurrencyId, Error> {
+    match parse_currency_with_registry(src, registry)? {
+        currency_id @ (Token(KSM) | Token(DOT) | Token(KINT) | Token(INTR) | ForeignAsset(_)) => Ok(currency_id),
+        _ => Err(Error::InvalidCurrency),
     }
 }
 
 pub fn parse_native_currency(src: &str) -> Result<CurrencyId, Error> {
-    match src.to_uppercase().as_str() {
-        id if id == KINT.symbol() => Ok(Token(KINT)),
-        id if id == INTR.symbol() => Ok(Token(INTR)),
+    match src.parse::<CurrencyArg>()?.currency_id() {
+        Some(currency_id @ (Token(KINT) | Token(INTR))) => Ok(currency_id),
         _ => Err(Error::InvalidCurrency),
     }
 }
 
 pub fn parse_wrapped_currency(src: &str) -> Result<CurrencyId, Error> {
-    match src.to_uppercase().as_str() {
-        id if id == KBTC.symbol() => Ok(Token(KBTC)),
-        id if id == IBTC.symbol() => Ok(Token(IBTC)),
+    match src.parse::<CurrencyArg>()?.currency_id() {
+        Some(currency_id @ (Token(KBTC) | Token(IBTC))) => Ok(currency_id),
         _ => Err(Error::InvalidCurrency),
     }
 }
@@ -0,0 +1,79 @@
+use crate::Error;
+use lazy_static::lazy_static;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use std::future::Future;
+
+const METHOD_LABEL: &str = "method";
+const OPERATION_LABEL: &str = "operation";
+
+lazy_static! {
+    static ref RPC_CALL_LATENCY: HistogramVec = HistogramVec::new(
+        HistogramOpts::new("parachain_rpc_latency_seconds", "Round-trip latency of parachain calls"),
+        &[METHOD_LABEL]
+    )
+    .expect("Failed to create prometheus metric");
+    static ref RPC_CALL_FAILURES: IntCounterVec = IntCounterVec::new(
+        Opts::new("parachain_rpc_failures", "Number of failed parachain calls"),
+        &[METHOD_LABEL]
+    )
+    .expect("Failed to create prometheus metric");
+    pub(crate) static ref RETRIES: IntCounterVec = IntCounterVec::new(
+        Opts::new("retries", "Number of times a failed operation was retried"),
+        &[OPERATION_LABEL]
+    )
+    .expect("Failed to create prometheus metric");
+}
+
+/// Register the runtime metrics, so that they are exported with the caller's metrics.
+pub fn register_runtime_metrics(registry: &Registry) -> Result<(), Error> {
+    registry.register(Box::new(RPC_CALL_LATENCY.clone()))?;
+    registry.register(Box::new(RPC_CALL_FAILURES.clone()))?;
+    registry.register(Box::new(RETRIES.clone()))?;
+    Ok(())
+}
+
+/// Record the latency of `call`, and count it as failed if it returns an error.
+pub(crate) async fn observe_rpc_call<T, F>(method: &str, call: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let timer = RPC_CALL_LATENCY.with_label_values(&[method]).start_timer();
+    let result = call.await;
+    timer.observe_duration();
+    if result.is_err() {
+        RPC_CALL_FAILURES.with_label_values(&[method]).inc();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call_count(method: &str) -> u64 {
+        RPC_CALL_LATENCY.with_label_values(&[method]).get_sample_count()
+    }
+
+    fn failure_count(method: &str) -> u64 {
+        RPC_CALL_FAILURES.with_label_values(&[method]).get()
+    }
+
+    #[tokio::test]
+    async fn should_record_successful_call() {
+        let method = "test_successfulCall";
+        assert_eq!(observe_rpc_call(method, async { Ok(1) }).await.unwrap(), 1);
+
+        assert_eq!(call_count(method), 1);
+        assert_eq!(failure_count(method), 0);
+    }
+
+    #[tokio::test]
+    async fn should_record_failed_call() {
+        let method = "test_failedCall";
+        let result: Result<(), _> = observe_rpc_call(method, async { Err(Error::ChannelClosed) }).await;
+        assert!(result.is_err());
+
+        assert_eq!(call_count(method), 1);
+        assert_eq!(failure_count(method), 1);
+    }
+}
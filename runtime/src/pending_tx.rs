@@ -0,0 +1,144 @@
+use crate::{Balance, Error, SubxtError};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+use subxt::{metadata::Metadata, tx::TxPayload};
+
+/// An extrinsic that was signed and submitted, but whose submission has not completed yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransaction {
+    /// `pallet.call` name of the extrinsic
+    pub method: String,
+    /// tip the extrinsic was signed with
+    pub tip: Balance,
+    call_data: Vec<u8>,
+}
+
+/// Submitted extrinsics by nonce, so that stuck ones can be replaced or cancelled.
+#[derive(Clone, Default)]
+pub(crate) struct PendingTransactions(Arc<RwLock<BTreeMap<u32, PendingTransaction>>>);
+
+impl PendingTransactions {
+    pub(crate) fn insert(&self, nonce: u32, method: String, tip: Balance, call_data: Vec<u8>) {
+        self.0
+            .write()
+            .unwrap()
+            .insert(nonce, PendingTransaction { method, tip, call_data });
+    }
+
+    /// Forget the extrinsic at `nonce`, unless it has since been replaced by one with a different tip.
+    pub(crate) fn remove(&self, nonce: u32, tip: Balance) {
+        let mut pending = self.0.write().unwrap();
+        if pending.get(&nonce).map_or(false, |tx| tx.tip == tip) {
+            pending.remove(&nonce);
+        }
+    }
+
+    pub(crate) fn list(&self) -> Vec<(u32, PendingTransaction)> {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(nonce, tx)| (*nonce, tx.clone()))
+            .collect()
+    }
+
+    /// The call of the extrinsic at `nonce`, to resubmit with `new_tip`. Transactions in the pool can
+    /// only be replaced by ones with a higher priority, so the tip must be higher than before.
+    pub(crate) fn replacement(&self, nonce: u32, new_tip: Balance) -> Result<RawCall, Error> {
+        let mut pending = self.0.write().unwrap();
+        let tx = pending.get_mut(&nonce).ok_or(Error::TransactionNotPending(nonce))?;
+        if new_tip <= tx.tip {
+            return Err(Error::ReplacementTipTooLow(new_tip, tx.tip));
+        }
+        tx.tip = new_tip;
+        Ok(RawCall(tx.call_data.clone()))
+    }
+
+    /// Track the no-op `call_data` in place of the extrinsic at `nonce`, returning the tip to submit it
+    /// with. The tip must outbid the extrinsic to evict it from the pool.
+    pub(crate) fn cancellation(&self, nonce: u32, call_data: Vec<u8>) -> Result<Balance, Error> {
+        let mut pending = self.0.write().unwrap();
+        let tx = pending.get_mut(&nonce).ok_or(Error::TransactionNotPending(nonce))?;
+        let tip = bumped_tip(tx.tip);
+        *tx = PendingTransaction {
+            method: "System.remark".to_string(),
+            tip,
+            call_data,
+        };
+        Ok(tip)
+    }
+}
+
+/// Raise `tip` by 10%, and by at least one planck.
+pub(crate) fn bumped_tip(tip: Balance) -> Balance {
+    tip.saturating_add((tip / 10).max(1))
+}
+
+pub(crate) fn encode_call<Call: TxPayload>(call: &Call, metadata: &Metadata) -> Result<Vec<u8>, Error> {
+    let mut call_data = vec![];
+    call.encode_call_data(metadata, &mut call_data)?;
+    Ok(call_data)
+}
+
+/// A call that has already been encoded, to resubmit it as-is.
+pub(crate) struct RawCall(pub(crate) Vec<u8>);
+
+impl TxPayload for RawCall {
+    fn encode_call_data(&self, _metadata: &Metadata, out: &mut Vec<u8>) -> Result<(), SubxtError> {
+        out.extend_from_slice(&self.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_replace_with_higher_tip() {
+        let pending = PendingTransactions::default();
+        pending.insert(3, "Redeem.execute_redeem".to_string(), 100, vec![1, 2, 3]);
+
+        assert!(matches!(
+            pending.replacement(3, 100),
+            Err(Error::ReplacementTipTooLow(100, 100))
+        ));
+        assert!(matches!(
+            pending.replacement(4, 200),
+            Err(Error::TransactionNotPending(4))
+        ));
+
+        let call = pending.replacement(3, 200).unwrap();
+        assert_eq!(call.0, vec![1, 2, 3]);
+        let (nonce, tx) = pending.list().remove(0);
+        assert_eq!((nonce, tx.method.as_str(), tx.tip), (3, "Redeem.execute_redeem", 200));
+
+        // completion of the original submission does not untrack the replacement
+        pending.remove(3, 100);
+        assert_eq!(pending.list().len(), 1);
+        pending.remove(3, 200);
+        assert!(pending.list().is_empty());
+    }
+
+    #[test]
+    fn should_cancel_with_bumped_tip() {
+        let pending = PendingTransactions::default();
+        pending.insert(5, "Issue.execute_issue".to_string(), 1000, vec![1, 2, 3]);
+
+        assert_eq!(pending.cancellation(5, vec![0, 7]).unwrap(), 1100);
+        let (nonce, tx) = pending.list().remove(0);
+        assert_eq!((nonce, tx.method.as_str(), tx.tip), (5, "System.remark", 1100));
+        assert!(matches!(
+            pending.cancellation(6, vec![0, 7]),
+            Err(Error::TransactionNotPending(6))
+        ));
+
+        // a stuck cancellation can be bumped in turn
+        assert_eq!(pending.replacement(5, 2000).unwrap().0, vec![0, 7]);
+
+        assert_eq!(bumped_tip(0), 1);
+        assert_eq!(bumped_tip(Balance::MAX), Balance::MAX);
+    }
+}
@@ -1,21 +1,187 @@
-use crate::Error;
+use crate::{metrics::RETRIES, Error, ShutdownReceiver};
 use backoff::{backoff::Backoff, ExponentialBackoff};
 use futures::Future;
-use std::{fmt::Debug, time::Duration};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
 
-/// Gets the default retrying policy. This should be used for unexpected errors, not for operations
-/// that are expected to take a while to succeed. That is, it is unsuitable for e.g. awaiting bitcoin
-/// confirmation proof, due to potentially high retrying time.
-/// Note: technically this is not a constant due to the use of `Default::default()`
-fn get_exponential_backoff() -> ExponentialBackoff {
-    ExponentialBackoff {
-        max_elapsed_time: Some(Duration::from_secs(60 * 10)), // retry for at most 10 minutes
-        max_interval: Duration::from_secs(60 * 2),            // only delay up to 2 minutes
-        initial_interval: Duration::from_secs(1),             // first retry after 1 second
-        current_interval: Duration::from_secs(1),             // increasing interval duration
-        multiplier: 2.0,                                      // delay doubles every time
-        randomization_factor: 0.25,                           // add random value within 25%
-        ..Default::default()
+/// Backoff curve used by `notify_retry`. The delay starts at `initial_interval` and is multiplied by
+/// `multiplier` after every failed attempt, up to `max_interval`. Each delay is randomized by up to
+/// `randomization_factor` of its value. Retrying stops once `max_elapsed` has passed, if set, or
+/// once the `circuit_breaker` opens.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub initial_interval: Duration,
+    pub multiplier: f64,
+    pub max_interval: Duration,
+    pub max_elapsed: Option<Duration>,
+    pub randomization_factor: f64,
+    pub circuit_breaker: Option<CircuitBreaker>,
+}
+
+impl Default for RetryConfig {
+    /// The default retrying policy. This should be used for unexpected errors, not for operations
+    /// that are expected to take a while to succeed. That is, it is unsuitable for e.g. awaiting bitcoin
+    /// confirmation proof, due to potentially high retrying time.
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(1),        // first retry after 1 second
+            multiplier: 2.0,                                 // delay doubles every time
+            max_interval: Duration::from_secs(60 * 2),       // only delay up to 2 minutes
+            max_elapsed: Some(Duration::from_secs(60 * 10)), // retry for at most 10 minutes
+            randomization_factor: 0.25,                      // add random value within 25%
+            circuit_breaker: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Same curve as the default, but never gives up.
+    pub fn infinite() -> Self {
+        Self {
+            max_elapsed: None,
+            ..Default::default()
+        }
+    }
+
+    /// The deterministic part of the backoff curve, jitter is applied separately by `jitter`.
+    fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff {
+            max_elapsed_time: self.max_elapsed,
+            max_interval: self.max_interval,
+            initial_interval: self.initial_interval,
+            current_interval: self.initial_interval,
+            multiplier: self.multiplier,
+            randomization_factor: 0.0,
+            ..Default::default()
+        }
+    }
+
+    /// Randomize `delay` by up to `randomization_factor` of its value, in either direction. This spreads
+    /// out the retries of vaults that all hit the same error at the same time.
+    fn jitter(&self, delay: Duration, rng: &mut impl Rng) -> Duration {
+        let randomization_factor = self.randomization_factor.clamp(0.0, 1.0);
+        if randomization_factor == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(rng.gen_range(1.0 - randomization_factor, 1.0 + randomization_factor))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls are let through
+    Closed { consecutive_failures: u32 },
+    /// Calls are rejected until the cooldown has passed
+    Open { until: Instant },
+    /// The cooldown has passed, a single test call decides whether to close or re-open the circuit
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    circuit: CircuitState,
+    /// Whether the test call of a half-open circuit was let through, and its outcome is not recorded yet
+    probing: bool,
+}
+
+/// Shared between all call sites of a class of operations (e.g. all oracle submissions), so that
+/// after `failure_threshold` consecutive failures, further attempts fail fast with
+/// `Error::CircuitOpen` for `cooldown`, instead of each being retried on its own.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    label: String,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(label: impl Into<String>, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            label: label.into(),
+            failure_threshold,
+            cooldown,
+            state: Arc::new(Mutex::new(BreakerState {
+                circuit: CircuitState::Closed {
+                    consecutive_failures: 0,
+                },
+                probing: false,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerState> {
+        let mut state = self.state.lock().unwrap();
+        if let CircuitState::Open { until } = state.circuit {
+            if Instant::now() >= until {
+                log::info!("Circuit breaker for {} is half-open, testing recovery", self.label);
+                state.circuit = CircuitState::HalfOpen;
+            }
+        }
+        state
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.lock().circuit
+    }
+
+    /// Returns `Error::CircuitOpen` if calls should currently not be attempted. While half-open, only the
+    /// first caller is let through, and the outcome of its call must be recorded before the next one is.
+    pub fn check(&self) -> Result<(), Error> {
+        let mut state = self.lock();
+        match state.circuit {
+            CircuitState::Closed { .. } => Ok(()),
+            CircuitState::HalfOpen if !state.probing => {
+                state.probing = true;
+                Ok(())
+            }
+            CircuitState::HalfOpen | CircuitState::Open { .. } => Err(Error::CircuitOpen(self.label.clone())),
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.lock();
+        if state.circuit == CircuitState::HalfOpen {
+            log::info!("Circuit breaker for {} is closed again", self.label);
+        }
+        state.circuit = CircuitState::Closed {
+            consecutive_failures: 0,
+        };
+        state.probing = false;
+    }
+
+    /// Record a call that failed for reasons that say nothing about the health of the remote (e.g. a
+    /// rejected extrinsic), so that it neither opens nor closes the circuit.
+    pub fn record_ignored(&self) {
+        self.lock().probing = false;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.lock();
+        state.probing = false;
+        let consecutive_failures = match state.circuit {
+            CircuitState::Closed { consecutive_failures } => consecutive_failures.saturating_add(1),
+            // a failed test call re-opens the circuit straight away
+            CircuitState::HalfOpen => self.failure_threshold,
+            CircuitState::Open { .. } => return,
+        };
+        state.circuit = if consecutive_failures >= self.failure_threshold {
+            log::warn!(
+                "Circuit breaker for {} opened after {} consecutive failures, cooling down for {:.3} s",
+                self.label,
+                consecutive_failures,
+                self.cooldown.as_secs_f64()
+            );
+            CircuitState::Open {
+                until: Instant::now() + self.cooldown,
+            }
+        } else {
+            CircuitState::Closed { consecutive_failures }
+        };
     }
 }
 
@@ -24,7 +190,14 @@ pub enum RetryPolicy<E> {
     Throw(E),
 }
 
-pub async fn notify_retry<E, L, FL, R, FR, T>(call: L, verify: R) -> Result<T, Error>
+/// Call `call` until `verify` accepts its result, backing off according to `config`. Every retry of
+/// `operation` is logged with the error and counted in the `retries` metric.
+pub async fn notify_retry<E, L, FL, R, FR, T>(
+    operation: &str,
+    config: &RetryConfig,
+    call: L,
+    verify: R,
+) -> Result<T, Error>
 where
     E: Debug,
     L: Fn() -> FL,
@@ -32,21 +205,335 @@ where
     R: Fn(Result<T, E>) -> FR,
     FR: Future<Output = Result<T, RetryPolicy<Error>>>,
 {
-    let mut backoff = get_exponential_backoff();
+    notify_retry_with_rng(operation, config, None, StdRng::from_entropy(), call, verify).await
+}
+
+/// Like `notify_retry`, but stops waiting for the next retry with `Error::Shutdown` as soon as
+/// `shutdown` is signaled.
+pub async fn notify_retry_until_shutdown<E, L, FL, R, FR, T>(
+    operation: &str,
+    config: &RetryConfig,
+    shutdown: &mut ShutdownReceiver,
+    call: L,
+    verify: R,
+) -> Result<T, Error>
+where
+    E: Debug,
+    L: Fn() -> FL,
+    FL: Future<Output = Result<T, E>>,
+    R: Fn(Result<T, E>) -> FR,
+    FR: Future<Output = Result<T, RetryPolicy<Error>>>,
+{
+    notify_retry_with_rng(operation, config, Some(shutdown), StdRng::from_entropy(), call, verify).await
+}
+
+async fn notify_retry_with_rng<E, L, FL, R, FR, T>(
+    operation: &str,
+    config: &RetryConfig,
+    mut shutdown: Option<&mut ShutdownReceiver>,
+    mut rng: StdRng,
+    call: L,
+    verify: R,
+) -> Result<T, Error>
+where
+    E: Debug,
+    L: Fn() -> FL,
+    FL: Future<Output = Result<T, E>>,
+    R: Fn(Result<T, E>) -> FR,
+    FR: Future<Output = Result<T, RetryPolicy<Error>>>,
+{
+    let mut backoff = config.backoff();
+    let breaker = config.circuit_breaker.as_ref();
+    let mut attempt: u32 = 0;
     loop {
-        let err = match verify(call().await).await {
+        attempt += 1;
+        if let Some(breaker) = breaker {
+            breaker.check()?;
+        }
+        let result = verify(call().await).await;
+        if let Some(breaker) = breaker {
+            match result {
+                Ok(_) => breaker.record_success(),
+                // only errors worth retrying indicate that the remote is unhealthy
+                Err(RetryPolicy::Skip(_)) => breaker.record_failure(),
+                Err(RetryPolicy::Throw(_)) => breaker.record_ignored(),
+            }
+        }
+        let err = match result {
             Ok(ok) => return Ok(ok),
             Err(RetryPolicy::Skip(err)) => err,
             Err(RetryPolicy::Throw(err)) => return Err(err),
         };
 
-        match backoff.next_backoff() {
+        match backoff.next_backoff().map(|wait| config.jitter(wait, &mut rng)) {
             Some(wait) => {
                 // error occurred, sleep before retrying
-                log::warn!("{} - next retry in {:.3} s", err, wait.as_secs_f64());
-                tokio::time::sleep(wait).await;
+                tracing::warn!(
+                    operation,
+                    attempt,
+                    error = %err,
+                    next_delay_secs = wait.as_secs_f64(),
+                    "{} - next retry in {:.3} s",
+                    err,
+                    wait.as_secs_f64()
+                );
+                RETRIES.with_label_values(&[operation]).inc();
+                match shutdown.as_deref_mut() {
+                    Some(shutdown) => tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        // a closed channel also means that the sender is gone
+                        _ = shutdown.recv() => break Err(Error::Shutdown),
+                    },
+                    None => tokio::time::sleep(wait).await,
+                }
             }
             None => break Err(Error::Timeout),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShutdownSender;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_retry_delays_follow_configured_curve() {
+        let config = RetryConfig {
+            initial_interval: Duration::from_millis(100),
+            multiplier: 3.0,
+            max_interval: Duration::from_millis(1000),
+            max_elapsed: None,
+            randomization_factor: 0.0,
+            circuit_breaker: None,
+        };
+        let mut backoff = config.backoff();
+        let delays: Vec<_> = (0..5).map(|_| backoff.next_backoff().unwrap()).collect();
+        assert_eq!(delays, [100, 300, 900, 1000, 1000].map(Duration::from_millis).to_vec());
+    }
+
+    #[test]
+    fn test_retry_jitter_is_bounded_and_seedable() {
+        let config = RetryConfig {
+            randomization_factor: 0.3,
+            ..Default::default()
+        };
+        let delay = Duration::from_secs(10);
+        let jittered = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..100).map(|_| config.jitter(delay, &mut rng)).collect::<Vec<_>>()
+        };
+
+        let delays = jittered(7);
+        assert_eq!(delays, jittered(7));
+        assert_ne!(delays, jittered(8));
+        for jittered_delay in delays {
+            assert!(jittered_delay >= delay.mul_f64(0.7));
+            assert!(jittered_delay <= delay.mul_f64(1.3));
+        }
+
+        let no_jitter = RetryConfig {
+            randomization_factor: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(no_jitter.jitter(delay, &mut StdRng::seed_from_u64(7)), delay);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_elapsed() {
+        let config = RetryConfig {
+            initial_interval: Duration::from_millis(10),
+            max_elapsed: Some(Duration::from_millis(50)),
+            randomization_factor: 0.0,
+            ..Default::default()
+        };
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = notify_retry(
+            "test",
+            &config,
+            || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::BlockNotFound)
+            },
+            |result: Result<(), Error>| async { result.map_err(RetryPolicy::Skip) },
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Timeout)));
+        assert!(attempts.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_infinite_keeps_retrying() {
+        let config = RetryConfig {
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(1),
+            ..RetryConfig::infinite()
+        };
+        let attempts = AtomicU32::new(0);
+        let result = notify_retry(
+            "test",
+            &config,
+            || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 20 {
+                    Err(Error::BlockNotFound)
+                } else {
+                    Ok(())
+                }
+            },
+            |result: Result<(), Error>| async { result.map_err(RetryPolicy::Skip) },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 21);
+    }
+
+    #[tokio::test]
+    async fn test_retry_counts_every_retry() {
+        let config = RetryConfig {
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(1),
+            ..RetryConfig::infinite()
+        };
+        let attempts = AtomicU32::new(0);
+        let result = notify_retry(
+            "test_countedOperation",
+            &config,
+            || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 3 {
+                    Err(Error::BlockNotFound)
+                } else {
+                    Ok(())
+                }
+            },
+            |result: Result<(), Error>| async { result.map_err(RetryPolicy::Skip) },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+        assert_eq!(RETRIES.with_label_values(&["test_countedOperation"]).get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_when_shutdown_is_signaled() {
+        let config = RetryConfig {
+            initial_interval: Duration::from_secs(60),
+            ..RetryConfig::infinite()
+        };
+        let shutdown_tx = ShutdownSender::new();
+        let mut shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            shutdown_tx.send(()).unwrap();
+        });
+
+        let start = Instant::now();
+        let result: Result<(), _> = notify_retry_until_shutdown(
+            "test",
+            &config,
+            &mut shutdown_rx,
+            || async { Err(Error::BlockNotFound) },
+            |result: Result<(), Error>| async { result.map_err(RetryPolicy::Skip) },
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Shutdown)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let breaker = CircuitBreaker::new("oracle submissions", 2, Duration::from_millis(50));
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Closed {
+                consecutive_failures: 0
+            }
+        );
+
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        breaker.record_failure();
+        assert!(matches!(breaker.state(), CircuitState::Open { .. }));
+        assert!(matches!(breaker.check(), Err(Error::CircuitOpen(label)) if label == "oracle submissions"));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.check().is_ok());
+        // only a single test call is let through
+        assert!(matches!(breaker.check(), Err(Error::CircuitOpen(_))));
+
+        // a failure while half-open re-opens the circuit immediately
+        breaker.record_failure();
+        assert!(matches!(breaker.state(), CircuitState::Open { .. }));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record_success();
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Closed {
+                consecutive_failures: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_when_circuit_opens() {
+        let breaker = CircuitBreaker::new("test", 3, Duration::from_secs(60));
+        let config = RetryConfig {
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(1),
+            circuit_breaker: Some(breaker.clone()),
+            ..RetryConfig::infinite()
+        };
+        let attempts = AtomicU32::new(0);
+        let call = || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Error::BlockNotFound)
+        };
+        let verify = |result: Result<(), Error>| async { result.map_err(RetryPolicy::Skip) };
+
+        assert!(matches!(
+            notify_retry("test", &config, call, verify).await,
+            Err(Error::CircuitOpen(_))
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // other call sites sharing the breaker fail fast
+        assert!(matches!(
+            notify_retry("test", &config, call, verify).await,
+            Err(Error::CircuitOpen(_))
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_thrown_errors_do_not_open_circuit() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(60));
+        let config = RetryConfig {
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        };
+
+        for _ in 0..3 {
+            let result: Result<(), _> = notify_retry(
+                "test",
+                &config,
+                || async { Err(Error::BlockNotFound) },
+                |result: Result<(), Error>| async { result.map_err(RetryPolicy::Throw) },
+            )
+            .await;
+            assert!(matches!(result, Err(Error::BlockNotFound)));
+        }
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Closed {
+                consecutive_failures: 0
+            }
+        );
+    }
+}
@@ -1,40 +1,85 @@
 use crate::{
-    conn::{new_websocket_client, new_websocket_client_with_retry},
-    metadata, notify_retry,
+    assets::foreign_asset_id,
+    blocks::{deduplicated_block_numbers, deduplicated_blocks, process_blocks_from_cursor, BlockCursor, ReorgTracker},
+    call_timeout::CallTimeouts,
+    conn::{
+        new_websocket_client, new_websocket_client_with_retry, ConnectionState, ConnectionStatus, Endpoints,
+        ReconnectingRpcClient,
+    },
+    conversion,
+    error::describe_dry_run_error,
+    events::decode_events,
+    fee_rate::{FeeRateSubscription, FeeRateUpdateReceiver, FeeRateUpdates},
+    metadata,
+    metrics::observe_rpc_call,
+    notify_retry_until_shutdown, parse_currency_with_registry,
+    pending_tx::{encode_call, PendingTransaction, PendingTransactions, RawCall},
+    thresholds::{ThresholdCache, Thresholds},
     types::*,
-    AccountId, AssetRegistry, CurrencyId, Error, InterBtcRuntime, InterBtcSigner, RetryPolicy, RichH256Le, SubxtError,
+    AccountId, AssetRegistry, CurrencyId, Error, FixedPointNumber, InterBtcRuntime, InterBtcSigner, RetryConfig,
+    RetryPolicy, RichH256Le, ShutdownReason, SubxtError, BLOCK_INTERVAL,
 };
 
 pub use crate::ShutdownSender;
 use async_trait::async_trait;
 use codec::{Decode, Encode};
-use futures::{future::join_all, stream::StreamExt, FutureExt, SinkExt, Stream};
+use futures::{
+    future::{join_all, try_join},
+    stream::{self, BoxStream, StreamExt},
+    FutureExt, SinkExt, Stream,
+};
 use module_oracle_rpc_runtime_api::BalanceWrapper;
 use primitives::UnsignedFixedPoint;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
-use std::{collections::BTreeSet, future::Future, ops::Range, sync::Arc, time::Duration};
+use sp_arithmetic::traits::{CheckedDiv, Zero};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    future::Future,
+    ops::Range,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use subxt::{
     blocks::ExtrinsicEvents,
     client::OnlineClient,
+    dynamic::Value as DynamicValue,
     events::StaticEvent,
+    ext::sp_core::{
+        storage::{StorageChangeSet, StorageKey},
+        Bytes,
+    },
     metadata::DecodeWithMetadata,
-    rpc::{rpc_params, RpcClientT},
+    rpc::{rpc_params, RpcClientT, RpcParams},
     storage::{address::Yes, StorageAddress},
-    tx::TxPayload,
+    tx::{Era, PlainTip, PolkadotExtrinsicParamsBuilder, TxPayload},
 };
 use tokio::{
-    sync::RwLock,
+    sync::{Mutex, RwLock},
     time::{sleep, timeout},
 };
+// number of blocks after which submitted extrinsics expire (about 13 minutes with 12 second blocks)
+const DEFAULT_TX_LIFETIME: u64 = 64;
+
 // timeout before retrying parachain calls (5 minutes)
 const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(300);
 
 // timeout before re-verifying block header inclusion
 const BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(6);
 
+// delay before retrying a failed block subscription
+const BLOCK_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
 // number of storage entries to fetch at a time
 const DEFAULT_PAGE_SIZE: u32 = 10;
 
+// interval at which the sync state of the node is polled
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+// a single batch of oracle values may use a fifth of the maximum block weight (ref time)
+const BATCH_WEIGHT_DIVISOR: u64 = 5;
+
 // sanity check to be sure that testing-utils is not accidentally selected
 #[cfg(all(
     any(test, feature = "testing-utils"),
@@ -62,79 +107,151 @@ cfg_if::cfg_if! {
     }
 }
 
-pub(crate) type FeeRateUpdateSender = tokio::sync::broadcast::Sender<FixedU128>;
-pub type FeeRateUpdateReceiver = tokio::sync::broadcast::Receiver<FixedU128>;
-
 #[derive(Clone)]
 pub struct InterBtcParachain {
     api: Arc<OnlineClient<InterBtcRuntime>>,
-    nonce: Arc<RwLock<u32>>,
+    // next nonce to sign with, `None` if it must be (re-)synced from the chain
+    nonce: Arc<RwLock<Option<u32>>>,
     signer: InterBtcSigner,
     account_id: AccountId,
     shutdown_tx: ShutdownSender,
-    fee_rate_update_tx: FeeRateUpdateSender,
+    fee_rate_updates: FeeRateUpdates,
+    // tip in the native currency added to every extrinsic, to get priority when the parachain is congested
+    tx_tip: Balance,
+    // number of blocks extrinsics remain valid for, zero for immortal extrinsics
+    tx_lifetime: u64,
+    pending_txs: PendingTransactions,
+    thresholds: ThresholdCache,
+    call_timeouts: CallTimeouts,
+    // only log the extrinsics that would be submitted, without submitting them
+    observer: bool,
+    // the endpoints the connection fails over between, if any
+    endpoints: Option<Arc<Endpoints>>,
+    // the state of the connection, if it is re-established when lost
+    connection: Option<Arc<ConnectionState>>,
     pub native_currency_id: CurrencyId,
     pub relay_chain_currency_id: CurrencyId,
     pub wrapped_currency_id: CurrencyId,
 }
 
 impl InterBtcParachain {
+    /// Connect to the parachain using `rpc_client`. Fails if the runtime of the node does not match the
+    /// metadata the client was compiled against, unless `allow_runtime_mismatch` is set.
     pub async fn new<P: RpcClientT>(
         rpc_client: P,
         signer: InterBtcSigner,
+        allow_runtime_mismatch: bool,
         shutdown_tx: ShutdownSender,
     ) -> Result<Self, Error> {
-        let account_id = signer.account_id().clone();
         let api = OnlineClient::from_rpc_client(Arc::new(rpc_client)).await?;
 
-        let runtime_version = api.rpc().runtime_version(None).await?;
-        let spec_name: String = runtime_version
-            .other
-            .get("specName")
-            .and_then(|value| value.as_str())
-            .map(ToString::to_string)
-            .unwrap_or_default();
-        if DEFAULT_SPEC_NAME == spec_name {
-            log::info!("spec_name={}", spec_name);
-        } else {
-            return Err(Error::ParachainMetadataMismatch(DEFAULT_SPEC_NAME.into(), spec_name));
+        let runtime_version = fetch_runtime_version(&api).await?;
+        log::info!("spec_name={}", runtime_version.spec_name);
+        log::info!("spec_version={}", runtime_version.spec_version);
+        log::info!("transaction_version={}", runtime_version.transaction_version);
+        match check_runtime_version(&runtime_version) {
+            Err(err) if allow_runtime_mismatch => log::warn!(
+                "!!! {} - calls to the parachain may fail to decode, upgrade the client !!!",
+                err
+            ),
+            result => result?,
         }
 
-        if DEFAULT_SPEC_VERSION.contains(&runtime_version.spec_version) {
-            log::info!("spec_version={}", runtime_version.spec_version);
-            log::info!("transaction_version={}", runtime_version.transaction_version);
-        } else {
-            return Err(Error::InvalidSpecVersion(
-                DEFAULT_SPEC_VERSION.start,
-                DEFAULT_SPEC_VERSION.end,
-                runtime_version.spec_version,
-            ));
-        }
+        let parachain_rpc = Self::from_online_client(api, signer, shutdown_tx)?;
+        // TODO: refresh on registration
+        parachain_rpc.store_assets_metadata().await?;
+        Ok(parachain_rpc)
+    }
 
+    /// Wrap a client that is already connected, without checking its runtime version or loading
+    /// the asset registry.
+    pub(crate) fn from_online_client(
+        api: OnlineClient<InterBtcRuntime>,
+        signer: InterBtcSigner,
+        shutdown_tx: ShutdownSender,
+    ) -> Result<Self, Error> {
+        let account_id = signer.account_id().clone();
         let currency_constants = metadata::constants().currency();
         let native_currency_id = api.constants().at(&currency_constants.get_native_currency_id())?;
         let relay_chain_currency_id = api.constants().at(&currency_constants.get_relay_chain_currency_id())?;
         let wrapped_currency_id = api.constants().at(&currency_constants.get_wrapped_currency_id())?;
 
-        // low capacity channel since we generally only care about the newest value, so it's ok
-        // if we miss an event
-        let (fee_rate_update_tx, _) = tokio::sync::broadcast::channel(2);
-
-        let parachain_rpc = Self {
+        Ok(Self {
             api: Arc::new(api),
-            nonce: Arc::new(RwLock::new(0)),
+            nonce: Arc::new(RwLock::new(None)),
             signer: signer,
             account_id,
             shutdown_tx,
-            fee_rate_update_tx,
+            fee_rate_updates: FeeRateUpdates::new(),
+            tx_tip: 0,
+            tx_lifetime: DEFAULT_TX_LIFETIME,
+            pending_txs: Default::default(),
+            thresholds: Default::default(),
+            call_timeouts: Default::default(),
+            observer: false,
+            endpoints: None,
+            connection: None,
             native_currency_id,
             relay_chain_currency_id,
             wrapped_currency_id,
-        };
+        })
+    }
 
-        // TODO: refresh on registration
-        parachain_rpc.store_assets_metadata().await?;
-        Ok(parachain_rpc)
+    /// Tip every extrinsic submitted through this client with `tip`, in the native currency. Since
+    /// the client is cheap to clone, this can also be used to override the tip for a single call:
+    /// `parachain_rpc.clone().with_tx_tip(tip).execute_redeem(..)`.
+    pub fn with_tx_tip(mut self, tip: Balance) -> Self {
+        self.tx_tip = tip;
+        self
+    }
+
+    /// Let extrinsics expire if they are not included within `blocks` blocks of being signed, so that a
+    /// delayed transaction cannot execute much later than intended. Zero makes extrinsics immortal.
+    pub fn with_tx_lifetime(mut self, blocks: u64) -> Self {
+        self.tx_lifetime = blocks;
+        self
+    }
+
+    /// Abandon calls to the parachain that take longer than `timeout` with `Error::CallTimeout`, so that a
+    /// hung node cannot stall the caller. This is independent of the retries of the individual calls.
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeouts.set_default(timeout);
+        self
+    }
+
+    /// Override the call timeout for `method`, i.e. `Pallet.call` for extrinsics, `Pallet.Entry` for
+    /// storage queries or the name of an RPC method.
+    pub fn with_call_timeout_for(mut self, method: impl Into<String>, timeout: Duration) -> Self {
+        self.call_timeouts.set(method.into(), timeout);
+        self
+    }
+
+    /// Never submit extrinsics: every submission is logged and fails with `Error::ObserverMode`
+    /// instead, while queries and subscriptions work as usual.
+    pub fn with_observer_mode(mut self) -> Self {
+        self.observer = true;
+        self
+    }
+
+    /// Fails with `Error::ObserverMode`, after logging `method`, if submitting is disabled.
+    fn check_not_observer(&self, method: &str) -> Result<(), Error> {
+        if self.observer {
+            log::info!("Observer mode: would submit {}", method);
+            return Err(Error::ObserverMode(method.to_string()));
+        }
+        Ok(())
+    }
+
+    /// The era to sign extrinsics with, together with the block it starts at. `None` if extrinsics are
+    /// immortal. The era starts at the finalized head, since extrinsics whose era starts at a block that
+    /// is reverted become invalid.
+    async fn tx_era(&self) -> Result<Option<(Era, H256)>, Error> {
+        if self.tx_lifetime == 0 {
+            return Ok(None);
+        }
+        let finalized_head = self.get_finalized_head().await?;
+        let header = self.get_header(finalized_head).await?.ok_or(Error::BlockNotFound)?;
+        Ok(Some((mortal_era(self.tx_lifetime, header.number), header.hash())))
     }
 
     #[cfg(feature = "testing-utils")]
@@ -173,8 +290,8 @@ impl InterBtcParachain {
     }
 
     pub async fn from_url(url: &str, signer: InterBtcSigner, shutdown_tx: ShutdownSender) -> Result<Self, Error> {
-        let ws_client = new_websocket_client(url, None, None).await?;
-        Self::new(ws_client, signer, shutdown_tx).await
+        let ws_client = new_websocket_client(url, None, None, None).await?;
+        Self::new(ws_client, signer, false, shutdown_tx).await
     }
 
     pub async fn from_url_with_retry(
@@ -183,56 +300,104 @@ impl InterBtcParachain {
         connection_timeout: Duration,
         shutdown_tx: ShutdownSender,
     ) -> Result<Self, Error> {
-        Self::from_url_and_config_with_retry(url, signer, None, None, connection_timeout, shutdown_tx).await
+        Self::from_url_and_config_with_retry(url, signer, None, None, None, connection_timeout, false, shutdown_tx)
+            .await
     }
 
     pub async fn from_url_and_config_with_retry(
         url: &str,
         signer: InterBtcSigner,
+        tls_config: Option<Arc<rustls::ClientConfig>>,
         max_concurrent_requests: Option<usize>,
         max_notifs_per_subscription: Option<usize>,
         connection_timeout: Duration,
+        allow_runtime_mismatch: bool,
         shutdown_tx: ShutdownSender,
     ) -> Result<Self, Error> {
-        let ws_client = new_websocket_client_with_retry(
-            url,
-            max_concurrent_requests,
-            max_notifs_per_subscription,
-            connection_timeout,
-        )
+        let endpoints = Arc::new(Endpoints::new(url));
+        let ws_client = ReconnectingRpcClient::new({
+            let endpoints = endpoints.clone();
+            move || {
+                let endpoints = endpoints.clone();
+                let tls_config = tls_config.clone();
+                async move {
+                    new_websocket_client_with_retry(
+                        &endpoints,
+                        tls_config,
+                        max_concurrent_requests,
+                        max_notifs_per_subscription,
+                        connection_timeout,
+                    )
+                    .await
+                }
+            }
+        })
         .await?;
-        Self::new(ws_client, signer, shutdown_tx).await
+        let connection = ws_client.connection_state();
+        let mut parachain_rpc = Self::new(ws_client, signer, allow_runtime_mismatch, shutdown_tx).await?;
+        parachain_rpc.endpoints = Some(endpoints);
+        parachain_rpc.connection = Some(connection);
+        Ok(parachain_rpc)
     }
 
-    async fn get_fresh_nonce(&self) -> u32 {
-        // For getting the nonce, use latest, possibly non-finalized block.
+    /// The parachain endpoint this client is currently connected to, if it was connected
+    /// through a list of endpoints.
+    pub fn active_endpoint(&self) -> Option<String> {
+        self.endpoints.as_ref().and_then(|endpoints| endpoints.active())
+    }
+
+    /// The state of the connection, if it is re-established when lost.
+    pub fn connection_status(&self) -> Option<ConnectionStatus> {
+        self.connection.as_ref().map(|connection| connection.status())
+    }
+
+    /// The number of times the connection was re-established after being lost.
+    pub fn reconnect_count(&self) -> u64 {
+        self.connection
+            .as_ref()
+            .map_or(0, |connection| connection.reconnect_count())
+    }
+
+    /// Fetch the nonce of the signer's account from the latest, possibly non-finalized, block.
+    pub(crate) async fn get_on_chain_nonce(&self) -> u32 {
         // TODO: we might want to wait until the latest block is actually finalized
-        // query account info in order to get the nonce value used for communication
         let storage_key = metadata::storage().system().account(&self.account_id);
-        let on_chain_nonce = self
-            .api
+        self.api
             .storage()
             .fetch(&storage_key, None)
             .await
             .transpose()
             .and_then(|x| x.ok())
             .map(|x| x.nonce)
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
 
+    /// Reserve the next nonce for signing. The counter is seeded from the chain on first
+    /// use (or after a resync) and incremented locally afterwards, so that concurrently
+    /// submitted extrinsics never share a nonce.
+    async fn get_fresh_nonce(&self) -> u32 {
+        // hold the lock while syncing so concurrent callers wait for the seeded value
         let mut next_nonce = self.nonce.write().await;
 
-        let ret = if on_chain_nonce > *next_nonce {
-            log::info!("Synced to on-chain nonce: {}", on_chain_nonce);
-            on_chain_nonce
-        } else {
-            *next_nonce
+        let ret = match *next_nonce {
+            Some(nonce) => nonce,
+            None => {
+                let on_chain_nonce = self.get_on_chain_nonce().await;
+                log::info!("Synced to on-chain nonce: {}", on_chain_nonce);
+                on_chain_nonce
+            }
         };
 
-        *next_nonce = ret.saturating_add(1);
+        *next_nonce = Some(ret.saturating_add(1));
 
         ret
     }
 
+    /// Discard the local nonce counter so that the next submission re-reads it from the chain.
+    async fn resync_nonce(&self) {
+        *self.nonce.write().await = None;
+    }
+
     async fn query_finalized<Address>(
         &self,
         address: Address,
@@ -240,8 +405,12 @@ impl InterBtcParachain {
     where
         Address: StorageAddress<IsFetchable = Yes>,
     {
-        let hash = self.get_finalized_block_hash().await?;
-        Ok(self.api.storage().fetch(&address, hash).await?)
+        let method = storage_method_name(&address);
+        self.observe_call(&method, async {
+            let hash = self.get_finalized_block_hash().await?;
+            Ok(self.api.storage().fetch(&address, hash).await?)
+        })
+        .await
     }
 
     async fn query_finalized_or_error<Address>(
@@ -261,8 +430,142 @@ impl InterBtcParachain {
     where
         Address: StorageAddress<IsFetchable = Yes, IsDefaultable = Yes>,
     {
-        let hash = self.get_finalized_block_hash().await?;
-        Ok(self.api.storage().fetch_or_default(&address, hash).await?)
+        let method = storage_method_name(&address);
+        self.observe_call(&method, async {
+            let hash = self.get_finalized_block_hash().await?;
+            Ok(self.api.storage().fetch_or_default(&address, hash).await?)
+        })
+        .await
+    }
+
+    /// Fetch all entries of the storage map at `address` as of the finalized head, page by page.
+    async fn query_finalized_iter<Address>(
+        &self,
+        address: Address,
+    ) -> Result<Vec<(StorageKey, <Address::Target as DecodeWithMetadata>::Target)>, Error>
+    where
+        Address: StorageAddress<IsIterable = Yes> + 'static,
+    {
+        let method = storage_method_name(&address);
+        self.observe_call(&method, async {
+            let hash = self.get_finalized_block_hash().await?;
+            let mut iter = self.api.storage().iter(address, DEFAULT_PAGE_SIZE, hash).await?;
+            let mut entries = Vec::new();
+            while let Some(entry) = iter.next().await? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        })
+        .await
+    }
+
+    async fn get_finalized_head(&self) -> Result<H256, Error> {
+        self.observe_call("chain_getFinalizedHead", async {
+            Ok(self.api.rpc().finalized_head().await?)
+        })
+        .await
+    }
+
+    async fn get_header(&self, hash: H256) -> Result<Option<InterBtcHeader>, Error> {
+        self.observe_call("chain_getHeader", async {
+            Ok(self.api.rpc().header(Some(hash)).await?)
+        })
+        .await
+    }
+
+    /// Call a custom RPC method of the parachain, recording its latency.
+    async fn rpc_request<Res: DeserializeOwned>(&self, method: &str, params: RpcParams) -> Result<Res, Error> {
+        self.observe_call(method, async { Ok(self.api.rpc().request(method, params).await?) })
+            .await
+    }
+
+    /// Record the latency of the call to `method`, abandoning it once its timeout elapses.
+    async fn observe_call<T, F>(&self, method: &str, call: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        observe_rpc_call(method, self.call_timeouts.run(method, call)).await
+    }
+
+    async fn get_sync_status(&self) -> Result<SyncStatus, Error> {
+        let health = self.rpc_request("system_health", rpc_params![]).await?;
+        let sync_state = self.rpc_request("system_syncState", rpc_params![]).await?;
+        parse_sync_status(&health, &sync_state)
+    }
+
+    /// Extrinsics that have been submitted but are not finalized yet, by nonce.
+    pub fn pending_transactions(&self) -> Vec<(u32, PendingTransaction)> {
+        self.pending_txs.list()
+    }
+
+    /// Resubmit the pending extrinsic at `nonce` with the higher `new_tip`, to get it out of the
+    /// transaction pool if it is stuck, e.g. because it is underpriced.
+    pub async fn replace_transaction(
+        &self,
+        nonce: u32,
+        new_tip: Balance,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        let call = self.pending_txs.replacement(nonce, new_tip)?;
+        self.resubmit(call, nonce, new_tip).await
+    }
+
+    /// Submit `pallet.call` with the given `fields`, for calls that have no typed wrapper yet,
+    /// e.g. because they were added in a runtime upgrade.
+    ///
+    /// The fields are encoded against the types of the call in the metadata of the node, so calls
+    /// or fields that do not exist are rejected before anything is submitted. Their meaning is not
+    /// checked by the compiler though, so prefer the typed methods wherever they exist.
+    pub async fn submit_raw(
+        &self,
+        pallet: &str,
+        call: &str,
+        fields: Vec<DynamicValue>,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        log::warn!("Submitting untyped call {}.{}", pallet, call);
+        let method = format!("{}.{}", pallet, call);
+        self.submit_as(subxt::dynamic::tx(pallet, call, fields), method, Finality::default())
+            .await
+    }
+
+    /// Evict the pending extrinsic at `nonce` by submitting a no-op `system.remark` with the same
+    /// nonce and a higher tip.
+    pub async fn cancel_transaction(&self, nonce: u32) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        let remark = encode_call(&metadata::tx().system().remark(vec![]), &self.api.metadata())?;
+        let tip = self.pending_txs.cancellation(nonce, remark.clone())?;
+        self.resubmit(RawCall(remark), nonce, tip).await
+    }
+
+    /// Sign `call` with the already used `nonce`, replacing the extrinsic with that nonce in the pool.
+    async fn resubmit(
+        &self,
+        call: RawCall,
+        nonce: u32,
+        tip: Balance,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        self.check_not_observer(&format!("replacement of the transaction with nonce {}", nonce))?;
+        log::info!("Resubmitting transaction with nonce {} and tip {}", nonce, tip);
+        let era = self.tx_era().await?;
+        let result = async {
+            let signed =
+                self.api
+                    .tx()
+                    .create_signed_with_nonce(&call, &self.signer, nonce, extrinsic_params(tip, era))?;
+            let tx_progress = self
+                .call_timeouts
+                .run("author_submitAndWatchExtrinsic", async {
+                    Ok(signed.submit_and_watch().await?)
+                })
+                .await?;
+
+            Ok::<_, Error>(if cfg!(feature = "testing-utils") {
+                tx_progress.wait_for_in_block().await?.wait_for_success().await?
+            } else {
+                tx_progress.wait_for_finalized_success().await?
+            })
+        }
+        .await;
+        self.pending_txs.remove(nonce, tip);
+        result
     }
 
     /// Gets a copy of the signer with a unique nonce
@@ -270,38 +573,86 @@ impl InterBtcParachain {
     where
         Call: TxPayload,
     {
-        notify_retry::<Error, _, _, _, _, _>(
+        self.submit(call, Finality::default()).await
+    }
+
+    /// Sign `call` with a unique nonce and submit it, returning its events once it has reached
+    /// `finality`. The hash of the block it was included in is `ExtrinsicEvents::block_hash`.
+    pub async fn submit<Call>(&self, call: Call, finality: Finality) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error>
+    where
+        Call: TxPayload,
+    {
+        let method = call
+            .validation_details()
+            .map(|details| format!("{}.{}", details.pallet_name, details.call_name))
+            .unwrap_or_else(|| "unknown_call".to_string());
+        self.submit_as(call, method, finality).await
+    }
+
+    /// Like `submit`, but `method` names the call in logs and metrics.
+    async fn submit_as<Call>(
+        &self,
+        call: Call,
+        method: String,
+        finality: Finality,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error>
+    where
+        Call: TxPayload,
+    {
+        self.check_not_observer(&method)?;
+        let call_data = encode_call(&call, &self.api.metadata())?;
+        // a timed out submission signals the shutdown itself, so don't keep retrying after it
+        let mut shutdown_rx = self.shutdown_tx.subscribe_with_id(format!("submit {}", method));
+        let submit = notify_retry_until_shutdown::<Error, _, _, _, _, _>(
+            &method,
+            &RetryConfig::default(),
+            &mut shutdown_rx,
             || async {
+                // fetch the era first, so that a failure does not leave a gap in the nonces
+                let era = self.tx_era().await?;
                 let nonce = self.get_fresh_nonce().await;
-                match timeout(TRANSACTION_TIMEOUT, async {
+                log::debug!("Submitting {} with nonce {} and tip {}", method, nonce, self.tx_tip);
+                self.pending_txs
+                    .insert(nonce, method.clone(), self.tx_tip, call_data.clone());
+                let result = match timeout(TRANSACTION_TIMEOUT, async {
+                    let signed = self.api.tx().create_signed_with_nonce(
+                        &call,
+                        &self.signer,
+                        nonce,
+                        extrinsic_params(self.tx_tip, era),
+                    )?;
+                    // only the submission itself, waiting for inclusion is bounded by the transaction timeout
                     let tx_progress = self
-                        .api
-                        .tx()
-                        .create_signed_with_nonce(&call, &self.signer, nonce, Default::default())?
-                        .submit_and_watch()
+                        .call_timeouts
+                        .run(&method, async { Ok(signed.submit_and_watch().await?) })
                         .await?;
 
-                    if cfg!(feature = "testing-utils") {
-                        tx_progress.wait_for_in_block().await?.wait_for_success().await
-                    } else {
-                        tx_progress.wait_for_finalized_success().await
-                    }
+                    Ok::<_, Error>(match finality {
+                        Finality::InBlock => tx_progress.wait_for_in_block().await?.wait_for_success().await?,
+                        Finality::Finalized => tx_progress.wait_for_finalized_success().await?,
+                    })
                 })
                 .await
                 {
                     Err(_) => {
                         log::warn!("Timeout on transaction submission - restart required");
-                        let _ = self.shutdown_tx.send(());
+                        let _ = self.shutdown_tx.shutdown(ShutdownReason::TransactionTimeout);
                         Err(Error::Timeout)
                     }
-                    Ok(x) => Ok(x?),
-                }
+                    Ok(x) => x,
+                };
+                self.pending_txs.remove(nonce, self.tx_tip);
+                result
             },
             |result| async {
                 match result.map_err(Into::<Error>::into) {
                     Ok(te) => Ok(te),
                     Err(err) => {
                         if let Some(data) = err.is_invalid_transaction() {
+                            if err.is_invalid_nonce() {
+                                log::info!("Resyncing nonce after invalid transaction: {}", data);
+                                self.resync_nonce().await;
+                            }
                             Err(RetryPolicy::Skip(Error::InvalidTransaction(data)))
                         } else if err.is_pool_too_low_priority().is_some() {
                             Err(RetryPolicy::Skip(Error::PoolTooLowPriority))
@@ -314,18 +665,71 @@ impl InterBtcParachain {
                     }
                 }
             },
-        )
-        .await
+        );
+        observe_rpc_call(&method, submit).await
     }
 
     pub async fn get_finalized_block_hash(&self) -> Result<Option<H256>, Error> {
         if cfg!(feature = "testing-utils") {
             Ok(None)
         } else {
-            Ok(Some(self.api.rpc().finalized_head().await?))
+            Ok(Some(self.get_finalized_head().await?))
         }
     }
 
+    /// Whether the block with `block_hash` is finalized, i.e. at or below the finalized head and
+    /// on the finalized chain.
+    pub async fn is_finalized(&self, block_hash: H256) -> Result<bool, Error> {
+        let finalized_head = self.get_finalized_head().await?;
+        let (header, finalized_header) = try_join(self.get_header(block_hash), self.get_header(finalized_head)).await?;
+        let (number, finalized_number) = match (header, finalized_header) {
+            (Some(header), Some(finalized_header)) => (header.number, finalized_header.number),
+            _ => return Err(Error::BlockNotFound),
+        };
+        Ok(number <= finalized_number && self.get_parachain_block_hash(BlockId::Number(number)).await? == block_hash)
+    }
+
+    /// Resolve a block number to its hash, hashes are returned as-is.
+    pub async fn get_parachain_block_hash(&self, block: BlockId) -> Result<H256, Error> {
+        match block {
+            BlockId::Hash(hash) => Ok(hash),
+            BlockId::Number(number) => self
+                .observe_call("chain_getBlockHash", async {
+                    Ok(self.api.rpc().block_hash(Some(number.into())).await?)
+                })
+                .await?
+                .ok_or(Error::BlockNotFound),
+        }
+    }
+
+    /// Check whether `call` would succeed if it were signed and submitted now, and what it
+    /// would cost. Nothing is broadcast and the local nonce counter is left untouched.
+    pub async fn dry_run<Call: TxPayload>(&self, call: Call) -> Result<DryRunOutcome, Error> {
+        let era = self.tx_era().await?;
+        let nonce = self.get_on_chain_nonce().await;
+        let tx =
+            self.api
+                .tx()
+                .create_signed_with_nonce(&call, &self.signer, nonce, extrinsic_params(self.tx_tip, era))?;
+
+        let result = tx.dry_run(None).await?;
+        let fee_info: Value = self
+            .rpc_request("payment_queryInfo", rpc_params![Bytes(tx.encoded().to_vec())])
+            .await?;
+        let estimated_fee = parse_partial_fee(&fee_info)?;
+
+        let error = match result {
+            Ok(Ok(())) => None,
+            Ok(Err(dispatch_error)) => Some(describe_dry_run_error(&self.api.metadata(), &dispatch_error)),
+            Err(validity_error) => Some(format!("Transaction is invalid: {:?}", validity_error)),
+        };
+        Ok(DryRunOutcome {
+            would_succeed: error.is_none(),
+            estimated_fee,
+            error,
+        })
+    }
+
     /// Subscribe to new parachain blocks.
     pub async fn on_block<F, R>(&self, on_block: F) -> Result<(), Error>
     where
@@ -395,6 +799,96 @@ impl InterBtcParachain {
         }
     }
 
+    /// Subscribe to events of type `T`. With `Finality::Finalized`, only events of finalized blocks
+    /// are yielded. With `Finality::InBlock`, events are yielded as soon as their block becomes the
+    /// best block, so they may still be rolled back: finalized blocks are announced with
+    /// `EventNotification::Finalized`, and switches of the best chain with
+    /// `EventNotification::Reorg`, after which the events of the new best chain are yielded.
+    pub async fn subscribe_event_notifications<T>(
+        &self,
+        finality: Finality,
+    ) -> Result<BoxStream<'static, Result<EventNotification<T>, Error>>, Error>
+    where
+        T: StaticEvent + Send + 'static,
+    {
+        let blocks = match finality {
+            Finality::Finalized => self
+                .api
+                .blocks()
+                .subscribe_finalized()
+                .await?
+                .map(|block| (true, block))
+                .boxed(),
+            Finality::InBlock => stream::select(
+                self.api.blocks().subscribe_best().await?.map(|block| (false, block)),
+                self.api
+                    .blocks()
+                    .subscribe_finalized()
+                    .await?
+                    .map(|block| (true, block)),
+            )
+            .boxed(),
+        };
+        let api = self.api.clone();
+        let tracker = Arc::new(Mutex::new(ReorgTracker::default()));
+
+        Ok(blocks
+            .then(move |(finalized, block)| {
+                let api = api.clone();
+                let tracker = tracker.clone();
+                async move {
+                    let block = block?;
+                    let (number, hash) = (block.number(), block.hash());
+                    if finalized && finality == Finality::InBlock {
+                        tracker.lock().await.finalize(number);
+                        return Ok(vec![Ok(EventNotification::Finalized {
+                            block_number: number,
+                            block_hash: hash,
+                        })]);
+                    }
+
+                    let mut tracker = tracker.lock().await;
+                    if tracker.contains(number, hash) {
+                        return Ok(vec![]);
+                    }
+                    // walk back to where the new best block connects to the tracked chain
+                    let mut branch = vec![(number, hash)];
+                    let mut parent_hash = block.header().parent_hash;
+                    while !tracker.connects(branch[branch.len() - 1].0, parent_hash) {
+                        let parent = api.rpc().header(Some(parent_hash)).await?.ok_or(Error::BlockNotFound)?;
+                        branch.push((parent.number, parent_hash));
+                        parent_hash = parent.parent_hash;
+                    }
+                    branch.reverse();
+
+                    let mut notifications: Vec<_> = tracker.import(&branch).into_iter().map(Ok).collect();
+                    if finalized {
+                        tracker.finalize(number);
+                    }
+                    drop(tracker);
+                    for (block_number, block_hash) in branch {
+                        let events = api.events().at(Some(block_hash)).await?;
+                        notifications.extend(decode_events::<T, _, _>(events.iter()).into_iter().map(|event| {
+                            event.map(|event| EventNotification::Event {
+                                event,
+                                block_number,
+                                block_hash,
+                                finalized,
+                            })
+                        }));
+                    }
+                    Result::<_, Error>::Ok(notifications)
+                }
+            })
+            .flat_map(|notifications| {
+                stream::iter(match notifications {
+                    Ok(notifications) => notifications,
+                    Err(err) => vec![Err(err)],
+                })
+            })
+            .boxed())
+    }
+
     /// Subscription service that should listen forever, only returns if the initial subscription
     /// cannot be established. Calls `on_error` when an error event has been received, or when an
     /// event has been received that failed to be decoded into a raw event.
@@ -480,11 +974,160 @@ impl InterBtcParachain {
         Ok(())
     }
 
+    /// Like `on_event`, but the last processed block is stored in `cursor`, and the events of the
+    /// blocks since then are replayed before listening for new ones, so that no events are missed
+    /// across restarts. Events are processed one at a time, so `on_event` should return quickly.
+    /// `on_error` is called for events that fail to decode.
+    pub async fn on_event_from_cursor<T, F, R, E>(
+        &self,
+        cursor: &BlockCursor,
+        mut on_event: F,
+        on_error: E,
+    ) -> Result<(), Error>
+    where
+        T: StaticEvent + core::fmt::Debug,
+        F: FnMut(T) -> R,
+        R: Future<Output = ()>,
+        E: Fn(Error),
+    {
+        let blocks = if cfg!(feature = "testing-utils") {
+            self.api.blocks().subscribe_best().await?.boxed()
+        } else {
+            self.api.blocks().subscribe_finalized().await?.boxed()
+        };
+        let blocks = blocks.then(|block| async move {
+            let block = block?;
+            let events = block.events().await?;
+            Result::<_, Error>::Ok((block.number(), decode_events::<T, _, _>(events.iter())))
+        });
+        futures::pin_mut!(blocks);
+
+        process_blocks_from_cursor(
+            blocks,
+            cursor,
+            |number| async move {
+                let block_hash = self.get_parachain_block_hash(BlockId::Number(number)).await?;
+                let events = self.api.events().at(Some(block_hash)).await?;
+                Ok(decode_events::<T, _, _>(events.iter()))
+            },
+            |event| {
+                let handled = match event {
+                    Ok(event) => {
+                        log::trace!("event: {:?}", event);
+                        Some(on_event(event))
+                    }
+                    Err(err) => {
+                        on_error(err);
+                        None
+                    }
+                };
+                async move {
+                    if let Some(handled) = handled {
+                        handled.await;
+                    }
+                }
+            },
+        )
+        .await
+    }
+
+    /// Listen for runtime upgrades and signal a shutdown with `ShutdownReason::RuntimeUpgraded`
+    /// when one is observed, so that the client can be restarted with fresh metadata before
+    /// decoding errors accumulate. Like `on_event`, this only returns on subscription failure.
+    pub async fn shutdown_on_runtime_upgrade(&self) -> Result<(), Error> {
+        let shutdown_tx = &self.shutdown_tx;
+        self.on_event::<CodeUpdatedEvent, _, _, _>(
+            |_| async move { signal_runtime_upgrade(shutdown_tx) },
+            |err| log::error!("Error (CodeUpdatedEvent): {}", err),
+        )
+        .await
+    }
+
     async fn batch(&self, calls: Vec<EncodedCall>) -> Result<(), Error> {
         self.with_unique_signer(metadata::tx().utility().batch(calls)).await?;
         Ok(())
     }
 
+    /// Submit `calls` in a single `utility.batch`, returning its events once it has reached
+    /// `finality`.
+    pub async fn submit_batch(
+        &self,
+        calls: Vec<EncodedCall>,
+        finality: Finality,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        self.submit(metadata::tx().utility().batch(calls), finality).await
+    }
+
+    /// Submit `calls` in a single `utility.batch_all`, which is reverted entirely if any of the
+    /// calls fails, returning its events once it has reached `finality`.
+    pub async fn submit_batch_all(
+        &self,
+        calls: Vec<EncodedCall>,
+        finality: Finality,
+    ) -> Result<ExtrinsicEvents<InterBtcRuntime>, Error> {
+        self.submit(metadata::tx().utility().batch_all(calls), finality).await
+    }
+
+    /// The weight (ref time) a batch may use, derived from the `BlockWeights` of the runtime.
+    pub fn max_batch_weight(&self) -> Result<u64, Error> {
+        let block_weights = self
+            .api
+            .constants()
+            .at(&subxt::dynamic::constant("System", "BlockWeights"))?;
+        parse_max_batch_weight(&serde_json::to_value(&block_weights)?)
+    }
+
+    /// Submit `calls` in as many `utility.batch_all`s as needed for each to weigh at most `max_weight`,
+    /// using the weights reported by the transaction payment API of the node. These are queried once
+    /// per call type (pallet and call index), so all calls of a type are assumed to weigh the same.
+    /// The batches are submitted one after another, and the result of each is returned with the
+    /// indices of its calls. Each batch is applied atomically, so a failed call fails (only) the
+    /// batch it is in.
+    pub async fn submit_batch_chunked(
+        &self,
+        calls: Vec<EncodedCall>,
+        max_weight: u64,
+    ) -> Result<Vec<(Range<usize>, Result<ExtrinsicEvents<InterBtcRuntime>, Error>)>, Error> {
+        let era = self.tx_era().await?;
+        let nonce = self.get_on_chain_nonce().await;
+        let encoded_calls: Vec<_> = calls.iter().map(Encode::encode).collect();
+        let mut calls_by_type = HashMap::new();
+        for encoded_call in &encoded_calls {
+            calls_by_type.entry(call_type(encoded_call)).or_insert(encoded_call);
+        }
+        let weight_by_type: HashMap<_, _> =
+            futures::future::try_join_all(calls_by_type.into_iter().map(|(call_type, encoded_call)| async move {
+                let tx = self.api.tx().create_signed_with_nonce(
+                    &RawCall(encoded_call.clone()),
+                    &self.signer,
+                    nonce,
+                    extrinsic_params(self.tx_tip, era),
+                )?;
+                let dispatch_info: Value = self
+                    .rpc_request("payment_queryInfo", rpc_params![Bytes(tx.encoded().to_vec())])
+                    .await?;
+                Ok::<_, Error>((call_type, parse_weight(&dispatch_info)?))
+            }))
+            .await?
+            .into_iter()
+            .collect();
+        let weights: Vec<_> = encoded_calls
+            .iter()
+            .map(|encoded_call| weight_by_type[&call_type(encoded_call)])
+            .collect();
+
+        let chunks = chunk_by_weight(&weights, max_weight)?;
+        log::debug!("Submitting {} calls in {} batches", calls.len(), chunks.len());
+        let mut calls = calls.into_iter();
+        let mut results = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let batch = calls.by_ref().take(chunk.len()).collect();
+            let result = self.submit_batch_all(batch, Finality::default()).await;
+            results.push((chunk, result));
+        }
+        Ok(results)
+    }
+
     /// Emulate the POOL_INVALID_TX error using token transfer extrinsics.
     #[cfg(test)]
     pub async fn get_invalid_tx_error(&self, recipient: AccountId) -> Error {
@@ -574,6 +1217,29 @@ impl InterBtcParachain {
         AssetRegistry::extend(self.get_foreign_assets_metadata().await?)
     }
 
+    /// Parse a currency using the cached asset registry. The registry is only refreshed from
+    /// the chain if the currency is not known yet, e.g. because it was registered recently:
+    /// `FOREIGN(<id>)` only fetches that asset, while symbols reload all of them.
+    pub async fn parse_currency(&self, src: &str) -> Result<CurrencyId, Error> {
+        match parse_currency_with_registry(src, &AssetRegistry::snapshot()?) {
+            Err(Error::InvalidCurrency) => {
+                match foreign_asset_id(&src.trim().to_uppercase()) {
+                    Some(foreign_asset_id) => {
+                        AssetRegistry::get_or_fetch_asset_metadata(self, foreign_asset_id?)
+                            .await
+                            .map_err(|err| match err {
+                                Error::AssetNotFound => Error::InvalidCurrency,
+                                err => err,
+                            })?;
+                    }
+                    None => self.store_assets_metadata().await?,
+                }
+                parse_currency_with_registry(src, &AssetRegistry::snapshot()?)
+            }
+            result => result,
+        }
+    }
+
     /// Cache registered assets and updates
     pub async fn listen_for_registered_assets(&self) -> Result<(), Error> {
         futures::future::try_join(
@@ -598,13 +1264,13 @@ impl InterBtcParachain {
         Ok(())
     }
 
-    /// Listen to fee_rate changes and broadcast new values on the fee_rate_update_tx channel
+    /// Listen to fee_rate changes and broadcast new values to the fee rate subscribers
     pub async fn listen_for_fee_rate_changes(&self) -> Result<(), Error> {
         self.on_event::<FeedValuesEvent, _, _, _>(
             |event| async move {
                 for (key, value) in event.values {
                     if let OracleKey::FeeEstimation = key {
-                        let _ = self.fee_rate_update_tx.send(value);
+                        self.fee_rate_updates.send(value);
                     }
                 }
             },
@@ -618,6 +1284,54 @@ impl InterBtcParachain {
         .await?;
         Ok(())
     }
+
+    /// Invalidate the cached thresholds of `collateral_currency` whenever one of them changes,
+    /// until the subscription ends.
+    async fn watch_thresholds(&self, collateral_currency: CurrencyId) -> Result<(), Error> {
+        let currency_pair = VaultCurrencyPair {
+            collateral: collateral_currency,
+            wrapped: self.wrapped_currency_id,
+        };
+        let vault_registry = metadata::storage().vault_registry();
+        let keys = vec![
+            self.api
+                .storage()
+                .address_bytes(&vault_registry.secure_collateral_threshold(&currency_pair))?,
+            self.api
+                .storage()
+                .address_bytes(&vault_registry.premium_redeem_threshold(&currency_pair))?,
+            self.api
+                .storage()
+                .address_bytes(&vault_registry.liquidation_collateral_threshold(&currency_pair))?,
+        ]
+        .into_iter()
+        .map(StorageKey)
+        .collect::<Vec<_>>();
+
+        let mut changes = self
+            .api
+            .rpc()
+            .subscribe::<StorageChangeSet<H256>>(
+                "state_subscribeStorage",
+                rpc_params![keys],
+                "state_unsubscribeStorage",
+            )
+            .await?;
+        // the first notification holds the current values
+        if let Some(Err(err)) = changes.next().await {
+            return Err(err.into());
+        }
+
+        let thresholds = self.thresholds.clone();
+        tokio::spawn(async move {
+            while let Some(Ok(change_set)) = changes.next().await {
+                log::info!("Thresholds of {:?} changed", collateral_currency);
+                thresholds.invalidate(collateral_currency, change_set.block);
+            }
+            thresholds.stop_watching(collateral_currency);
+        });
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -627,6 +1341,10 @@ pub trait UtilFuncs {
 
     async fn get_rpc_properties(&self) -> Result<serde_json::Map<String, Value>, Error>;
 
+    /// Gets the version of the runtime the node currently runs, which may differ from the
+    /// version at the time of connecting after a runtime upgrade.
+    async fn runtime_version(&self) -> Result<RuntimeVersion, Error>;
+
     /// Gets the ID of the native currency.
     fn get_native_currency_id(&self) -> CurrencyId;
 
@@ -638,6 +1356,14 @@ pub trait UtilFuncs {
     async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, Error>;
 
     async fn get_foreign_asset_metadata(&self, id: u32) -> Result<AssetMetadata, Error>;
+
+    /// Wait until the node has caught up with the network, i.e. it is not in a major sync and its
+    /// best block is at most `tolerance` blocks behind the highest block it has seen.
+    ///
+    /// # Arguments
+    /// * `tolerance` - number of blocks the node may lag behind
+    /// * `timeout_after` - how long to wait before returning `Error::SyncTimeout`
+    async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), Error>;
 }
 
 #[async_trait]
@@ -648,7 +1374,18 @@ impl UtilFuncs for InterBtcParachain {
     }
 
     async fn get_rpc_properties(&self) -> Result<serde_json::Map<String, Value>, Error> {
-        Ok(self.api.rpc().system_properties().await?)
+        self.observe_call("system_properties", async {
+            Ok(self.api.rpc().system_properties().await?)
+        })
+        .await
+    }
+
+    async fn runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        fetch_runtime_version(&self.api).await
+    }
+
+    async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), Error> {
+        wait_until_synced(|| self.get_sync_status(), tolerance, timeout_after, SYNC_POLL_INTERVAL).await
     }
 
     fn get_native_currency_id(&self) -> CurrencyId {
@@ -664,12 +1401,10 @@ impl UtilFuncs for InterBtcParachain {
     }
 
     async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, Error> {
-        let head = self.get_finalized_block_hash().await?;
         let key_addr = metadata::storage().asset_registry().metadata_root();
-        let mut iter = self.api.storage().iter(key_addr, DEFAULT_PAGE_SIZE, head).await?;
 
         let mut ret = Vec::new();
-        while let Some((key, value)) = iter.next().await? {
+        for (key, value) in self.query_finalized_iter(key_addr).await? {
             let raw_key = key.0.clone();
 
             // last bytes are the raw key
@@ -694,6 +1429,13 @@ pub trait CollateralBalancesPallet {
 
     async fn get_free_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, Error>;
 
+    /// Get the free balances of `id` in all of `currency_ids` with a single storage query
+    async fn get_balances(
+        &self,
+        id: AccountId,
+        currency_ids: &[CurrencyId],
+    ) -> Result<Vec<(CurrencyId, Balance)>, Error>;
+
     async fn get_reserved_balance(&self, currency_id: CurrencyId) -> Result<Balance, Error>;
 
     async fn get_reserved_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, Error>;
@@ -712,6 +1454,50 @@ impl CollateralBalancesPallet for InterBtcParachain {
         Ok(self.query_finalized_or_default(storage_key).await?.free)
     }
 
+    async fn get_balances(
+        &self,
+        id: AccountId,
+        currency_ids: &[CurrencyId],
+    ) -> Result<Vec<(CurrencyId, Balance)>, Error> {
+        let keys = currency_ids
+            .iter()
+            .map(|currency_id| {
+                self.api
+                    .storage()
+                    .address_bytes(&metadata::storage().tokens().accounts(&id, currency_id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let head = self.get_finalized_block_hash().await?;
+        let mut values: HashMap<_, _> = self
+            .observe_call("state_queryStorageAt", async {
+                Ok(self
+                    .api
+                    .rpc()
+                    .query_storage_at(keys.iter().map(Vec::as_slice), head)
+                    .await?)
+            })
+            .await?
+            .into_iter()
+            .flat_map(|change_set| change_set.changes)
+            .map(|(key, value)| (key.0, value))
+            .collect();
+
+        currency_ids
+            .iter()
+            .zip(keys)
+            .map(|(currency_id, key)| {
+                // accounts that never held a currency have no storage entry
+                let free = match values.remove(&key).flatten() {
+                    Some(data) => {
+                        metadata::runtime_types::orml_tokens::AccountData::<Balance>::decode(&mut &data.0[..])?.free
+                    }
+                    None => 0,
+                };
+                Ok((*currency_id, free))
+            })
+            .collect()
+    }
+
     async fn get_reserved_balance(&self, currency_id: CurrencyId) -> Result<Balance, Error> {
         Ok(Self::get_reserved_balance_for_id(self, self.account_id.clone(), currency_id).await?)
     }
@@ -760,7 +1546,26 @@ pub trait ReplacePallet {
         new_vault: &VaultId,
         old_vault: &VaultId,
         amount_btc: u128,
-        collateral: u128,
+        collateral: u128,
+        btc_address: BtcAddress,
+    ) -> Result<(), Error>;
+
+    /// Accept request of vault replacement, locking the collateral needed to keep the
+    /// replaced amount at the secure threshold, plus `buffer_percent` on top
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the initiator of the transaction: the new vault
+    /// * `old_vault` - the vault to replace
+    /// * `amount_btc` - the amount of [Wrapped] to replace
+    /// * `buffer_percent` - additional collateral to lock, in percent of the minimum
+    /// * `btc_address` - the address to send funds to
+    async fn accept_replace_auto(
+        &self,
+        new_vault: &VaultId,
+        old_vault: &VaultId,
+        amount_btc: u128,
+        buffer_percent: u32,
         btc_address: BtcAddress,
     ) -> Result<(), Error>;
     //
@@ -803,6 +1608,21 @@ pub trait ReplacePallet {
 
     /// Gets the minimum btc amount for replace requests
     async fn get_replace_dust_amount(&self) -> Result<u128, Error>;
+
+    /// Request the replacement of enough [Wrapped] to bring the collateralization of the vault
+    /// back to `target_ratio` at the current exchange rate, but at least the dust amount
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - sender of the transaction: the old vault
+    /// * `target_ratio` - the collateralization to reach, e.g. 1.5 for 150%
+    ///
+    /// Returns the requested amount, or `None` if the vault is already at `target_ratio`.
+    async fn request_replace_to_threshold(
+        &self,
+        vault_id: &VaultId,
+        target_ratio: FixedU128,
+    ) -> Result<Option<u128>, Error>;
 }
 
 #[async_trait]
@@ -846,6 +1666,31 @@ impl ReplacePallet for InterBtcParachain {
         Ok(())
     }
 
+    async fn accept_replace_auto(
+        &self,
+        new_vault: &VaultId,
+        old_vault: &VaultId,
+        amount_btc: u128,
+        buffer_percent: u32,
+        btc_address: BtcAddress,
+    ) -> Result<(), Error> {
+        let collateral_currency = new_vault.collateral_currency();
+        let (thresholds, collateral_equivalent, free_balance) = futures::future::try_join3(
+            self.thresholds(collateral_currency),
+            self.wrapped_to_collateral(amount_btc, collateral_currency),
+            self.get_free_balance(collateral_currency),
+        )
+        .await?;
+
+        let collateral = required_replace_collateral(collateral_equivalent, thresholds.secure, buffer_percent)?;
+        if free_balance < collateral {
+            return Err(Error::InsufficientFunds);
+        }
+
+        self.accept_replace(new_vault, old_vault, amount_btc, collateral, btc_address)
+            .await
+    }
+
     async fn execute_replace(&self, replace_id: H256, merkle_proof: &[u8], raw_tx: &[u8]) -> Result<(), Error> {
         self.with_unique_signer(metadata::tx().replace().execute_replace(
             replace_id,
@@ -869,9 +1714,7 @@ impl ReplacePallet for InterBtcParachain {
     ) -> Result<Vec<(H256, InterBtcReplaceRequest)>, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: Vec<H256> = self
-            .api
-            .rpc()
-            .request("replace_getNewVaultReplaceRequests", rpc_params![account_id, head])
+            .rpc_request("replace_getNewVaultReplaceRequests", rpc_params![account_id, head])
             .await?;
         join_all(
             result
@@ -890,9 +1733,7 @@ impl ReplacePallet for InterBtcParachain {
     ) -> Result<Vec<(H256, InterBtcReplaceRequest)>, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: Vec<H256> = self
-            .api
-            .rpc()
-            .request("replace_getOldVaultReplaceRequests", rpc_params![account_id, head])
+            .rpc_request("replace_getOldVaultReplaceRequests", rpc_params![account_id, head])
             .await?;
         join_all(
             result
@@ -918,11 +1759,46 @@ impl ReplacePallet for InterBtcParachain {
         self.query_finalized_or_error(metadata::storage().replace().replace_btc_dust_value())
             .await
     }
+
+    async fn request_replace_to_threshold(
+        &self,
+        vault_id: &VaultId,
+        target_ratio: FixedU128,
+    ) -> Result<Option<u128>, Error> {
+        let collateral_currency = vault_id.collateral_currency();
+        let (vault, collateral, dust_amount, griefing_rate, free_balance) = futures::try_join!(
+            self.get_vault(vault_id),
+            self.get_vault_total_collateral(vault_id.clone()),
+            self.get_replace_dust_amount(),
+            self.get_replace_griefing_collateral(),
+            self.get_free_balance(collateral_currency),
+        )?;
+        let collateral_in_wrapped = self.collateral_to_wrapped(collateral, collateral_currency).await?;
+
+        let amount = match replace_amount_to_threshold(&vault, collateral_in_wrapped, target_ratio, dust_amount)? {
+            Some(amount) => amount,
+            None => return Ok(None),
+        };
+
+        // the griefing collateral is locked from the free balance of the vault
+        let griefing_collateral = griefing_rate
+            .checked_mul_int(self.wrapped_to_collateral(amount, collateral_currency).await?)
+            .ok_or(Error::ArithmeticOverflow)?;
+        if free_balance < griefing_collateral {
+            return Err(Error::InsufficientFunds);
+        }
+
+        self.request_replace(vault_id, amount).await?;
+        Ok(Some(amount))
+    }
 }
 
 #[async_trait]
 pub trait TimestampPallet {
     async fn get_time_now(&self) -> Result<u64, Error>;
+
+    /// Offset of the parachain time from the local system clock
+    async fn clock_drift(&self) -> Result<ClockDrift, Error>;
 }
 
 #[async_trait]
@@ -932,12 +1808,369 @@ impl TimestampPallet for InterBtcParachain {
         self.query_finalized_or_error(metadata::storage().timestamp().now())
             .await
     }
+
+    /// Compare the time of the latest finalized block against the local system clock.
+    async fn clock_drift(&self) -> Result<ClockDrift, Error> {
+        let chain_time_ms = self.get_time_now().await?;
+        Ok(ClockDrift::between(chain_time_ms, SystemTime::now()))
+    }
+}
+
+/// The collateral needed to back `collateral_equivalent` (the replaced amount, valued in
+/// collateral) at `threshold`, increased by `buffer_percent`.
+pub(crate) fn required_replace_collateral(
+    collateral_equivalent: u128,
+    threshold: FixedU128,
+    buffer_percent: u32,
+) -> Result<u128, Error> {
+    threshold
+        .checked_mul_int(collateral_equivalent)
+        .and_then(|collateral| collateral.checked_mul(100u128.saturating_add(buffer_percent.into())))
+        .map(|collateral| collateral / 100)
+        .ok_or(Error::ArithmeticOverflow)
+}
+
+/// The page of issue requests of `vault_id` among `entries`, the raw keys and requests of a scan
+/// of at most `page_size` storage entries. A full scan may be followed by more entries.
+pub(crate) fn issue_requests_page(
+    entries: Vec<(Vec<u8>, InterBtcIssueRequest)>,
+    vault_id: &VaultId,
+    page_size: u32,
+) -> IssueRequestsPage {
+    let next_cursor = match entries.last() {
+        Some((key, _)) if entries.len() >= page_size as usize => Some(key.clone()),
+        _ => None,
+    };
+    let requests = entries
+        .into_iter()
+        .filter(|(_, request)| &request.vault == vault_id)
+        // last bytes are the raw key
+        .map(|(key, request)| (H256::from_slice(&key[key.len() - 32..]), request))
+        .collect();
+    IssueRequestsPage { requests, next_cursor }
+}
+
+/// The pending `requests` of `vault_id` due within `within` of `current_height` and the relay's
+/// `current_btc_height`, sorted by the time remaining. Like the redeem pallet, the deadline uses the
+/// longer of the request's period and the current `redeem_period`, and only passes once the period
+/// has elapsed on both chains.
+pub(crate) fn redeems_nearing_deadline(
+    requests: Vec<(H256, InterBtcRedeemRequest)>,
+    vault_id: &VaultId,
+    redeem_period: BlockNumber,
+    current_height: BlockNumber,
+    current_btc_height: u32,
+    within: Duration,
+) -> Vec<(H256, InterBtcRedeemRequest, Duration)> {
+    let mut nearing_deadline: Vec<_> = requests
+        .into_iter()
+        .filter(|(_, request)| request.status == RedeemRequestStatus::Pending && &request.vault == vault_id)
+        .filter_map(|(redeem_id, request)| {
+            let period = request.period.max(redeem_period);
+            let deadline = request.opentime.saturating_add(period);
+            let time_to_parachain_deadline = BLOCK_INTERVAL * deadline.saturating_sub(current_height);
+
+            // the period in bitcoin blocks, rounded up
+            let period_millis = BLOCK_INTERVAL.as_millis().saturating_mul(period.into());
+            let btc_period = period_millis
+                .saturating_add(bitcoin::BLOCK_INTERVAL.as_millis().saturating_sub(1))
+                .checked_div(bitcoin::BLOCK_INTERVAL.as_millis())?;
+            let btc_deadline = u128::from(request.btc_height).saturating_add(btc_period);
+            let btc_blocks_remaining = btc_deadline.saturating_sub(current_btc_height.into()).try_into().ok()?;
+            let time_to_bitcoin_deadline = bitcoin::BLOCK_INTERVAL.checked_mul(btc_blocks_remaining)?;
+
+            let time_remaining = time_to_parachain_deadline.max(time_to_bitcoin_deadline);
+            (time_remaining <= within).then_some((redeem_id, request, time_remaining))
+        })
+        .collect();
+    nearing_deadline.sort_by_key(|(_, _, time_remaining)| *time_remaining);
+    nearing_deadline
+}
+
+/// The amount of [Wrapped] that `vault` has to replace for `collateral_in_wrapped` to back the rest
+/// of its tokens at `target_ratio`, raised to the `dust_amount`. Tokens already requested to be
+/// replaced count as replaced, and only tokens that are neither being redeemed nor replaced can be
+/// requested. `None` if the vault is already at `target_ratio`.
+pub(crate) fn replace_amount_to_threshold(
+    vault: &InterBtcVault,
+    collateral_in_wrapped: u128,
+    target_ratio: FixedU128,
+    dust_amount: u128,
+) -> Result<Option<u128>, Error> {
+    let backed = FixedU128::checked_from_integer(collateral_in_wrapped)
+        .and_then(|collateral| collateral.checked_div(&target_ratio))
+        // round down, so that the target is reached
+        .map(|backed| backed.into_inner() / FixedU128::accuracy())
+        .ok_or(Error::ArithmeticOverflow)?;
+    let excess = vault
+        .issued_tokens
+        .saturating_add(vault.to_be_issued_tokens)
+        .saturating_sub(vault.to_be_replaced_tokens)
+        .saturating_sub(backed);
+    if excess == 0 {
+        return Ok(None);
+    }
+
+    let requestable = vault
+        .issued_tokens
+        .saturating_sub(vault.to_be_redeemed_tokens)
+        .saturating_sub(vault.to_be_replaced_tokens);
+    let amount = excess.max(dust_amount).min(requestable);
+    if amount < dust_amount {
+        return Err(Error::ReplaceAmountTooLow(requestable, dust_amount));
+    }
+    Ok(Some(amount))
+}
+
+/// The exchange rate at which `collateral` backs `issued_tokens` at exactly the liquidation
+/// `threshold`, or `None` if nothing is issued or the threshold is zero, since the vault cannot be
+/// liquidated then.
+pub(crate) fn liquidation_rate(
+    collateral: u128,
+    issued_tokens: u128,
+    threshold: FixedU128,
+) -> Result<Option<FixedU128>, Error> {
+    if issued_tokens == 0 || threshold.is_zero() {
+        return Ok(None);
+    }
+    // collateral / (issued_tokens * rate) = threshold
+    FixedU128::checked_from_rational(collateral, issued_tokens)
+        .and_then(|collateral_per_token| collateral_per_token.checked_div(&threshold))
+        .map(Some)
+        .ok_or(Error::ArithmeticOverflow)
+}
+
+/// The collateralization of a vault, `None` if it has not issued any tokens.
+pub(crate) fn collateralization_ratio(collateral: u128, issued_in_collateral: u128) -> Option<FixedU128> {
+    FixedU128::checked_from_rational(collateral, issued_in_collateral)
+}
+
+/// Check that withdrawing `amount` of `collateral` leaves `issued_tokens` collateralized by at
+/// least `min_ratio`, at `exchange_rate` collateral per wrapped token.
+pub(crate) fn check_withdrawal(
+    collateral: u128,
+    amount: u128,
+    issued_tokens: u128,
+    exchange_rate: FixedU128,
+    min_ratio: FixedU128,
+) -> Result<(), Error> {
+    let remaining = collateral.checked_sub(amount).ok_or(Error::InsufficientFunds)?;
+    let issued_in_collateral = exchange_rate
+        .checked_mul_int(issued_tokens)
+        .ok_or(Error::ArithmeticOverflow)?;
+    if issued_in_collateral == 0 {
+        // nothing to back
+        return Ok(());
+    }
+    match collateralization_ratio(remaining, issued_in_collateral) {
+        Some(ratio) if ratio < min_ratio => Err(Error::WouldBreachThreshold(ratio, min_ratio)),
+        // only overflows if the ratio is too large to represent
+        _ => Ok(()),
+    }
+}
+
+/// Label of a storage query in the call metrics, e.g. `Oracle.Aggregate`
+fn storage_method_name<Address: StorageAddress>(address: &Address) -> String {
+    format!("{}.{}", address.pallet_name(), address.entry_name())
+}
+
+/// The pallet and call index that an encoded call starts with
+fn call_type(encoded_call: &[u8]) -> &[u8] {
+    &encoded_call[..encoded_call.len().min(2)]
+}
+
+/// The ref time of a `weight`, which is a number before weights v2 and an object with the
+/// `refTime` and `proofSize` since.
+fn ref_time(weight: &Value) -> Option<u64> {
+    match weight {
+        Value::Number(weight) => weight.as_u64(),
+        Value::Object(weight) => weight
+            .get("refTime")
+            .or_else(|| weight.get("ref_time"))
+            .and_then(Value::as_u64),
+        _ => None,
+    }
+}
+
+/// Extract the (ref time) `weight` from a `payment_queryInfo` response.
+pub(crate) fn parse_weight(dispatch_info: &Value) -> Result<u64, Error> {
+    dispatch_info
+        .get("weight")
+        .and_then(ref_time)
+        .ok_or_else(|| Error::InvalidWeight(dispatch_info.to_string()))
+}
+
+/// The weight (ref time) a batch may use, given the `System.BlockWeights` constant.
+pub(crate) fn parse_max_batch_weight(block_weights: &Value) -> Result<u64, Error> {
+    block_weights
+        .get("max_block")
+        .and_then(ref_time)
+        .map(|max_block| max_block / BATCH_WEIGHT_DIVISOR)
+        .ok_or_else(|| Error::InvalidWeight(block_weights.to_string()))
+}
+
+/// Split the calls with `weights` into consecutive chunks, in order, that each weigh at most
+/// `max_weight`.
+pub(crate) fn chunk_by_weight(weights: &[u64], max_weight: u64) -> Result<Vec<Range<usize>>, Error> {
+    let mut chunks = vec![];
+    let mut start = 0;
+    let mut chunk_weight = 0u64;
+    for (index, weight) in weights.iter().copied().enumerate() {
+        if weight > max_weight {
+            return Err(Error::CallTooHeavy(index, weight, max_weight));
+        }
+        match chunk_weight.checked_add(weight) {
+            Some(total) if total <= max_weight => chunk_weight = total,
+            _ => {
+                chunks.push(start..index);
+                start = index;
+                chunk_weight = weight;
+            }
+        }
+    }
+    if start < weights.len() {
+        chunks.push(start..weights.len());
+    }
+    Ok(chunks)
+}
+
+/// Extract the `partialFee` from a `payment_queryInfo` response, which the node
+/// serializes as a decimal string to avoid overflowing json numbers.
+pub(crate) fn parse_partial_fee(fee_info: &Value) -> Result<Balance, Error> {
+    match fee_info.get("partialFee") {
+        Some(Value::String(fee)) => fee.parse().ok(),
+        Some(Value::Number(fee)) => fee.as_u64().map(Into::into),
+        _ => None,
+    }
+    .ok_or_else(|| Error::InvalidFeeEstimate(fee_info.to_string()))
+}
+
+async fn fetch_runtime_version(api: &OnlineClient<InterBtcRuntime>) -> Result<RuntimeVersion, Error> {
+    let runtime_version = api.rpc().runtime_version(None).await?;
+    Ok(RuntimeVersion {
+        spec_name: runtime_version
+            .other
+            .get("specName")
+            .and_then(|value| value.as_str())
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        spec_version: runtime_version.spec_version,
+        transaction_version: runtime_version.transaction_version,
+    })
+}
+
+/// Check that the runtime of the node matches the metadata the client was compiled against.
+pub(crate) fn check_runtime_version(runtime_version: &RuntimeVersion) -> Result<(), Error> {
+    if runtime_version.spec_name != DEFAULT_SPEC_NAME {
+        return Err(Error::ParachainMetadataMismatch(
+            DEFAULT_SPEC_NAME.into(),
+            runtime_version.spec_name.clone(),
+        ));
+    }
+    if !DEFAULT_SPEC_VERSION.contains(&runtime_version.spec_version) {
+        return Err(Error::InvalidSpecVersion(
+            DEFAULT_SPEC_VERSION.start,
+            DEFAULT_SPEC_VERSION.end,
+            runtime_version.spec_version,
+        ));
+    }
+    Ok(())
+}
+
+/// Combine the `system_health` and `system_syncState` responses of the node.
+pub(crate) fn parse_sync_status(health: &Value, sync_state: &Value) -> Result<SyncStatus, Error> {
+    let invalid = || Error::InvalidSyncState(format!("health: {}, sync state: {}", health, sync_state));
+    let block_number = |key: &str| sync_state.get(key).and_then(Value::as_u64).map(u32::try_from);
+    Ok(SyncStatus {
+        is_syncing: health.get("isSyncing").and_then(Value::as_bool).ok_or_else(invalid)?,
+        current_block: block_number("currentBlock")
+            .ok_or_else(invalid)?
+            .map_err(|_| invalid())?,
+        // not set by nodes that have no peers yet
+        highest_block: block_number("highestBlock").transpose().map_err(|_| invalid())?,
+    })
+}
+
+/// Poll the sync status with `get_status` until it is synced within `tolerance` blocks.
+pub(crate) async fn wait_until_synced<F, Fut>(
+    get_status: F,
+    tolerance: u32,
+    timeout_after: Duration,
+    poll_interval: Duration,
+) -> Result<(), Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<SyncStatus, Error>>,
+{
+    timeout(timeout_after, async {
+        loop {
+            let status = get_status().await?;
+            if status.is_synced(tolerance) {
+                return Ok(());
+            }
+            log::info!(
+                "Waiting for the parachain node to sync: at block {} of {:?}",
+                status.current_block,
+                status.highest_block
+            );
+            sleep(poll_interval).await;
+        }
+    })
+    .await
+    .map_err(|_| Error::SyncTimeout)?
+}
+
+/// The additional extrinsic parameters to sign with, tipping the block author `tip`. If `era` is given,
+/// the extrinsic is only valid during that era, which starts at the block with the given hash.
+pub(crate) fn extrinsic_params(
+    tip: Balance,
+    era: Option<(Era, H256)>,
+) -> PolkadotExtrinsicParamsBuilder<InterBtcRuntime> {
+    let params = PolkadotExtrinsicParamsBuilder::new().tip(PlainTip::new(tip));
+    match era {
+        Some((era, checkpoint)) => params.era(era, checkpoint),
+        None => params,
+    }
+}
+
+/// An era that is valid for (at least) `lifetime` blocks, starting at `current_block`. The lifetime is
+/// rounded up to a power of two, as required by the encoding.
+pub(crate) fn mortal_era(lifetime: u64, current_block: BlockNumber) -> Era {
+    Era::mortal(lifetime, current_block.into())
+}
+
+/// Request a shutdown with `ShutdownReason::RuntimeUpgraded`, so that the client is restarted with the
+/// metadata of the new runtime.
+pub(crate) fn signal_runtime_upgrade(shutdown_tx: &ShutdownSender) {
+    log::warn!("Parachain runtime was upgraded - restart required");
+    let _ = shutdown_tx.shutdown(ShutdownReason::RuntimeUpgraded);
+}
+
+/// Number of confirmations of the relayed block at `block_height` when the best relayed block is at
+/// `best_block_height`, counting the block itself. Zero if the block is not relayed yet.
+pub(crate) fn relay_confirmations(block_height: u32, best_block_height: u32) -> u32 {
+    best_block_height
+        .checked_sub(block_height)
+        .map_or(0, |depth| depth.saturating_add(1))
+}
+
+/// One `oracle.feed_values` call per value, to be submitted in a `utility.batch_all`
+pub(crate) fn oracle_feed_values_calls(values: Vec<(OracleKey, FixedU128)>) -> Vec<EncodedCall> {
+    values
+        .into_iter()
+        .map(|value| {
+            EncodedCall::Oracle(metadata::runtime_types::oracle::pallet::Call::feed_values { values: vec![value] })
+        })
+        .collect()
 }
 
 #[async_trait]
 pub trait OraclePallet {
     async fn get_exchange_rate(&self, currency_id: CurrencyId) -> Result<FixedU128, Error>;
 
+    /// Get the exchange rate as of the given block, or `None` if it was not set at the time.
+    async fn get_exchange_rate_at(&self, currency_id: CurrencyId, block: BlockId) -> Result<Option<FixedU128>, Error>;
+
     async fn feed_values(&self, values: Vec<(OracleKey, FixedU128)>) -> Result<(), Error>;
 
     async fn set_bitcoin_fees(&self, value: FixedU128) -> Result<(), Error>;
@@ -948,6 +2181,10 @@ pub trait OraclePallet {
 
     async fn collateral_to_wrapped(&self, amount: u128, currency_id: CurrencyId) -> Result<u128, Error>;
 
+    /// Convert `amount` between any two currencies at the current exchange rates, routing through the
+    /// wrapped currency if neither is the wrapped currency.
+    async fn convert(&self, amount: Balance, from: CurrencyId, to: CurrencyId) -> Result<Balance, Error>;
+
     async fn has_updated(&self, key: &OracleKey) -> Result<bool, Error>;
 
     fn on_fee_rate_change(&self) -> FeeRateUpdateReceiver;
@@ -966,14 +2203,44 @@ impl OraclePallet for InterBtcParachain {
         .await
     }
 
-    /// Sets the current exchange rate (i.e. DOT/BTC)
+    async fn get_exchange_rate_at(&self, currency_id: CurrencyId, block: BlockId) -> Result<Option<FixedU128>, Error> {
+        let hash = self.get_parachain_block_hash(block).await?;
+        Ok(self
+            .api
+            .storage()
+            .fetch(
+                &metadata::storage()
+                    .oracle()
+                    .aggregate(&OracleKey::ExchangeRate(currency_id)),
+                Some(hash),
+            )
+            .await?)
+    }
+
+    /// Sets the current exchange rates (i.e. DOT/BTC), in batches that are each applied atomically.
+    /// All batches are submitted even if one fails, the error then names the keys of every failed
+    /// batch.
     ///
     /// # Arguments
-    /// * `value` - the current exchange rate
+    /// * `values` - the oracle keys and their current values
     async fn feed_values(&self, values: Vec<(OracleKey, FixedU128)>) -> Result<(), Error> {
-        self.with_unique_signer(metadata::tx().oracle().feed_values(values))
+        let keys: Vec<_> = values.iter().map(|(key, _)| key.clone()).collect();
+        let results = self
+            .submit_batch_chunked(oracle_feed_values_calls(values), self.max_batch_weight()?)
             .await?;
-        Ok(())
+        let mut failed_keys = vec![];
+        let mut first_err = None;
+        for (chunk, result) in results {
+            if let Err(err) = result {
+                log::warn!("Failed to feed values for {:?}: {}", &keys[chunk.clone()], err);
+                failed_keys.extend_from_slice(&keys[chunk]);
+                first_err.get_or_insert(err);
+            }
+        }
+        match first_err {
+            Some(err) => Err(Error::FeedValuesFailed(failed_keys, Box::new(err))),
+            None => Ok(()),
+        }
     }
 
     /// Sets the estimated Satoshis per bytes required to get a Bitcoin transaction included in
@@ -1002,9 +2269,7 @@ impl OraclePallet for InterBtcParachain {
     async fn wrapped_to_collateral(&self, amount: u128, currency_id: CurrencyId) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: BalanceWrapper<_> = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "oracle_wrappedToCollateral",
                 rpc_params![BalanceWrapper { amount }, currency_id, head],
             )
@@ -1017,9 +2282,7 @@ impl OraclePallet for InterBtcParachain {
     async fn collateral_to_wrapped(&self, amount: u128, currency_id: CurrencyId) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: BalanceWrapper<_> = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "oracle_collateralToWrapped",
                 rpc_params![BalanceWrapper { amount }, currency_id, head],
             )
@@ -1028,6 +2291,24 @@ impl OraclePallet for InterBtcParachain {
         Ok(result.amount)
     }
 
+    async fn convert(&self, amount: Balance, from: CurrencyId, to: CurrencyId) -> Result<Balance, Error> {
+        let mut exchange_rates = Vec::new();
+        if from != to {
+            for currency_id in [from, to] {
+                if currency_id != self.wrapped_currency_id {
+                    exchange_rates.push((currency_id, self.get_exchange_rate(currency_id).await?));
+                }
+            }
+        }
+        conversion::convert(amount, from, to, self.wrapped_currency_id, |currency_id| {
+            exchange_rates
+                .iter()
+                .find(|(id, _)| *id == currency_id)
+                .map(|(_, exchange_rate)| *exchange_rate)
+                .ok_or(Error::InvalidCurrency)
+        })
+    }
+
     async fn has_updated(&self, key: &OracleKey) -> Result<bool, Error> {
         Ok(self
             .query_finalized_or_error(metadata::storage().oracle().raw_values_updated(key))
@@ -1036,7 +2317,7 @@ impl OraclePallet for InterBtcParachain {
     }
 
     fn on_fee_rate_change(&self) -> FeeRateUpdateReceiver {
-        self.fee_rate_update_tx.subscribe()
+        self.fee_rate_updates.receiver()
     }
 }
 
@@ -1048,6 +2329,9 @@ pub trait SecurityPallet {
 
     /// Gets the current active block number of the parachain
     async fn get_current_active_block_number(&self) -> Result<u32, Error>;
+
+    /// Stream the number of every new best block, resubscribing if the subscription drops
+    fn subscribe_best_blocks(&self) -> BoxStream<'static, Result<BlockNumber, Error>>;
 }
 
 #[async_trait]
@@ -1070,6 +2354,23 @@ impl SecurityPallet for InterBtcParachain {
         self.query_finalized_or_default(metadata::storage().security().active_block_count())
             .await
     }
+
+    fn subscribe_best_blocks(&self) -> BoxStream<'static, Result<BlockNumber, Error>> {
+        let api = self.api.clone();
+        deduplicated_block_numbers(
+            move || {
+                let api = api.clone();
+                async move {
+                    let subscription = api.blocks().subscribe_best().await?;
+                    Ok(subscription
+                        .map(|block| block.map(|block| block.number()).map_err(Error::from))
+                        .boxed())
+                }
+            },
+            BLOCK_RESUBSCRIBE_DELAY,
+        )
+        .boxed()
+    }
 }
 
 #[async_trait]
@@ -1091,6 +2392,19 @@ pub trait IssuePallet {
     async fn get_issue_period(&self) -> Result<u32, Error>;
 
     async fn get_all_active_issues(&self) -> Result<Vec<(H256, InterBtcIssueRequest)>, Error>;
+
+    /// List the issue requests of `vault_id`, scanning `page_size` entries of the issue requests
+    /// at a time, starting after `cursor`. Pass the `next_cursor` of the page to get the next one.
+    async fn list_issue_requests(
+        &self,
+        vault_id: &VaultId,
+        page_size: u32,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<IssueRequestsPage, Error>;
+
+    /// Subscribe to new issue requests, starting from the current block. Resubscribes on
+    /// failure without yielding events from the same block twice.
+    fn subscribe_issue_requests(&self) -> BoxStream<'static, Result<RequestIssueEvent, Error>>;
 }
 
 #[async_trait]
@@ -1129,9 +2443,7 @@ impl IssuePallet for InterBtcParachain {
     ) -> Result<Vec<(H256, InterBtcIssueRequest)>, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: Vec<H256> = self
-            .api
-            .rpc()
-            .request("issue_getVaultIssueRequests", rpc_params![account_id, head])
+            .rpc_request("issue_getVaultIssueRequests", rpc_params![account_id, head])
             .await?;
         join_all(
             result
@@ -1154,11 +2466,9 @@ impl IssuePallet for InterBtcParachain {
 
         let mut issue_requests = Vec::new();
 
-        let head = self.get_finalized_block_hash().await?;
         let key_addr = metadata::storage().issue().issue_requests_root();
-        let mut iter = self.api.storage().iter(key_addr, DEFAULT_PAGE_SIZE, head).await?;
 
-        while let Some((issue_id, request)) = iter.next().await? {
+        for (issue_id, request) in self.query_finalized_iter(key_addr).await? {
             // todo: we also need to check the bitcoin height
             if request.status == IssueRequestStatus::Pending && request.opentime + issue_period > current_height {
                 let key_hash = issue_id.0.as_slice();
@@ -1169,6 +2479,75 @@ impl IssuePallet for InterBtcParachain {
         }
         Ok(issue_requests)
     }
+
+    async fn list_issue_requests(
+        &self,
+        vault_id: &VaultId,
+        page_size: u32,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<IssueRequestsPage, Error> {
+        let head = self.get_finalized_block_hash().await?;
+        let root = self
+            .api
+            .storage()
+            .address_root_bytes(&metadata::storage().issue().issue_requests_root());
+        let keys = self
+            .observe_call("state_getKeysPaged", async {
+                Ok(self
+                    .api
+                    .rpc()
+                    .storage_keys_paged(&root, page_size, cursor.as_deref(), head)
+                    .await?)
+            })
+            .await?;
+
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = self
+                .observe_call("state_getStorage", async {
+                    Ok(self.api.storage().fetch_raw(&key.0, head).await?)
+                })
+                .await?;
+            if let Some(value) = value {
+                entries.push((key.0, InterBtcIssueRequest::decode(&mut &value[..])?));
+            }
+        }
+        Ok(issue_requests_page(entries, vault_id, page_size))
+    }
+
+    fn subscribe_issue_requests(&self) -> BoxStream<'static, Result<RequestIssueEvent, Error>> {
+        let api = self.api.clone();
+        deduplicated_blocks(
+            move || {
+                let api = api.clone();
+                async move {
+                    let subscription = if cfg!(feature = "testing-utils") {
+                        api.blocks().subscribe_best().await?.boxed()
+                    } else {
+                        api.blocks().subscribe_finalized().await?.boxed()
+                    };
+                    Ok(subscription
+                        .then(|block| async move {
+                            let block = block?;
+                            let events = block.events().await?;
+                            Result::<_, Error>::Ok((
+                                block.number(),
+                                decode_events::<RequestIssueEvent, _, _>(events.iter()),
+                            ))
+                        })
+                        .boxed())
+                }
+            },
+            BLOCK_RESUBSCRIBE_DELAY,
+        )
+        .flat_map(|block| {
+            stream::iter(match block {
+                Ok((_, events)) => events,
+                Err(err) => vec![Err(err)],
+            })
+        })
+        .boxed()
+    }
 }
 
 #[async_trait]
@@ -1191,6 +2570,14 @@ pub trait RedeemPallet {
     ) -> Result<Vec<(H256, InterBtcRedeemRequest)>, Error>;
 
     async fn get_redeem_period(&self) -> Result<BlockNumber, Error>;
+
+    /// The pending redeem requests of `vault_id` whose deadline is at most `within` away on both the
+    /// parachain and the bitcoin relay, most urgent first, with the time remaining until their deadline.
+    async fn redeems_nearing_deadline(
+        &self,
+        vault_id: &VaultId,
+        within: Duration,
+    ) -> Result<Vec<(H256, InterBtcRedeemRequest, Duration)>, Error>;
 }
 
 #[async_trait]
@@ -1235,9 +2622,7 @@ impl RedeemPallet for InterBtcParachain {
     ) -> Result<Vec<(H256, InterBtcRedeemRequest)>, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: Vec<H256> = self
-            .api
-            .rpc()
-            .request("redeem_getVaultRedeemRequests", rpc_params![account_id, head])
+            .rpc_request("redeem_getVaultRedeemRequests", rpc_params![account_id, head])
             .await?;
         join_all(
             result
@@ -1253,6 +2638,27 @@ impl RedeemPallet for InterBtcParachain {
         self.query_finalized_or_error(metadata::storage().redeem().redeem_period())
             .await
     }
+
+    async fn redeems_nearing_deadline(
+        &self,
+        vault_id: &VaultId,
+        within: Duration,
+    ) -> Result<Vec<(H256, InterBtcRedeemRequest, Duration)>, Error> {
+        let (requests, redeem_period, current_height, current_btc_height) = futures::try_join!(
+            self.get_vault_redeem_requests(vault_id.account_id.clone()),
+            self.get_redeem_period(),
+            self.get_current_active_block_number(),
+            self.get_best_block_height(),
+        )?;
+        Ok(redeems_nearing_deadline(
+            requests,
+            vault_id,
+            redeem_period,
+            current_height,
+            current_btc_height,
+            within,
+        ))
+    }
 }
 
 #[async_trait]
@@ -1277,6 +2683,16 @@ pub trait BtcRelayPallet {
 
     async fn verify_block_header_inclusion(&self, block_hash: H256Le) -> Result<(), Error>;
 
+    /// Wait until the Bitcoin block has `required_confirmations` in the relay's main chain,
+    /// or `StableBitcoinConfirmations` if not specified. The relay only stores headers, so
+    /// transactions are identified by the hash of the block that includes them.
+    async fn wait_for_confirmations(
+        &self,
+        block_hash: H256Le,
+        required_confirmations: Option<u32>,
+        timeout_after: Duration,
+    ) -> Result<(), Error>;
+
     async fn initialize_btc_relay(&self, header: RawBlockHeader, height: BitcoinBlockHeight) -> Result<(), Error>;
 
     async fn store_block_header(&self, header: RawBlockHeader) -> Result<(), Error>;
@@ -1355,9 +2771,7 @@ impl BtcRelayPallet for InterBtcParachain {
     async fn verify_block_header_inclusion(&self, block_hash: H256Le) -> Result<(), Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: Result<(), metadata::DispatchError> = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "btcRelay_verifyBlockHeaderInclusion",
                 rpc_params![Into::<RichH256Le>::into(block_hash), head],
             )
@@ -1369,6 +2783,45 @@ impl BtcRelayPallet for InterBtcParachain {
         })
     }
 
+    async fn wait_for_confirmations(
+        &self,
+        block_hash: H256Le,
+        required_confirmations: Option<u32>,
+        timeout_after: Duration,
+    ) -> Result<(), Error> {
+        let required_confirmations = match required_confirmations {
+            Some(confirmations) => confirmations,
+            None => self.get_bitcoin_confirmations().await?,
+        };
+        // re-check whenever the relay state may have changed
+        let mut blocks = self.subscribe_best_blocks();
+
+        timeout(timeout_after, async {
+            loop {
+                let confirmations = match self.get_block_header(block_hash.clone()).await {
+                    Ok(header) if self.get_block_hash(header.block_height).await? == block_hash => {
+                        relay_confirmations(header.block_height, self.get_best_block_height().await?)
+                    }
+                    // not relayed yet, or not in the main chain (anymore)
+                    Ok(_) | Err(Error::StorageItemNotFound) => 0,
+                    Err(err) => return Err(err),
+                };
+                if confirmations >= required_confirmations {
+                    return Ok(());
+                }
+                log::trace!(
+                    "block {} has {}/{} confirmations",
+                    Into::<RichH256Le>::into(block_hash.clone()),
+                    confirmations,
+                    required_confirmations
+                );
+                blocks.next().await.ok_or(Error::ChannelClosed)??;
+            }
+        })
+        .await
+        .map_err(|_| Error::ConfirmationTimeout)?
+    }
+
     /// Initializes the relay with the provided block header and height,
     /// should be called automatically by relayer subject to the
     /// result of `is_initialized`.
@@ -1427,6 +2880,16 @@ pub trait VaultRegistryPallet {
 
     async fn withdraw_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), Error>;
 
+    /// Withdraw `amount` of collateral, unless that would leave the vault below a collateralization
+    /// of `min_ratio` at the current exchange rate, in which case `Error::WouldBreachThreshold` is
+    /// returned without submitting anything.
+    async fn withdraw_collateral_safe(
+        &self,
+        vault_id: &VaultId,
+        amount: u128,
+        min_ratio: FixedU128,
+    ) -> Result<(), Error>;
+
     async fn get_public_key(&self) -> Result<Option<BtcPublicKey>, Error>;
 
     async fn register_public_key(&self, public_key: BtcPublicKey) -> Result<(), Error>;
@@ -1443,6 +2906,23 @@ pub trait VaultRegistryPallet {
 
     async fn get_collateralization_from_vault(&self, vault_id: VaultId, only_issued: bool) -> Result<u128, Error>;
 
+    /// Compute the collateralization of the vault from its collateral, issued tokens and the
+    /// current exchange rate, `None` if it has not issued any tokens.
+    async fn get_collateralization_ratio(&self, vault_id: &VaultId) -> Result<Option<FixedU128>, Error>;
+
+    /// Sync status of the node, free balances and collateralization of the vaults of this account,
+    /// to tell at a glance whether they are healthy.
+    async fn health_summary(&self) -> Result<HealthSummary, Error>;
+
+    /// The exchange rate at which the vault of this account with `collateral_currency` would
+    /// reach the liquidation threshold, along with the current rate. `None` if the vault has not
+    /// issued any tokens or the liquidation threshold is zero, so it cannot be liquidated.
+    async fn liquidation_price(&self, collateral_currency: CurrencyId) -> Result<Option<LiquidationPrice>, Error>;
+
+    /// The secure, premium redeem and liquidation thresholds for `collateral_currency`. These are
+    /// cached until they change on chain.
+    async fn thresholds(&self, collateral_currency: CurrencyId) -> Result<Thresholds, Error>;
+
     async fn set_current_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), Error>;
 
     async fn set_pending_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), Error>;
@@ -1475,9 +2955,7 @@ impl VaultRegistryPallet for InterBtcParachain {
     async fn get_vaults_by_account_id(&self, account_id: &AccountId) -> Result<Vec<VaultId>, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result = self
-            .api
-            .rpc()
-            .request("vaultRegistry_getVaultsByAccountId", rpc_params![account_id, head])
+            .rpc_request("vaultRegistry_getVaultsByAccountId", rpc_params![account_id, head])
             .await?;
 
         Ok(result)
@@ -1485,12 +2963,10 @@ impl VaultRegistryPallet for InterBtcParachain {
 
     /// Fetch all active vaults.
     async fn get_all_vaults(&self) -> Result<Vec<InterBtcVault>, Error> {
-        let head = self.get_finalized_block_hash().await?;
         let key_addr = metadata::storage().vault_registry().vaults_root();
-        let mut iter = self.api.storage().iter(key_addr, DEFAULT_PAGE_SIZE, head).await?;
 
         let mut vaults = Vec::new();
-        while let Some((_, account)) = iter.next().await? {
+        for (_, account) in self.query_finalized_iter(key_addr).await? {
             if let VaultStatus::Active(..) = account.status {
                 vaults.push(account);
             }
@@ -1548,6 +3024,22 @@ impl VaultRegistryPallet for InterBtcParachain {
         Ok(())
     }
 
+    async fn withdraw_collateral_safe(
+        &self,
+        vault_id: &VaultId,
+        amount: u128,
+        min_ratio: FixedU128,
+    ) -> Result<(), Error> {
+        let (vault, collateral, exchange_rate) = futures::try_join!(
+            self.get_vault(vault_id),
+            self.get_vault_total_collateral(vault_id.clone()),
+            self.get_exchange_rate(vault_id.collateral_currency()),
+        )?;
+        let issued_tokens = vault.issued_tokens.saturating_add(vault.to_be_issued_tokens);
+        check_withdrawal(collateral, amount, issued_tokens, exchange_rate, min_ratio)?;
+        self.withdraw_collateral(vault_id, amount).await
+    }
+
     async fn get_public_key(&self) -> Result<Option<BtcPublicKey>, Error> {
         self.query_finalized(
             metadata::storage()
@@ -1578,9 +3070,7 @@ impl VaultRegistryPallet for InterBtcParachain {
     ) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: BalanceWrapper<_> = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "vaultRegistry_getRequiredCollateralForWrapped",
                 rpc_params![BalanceWrapper { amount: amount_btc }, collateral_currency, head],
             )
@@ -1594,9 +3084,7 @@ impl VaultRegistryPallet for InterBtcParachain {
     async fn get_required_collateral_for_vault(&self, vault_id: VaultId) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: BalanceWrapper<_> = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "vaultRegistry_getRequiredCollateralForVault",
                 rpc_params![vault_id, head],
             )
@@ -1608,9 +3096,7 @@ impl VaultRegistryPallet for InterBtcParachain {
     async fn get_vault_total_collateral(&self, vault_id: VaultId) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: BalanceWrapper<_> = self
-            .api
-            .rpc()
-            .request("vaultRegistry_getVaultTotalCollateral", rpc_params![vault_id, head])
+            .rpc_request("vaultRegistry_getVaultTotalCollateral", rpc_params![vault_id, head])
             .await?;
 
         Ok(result.amount)
@@ -1619,9 +3105,7 @@ impl VaultRegistryPallet for InterBtcParachain {
     async fn get_collateralization_from_vault(&self, vault_id: VaultId, only_issued: bool) -> Result<u128, Error> {
         let head = self.get_finalized_block_hash().await?;
         let result: UnsignedFixedPoint = self
-            .api
-            .rpc()
-            .request(
+            .rpc_request(
                 "vaultRegistry_getCollateralizationFromVault",
                 rpc_params![vault_id, only_issued, head],
             )
@@ -1630,6 +3114,114 @@ impl VaultRegistryPallet for InterBtcParachain {
         Ok(result.into_inner())
     }
 
+    async fn get_collateralization_ratio(&self, vault_id: &VaultId) -> Result<Option<FixedU128>, Error> {
+        let collateral_currency = vault_id.collateral_currency();
+        let (collateral, vault) = futures::future::try_join(
+            self.get_vault_total_collateral(vault_id.clone()),
+            self.get_vault(vault_id),
+        )
+        .await?;
+        let issued_tokens = vault.issued_tokens.saturating_add(vault.to_be_issued_tokens);
+        let issued_in_collateral = self.wrapped_to_collateral(issued_tokens, collateral_currency).await?;
+
+        Ok(collateralization_ratio(collateral, issued_in_collateral))
+    }
+
+    async fn health_summary(&self) -> Result<HealthSummary, Error> {
+        let (sync_status, vault_ids) = futures::future::try_join(
+            self.get_sync_status(),
+            self.get_vaults_by_account_id(self.get_account_id()),
+        )
+        .await?;
+
+        let vaults = futures::future::try_join_all(vault_ids.iter().map(|vault_id| async move {
+            let (collateralization, thresholds) = futures::future::try_join(
+                self.get_collateralization_ratio(vault_id),
+                self.thresholds(vault_id.collateral_currency()),
+            )
+            .await?;
+            Ok::<_, Error>(VaultHealth {
+                vault_id: vault_id.clone(),
+                collateralization,
+                secure_threshold: thresholds.secure,
+            })
+        }))
+        .await?;
+
+        let mut currencies = vec![self.native_currency_id];
+        for vault_id in vault_ids.iter() {
+            if !currencies.contains(&vault_id.collateral_currency()) {
+                currencies.push(vault_id.collateral_currency());
+            }
+        }
+        let free_balances = futures::future::try_join_all(currencies.into_iter().map(|currency_id| async move {
+            Ok::<_, Error>((currency_id, self.get_free_balance(currency_id).await?))
+        }))
+        .await?;
+
+        Ok(HealthSummary {
+            sync_status,
+            free_balances,
+            native_currency_id: self.native_currency_id,
+            vaults,
+        })
+    }
+
+    async fn liquidation_price(&self, collateral_currency: CurrencyId) -> Result<Option<LiquidationPrice>, Error> {
+        let vault_id = VaultId::new(
+            self.get_account_id().clone(),
+            collateral_currency,
+            self.wrapped_currency_id,
+        );
+        let (vault, collateral, thresholds, current_rate) = futures::try_join!(
+            self.get_vault(&vault_id),
+            self.get_vault_total_collateral(vault_id.clone()),
+            self.thresholds(collateral_currency),
+            self.get_exchange_rate(collateral_currency),
+        )?;
+        let issued_tokens = vault.issued_tokens.saturating_add(vault.to_be_issued_tokens);
+
+        Ok(
+            liquidation_rate(collateral, issued_tokens, thresholds.liquidation)?.map(|liquidation_rate| {
+                LiquidationPrice {
+                    liquidation_rate,
+                    current_rate,
+                }
+            }),
+        )
+    }
+
+    async fn thresholds(&self, collateral_currency: CurrencyId) -> Result<Thresholds, Error> {
+        if self.thresholds.start_watching(collateral_currency) {
+            // subscribe before fetching, so that no change can be missed in between
+            if let Err(err) = self.watch_thresholds(collateral_currency).await {
+                self.thresholds.stop_watching(collateral_currency);
+                return Err(err);
+            }
+        }
+
+        let currency_pair = VaultCurrencyPair {
+            collateral: collateral_currency,
+            wrapped: self.wrapped_currency_id,
+        };
+        let vault_registry = metadata::storage().vault_registry();
+        let fetch = async {
+            let (secure, premium_redeem, liquidation) = futures::try_join!(
+                self.query_finalized_or_error(vault_registry.secure_collateral_threshold(&currency_pair)),
+                self.query_finalized_or_error(vault_registry.premium_redeem_threshold(&currency_pair)),
+                self.query_finalized_or_error(vault_registry.liquidation_collateral_threshold(&currency_pair)),
+            )?;
+            Ok(Thresholds {
+                secure,
+                premium_redeem,
+                liquidation,
+            })
+        };
+        self.thresholds
+            .get_or_fetch(collateral_currency, fetch, |block_hash| self.is_finalized(block_hash))
+            .await
+    }
+
     /// For testing purposes only. Sets the current vault client release.
     ///
     /// # Arguments
@@ -1675,6 +3267,26 @@ pub trait FeePallet {
     async fn get_issue_griefing_collateral(&self) -> Result<FixedU128, Error>;
     async fn get_issue_fee(&self) -> Result<FixedU128, Error>;
     async fn get_replace_griefing_collateral(&self) -> Result<FixedU128, Error>;
+    async fn get_redeem_fee(&self) -> Result<FixedU128, Error>;
+
+    /// Fee that is charged for issuing `amount`, as calculated by the fee pallet
+    async fn estimate_issue_fee(&self, amount: Balance) -> Result<Balance, Error>;
+
+    /// Fee that is charged for redeeming `amount`, as calculated by the fee pallet
+    async fn estimate_redeem_fee(&self, amount: Balance) -> Result<Balance, Error>;
+
+    /// Receive bitcoin fee rate updates, starting with the latest known fee rate
+    fn subscribe_fee_rate_updates(&self) -> FeeRateSubscription;
+}
+
+/// Multiply `amount` by `rate`, rounding to the nearest integer and up on ties, which matches
+/// the rounding the fee pallet uses.
+pub(crate) fn calculate_fee(amount: Balance, rate: FixedU128) -> Result<Balance, Error> {
+    let accuracy = U256::from(FixedU128::accuracy());
+    let product = U256::from(rate.into_inner()) * U256::from(amount);
+    ((product + accuracy / 2) / accuracy)
+        .try_into()
+        .map_err(|_| Error::ArithmeticOverflow)
 }
 
 #[async_trait]
@@ -1693,6 +3305,23 @@ impl FeePallet for InterBtcParachain {
         self.query_finalized_or_error(metadata::storage().fee().replace_griefing_collateral())
             .await
     }
+
+    async fn get_redeem_fee(&self) -> Result<FixedU128, Error> {
+        self.query_finalized_or_error(metadata::storage().fee().redeem_fee())
+            .await
+    }
+
+    async fn estimate_issue_fee(&self, amount: Balance) -> Result<Balance, Error> {
+        calculate_fee(amount, self.get_issue_fee().await?)
+    }
+
+    async fn estimate_redeem_fee(&self, amount: Balance) -> Result<Balance, Error> {
+        calculate_fee(amount, self.get_redeem_fee().await?)
+    }
+
+    fn subscribe_fee_rate_updates(&self) -> FeeRateSubscription {
+        self.fee_rate_updates.subscribe()
+    }
 }
 
 #[async_trait]
@@ -1,5 +1,49 @@
-use std::sync::{Arc, RwLock};
-use tokio::sync::broadcast::error::{RecvError, SendError};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        broadcast::error::{RecvError, SendError},
+        Notify,
+    },
+    time::Instant,
+};
+
+/// Why a shutdown was triggered. Only the reason of the first shutdown signal is kept, since later
+/// ones are usually a consequence of it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ShutdownReason {
+    #[default]
+    Unspecified,
+    /// A submitted transaction was not included in time
+    TransactionTimeout,
+    /// The connection to the parachain was lost
+    Disconnected,
+    /// The parachain runtime was upgraded, so the compiled metadata may be stale
+    RuntimeUpgraded,
+    Custom(String),
+}
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownReason::Unspecified => write!(f, "unspecified"),
+            ShutdownReason::TransactionTimeout => write!(f, "transaction timed out"),
+            ShutdownReason::Disconnected => write!(f, "disconnected from parachain"),
+            ShutdownReason::RuntimeUpgraded => write!(f, "parachain runtime upgraded"),
+            ShutdownReason::Custom(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Receivers that have neither acknowledged the shutdown nor been dropped, by correlation id.
+type PendingReceivers = Arc<Mutex<HashMap<u64, String>>>;
 
 /// A wrapper arround a tokio broadcast channel that makes sure that
 /// listeners created after a shutdown signal has already been sent
@@ -8,6 +52,10 @@ use tokio::sync::broadcast::error::{RecvError, SendError};
 pub struct ShutdownSender {
     pub sent_shutdown: Arc<RwLock<bool>>,
     pub channel: tokio::sync::broadcast::Sender<()>,
+    reason: Arc<RwLock<Option<ShutdownReason>>>,
+    next_id: Arc<AtomicU64>,
+    pending: PendingReceivers,
+    acked: Arc<Notify>,
 }
 
 impl ShutdownSender {
@@ -16,10 +64,15 @@ impl ShutdownSender {
         Self {
             sent_shutdown: Arc::new(RwLock::new(false)),
             channel: shutdown_tx,
+            reason: Default::default(),
+            next_id: Default::default(),
+            pending: Default::default(),
+            acked: Default::default(),
         }
     }
 
     pub fn send(&self, value: ()) -> Result<usize, SendError<()>> {
+        self.reason.write().unwrap().get_or_insert(ShutdownReason::Unspecified);
         // Record that we sent the signal for listeners created in the future.
         // Note that unwrap is suitable here since the read only fails if a thread
         // holding the lock has panicked, in which case propagating the panic is
@@ -29,8 +82,65 @@ impl ShutdownSender {
         self.channel.send(value)
     }
 
+    /// Send the shutdown signal, recording `reason` for the receivers unless an earlier signal
+    /// already gave one.
+    pub fn shutdown(&self, reason: ShutdownReason) -> Result<usize, SendError<()>> {
+        self.reason.write().unwrap().get_or_insert(reason);
+        self.send(())
+    }
+
+    /// The reason of the first shutdown signal, if one was sent.
+    pub fn reason(&self) -> Option<ShutdownReason> {
+        self.reason.read().unwrap().clone()
+    }
+
+    /// Send the shutdown signal and wait until every receiver has either been dropped or has
+    /// called `ShutdownReceiver::ack`. Returns false if some receivers were still running when
+    /// `timeout` expired, in which case their correlation ids are logged.
+    pub async fn shutdown_with_timeout(&self, timeout: Duration) -> bool {
+        // an error only means there are no receivers left to wait for
+        let _ = self.send(());
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            // register interest before checking, so that an ack in between is not missed
+            let acked = self.acked.notified();
+            if self.pending.lock().unwrap().is_empty() {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, acked).await.is_err() {
+                break;
+            }
+        }
+
+        let mut laggards: Vec<_> = self.pending.lock().unwrap().values().cloned().collect();
+        if laggards.is_empty() {
+            return true;
+        }
+        laggards.sort();
+        log::warn!(
+            "Shutdown timed out after {:.3} s, still waiting for: {}",
+            timeout.as_secs_f64(),
+            laggards.join(", ")
+        );
+        false
+    }
+
     pub fn subscribe(&self) -> ShutdownReceiver {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribe_inner(id, format!("receiver-{}", id))
+    }
+
+    /// Like `subscribe`, but the receiver is reported as `correlation_id` if it does not finish
+    /// in time during `shutdown_with_timeout`.
+    pub fn subscribe_with_id(&self, correlation_id: impl Into<String>) -> ShutdownReceiver {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribe_inner(id, correlation_id.into())
+    }
+
+    fn subscribe_inner(&self, id: u64, correlation_id: String) -> ShutdownReceiver {
         let subscription = self.channel.subscribe();
+        self.pending.lock().unwrap().insert(id, correlation_id);
         // Check if signal was already sent before we started listening.
         // Note that unwrap is suitable here since the read only fails if a thread
         // holding the lock has panicked, in which case propagating the panic is
@@ -39,6 +149,10 @@ impl ShutdownSender {
         ShutdownReceiver {
             received_shutdown: sent,
             inner: subscription,
+            reason: self.reason.clone(),
+            id,
+            pending: self.pending.clone(),
+            acked: self.acked.clone(),
         }
     }
 
@@ -56,6 +170,10 @@ impl Default for ShutdownSender {
 pub struct ShutdownReceiver {
     received_shutdown: bool,
     inner: tokio::sync::broadcast::Receiver<()>,
+    reason: Arc<RwLock<Option<ShutdownReason>>>,
+    id: u64,
+    pending: PendingReceivers,
+    acked: Arc<Notify>,
 }
 
 impl ShutdownReceiver {
@@ -66,4 +184,87 @@ impl ShutdownReceiver {
             self.inner.recv().await
         }
     }
+
+    /// Why the shutdown was triggered, once `recv` has returned.
+    pub fn reason(&self) -> Option<ShutdownReason> {
+        self.reason.read().unwrap().clone()
+    }
+
+    /// Signal that this task has finished shutting down. Dropping the receiver has the same effect.
+    pub fn ack(&self) {
+        if self.pending.lock().unwrap().remove(&self.id).is_some() {
+            self.acked.notify_waiters();
+        }
+    }
+}
+
+impl Drop for ShutdownReceiver {
+    fn drop(&mut self) {
+        self.ack();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_reason_is_propagated() {
+        let shutdown_tx = ShutdownSender::new();
+        let mut receivers = vec![shutdown_tx.subscribe(), shutdown_tx.subscribe()];
+        assert_eq!(receivers[0].reason(), None);
+
+        shutdown_tx
+            .shutdown(ShutdownReason::Custom("bitcoin relay failed".to_string()))
+            .unwrap();
+        // later signals don't overwrite the original reason
+        shutdown_tx.shutdown(ShutdownReason::Disconnected).unwrap();
+        // receivers created after the shutdown see the reason as well
+        receivers.push(shutdown_tx.subscribe());
+
+        for receiver in receivers.iter_mut() {
+            receiver.recv().await.unwrap();
+            assert_eq!(
+                receiver.reason(),
+                Some(ShutdownReason::Custom("bitcoin relay failed".to_string()))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_receivers() {
+        let shutdown_tx = ShutdownSender::new();
+        let mut dropping = shutdown_tx.subscribe();
+        let mut acking = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            dropping.recv().await.unwrap();
+        });
+        tokio::spawn(async move {
+            acking.recv().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            acking.ack();
+            // keep the receiver alive to check that the ack alone is enough
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        assert!(shutdown_tx.shutdown_with_timeout(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_times_out_on_slow_receiver() {
+        let shutdown_tx = ShutdownSender::new();
+        let mut fast = shutdown_tx.subscribe_with_id("fast");
+        let mut slow = shutdown_tx.subscribe_with_id("slow");
+        tokio::spawn(async move {
+            fast.recv().await.unwrap();
+        });
+        tokio::spawn(async move {
+            slow.recv().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        assert!(!shutdown_tx.shutdown_with_timeout(Duration::from_millis(50)).await);
+        let pending: Vec<_> = shutdown_tx.pending.lock().unwrap().values().cloned().collect();
+        assert_eq!(pending, vec!["slow".to_string()]);
+    }
 }
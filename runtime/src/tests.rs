@@ -3,15 +3,23 @@
 const DEFAULT_TESTING_CURRENCY: CurrencyId = Token(KSM);
 
 use super::{
-    BtcAddress, BtcPublicKey, BtcRelayPallet, CollateralBalancesPallet, CurrencyId, FixedPointNumber, FixedU128,
-    OraclePallet, RawBlockHeader, ReplacePallet, SecurityPallet, StatusCode, Token, TryFromSymbol, VaultRegistryPallet,
-    KBTC, KINT, KSM,
+    BtcAddress, BtcPublicKey, BtcRelayPallet, CollateralBalancesPallet, CurrencyId, FeePallet, FixedPointNumber,
+    FixedU128, OraclePallet, RawBlockHeader, ReplacePallet, SecurityPallet, StatusCode, SudoPallet, Token,
+    TryFromSymbol, VaultRegistryPallet, KBTC, KINT, KSM,
 };
-use crate::{integration::*, FeedValuesEvent, OracleKey, RuntimeCurrencyInfo, VaultId, H160, U256};
+use crate::{
+    integration::*, AccountId, ClockDrift, FeedValuesEvent, HealthStatus, HealthSummary, InterBtcRuntime, OracleKey,
+    RuntimeCurrencyInfo, RuntimeVersion, SyncStatus, UtilFuncs, VaultHealth, VaultId, H160, H256, U256,
+};
+use codec::{Compact, Encode};
 use module_bitcoin::{formatter::TryFormattable, types::BlockBuilder};
 pub use primitives::CurrencyId::ForeignAsset;
 use sp_keyring::AccountKeyring;
-use std::{convert::TryInto, time::Duration};
+use std::{
+    convert::TryInto,
+    time::{Duration, UNIX_EPOCH},
+};
+use subxt::tx::{Era, ExtrinsicParams, PolkadotExtrinsicParams};
 
 fn dummy_public_key() -> BtcPublicKey {
     BtcPublicKey {
@@ -59,6 +67,123 @@ async fn test_getters() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_balances() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let account_id = parachain_rpc.get_account_id().clone();
+
+    let currency_ids = [Token(KSM), Token(KINT), ForeignAsset(404)];
+    let balances = parachain_rpc
+        .get_balances(account_id.clone(), &currency_ids)
+        .await
+        .unwrap();
+
+    let mut expected = Vec::new();
+    for currency_id in currency_ids {
+        let balance = parachain_rpc
+            .get_free_balance_for_id(account_id.clone(), currency_id)
+            .await
+            .unwrap();
+        expected.push((currency_id, balance));
+    }
+    assert_eq!(balances, expected);
+    assert_eq!(balances[0].1, 1 << 60);
+    assert_eq!(balances[2].1, 0);
+}
+
+#[test]
+fn test_fee_calculation() {
+    let fee = |amount, rate| crate::rpc::calculate_fee(amount, rate).unwrap();
+    // 0.5% of 1 BTC
+    assert_eq!(
+        fee(100_000_000, FixedU128::saturating_from_rational(5u128, 1000u128)),
+        500_000
+    );
+    // rounds to the nearest integer
+    assert_eq!(fee(333, FixedU128::saturating_from_rational(1u128, 100u128)), 3);
+    assert_eq!(fee(366, FixedU128::saturating_from_rational(1u128, 100u128)), 4);
+    // and up on ties
+    assert_eq!(fee(250, FixedU128::saturating_from_rational(1u128, 100u128)), 3);
+    assert_eq!(fee(u128::MAX, FixedU128::from_inner(0)), 0);
+    assert!(matches!(
+        crate::rpc::calculate_fee(u128::MAX, FixedU128::saturating_from_integer(2u128)),
+        Err(crate::Error::ArithmeticOverflow)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_estimate_fees() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+
+    let amount = 100_000_000;
+    let issue_fee = parachain_rpc.get_issue_fee().await.unwrap();
+    assert_eq!(
+        parachain_rpc.estimate_issue_fee(amount).await.unwrap(),
+        issue_fee.saturating_mul_int(amount)
+    );
+    let redeem_fee = parachain_rpc.get_redeem_fee().await.unwrap();
+    assert_eq!(
+        parachain_rpc.estimate_redeem_fee(amount).await.unwrap(),
+        redeem_fee.saturating_mul_int(amount)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_exchange_rate_at() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let oracle_provider = setup_provider(client, AccountKeyring::Bob).await;
+
+    let key = OracleKey::ExchangeRate(DEFAULT_TESTING_CURRENCY);
+    let first_rate = FixedU128::saturating_from_rational(1u128, 100u128);
+    let second_rate = FixedU128::saturating_from_rational(1u128, 50u128);
+
+    // values are aggregated at the start of the next block
+    oracle_provider
+        .feed_values(vec![(key.clone(), first_rate)])
+        .await
+        .unwrap();
+    oracle_provider
+        .feed_values(vec![(key.clone(), second_rate)])
+        .await
+        .unwrap();
+    let historical_height = parachain_rpc.get_current_chain_height().await.unwrap();
+    oracle_provider.feed_values(vec![(key, second_rate)]).await.unwrap();
+
+    assert_eq!(
+        parachain_rpc.get_exchange_rate(DEFAULT_TESTING_CURRENCY).await.unwrap(),
+        second_rate
+    );
+    assert_eq!(
+        parachain_rpc
+            .get_exchange_rate_at(DEFAULT_TESTING_CURRENCY, historical_height.into())
+            .await
+            .unwrap(),
+        Some(first_rate)
+    );
+
+    let historical_hash = parachain_rpc
+        .get_parachain_block_hash(historical_height.into())
+        .await
+        .unwrap();
+    assert_eq!(
+        parachain_rpc
+            .get_exchange_rate_at(DEFAULT_TESTING_CURRENCY, historical_hash.into())
+            .await
+            .unwrap(),
+        Some(first_rate)
+    );
+    assert_eq!(
+        parachain_rpc
+            .get_exchange_rate_at(ForeignAsset(404), historical_height.into())
+            .await
+            .unwrap(),
+        None
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_invalid_tx_matching() {
     let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
@@ -77,6 +202,25 @@ async fn test_too_low_priority_matching() {
     assert!(err.is_pool_too_low_priority().is_some())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_submissions_use_sequential_nonces() {
+    const NUM_TXS: u32 = 10;
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let recipient: AccountId = AccountKeyring::Bob.into();
+
+    let initial_nonce = parachain_rpc.get_on_chain_nonce().await;
+    let results = futures::future::join_all(
+        (0..NUM_TXS).map(|_| parachain_rpc.transfer_to(&recipient, 100, DEFAULT_TESTING_CURRENCY)),
+    )
+    .await;
+
+    for result in results {
+        result.unwrap();
+    }
+    assert_eq!(parachain_rpc.get_on_chain_nonce().await, initial_nonce + NUM_TXS);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_subxt_processing_events_after_dispatch_error() {
     let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
@@ -104,6 +248,265 @@ async fn test_subxt_processing_events_after_dispatch_error() {
     result.2.unwrap();
 }
 
+#[test]
+fn test_oracle_feed_values_batch() {
+    let values = vec![
+        (OracleKey::ExchangeRate(Token(KSM)), FixedU128::from(1)),
+        (OracleKey::ExchangeRate(Token(KINT)), FixedU128::from(2)),
+        (OracleKey::FeeEstimation, FixedU128::from(3)),
+    ];
+
+    let calls = crate::rpc::oracle_feed_values_calls(values.clone());
+    assert_eq!(calls.len(), values.len());
+    for (call, value) in calls.into_iter().zip(values) {
+        assert_eq!(
+            call,
+            crate::EncodedCall::Oracle(crate::metadata::runtime_types::oracle::pallet::Call::feed_values {
+                values: vec![value]
+            })
+        );
+    }
+}
+
+#[test]
+fn test_required_replace_collateral() {
+    let required_collateral = crate::rpc::required_replace_collateral;
+    // 150% secure threshold
+    let threshold = FixedU128::saturating_from_rational(3u128, 2u128);
+    assert_eq!(required_collateral(1000, threshold, 0).unwrap(), 1500);
+    assert_eq!(required_collateral(1000, threshold, 10).unwrap(), 1650);
+    // 200% secure threshold
+    let threshold = FixedU128::from(2);
+    assert_eq!(required_collateral(1000, threshold, 0).unwrap(), 2000);
+    assert_eq!(required_collateral(1000, threshold, 5).unwrap(), 2100);
+    assert_eq!(required_collateral(0, threshold, 5).unwrap(), 0);
+    assert!(matches!(
+        required_collateral(u128::MAX, threshold, 0),
+        Err(crate::Error::ArithmeticOverflow)
+    ));
+}
+
+#[test]
+fn test_issue_requests_page() {
+    use crate::{InterBtcIssueRequest, IssueRequestStatus};
+    use std::{collections::BTreeMap, ops::Bound};
+
+    let alice = VaultId::new(AccountKeyring::Alice.into(), Token(KSM), Token(KBTC));
+    let bob = VaultId::new(AccountKeyring::Bob.into(), Token(KSM), Token(KBTC));
+    let issue_request = |vault: &VaultId| InterBtcIssueRequest {
+        vault: vault.clone(),
+        opentime: 0,
+        period: 0,
+        griefing_collateral: 0,
+        amount: 0,
+        fee: 0,
+        requester: AccountKeyring::Charlie.into(),
+        btc_address: Default::default(),
+        btc_public_key: dummy_public_key(),
+        btc_height: 0,
+        status: IssueRequestStatus::Pending,
+    };
+    // storage keys are the prefix of the map followed by the raw issue id
+    let storage_key = |id: u8| [vec![0xff; 16], vec![id; 32]].concat();
+    let storage: BTreeMap<_, _> = [(1, &alice), (2, &bob), (3, &alice), (4, &alice), (5, &bob)]
+        .into_iter()
+        .map(|(id, vault)| (storage_key(id), issue_request(vault)))
+        .collect();
+
+    let mut cursor = None;
+    let mut pages = Vec::new();
+    loop {
+        // the keys after the cursor, as `state_getKeysPaged` returns them
+        let start = cursor.clone().map_or(Bound::Unbounded, Bound::Excluded);
+        let entries = storage
+            .range((start, Bound::Unbounded))
+            .take(2)
+            .map(|(key, request)| (key.clone(), request.clone()))
+            .collect();
+        let page = crate::rpc::issue_requests_page(entries, &alice, 2);
+        pages.push(page.requests.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        assert!(page.requests.iter().all(|(_, request)| request.vault == alice));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    let id = |id: u8| H256::from_slice(&[id; 32]);
+    assert_eq!(pages, vec![vec![id(1)], vec![id(3), id(4)], vec![]]);
+}
+
+#[test]
+fn test_redeems_nearing_deadline() {
+    use crate::{InterBtcRedeemRequest, RedeemRequestStatus};
+
+    let vault_id = VaultId::new(AccountKeyring::Alice.into(), Token(KSM), Token(KBTC));
+    let other_vault_id = VaultId::new(AccountKeyring::Bob.into(), Token(KSM), Token(KBTC));
+    let redeem_request = |opentime, period, status, vault: &VaultId| InterBtcRedeemRequest {
+        vault: vault.clone(),
+        opentime,
+        period,
+        amount_btc: 0,
+        transfer_fee_btc: 0,
+        fee: 0,
+        premium: 0,
+        redeemer: AccountKeyring::Charlie.into(),
+        btc_address: Default::default(),
+        btc_height: 0,
+        status,
+    };
+    let id = |id: u8| H256::from_slice(&[id; 32]);
+    let requests = vec![
+        // deadline at 1100, 100 blocks away
+        (
+            id(1),
+            redeem_request(100, 1000, RedeemRequestStatus::Pending, &vault_id),
+        ),
+        // deadline at 1010, using the longer global period
+        (id(2), redeem_request(10, 500, RedeemRequestStatus::Pending, &vault_id)),
+        // deadline at 2000, outside the window
+        (
+            id(3),
+            redeem_request(1000, 1000, RedeemRequestStatus::Pending, &vault_id),
+        ),
+        // already expired
+        (id(4), redeem_request(0, 900, RedeemRequestStatus::Pending, &vault_id)),
+        (
+            id(5),
+            redeem_request(10, 1000, RedeemRequestStatus::Completed, &vault_id),
+        ),
+        (
+            id(6),
+            redeem_request(10, 1000, RedeemRequestStatus::Pending, &other_vault_id),
+        ),
+        // expired on the parachain, but the 20 bitcoin blocks of the period end at bitcoin height 31
+        (
+            id(7),
+            InterBtcRedeemRequest {
+                btc_height: 11,
+                ..redeem_request(0, 900, RedeemRequestStatus::Pending, &vault_id)
+            },
+        ),
+        // expired on both chains
+        (
+            id(8),
+            InterBtcRedeemRequest {
+                btc_height: 10,
+                ..redeem_request(0, 900, RedeemRequestStatus::Pending, &vault_id)
+            },
+        ),
+    ];
+
+    let nearing_deadline =
+        crate::rpc::redeems_nearing_deadline(requests, &vault_id, 1000, 1000, 30, Duration::from_secs(1200));
+    let nearing_deadline: Vec<_> = nearing_deadline
+        .into_iter()
+        .map(|(redeem_id, _, time_remaining)| (redeem_id, time_remaining))
+        .collect();
+    assert_eq!(
+        nearing_deadline,
+        vec![
+            (id(4), Duration::ZERO),
+            (id(8), Duration::ZERO),
+            (id(2), Duration::from_secs(120)),
+            (id(7), Duration::from_secs(600)),
+            (id(1), Duration::from_secs(1200)),
+        ]
+    );
+}
+
+#[test]
+fn test_replace_amount_to_threshold() {
+    use crate::{InterBtcVault, VaultStatus};
+
+    let replace_amount = crate::rpc::replace_amount_to_threshold;
+    let vault = |issued_tokens, to_be_redeemed_tokens, to_be_replaced_tokens| InterBtcVault {
+        id: VaultId::new(AccountKeyring::Alice.into(), Token(KSM), Token(KBTC)),
+        status: VaultStatus::Active(true),
+        banned_until: None,
+        secure_collateral_threshold: None,
+        to_be_issued_tokens: 0,
+        issued_tokens,
+        to_be_redeemed_tokens,
+        to_be_replaced_tokens,
+        replace_collateral: 0,
+        liquidated_collateral: 0,
+        active_replace_collateral: 0,
+    };
+    // 150%, so collateral worth 1500 backs 1000 tokens
+    let threshold = FixedU128::saturating_from_rational(3u128, 2u128);
+
+    assert_eq!(replace_amount(&vault(900, 0, 0), 1500, threshold, 10).unwrap(), None);
+    assert_eq!(replace_amount(&vault(1000, 0, 0), 1500, threshold, 10).unwrap(), None);
+    assert_eq!(
+        replace_amount(&vault(1200, 0, 0), 1500, threshold, 10).unwrap(),
+        Some(200)
+    );
+    // 200%
+    let threshold = FixedU128::from(2);
+    assert_eq!(
+        replace_amount(&vault(1200, 0, 0), 1500, threshold, 10).unwrap(),
+        Some(450)
+    );
+    // never less than the dust amount
+    assert_eq!(
+        replace_amount(&vault(760, 0, 0), 1500, threshold, 100).unwrap(),
+        Some(100)
+    );
+    // tokens being replaced already count as replaced
+    assert_eq!(
+        replace_amount(&vault(1200, 0, 400), 1500, threshold, 10).unwrap(),
+        Some(50)
+    );
+    assert_eq!(replace_amount(&vault(1200, 0, 450), 1500, threshold, 10).unwrap(), None);
+    // tokens being redeemed cannot be replaced
+    assert_eq!(
+        replace_amount(&vault(1200, 1000, 0), 1500, threshold, 10).unwrap(),
+        Some(200)
+    );
+    assert!(matches!(
+        replace_amount(&vault(1200, 1150, 0), 1500, threshold, 100),
+        Err(crate::Error::ReplaceAmountTooLow(50, 100))
+    ));
+}
+
+#[test]
+fn test_liquidation_price() {
+    let liquidation_rate = crate::rpc::liquidation_rate;
+    // 110% liquidation threshold
+    let threshold = FixedU128::saturating_from_rational(11u128, 10u128);
+
+    // 2200 collateral for 100 tokens is 110% at a rate of 20
+    assert_eq!(
+        liquidation_rate(2200, 100, threshold).unwrap(),
+        Some(FixedU128::from(20))
+    );
+    // 150% liquidation threshold
+    let threshold = FixedU128::saturating_from_rational(3u128, 2u128);
+    assert_eq!(
+        liquidation_rate(3000, 200, threshold).unwrap(),
+        Some(FixedU128::from(10))
+    );
+    assert_eq!(liquidation_rate(3000, 0, threshold).unwrap(), None);
+    assert_eq!(liquidation_rate(3000, 200, FixedU128::from(0)).unwrap(), None);
+
+    let price = crate::LiquidationPrice {
+        liquidation_rate: FixedU128::from(10),
+        current_rate: FixedU128::from(8),
+    };
+    assert_eq!(price.margin_of_safety_percent(), Some(25.0));
+    let price = crate::LiquidationPrice {
+        liquidation_rate: FixedU128::from(10),
+        current_rate: FixedU128::from(20),
+    };
+    assert_eq!(price.margin_of_safety_percent(), Some(-50.0));
+    let price = crate::LiquidationPrice {
+        liquidation_rate: FixedU128::from(10),
+        current_rate: FixedU128::from(0),
+    };
+    assert_eq!(price.margin_of_safety_percent(), None);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_register_vault() {
     let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
@@ -164,6 +567,572 @@ async fn test_btc_relay() {
     }
 }
 
+#[test]
+fn test_relay_confirmations() {
+    let relay_confirmations = crate::rpc::relay_confirmations;
+    assert_eq!(relay_confirmations(5, 4), 0);
+    assert_eq!(relay_confirmations(5, 5), 1);
+    assert_eq!(relay_confirmations(5, 10), 6);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shutdown_on_runtime_upgrade() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let shutdown_tx = crate::ShutdownSender::new();
+    let mut shutdown_rx = shutdown_tx.subscribe();
+    let json_client: subxt_client::JsonRpcClient = client.into();
+    let parachain_rpc = crate::InterBtcParachain::new(
+        json_client,
+        crate::InterBtcSigner::new(AccountKeyring::Alice.pair()),
+        false,
+        shutdown_tx,
+    )
+    .await
+    .unwrap();
+
+    let upgrade = async {
+        // give the listener time to subscribe to the events
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        parachain_rpc
+            .sudo(crate::EncodedCall::System(
+                crate::metadata::runtime_types::frame_system::pallet::Call::set_code_without_checks {
+                    code: interbtc_runtime::WASM_BINARY.unwrap().to_vec(),
+                },
+            ))
+            .await
+            .unwrap();
+        shutdown_rx.recv().await.unwrap();
+    };
+    tokio::select! {
+        _ = upgrade => {}
+        result = parachain_rpc.shutdown_on_runtime_upgrade() => panic!("listener stopped: {:?}", result),
+    }
+    assert_eq!(shutdown_rx.reason(), Some(crate::ShutdownReason::RuntimeUpgraded));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wait_for_confirmations() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    set_exchange_rate(client.clone()).await;
+
+    let address = BtcAddress::P2PKH(H160::zero());
+    let block = BlockBuilder::new()
+        .with_version(4)
+        .with_coinbase(&address, 50, 3)
+        .with_timestamp(1588813835)
+        .mine(U256::from(2).pow(254.into()))
+        .unwrap();
+    let genesis_hash = block.header.hash;
+    parachain_rpc
+        .initialize_btc_relay(to_block_header(block.header.try_format().unwrap()), 0)
+        .await
+        .unwrap();
+
+    // not enough blocks are relayed in time
+    assert!(matches!(
+        parachain_rpc
+            .wait_for_confirmations(genesis_hash.into(), Some(3), Duration::from_secs(1))
+            .await,
+        Err(crate::Error::ConfirmationTimeout)
+    ));
+
+    let advance_relay = async {
+        let mut block_hash = genesis_hash;
+        for height in 1..=2 {
+            let block = BlockBuilder::new()
+                .with_previous_hash(block_hash)
+                .with_version(4)
+                .with_coinbase(&address, 50, height - 1)
+                .with_timestamp(1588813835)
+                .mine(U256::from(2).pow(254.into()))
+                .unwrap();
+            block_hash = block.header.hash;
+            parachain_rpc
+                .store_block_header(to_block_header(block.header.try_format().unwrap()))
+                .await
+                .unwrap();
+        }
+    };
+    let (result, _) = tokio::join!(
+        parachain_rpc.wait_for_confirmations(genesis_hash.into(), Some(3), Duration::from_secs(60)),
+        advance_relay
+    );
+    result.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dry_run_reports_decoded_error() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+
+    let outcome = parachain_rpc
+        .dry_run(crate::metadata::tx().issue().cancel_issue(crate::H256::zero()))
+        .await
+        .unwrap();
+
+    assert!(!outcome.would_succeed);
+    assert!(outcome.estimated_fee > 0);
+    assert!(outcome.error.unwrap().starts_with("Issue::IssueIdNotFound"));
+}
+
+#[test]
+fn test_collateralization_ratio() {
+    let collateralization_ratio = crate::rpc::collateralization_ratio;
+    assert_eq!(
+        collateralization_ratio(300, 200),
+        Some(FixedU128::saturating_from_rational(3u128, 2u128))
+    );
+    // nothing issued
+    assert_eq!(collateralization_ratio(300, 0), None);
+}
+
+#[test]
+fn test_check_withdrawal() {
+    let check_withdrawal = crate::rpc::check_withdrawal;
+    let min_ratio = FixedU128::saturating_from_rational(3u128, 2u128);
+    // 100 tokens at a rate of 10 are worth 1000 collateral
+    let rate = FixedU128::from(10);
+
+    // 2000 - 400 leaves 160%
+    assert!(check_withdrawal(2000, 400, 100, rate, min_ratio).is_ok());
+    // exactly at the minimum is fine
+    assert!(check_withdrawal(2000, 500, 100, rate, min_ratio).is_ok());
+    // 2000 - 600 leaves 140%
+    assert!(matches!(
+        check_withdrawal(2000, 600, 100, rate, min_ratio),
+        Err(crate::Error::WouldBreachThreshold(ratio, min)) if ratio == FixedU128::saturating_from_rational(7u128, 5u128) && min == min_ratio
+    ));
+    // the threshold depends on the rate
+    assert!(check_withdrawal(2000, 400, 100, FixedU128::from(11), min_ratio).is_err());
+    // without issued tokens, all of the collateral can be withdrawn
+    assert!(check_withdrawal(2000, 2000, 0, rate, min_ratio).is_ok());
+    assert!(matches!(
+        check_withdrawal(2000, 2001, 0, rate, min_ratio),
+        Err(crate::Error::InsufficientFunds)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submit_waits_for_finalization() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let recipient: AccountId = AccountKeyring::Bob.into();
+
+    let events = parachain_rpc
+        .submit(
+            crate::metadata::tx()
+                .tokens()
+                .transfer(recipient, DEFAULT_TESTING_CURRENCY, 100),
+            crate::Finality::Finalized,
+        )
+        .await
+        .unwrap();
+
+    assert!(parachain_rpc.is_finalized(events.block_hash()).await.unwrap());
+    assert!(events.has::<crate::metadata::tokens::events::Transfer>().unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submission_is_pending_until_complete() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let recipient: AccountId = AccountKeyring::Bob.into();
+    let nonce = parachain_rpc.get_on_chain_nonce().await;
+
+    let (result, pending) = tokio::join!(
+        parachain_rpc.transfer_to(&recipient, 100, DEFAULT_TESTING_CURRENCY),
+        async {
+            loop {
+                match parachain_rpc.pending_transactions().pop() {
+                    Some(pending) => break pending,
+                    None => tokio::task::yield_now().await,
+                }
+            }
+        }
+    );
+    result.unwrap();
+    let (pending_nonce, pending_tx) = pending;
+    assert_eq!(pending_nonce, nonce);
+    assert_eq!((pending_tx.method.as_str(), pending_tx.tip), ("Tokens.transfer", 0));
+
+    // completed submissions can no longer be replaced
+    assert!(parachain_rpc.pending_transactions().is_empty());
+    assert!(matches!(
+        parachain_rpc.replace_transaction(nonce, 1).await,
+        Err(crate::Error::TransactionNotPending(n)) if n == nonce
+    ));
+    assert!(matches!(
+        parachain_rpc.cancel_transaction(nonce).await,
+        Err(crate::Error::TransactionNotPending(n)) if n == nonce
+    ));
+    assert_eq!(parachain_rpc.get_on_chain_nonce().await, nonce + 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submit_raw_matches_typed_call() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let remark = b"dynamic".to_vec();
+    let remarked = |events: subxt::blocks::ExtrinsicEvents<InterBtcRuntime>| {
+        events
+            .find_first::<crate::metadata::system::events::Remarked>()
+            .unwrap()
+            .unwrap()
+    };
+
+    let typed = parachain_rpc
+        .submit(
+            crate::metadata::tx().system().remark_with_event(remark.clone()),
+            crate::Finality::default(),
+        )
+        .await
+        .unwrap();
+    let dynamic = parachain_rpc
+        .submit_raw(
+            "System",
+            "remark_with_event",
+            vec![subxt::dynamic::Value::from_bytes(&remark)],
+        )
+        .await
+        .unwrap();
+    assert_eq!(remarked(dynamic), remarked(typed));
+
+    assert!(parachain_rpc
+        .submit_raw(
+            "System",
+            "no_such_call",
+            vec![subxt::dynamic::Value::from_bytes(&remark)]
+        )
+        .await
+        .is_err());
+    assert!(parachain_rpc
+        .submit_raw(
+            "NoSuchPallet",
+            "remark",
+            vec![subxt::dynamic::Value::from_bytes(&remark)]
+        )
+        .await
+        .is_err());
+    // fields that don't match the call's are rejected
+    assert!(parachain_rpc
+        .submit_raw("System", "remark_with_event", vec![subxt::dynamic::Value::bool(true)])
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_observer_mode_intercepts_submissions() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client.clone(), AccountKeyring::Alice).await;
+    let observer = parachain_rpc.clone().with_observer_mode();
+    let remark = crate::metadata::tx().system().remark_with_event(b"observed".to_vec());
+
+    assert!(matches!(
+        observer.submit(remark.clone(), crate::Finality::default()).await,
+        Err(crate::Error::ObserverMode(method)) if method == "System.remark_with_event"
+    ));
+    assert!(matches!(
+        observer
+            .submit_raw(
+                "System",
+                "remark_with_event",
+                vec![subxt::dynamic::Value::from_bytes(b"observed")]
+            )
+            .await,
+        Err(crate::Error::ObserverMode(method)) if method == "System.remark_with_event"
+    ));
+    assert!(matches!(
+        observer
+            .feed_values(vec![(OracleKey::ExchangeRate(Token(KSM)), FixedU128::from(1))])
+            .await,
+        Err(crate::Error::ObserverMode(_))
+    ));
+    // queries are not affected
+    assert_eq!(observer.get_free_balance(Token(KSM)).await.unwrap(), 1 << 60);
+
+    // nothing was submitted, so the nonce of the account is still in sync
+    assert!(parachain_rpc.submit(remark, crate::Finality::default()).await.is_ok());
+}
+
+#[test]
+fn test_parse_partial_fee() {
+    let parse_partial_fee = crate::rpc::parse_partial_fee;
+    assert_eq!(
+        parse_partial_fee(&serde_json::json!({ "partialFee": "340282366920938463463374607431768211455" })).unwrap(),
+        u128::MAX
+    );
+    assert_eq!(
+        parse_partial_fee(&serde_json::json!({ "partialFee": 1000 })).unwrap(),
+        1000
+    );
+    assert!(parse_partial_fee(&serde_json::json!({ "weight": 1000 })).is_err());
+}
+
+#[test]
+fn test_parse_weight() {
+    let parse_weight = crate::rpc::parse_weight;
+    assert_eq!(parse_weight(&serde_json::json!({ "weight": 1000 })).unwrap(), 1000);
+    assert_eq!(
+        parse_weight(&serde_json::json!({ "weight": { "refTime": 2000, "proofSize": 0 } })).unwrap(),
+        2000
+    );
+    assert!(parse_weight(&serde_json::json!({ "partialFee": "1000" })).is_err());
+}
+
+#[test]
+fn test_parse_max_batch_weight() {
+    let parse_max_batch_weight = crate::rpc::parse_max_batch_weight;
+    assert_eq!(
+        parse_max_batch_weight(&serde_json::json!({ "base_block": 1, "max_block": 500_000_000_000u64 })).unwrap(),
+        100_000_000_000
+    );
+    assert_eq!(
+        parse_max_batch_weight(&serde_json::json!({
+            "base_block": { "ref_time": 1, "proof_size": 0 },
+            "max_block": { "ref_time": 500_000_000_000u64, "proof_size": 5_242_880 },
+        }))
+        .unwrap(),
+        100_000_000_000
+    );
+    assert!(parse_max_batch_weight(&serde_json::json!({ "base_block": 1 })).is_err());
+}
+
+#[test]
+fn test_chunk_by_weight() {
+    let chunk_by_weight = crate::rpc::chunk_by_weight;
+
+    // 300 oracle values of equal weight, 64 of which fit in a batch
+    let chunks = chunk_by_weight(&[1_000; 300], 64_000).unwrap();
+    assert_eq!(chunks.len(), 5);
+    assert_eq!(chunks[0], 0..64);
+    assert_eq!(chunks[4], 256..300);
+    assert!(chunks.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+    assert_eq!(chunk_by_weight(&[3, 4, 3, 10, 1], 10).unwrap(), vec![0..3, 3..4, 4..5]);
+    assert!(chunk_by_weight(&[], 10).unwrap().is_empty());
+    assert!(matches!(
+        chunk_by_weight(&[3, 11, 3], 10),
+        Err(crate::Error::CallTooHeavy(1, 11, 10))
+    ));
+}
+
+#[test]
+fn test_check_runtime_version() {
+    let check_runtime_version = crate::rpc::check_runtime_version;
+    let compiled_version = RuntimeVersion {
+        spec_name: crate::DEFAULT_SPEC_NAME.to_string(),
+        spec_version: 1021000,
+        transaction_version: 2,
+    };
+    assert!(check_runtime_version(&compiled_version).is_ok());
+
+    assert!(matches!(
+        check_runtime_version(&RuntimeVersion {
+            spec_name: "kintsugi-parachain".to_string(),
+            ..compiled_version.clone()
+        }),
+        Err(crate::Error::ParachainMetadataMismatch(expected, actual))
+            if expected == crate::DEFAULT_SPEC_NAME && actual == "kintsugi-parachain"
+    ));
+    assert!(matches!(
+        check_runtime_version(&RuntimeVersion {
+            spec_version: 1022000,
+            ..compiled_version
+        }),
+        Err(crate::Error::InvalidSpecVersion(_, _, 1022000))
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_runtime_version() {
+    let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
+    let parachain_rpc = setup_provider(client, AccountKeyring::Alice).await;
+
+    let runtime_version = parachain_rpc.runtime_version().await.unwrap();
+    assert_eq!(runtime_version.spec_name, crate::DEFAULT_SPEC_NAME);
+    assert!(crate::rpc::check_runtime_version(&runtime_version).is_ok());
+}
+
+#[test]
+fn test_extrinsic_params_encode_tip() {
+    let encode_extra = |tip| {
+        let params = <PolkadotExtrinsicParams<InterBtcRuntime> as ExtrinsicParams<u32, H256>>::new(
+            1,
+            1,
+            7,
+            H256::zero(),
+            crate::rpc::extrinsic_params(tip, None),
+        );
+        let mut extra = vec![];
+        params.encode_extra_to(&mut extra);
+        extra
+    };
+
+    // immortal era, followed by the compact encoded nonce and tip
+    let mut expected = vec![0];
+    expected.extend(Compact(7u64).encode());
+    expected.extend(Compact(1_000u128).encode());
+    assert_eq!(encode_extra(1_000), expected);
+    assert_ne!(encode_extra(0), expected);
+}
+
+#[test]
+fn test_extrinsic_params_encode_mortal_era() {
+    let era = crate::rpc::mortal_era(64, 100);
+    assert_eq!(era, Era::mortal(64, 100));
+    assert!(matches!(era, Era::Mortal(64, _)));
+
+    let checkpoint = H256::repeat_byte(1);
+    let params = <PolkadotExtrinsicParams<InterBtcRuntime> as ExtrinsicParams<u32, H256>>::new(
+        1,
+        1,
+        7,
+        H256::zero(),
+        crate::rpc::extrinsic_params(0, Some((era, checkpoint))),
+    );
+
+    let mut extra = vec![];
+    params.encode_extra_to(&mut extra);
+    assert!(extra.starts_with(&era.encode()));
+
+    // mortal extrinsics commit to the block the era starts at, rather than the genesis block
+    let mut additional = vec![];
+    params.encode_additional_to(&mut additional);
+    assert!(additional.ends_with(checkpoint.as_bytes()));
+}
+
+#[test]
+fn test_health_summary_status() {
+    let vault_id = VaultId::new(AccountKeyring::Bob.into(), Token(KSM), Token(KBTC));
+    let vault = |collateralization: Option<f64>| VaultHealth {
+        vault_id: vault_id.clone(),
+        collateralization: collateralization.map(FixedU128::from_float),
+        secure_threshold: FixedU128::from_float(1.5),
+    };
+    let synced = SyncStatus {
+        is_syncing: false,
+        current_block: 100,
+        highest_block: Some(101),
+    };
+    let summary = HealthSummary {
+        sync_status: synced,
+        free_balances: vec![(Token(KINT), 1_000), (Token(KSM), 0)],
+        native_currency_id: Token(KINT),
+        vaults: vec![vault(Some(2.0)), vault(None)],
+    };
+    assert_eq!(summary.status(2), HealthStatus::Healthy);
+
+    // the node lags behind
+    assert_eq!(summary.status(0), HealthStatus::Warning);
+
+    // no funds left to pay fees with
+    let mut out_of_fees = summary.clone();
+    out_of_fees.free_balances[0].1 = 0;
+    assert_eq!(out_of_fees.status(2), HealthStatus::Warning);
+
+    // undercollateralized vaults take precedence over everything else
+    let mut undercollateralized = out_of_fees.clone();
+    undercollateralized.vaults.push(vault(Some(1.4)));
+    assert_eq!(undercollateralized.status(0), HealthStatus::Critical);
+}
+
+#[test]
+fn test_clock_drift() {
+    let local_time = UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+    let drift = ClockDrift::between(1_000_002_500, local_time);
+    assert_eq!(drift, ClockDrift::Ahead(Duration::from_millis(2_500)));
+    assert_eq!(drift.magnitude(), Duration::from_millis(2_500));
+
+    let drift = ClockDrift::between(999_990_000, local_time);
+    assert_eq!(drift, ClockDrift::Behind(Duration::from_secs(10)));
+    assert_eq!(drift.magnitude(), Duration::from_secs(10));
+
+    assert_eq!(
+        ClockDrift::between(1_000_000_000, local_time),
+        ClockDrift::Ahead(Duration::ZERO)
+    );
+}
+
+#[test]
+fn test_parse_sync_status() {
+    let parse_sync_status = crate::rpc::parse_sync_status;
+    let health = serde_json::json!({ "peers": 3, "isSyncing": false, "shouldHavePeers": true });
+    assert_eq!(
+        parse_sync_status(
+            &health,
+            &serde_json::json!({ "startingBlock": 0, "currentBlock": 98, "highestBlock": 100 })
+        )
+        .unwrap(),
+        SyncStatus {
+            is_syncing: false,
+            current_block: 98,
+            highest_block: Some(100),
+        }
+    );
+    assert_eq!(
+        parse_sync_status(
+            &health,
+            &serde_json::json!({ "startingBlock": 0, "currentBlock": 98, "highestBlock": null })
+        )
+        .unwrap()
+        .highest_block,
+        None
+    );
+    assert!(parse_sync_status(&serde_json::json!({}), &serde_json::json!({ "currentBlock": 98 })).is_err());
+}
+
+#[tokio::test]
+async fn test_wait_until_synced() {
+    let statuses = std::sync::Mutex::new(
+        vec![
+            // major sync
+            SyncStatus {
+                is_syncing: true,
+                current_block: 10,
+                highest_block: Some(100),
+            },
+            // too far behind
+            SyncStatus {
+                is_syncing: false,
+                current_block: 95,
+                highest_block: Some(100),
+            },
+            SyncStatus {
+                is_syncing: false,
+                current_block: 99,
+                highest_block: Some(100),
+            },
+        ]
+        .into_iter(),
+    );
+    let polls = std::sync::atomic::AtomicUsize::new(0);
+    let get_status = || {
+        polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let status = statuses.lock().unwrap().next().expect("polled after synced");
+        async move { Ok(status) }
+    };
+
+    crate::rpc::wait_until_synced(get_status, 2, Duration::from_secs(1), Duration::from_millis(10))
+        .await
+        .unwrap();
+    assert_eq!(polls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+    // a node that never catches up
+    let syncing = || async {
+        Ok(SyncStatus {
+            is_syncing: true,
+            current_block: 10,
+            highest_block: Some(100),
+        })
+    };
+    assert!(matches!(
+        crate::rpc::wait_until_synced(syncing, 2, Duration::from_millis(50), Duration::from_millis(10)).await,
+        Err(crate::Error::SyncTimeout)
+    ));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_currency_id_parsing() {
     let (client, _tmp_dir) = default_provider_client(AccountKeyring::Alice).await;
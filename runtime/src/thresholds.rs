@@ -0,0 +1,154 @@
+use crate::{CurrencyId, Error, FixedU128, H256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+/// The collateralization thresholds of the vault registry for a collateral currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// below this, a vault cannot issue more tokens
+    pub secure: FixedU128,
+    /// below this, redeemers receive a premium
+    pub premium_redeem: FixedU128,
+    /// below this, a vault is liquidated
+    pub liquidation: FixedU128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CachedThresholds {
+    Valid(Thresholds),
+    /// changed in the block with this hash, so the thresholds must not be cached again until that
+    /// block is finalized, otherwise the old values read from the finalized block would stick
+    Changed(H256),
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    thresholds: BTreeMap<CurrencyId, CachedThresholds>,
+    // currencies whose thresholds are watched for storage changes
+    watched: BTreeSet<CurrencyId>,
+}
+
+/// Thresholds by collateral currency, dropped whenever they change on chain.
+#[derive(Clone, Default)]
+pub(crate) struct ThresholdCache(Arc<RwLock<Inner>>);
+
+impl ThresholdCache {
+    /// Mark `currency_id` as watched, returns `false` if it already was.
+    pub(crate) fn start_watching(&self, currency_id: CurrencyId) -> bool {
+        self.0.write().unwrap().watched.insert(currency_id)
+    }
+
+    /// Forget `currency_id` once its changes can no longer be observed.
+    pub(crate) fn stop_watching(&self, currency_id: CurrencyId) {
+        let mut inner = self.0.write().unwrap();
+        inner.watched.remove(&currency_id);
+        inner.thresholds.remove(&currency_id);
+    }
+
+    /// The thresholds of `currency_id` changed in the block with hash `block_hash`.
+    pub(crate) fn invalidate(&self, currency_id: CurrencyId, block_hash: H256) {
+        self.0
+            .write()
+            .unwrap()
+            .thresholds
+            .insert(currency_id, CachedThresholds::Changed(block_hash));
+    }
+
+    /// The cached thresholds of `currency_id`, or the ones returned by `fetch`. Fetched thresholds
+    /// are only cached while `currency_id` is watched, and once the block of the last change is
+    /// finalized according to `is_finalized`.
+    pub(crate) async fn get_or_fetch<F, C>(
+        &self,
+        currency_id: CurrencyId,
+        fetch: F,
+        is_finalized: impl FnOnce(H256) -> C,
+    ) -> Result<Thresholds, Error>
+    where
+        F: Future<Output = Result<Thresholds, Error>>,
+        C: Future<Output = Result<bool, Error>>,
+    {
+        let cached = self.0.read().unwrap().thresholds.get(&currency_id).copied();
+        let changed_in = match cached {
+            Some(CachedThresholds::Valid(thresholds)) => return Ok(thresholds),
+            Some(CachedThresholds::Changed(block_hash)) => Some(block_hash),
+            None => None,
+        };
+
+        let thresholds = fetch.await?;
+        let settled = match changed_in {
+            Some(block_hash) => is_finalized(block_hash).await.unwrap_or(false),
+            None => true,
+        };
+
+        let mut inner = self.0.write().unwrap();
+        // don't overwrite a change that was observed while fetching
+        if settled && inner.watched.contains(&currency_id) && inner.thresholds.get(&currency_id).copied() == cached {
+            inner
+                .thresholds
+                .insert(currency_id, CachedThresholds::Valid(thresholds));
+        }
+        Ok(thresholds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Token, DOT, KSM};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn thresholds(secure: u128) -> Thresholds {
+        Thresholds {
+            secure: FixedU128::from_inner(secure),
+            premium_redeem: FixedU128::from_inner(2),
+            liquidation: FixedU128::from_inner(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fetch_thresholds_once_until_invalidated() {
+        let cache = &ThresholdCache::default();
+        let reads = &AtomicUsize::new(0);
+        let secure = &AtomicUsize::new(3);
+        let get = move |finalized: bool| {
+            cache.get_or_fetch(
+                Token(DOT),
+                async move {
+                    reads.fetch_add(1, Ordering::SeqCst);
+                    Ok(thresholds(secure.load(Ordering::SeqCst) as u128))
+                },
+                move |_| async move { Ok(finalized) },
+            )
+        };
+        assert!(cache.start_watching(Token(DOT)));
+        assert!(!cache.start_watching(Token(DOT)));
+
+        assert_eq!(get(true).await.unwrap(), thresholds(3));
+        assert_eq!(get(true).await.unwrap(), thresholds(3));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        // other currencies are not affected
+        cache.invalidate(Token(KSM), H256::zero());
+        assert_eq!(get(true).await.unwrap(), thresholds(3));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        secure.store(4, Ordering::SeqCst);
+        cache.invalidate(Token(DOT), H256::zero());
+        // not cached until the change is finalized
+        assert_eq!(get(false).await.unwrap(), thresholds(4));
+        assert_eq!(get(false).await.unwrap(), thresholds(4));
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+        assert_eq!(get(true).await.unwrap(), thresholds(4));
+        assert_eq!(get(true).await.unwrap(), thresholds(4));
+        assert_eq!(reads.load(Ordering::SeqCst), 4);
+
+        // nothing is cached without watching for changes
+        cache.stop_watching(Token(DOT));
+        assert_eq!(get(true).await.unwrap(), thresholds(4));
+        assert_eq!(get(true).await.unwrap(), thresholds(4));
+        assert_eq!(reads.load(Ordering::SeqCst), 6);
+    }
+}
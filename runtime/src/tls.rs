@@ -0,0 +1,227 @@
+//! Websocket connections over mutual TLS. The jsonrpsee builder only supports the native or
+//! webpki root stores, so the TLS stream is set up here and handed to the client as a transport.
+
+use crate::{error::JsonRpseeError, Error};
+use async_trait::async_trait;
+use jsonrpsee::core::client::{
+    Client as WsClient, ClientBuilder, ReceivedMessage, TransportReceiverT, TransportSenderT,
+};
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerName};
+use rustls_pemfile::Item;
+use soketto::{
+    connection::{self, Error as SokettoError},
+    data::Data,
+    handshake,
+};
+use std::{convert::TryFrom, fs::File, io::BufReader, path::Path, sync::Arc};
+use tokio::net::TcpStream;
+use tokio_rustls::{client::TlsStream, TlsConnector};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+use url::Url;
+
+type Stream = Compat<TlsStream<TcpStream>>;
+
+/// Build the TLS configuration from the given PEM files, `None` if none are given.
+///
+/// # Arguments
+/// * `cert` - client certificate chain to present to the node
+/// * `key` - private key of the client certificate
+/// * `ca` - CA to verify the node with, instead of the system roots
+pub(crate) fn load_client_config(
+    cert: Option<&Path>,
+    key: Option<&Path>,
+    ca: Option<&Path>,
+) -> Result<Option<ClientConfig>, Error> {
+    if cert.is_none() && key.is_none() && ca.is_none() {
+        return Ok(None);
+    }
+
+    let mut roots = RootCertStore::empty();
+    let ca_certs = match ca {
+        Some(ca) => load_certs(ca)?,
+        None => rustls_native_certs::load_native_certs()
+            .map_err(|err| Error::TlsConfig(format!("failed to load native certificates: {}", err)))?
+            .into_iter()
+            .map(|cert| Certificate(cert.0))
+            .collect(),
+    };
+    for cert in ca_certs {
+        roots
+            .add(&cert)
+            .map_err(|err| Error::TlsConfig(format!("invalid CA certificate: {}", err)))?;
+    }
+
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let config = match (cert, key) {
+        (Some(cert), Some(key)) => builder
+            .with_single_cert(load_certs(cert)?, load_private_key(key)?)
+            .map_err(|err| Error::TlsConfig(err.to_string()))?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => {
+            return Err(Error::TlsConfig(
+                "client certificate and key must be given together".to_string(),
+            ))
+        }
+    };
+    Ok(Some(config))
+}
+
+fn open_pem(path: &Path) -> Result<BufReader<File>, Error> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|err| Error::TlsConfig(format!("failed to open {}: {}", path.display(), err)))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<Certificate>, Error> {
+    let certs = rustls_pemfile::certs(&mut open_pem(path)?)
+        .map_err(|err| Error::TlsConfig(format!("failed to read {}: {}", path.display(), err)))?;
+    if certs.is_empty() {
+        return Err(Error::TlsConfig(format!("no certificates found in {}", path.display())));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKey, Error> {
+    let mut reader = open_pem(path)?;
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|err| Error::TlsConfig(format!("failed to read {}: {}", path.display(), err)))?
+        {
+            Some(Item::RSAKey(key)) | Some(Item::PKCS8Key(key)) | Some(Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(Error::TlsConfig(format!("no private key found in {}", path.display()))),
+        }
+    }
+}
+
+pub(crate) async fn new_tls_websocket_client(
+    url: &str,
+    config: Arc<ClientConfig>,
+    max_concurrent_requests: usize,
+    max_notifs_per_subscription: usize,
+) -> Result<WsClient, Error> {
+    // report failures as transport errors, like the jsonrpsee builder does
+    let (sender, receiver) = connect(url, config).await.map_err(JsonRpseeError::Transport)?;
+    Ok(ClientBuilder::default()
+        .max_concurrent_requests(max_concurrent_requests)
+        .max_notifs_per_subscription(max_notifs_per_subscription)
+        .build_with_tokio(sender, receiver))
+}
+
+async fn connect(url: &str, config: Arc<ClientConfig>) -> Result<(Sender, Receiver), anyhow::Error> {
+    let url = Url::parse(url)?;
+    if url.scheme() != "wss" {
+        anyhow::bail!("client certificates require a wss:// url, found {}", url);
+    }
+    let host = url.host_str().ok_or_else(|| anyhow::anyhow!("no host in {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let tcp_stream = TcpStream::connect((host, port)).await?;
+    let tls_stream = TlsConnector::from(config)
+        .connect(ServerName::try_from(host)?, tcp_stream)
+        .await?;
+
+    let host_header = format!("{}:{}", host, port);
+    let mut client = handshake::Client::new(tls_stream.compat(), &host_header, url.path());
+    match client.handshake().await? {
+        handshake::ServerResponse::Accepted { .. } => {}
+        handshake::ServerResponse::Rejected { status_code } => {
+            anyhow::bail!("websocket handshake rejected with status {}", status_code)
+        }
+        handshake::ServerResponse::Redirect { status_code, location } => {
+            anyhow::bail!("websocket handshake redirected ({}) to {}", status_code, location)
+        }
+    }
+
+    let (sender, receiver) = client.into_builder().finish();
+    Ok((Sender(sender), Receiver(receiver)))
+}
+
+struct Sender(connection::Sender<Stream>);
+
+#[async_trait]
+impl TransportSenderT for Sender {
+    type Error = SokettoError;
+
+    async fn send(&mut self, body: String) -> Result<(), Self::Error> {
+        self.0.send_text(body).await?;
+        self.0.flush().await
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.0.close().await
+    }
+}
+
+struct Receiver(connection::Receiver<Stream>);
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+    type Error = SokettoError;
+
+    async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+        let mut message = Vec::new();
+        match self.0.receive_data(&mut message).await? {
+            // soketto has already validated the utf-8 of text frames
+            Data::Text(_) => Ok(ReceivedMessage::Text(String::from_utf8_lossy(&message).into_owned())),
+            Data::Binary(_) => Ok(ReceivedMessage::Bytes(message)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn testdata(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/tls")
+            .join(name)
+    }
+
+    #[test]
+    fn should_not_build_config_without_files() {
+        assert!(load_client_config(None, None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_build_config_from_pem_files() {
+        let config = load_client_config(
+            Some(&testdata("client.pem")),
+            Some(&testdata("client.key")),
+            Some(&testdata("ca.pem")),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(config.client_auth_cert_resolver.has_certs());
+    }
+
+    #[test]
+    fn should_build_config_with_only_ca() {
+        let config = load_client_config(None, None, Some(&testdata("ca.pem")))
+            .unwrap()
+            .unwrap();
+
+        assert!(!config.client_auth_cert_resolver.has_certs());
+    }
+
+    #[test]
+    fn should_reject_cert_without_key() {
+        assert!(matches!(
+            load_client_config(Some(&testdata("client.pem")), None, Some(&testdata("ca.pem"))),
+            Err(Error::TlsConfig(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_key_as_certificate() {
+        assert!(matches!(
+            load_client_config(None, None, Some(&testdata("client.key"))),
+            Err(Error::TlsConfig(_))
+        ));
+    }
+}
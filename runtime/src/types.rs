@@ -11,6 +11,8 @@ pub use primitives::{
 pub use currency_id::CurrencyIdExt;
 pub use h256_le::RichH256Le;
 pub use module_btc_relay::{RichBlockHeader, MAIN_CHAIN_ID};
+use sp_arithmetic::traits::Zero;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub type AccountId = subxt::ext::sp_runtime::AccountId32;
 pub type Balance = primitives::Balance;
@@ -63,6 +65,8 @@ mod metadata_aliases {
 
     pub use metadata::security::events::UpdateActiveBlock as UpdateActiveBlockEvent;
 
+    pub use metadata::system::events::CodeUpdated as CodeUpdatedEvent;
+
     pub use metadata::vault_registry::events::{
         DepositCollateral as DepositCollateralEvent, LiquidateVault as LiquidateVaultEvent,
         RegisterAddress as RegisterAddressEvent, RegisterVault as RegisterVaultEvent,
@@ -124,6 +128,224 @@ mod metadata_aliases {
     pub use metadata::runtime_types::security::pallet::Call as SecurityCall;
 }
 
+/// A parachain block, identified either by its hash or by its number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockId {
+    Hash(H256),
+    Number(BlockNumber),
+}
+
+impl From<H256> for BlockId {
+    fn from(hash: H256) -> Self {
+        Self::Hash(hash)
+    }
+}
+
+impl From<BlockNumber> for BlockId {
+    fn from(number: BlockNumber) -> Self {
+        Self::Number(number)
+    }
+}
+
+/// How far a submitted extrinsic has to progress before the submission returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finality {
+    /// Included in a block, which may still be reorganized away
+    InBlock,
+    /// Included in a finalized block
+    Finalized,
+}
+
+impl Default for Finality {
+    /// Finalized, except with `testing-utils`, where waiting for finality would slow down the tests
+    fn default() -> Self {
+        if cfg!(feature = "testing-utils") {
+            Finality::InBlock
+        } else {
+            Finality::Finalized
+        }
+    }
+}
+
+/// Notification of an event subscription, see `InterBtcParachain::subscribe_event_notifications`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventNotification<T> {
+    /// An event emitted in the given block
+    Event {
+        event: T,
+        block_number: BlockNumber,
+        block_hash: H256,
+        /// whether the block was finalized, otherwise it may still be rolled back by a reorg
+        finalized: bool,
+    },
+    /// The block, and with it all of its ancestors, was finalized
+    Finalized {
+        block_number: BlockNumber,
+        block_hash: H256,
+    },
+    /// The best chain switched from `from` to `to`. Events from the `retracted` blocks were rolled
+    /// back, so any action taken on them should be invalidated.
+    Reorg { from: H256, to: H256, retracted: Vec<H256> },
+}
+
+/// The predicted result of submitting an extrinsic, obtained without broadcasting it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunOutcome {
+    pub would_succeed: bool,
+    /// inclusion fee the signer would pay, in the native currency
+    pub estimated_fee: Balance,
+    /// description of the failure, if `would_succeed` is false
+    pub error: Option<String>,
+}
+
+/// Version of the runtime of the connected node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeVersion {
+    pub spec_name: String,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+}
+
+/// Sync progress of the node, as reported by `system_health` and `system_syncState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// whether the node is in a major sync
+    pub is_syncing: bool,
+    pub current_block: u32,
+    /// highest block seen by the peers of the node, if it has any
+    pub highest_block: Option<u32>,
+}
+
+impl SyncStatus {
+    /// Whether the node is not in a major sync, and at most `tolerance` blocks behind its peers
+    pub fn is_synced(&self, tolerance: u32) -> bool {
+        let lag = self
+            .highest_block
+            .map_or(0, |highest_block| highest_block.saturating_sub(self.current_block));
+        !self.is_syncing && lag <= tolerance
+    }
+}
+
+/// Overall health of a vault, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    Healthy,
+    /// the vault can operate, but needs attention, e.g. because its node is lagging behind
+    Warning,
+    /// collateral is at risk
+    Critical,
+}
+
+/// Collateralization of a single vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultHealth {
+    pub vault_id: VaultId,
+    /// `None` if the vault has not issued any tokens
+    pub collateralization: Option<FixedU128>,
+    pub secure_threshold: FixedU128,
+}
+
+impl VaultHealth {
+    pub fn is_below_secure_threshold(&self) -> bool {
+        self.collateralization
+            .map_or(false, |collateralization| collateralization < self.secure_threshold)
+    }
+}
+
+/// Exchange rate at which a vault would reach the liquidation threshold, in the unit of the oracle
+/// rate, i.e. the smallest unit of the collateral per satoshi
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidationPrice {
+    pub liquidation_rate: FixedU128,
+    pub current_rate: FixedU128,
+}
+
+impl LiquidationPrice {
+    /// How much the exchange rate can rise before the vault is liquidated, in percent of the
+    /// current rate. Negative once the vault is below the liquidation threshold, `None` without a
+    /// current rate to compare against.
+    pub fn margin_of_safety_percent(&self) -> Option<f64> {
+        if self.current_rate.is_zero() {
+            return None;
+        }
+        Some((self.liquidation_rate.to_float() / self.current_rate.to_float() - 1.0) * 100.0)
+    }
+}
+
+/// Everything needed to tell whether the vaults of an account are healthy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthSummary {
+    pub sync_status: SyncStatus,
+    /// free balance of the native currency, and of the collateral currency of every vault
+    pub free_balances: Vec<(CurrencyId, Balance)>,
+    pub native_currency_id: CurrencyId,
+    pub vaults: Vec<VaultHealth>,
+}
+
+impl HealthSummary {
+    /// Critical if any vault is below the secure threshold, a warning if the node is more than
+    /// `sync_tolerance` blocks behind or there are no funds left to pay transaction fees with.
+    pub fn status(&self, sync_tolerance: u32) -> HealthStatus {
+        let out_of_fees = self
+            .free_balances
+            .iter()
+            .any(|(currency_id, balance)| *currency_id == self.native_currency_id && *balance == 0);
+        if self.vaults.iter().any(VaultHealth::is_below_secure_threshold) {
+            HealthStatus::Critical
+        } else if !self.sync_status.is_synced(sync_tolerance) || out_of_fees {
+            HealthStatus::Warning
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+/// Offset of the parachain time, as set by the `timestamp` pallet, from the local clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockDrift {
+    /// the parachain time is ahead of the local clock
+    Ahead(Duration),
+    /// the parachain time is behind the local clock
+    Behind(Duration),
+}
+
+impl ClockDrift {
+    /// Compare the parachain time in milliseconds since the epoch against `local_time`
+    pub fn between(chain_time_ms: u64, local_time: SystemTime) -> Self {
+        let chain_time = Duration::from_millis(chain_time_ms);
+        let local_time = local_time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        if chain_time >= local_time {
+            ClockDrift::Ahead(chain_time - local_time)
+        } else {
+            ClockDrift::Behind(local_time - chain_time)
+        }
+    }
+
+    /// The size of the drift, regardless of its direction
+    pub fn magnitude(&self) -> Duration {
+        match self {
+            ClockDrift::Ahead(drift) | ClockDrift::Behind(drift) => *drift,
+        }
+    }
+}
+
+impl std::fmt::Display for ClockDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClockDrift::Ahead(drift) => write!(f, "{:.3} s ahead of", drift.as_secs_f64()),
+            ClockDrift::Behind(drift) => write!(f, "{:.3} s behind", drift.as_secs_f64()),
+        }
+    }
+}
+
+/// A page of the issue requests of a vault, in storage order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueRequestsPage {
+    pub requests: Vec<(H256, InterBtcIssueRequest)>,
+    /// raw storage key to continue listing after, if there may be more requests
+    pub next_cursor: Option<Vec<u8>>,
+}
+
 impl crate::RawBlockHeader {
     pub fn hash(&self) -> crate::H256Le {
         module_bitcoin::utils::sha256d_le(&self.0).into()
@@ -1,5 +1,11 @@
+use crate::log_file::{LogRotation, RotatingFile, SharedRotatingFile};
 use clap::Parser;
-use std::str::FromStr;
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
 
 #[derive(Clone, Debug)]
 pub enum RestartPolicy {
@@ -56,15 +62,42 @@ pub struct ServiceConfig {
     #[clap(long, default_value = "always")]
     pub restart_policy: RestartPolicy,
 
-    /// Logging output format.
+    /// Logging output format: `full` for humans, or `json` for one JSON object per line.
     #[clap(long, default_value = "full")]
     pub logging_format: LoggingFormat,
+
+    /// Also write logs to this file, regardless of the console output.
+    #[clap(long)]
+    pub log_file: Option<PathBuf>,
+
+    /// When to start a new log file: `daily`, `hourly`, or `size:<bytes>` with an optional `K`, `M`
+    /// or `G` suffix, e.g. `size:100M`.
+    #[clap(long, default_value = "daily", requires = "log_file")]
+    pub log_rotation: LogRotation,
+
+    /// Number of rotated log files to keep, older ones are deleted.
+    #[clap(long, default_value = "7", requires = "log_file")]
+    pub log_max_files: usize,
+}
+
+impl ServiceConfig {
+    /// Install the global tracing subscriber, failing if the log file cannot be opened.
+    pub fn init_logging(&self) -> Result<(), io::Error> {
+        let log_file = self
+            .log_file
+            .clone()
+            .map(|path| RotatingFile::open(path, self.log_rotation, self.log_max_files))
+            .transpose()?
+            .map(SharedRotatingFile::new);
+        crate::trace::init_logging(&self.logging_format, log_file);
+        Ok(())
+    }
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct MonitoringConfig {
     /// Do not expose a Prometheus metric endpoint.
-    #[clap(long)]
+    #[clap(long, alias = "metrics-disabled")]
     pub no_prometheus: bool,
 
     /// Expose Prometheus exporter on all interfaces.
@@ -76,4 +109,23 @@ pub struct MonitoringConfig {
     /// Specify Prometheus exporter TCP Port.
     #[clap(long, default_value = "9615")]
     pub prometheus_port: u16,
+
+    /// Address to bind the Prometheus exporter to, e.g. `0.0.0.0:9615` in a container.
+    /// Overrides `--prometheus-external` and `--prometheus-port`.
+    #[clap(long, conflicts_with_all = ["prometheus_external", "prometheus_port"])]
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl MonitoringConfig {
+    /// The address to bind the Prometheus exporter to, `127.0.0.1:9615` by default.
+    pub fn prometheus_addr(&self) -> SocketAddr {
+        self.metrics_addr.unwrap_or_else(|| {
+            let host = if self.prometheus_external {
+                Ipv4Addr::UNSPECIFIED
+            } else {
+                Ipv4Addr::LOCALHOST
+            };
+            SocketAddr::new(host.into(), self.prometheus_port)
+        })
+    }
 }
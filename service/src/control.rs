@@ -0,0 +1,132 @@
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Signals to temporarily stop, and later continue, taking on new work, e.g. during maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlSignal {
+    Pause,
+    Resume,
+}
+
+/// A wrapper around a tokio broadcast channel for pause/resume signals. Like `ShutdownSender`, it
+/// remembers the last signal, so that receivers created after a pause also start out paused.
+#[derive(Clone)]
+pub struct ControlSender {
+    paused: Arc<RwLock<bool>>,
+    channel: broadcast::Sender<ControlSignal>,
+}
+
+impl ControlSender {
+    pub fn new() -> Self {
+        let (channel, _) = broadcast::channel(16);
+        Self {
+            paused: Default::default(),
+            channel,
+        }
+    }
+
+    pub fn send(&self, signal: ControlSignal) {
+        *self.paused.write().unwrap() = signal == ControlSignal::Pause;
+        // an error only means that nobody is listening right now
+        let _ = self.channel.send(signal);
+    }
+
+    pub fn pause(&self) {
+        self.send(ControlSignal::Pause)
+    }
+
+    pub fn resume(&self) {
+        self.send(ControlSignal::Resume)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.read().unwrap()
+    }
+
+    pub fn subscribe(&self) -> ControlReceiver {
+        ControlReceiver {
+            inner: self.channel.subscribe(),
+            paused: self.paused.clone(),
+        }
+    }
+}
+
+impl Default for ControlSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ControlReceiver {
+    inner: broadcast::Receiver<ControlSignal>,
+    paused: Arc<RwLock<bool>>,
+}
+
+impl ControlReceiver {
+    pub fn is_paused(&self) -> bool {
+        *self.paused.read().unwrap()
+    }
+
+    /// Return immediately if not paused, otherwise wait for the resume signal.
+    pub async fn wait_until_resumed(&mut self) {
+        // signals sent after subscribing are buffered, so a resume between the check and `recv`
+        // is not missed
+        while self.is_paused() {
+            match self.inner.recv().await {
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                // the sender is gone, so nobody can resume us anymore
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn test_paused_receiver_waits_for_resume() {
+        let control_tx = ControlSender::new();
+        let mut running = control_tx.subscribe();
+        assert!(!running.is_paused());
+        running.wait_until_resumed().await;
+
+        control_tx.pause();
+        // receivers created after the pause start out paused as well
+        let mut receivers = vec![running, control_tx.subscribe()];
+        for receiver in receivers.iter_mut() {
+            assert!(receiver.is_paused());
+            assert!(timeout(Duration::from_millis(50), receiver.wait_until_resumed())
+                .await
+                .is_err());
+        }
+
+        let waiting = tokio::spawn(async move {
+            for receiver in receivers.iter_mut() {
+                receiver.wait_until_resumed().await;
+            }
+        });
+        control_tx.resume();
+        timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap();
+        assert!(!control_tx.is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_last_signal_wins() {
+        let control_tx = ControlSender::new();
+        let mut control_rx = control_tx.subscribe();
+
+        control_tx.pause();
+        control_tx.resume();
+        control_tx.pause();
+        assert!(control_rx.is_paused());
+
+        control_tx.resume();
+        timeout(Duration::from_secs(5), control_rx.wait_until_resumed())
+            .await
+            .unwrap();
+    }
+}
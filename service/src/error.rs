@@ -1,7 +1,7 @@
 use bitcoin::Error as BitcoinError;
 use runtime::Error as RuntimeError;
 use serde_json::Error as SerdeJsonError;
-use std::{io::Error as IoError, num::ParseIntError};
+use std::{io::Error as IoError, net::SocketAddr, num::ParseIntError};
 use thiserror::Error;
 use tokio::task::JoinError as TokioJoinError;
 
@@ -22,6 +22,10 @@ pub enum Error<InnerError> {
     ServiceAlreadyRunning(u32),
     #[error("Process with pid {0} not found")]
     ProcessNotFound(String),
+    #[error("Failed to bind the Prometheus exporter to {0}: {1}")]
+    MetricsBindError(SocketAddr, String),
+    #[error("Invalid config file: {0}")]
+    ConfigFileError(String),
 
     #[error("SerdeJsonError: {0}")]
     SerdeJsonError(#[from] SerdeJsonError),
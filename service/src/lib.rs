@@ -10,12 +10,17 @@ use runtime::{
 use std::{fmt, sync::Arc, time::Duration};
 
 mod cli;
+mod control;
 mod error;
+mod log_file;
+pub mod testing;
 mod trace;
 
 pub use cli::{LoggingFormat, MonitoringConfig, RestartPolicy, ServiceConfig};
+pub use control::{ControlReceiver, ControlSender, ControlSignal};
 pub use error::Error;
-pub use runtime::{ShutdownReceiver, ShutdownSender};
+pub use log_file::LogRotation;
+pub use runtime::{ShutdownReason, ShutdownReceiver, ShutdownSender};
 pub use trace::init_subscriber;
 pub use warp;
 
@@ -32,6 +37,7 @@ pub trait Service<Config, InnerError> {
         config: Config,
         monitoring_config: MonitoringConfig,
         shutdown: ShutdownSender,
+        control: ControlSender,
         constructor: Box<dyn Fn(VaultId) -> Result<DynBitcoinCoreApi, BitcoinError> + Send + Sync>,
     ) -> Self;
     async fn start(&self) -> Result<(), Error<InnerError>>;
@@ -45,7 +51,10 @@ pub struct ConnectionManager<Config: Clone, F: Fn()> {
     service_config: ServiceConfig,
     monitoring_config: MonitoringConfig,
     config: Config,
+    // outlives restarts, so that a pause is kept until explicitly resumed
+    control_tx: ControlSender,
     increment_restart_counter: F,
+    observer: bool,
 }
 
 impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
@@ -68,10 +77,23 @@ impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
             service_config,
             monitoring_config,
             config,
+            control_tx: ControlSender::new(),
             increment_restart_counter,
+            observer: false,
         }
     }
 
+    /// Don't create or load the master wallet, since the service only observes.
+    pub fn with_observer_mode(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Sender to pause and resume taking on new work, across restarts of the service.
+    pub fn control(&self) -> ControlSender {
+        self.control_tx.clone()
+    }
+
     pub async fn start<S: Service<Config, InnerError>, InnerError: fmt::Display>(
         &self,
     ) -> Result<(), Error<InnerError>> {
@@ -83,19 +105,25 @@ impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
             let shutdown_tx = ShutdownSender::new();
 
             let prefix = self.wallet_name.clone().unwrap_or_else(|| "vault".to_string());
-            let bitcoin_core = self.bitcoin_config.new_client(Some(format!("{prefix}-master"))).await?;
+            let bitcoin_core = self
+                .bitcoin_config
+                .new_client(Some(format!("{prefix}-master")), self.observer)
+                .await?;
 
             // only open connection to parachain after bitcoind sync to prevent timeout
             let signer = self.signer.clone();
             let btc_parachain = BtcParachain::from_url_and_config_with_retry(
                 &self.parachain_config.btc_parachain_url,
                 signer,
+                self.parachain_config.tls_client_config()?,
                 self.parachain_config.max_concurrent_requests,
                 self.parachain_config.max_notifs_per_subscription,
                 self.parachain_config.btc_parachain_connection_timeout_ms,
+                self.parachain_config.allow_runtime_mismatch,
                 shutdown_tx.clone(),
             )
             .await?;
+            let btc_parachain = self.parachain_config.configure(btc_parachain);
 
             let config_copy = self.bitcoin_config.clone();
             let network_copy = bitcoin_core.network();
@@ -121,6 +149,7 @@ impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
                 config,
                 self.monitoring_config.clone(),
                 shutdown_tx.clone(),
+                self.control_tx.clone(),
                 Box::new(constructor),
             );
             match service.start().await {
@@ -136,7 +165,10 @@ impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
                 }
             }
             // propagate shutdown signal from main tasks
-            let _ = shutdown_tx.send(());
+            let _ = shutdown_tx.shutdown(ShutdownReason::Disconnected);
+            if let Some(reason) = shutdown_tx.reason() {
+                tracing::info!("Shutting down, reason: {}", reason);
+            }
 
             let rate_limiter = RateLimiter::direct(Quota::per_minute(nonzero!(4u32)));
 
@@ -167,6 +199,7 @@ impl<Config: Clone + Send + 'static, F: Fn()> ConnectionManager<Config, F> {
 pub async fn wait_or_shutdown<F, E>(shutdown_tx: ShutdownSender, future2: F) -> Result<(), E>
 where
     F: Future<Output = Result<(), E>>,
+    E: fmt::Display,
 {
     match run_cancelable(shutdown_tx.subscribe(), future2).await {
         TerminationStatus::Cancelled => {
@@ -175,7 +208,11 @@ where
         }
         TerminationStatus::Completed(res) => {
             tracing::trace!("Sending shutdown signal");
-            let _ = shutdown_tx.send(());
+            let reason = match &res {
+                Ok(()) => "task exited".to_string(),
+                Err(err) => format!("task failed: {}", err),
+            };
+            let _ = shutdown_tx.shutdown(ShutdownReason::Custom(reason));
             res
         }
     }
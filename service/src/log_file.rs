@@ -0,0 +1,224 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// When to start a new log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Daily,
+    Hourly,
+    /// Once the file would grow beyond this many bytes
+    Size(u64),
+}
+
+impl FromStr for LogRotation {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let err = || "Could not parse input as LogRotation".to_string();
+        match code {
+            "daily" => Ok(LogRotation::Daily),
+            "hourly" => Ok(LogRotation::Hourly),
+            _ => {
+                let size = code.strip_prefix("size:").ok_or_else(err)?;
+                let (digits, multiplier) = match size.chars().last() {
+                    Some('K') => (&size[..size.len() - 1], 1 << 10),
+                    Some('M') => (&size[..size.len() - 1], 1 << 20),
+                    Some('G') => (&size[..size.len() - 1], 1 << 30),
+                    _ => (size, 1),
+                };
+                let size = digits.parse::<u64>().map_err(|_| err())?;
+                match size.checked_mul(multiplier) {
+                    Some(size) if size > 0 => Ok(LogRotation::Size(size)),
+                    _ => Err(err()),
+                }
+            }
+        }
+    }
+}
+
+impl LogRotation {
+    /// The period `time` falls in, `None` if the rotation is not time based.
+    fn period(&self, time: SystemTime) -> Option<u64> {
+        let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        match self {
+            LogRotation::Daily => Some(secs / (24 * 60 * 60)),
+            LogRotation::Hourly => Some(secs / (60 * 60)),
+            LogRotation::Size(_) => None,
+        }
+    }
+}
+
+/// Append-only log file that is rotated to `<name>.1`, `<name>.2`, ... once it grows too large or
+/// a new day or hour starts, deleting the oldest files.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    rotation: LogRotation,
+    /// the period the current file was written in, for time based rotation
+    period: Option<u64>,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, rotation: LogRotation, max_files: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        // a file left over from an earlier run is rotated if it belongs to an earlier period
+        let period = rotation.period(metadata.modified()?);
+        Ok(Self {
+            path,
+            file,
+            written: metadata.len(),
+            rotation,
+            period,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn should_rotate(&self, len: usize, now: SystemTime) -> bool {
+        if self.written == 0 {
+            return false;
+        }
+        match self.rotation {
+            LogRotation::Size(max_file_size) => self.written + len as u64 > max_file_size,
+            rotation => rotation.period(now) != self.period,
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let rotated_path = self.rotated_path(index);
+                if rotated_path.exists() {
+                    fs::rename(rotated_path, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = SystemTime::now();
+        if self.should_rotate(buf.len(), now) {
+            self.rotate()?;
+        }
+        self.period = self.rotation.period(now);
+        let len = self.file.write(buf)?;
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Handle to a `RotatingFile` that can be shared by the writers of the tracing subscriber.
+#[derive(Clone)]
+pub(crate) struct SharedRotatingFile(Arc<Mutex<RotatingFile>>);
+
+impl SharedRotatingFile {
+    pub(crate) fn new(file: RotatingFile) -> Self {
+        Self(Arc::new(Mutex::new(file)))
+    }
+}
+
+impl Write for SharedRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn log_file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_log_rotation_parsing() {
+        assert_eq!("daily".parse(), Ok(LogRotation::Daily));
+        assert_eq!("hourly".parse(), Ok(LogRotation::Hourly));
+        assert_eq!("size:4096".parse(), Ok(LogRotation::Size(4096)));
+        assert_eq!("size:100M".parse(), Ok(LogRotation::Size(100 << 20)));
+        for invalid in ["weekly", "size:", "size:0", "size:10T", "4096"] {
+            assert!(invalid.parse::<LogRotation>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_log_file_is_rotated_by_size() {
+        let tmp = TempDir::new("service-tests").expect("failed to create tempdir");
+        let mut file = RotatingFile::open(tmp.path().join("vault.log"), LogRotation::Size(10), 2).unwrap();
+
+        file.write_all(b"aaaaaaaa\n").unwrap();
+        assert_eq!(log_file_names(tmp.path()), vec!["vault.log"]);
+        // this one would exceed the threshold, so it starts a second file
+        file.write_all(b"bbbbbbbb\n").unwrap();
+        assert_eq!(log_file_names(tmp.path()), vec!["vault.log", "vault.log.1"]);
+        assert_eq!(fs::read_to_string(tmp.path().join("vault.log")).unwrap(), "bbbbbbbb\n");
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("vault.log.1")).unwrap(),
+            "aaaaaaaa\n"
+        );
+
+        // only the two most recent rotated files are kept
+        file.write_all(b"cccccccc\n").unwrap();
+        file.write_all(b"dddddddd\n").unwrap();
+        assert_eq!(
+            log_file_names(tmp.path()),
+            vec!["vault.log", "vault.log.1", "vault.log.2"]
+        );
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("vault.log.2")).unwrap(),
+            "bbbbbbbb\n"
+        );
+    }
+
+    #[test]
+    fn test_log_file_is_rotated_by_time() {
+        let tmp = TempDir::new("service-tests").expect("failed to create tempdir");
+        let mut file = RotatingFile::open(tmp.path().join("vault.log"), LogRotation::Hourly, 2).unwrap();
+        file.write_all(b"aaaaaaaa\n").unwrap();
+
+        let now = SystemTime::now();
+        assert!(!file.should_rotate(9, now));
+        assert!(file.should_rotate(9, now + std::time::Duration::from_secs(60 * 60)));
+    }
+}
@@ -0,0 +1,27 @@
+#![cfg(any(test, feature = "testing-utils"))]
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+/// In-memory writer for tracing subscribers, to check the log output in tests.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Everything that has been written so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
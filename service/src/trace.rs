@@ -1,4 +1,12 @@
-use tracing_subscriber::{fmt, layer::SubscriberExt, prelude::*, EnvFilter};
+use crate::{cli::LoggingFormat, log_file::SharedRotatingFile};
+use tracing::Subscriber;
+use tracing_subscriber::{
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    prelude::*,
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
 
 fn init_filter() -> EnvFilter {
     EnvFilter::try_from_default_env()
@@ -6,20 +14,73 @@ fn init_filter() -> EnvFilter {
         .unwrap()
 }
 
-pub fn init_json_subscriber() {
-    let fmt_layer = fmt::layer().json();
+/// Writes every event as a single line JSON object, with its timestamp, level, target and fields.
+fn json_layer<S, W>(make_writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: MakeWriter + Send + Sync + 'static,
+{
+    fmt::layer().json().with_writer(make_writer)
+}
 
-    let _ = tracing_subscriber::registry()
-        .with(init_filter())
-        .with(fmt_layer)
-        .try_init();
+pub fn init_json_subscriber() {
+    init_logging(&LoggingFormat::Json, None)
 }
 
 pub fn init_subscriber() {
-    let fmt_layer = fmt::layer();
+    init_logging(&LoggingFormat::Full, None)
+}
+
+/// Log to the console in the given format and, if set, also to `log_file`. The file gets the
+/// same format, but without terminal colors.
+pub(crate) fn init_logging(format: &LoggingFormat, log_file: Option<SharedRotatingFile>) {
+    let (full, json) = match format {
+        LoggingFormat::Full => (true, false),
+        LoggingFormat::Json => (false, true),
+    };
+    let full_file_layer = log_file
+        .clone()
+        .filter(|_| full)
+        .map(|file| fmt::layer().with_ansi(false).with_writer(move || file.clone()));
+    let json_file_layer = log_file.filter(|_| json).map(|file| json_layer(move || file.clone()));
 
     let _ = tracing_subscriber::registry()
         .with(init_filter())
-        .with(fmt_layer)
+        .with(full.then(fmt::layer))
+        .with(json.then(|| json_layer(std::io::stdout)))
+        .with(full_file_layer)
+        .with(json_file_layer)
         .try_init();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SharedBuffer;
+    use serde_json::Value;
+
+    #[test]
+    fn test_json_logs_are_parseable() {
+        let buffer = SharedBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(json_layer(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(redeem_id = 42, "Executing redeem");
+            tracing::warn!("Disconnected: {}", "connection reset");
+        });
+
+        let output = buffer.contents();
+        let lines: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["target"], module_path!());
+        assert_eq!(lines[0]["fields"]["message"], "Executing redeem");
+        assert_eq!(lines[0]["fields"]["redeem_id"], 42);
+
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["fields"]["message"], "Disconnected: connection reset");
+    }
+}
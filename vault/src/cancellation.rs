@@ -320,10 +320,11 @@ mod tests {
     use futures::channel::mpsc;
     use jsonrpc_core::serde_json::{Map, Value};
     use runtime::{
-        AccountId, AssetMetadata, BtcAddress, BtcPublicKey, CurrencyId, ErrorCode, InterBtcIssueRequest,
-        InterBtcReplaceRequest, IssueRequestStatus, RequestIssueEvent, StatusCode, Token, VaultId, DOT, IBTC,
+        AccountId, AssetMetadata, BtcAddress, BtcPublicKey, CurrencyId, ErrorCode, FixedU128, InterBtcIssueRequest,
+        InterBtcReplaceRequest, IssueRequestStatus, IssueRequestsPage, RequestIssueEvent, RuntimeVersion, StatusCode,
+        Token, VaultId, DOT, IBTC,
     };
-    use std::collections::BTreeSet;
+    use std::{collections::BTreeSet, time::Duration};
 
     macro_rules! assert_err {
         ($result:expr, $err:pat) => {{
@@ -347,6 +348,8 @@ mod tests {
             async fn get_vault_issue_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcIssueRequest)>, RuntimeError>;
             async fn get_issue_period(&self) -> Result<u32, RuntimeError>;
             async fn get_all_active_issues(&self) -> Result<Vec<(H256, InterBtcIssueRequest)>, RuntimeError>;
+            async fn list_issue_requests(&self, vault_id: &VaultId, page_size: u32, cursor: Option<Vec<u8>>) -> Result<IssueRequestsPage, RuntimeError>;
+            fn subscribe_issue_requests(&self) -> futures::stream::BoxStream<'static, Result<RequestIssueEvent, RuntimeError>>;
         }
 
 
@@ -355,6 +358,7 @@ mod tests {
             async fn request_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn withdraw_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn accept_replace(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, collateral: u128, btc_address: BtcAddress) -> Result<(), RuntimeError>;
+            async fn accept_replace_auto(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, buffer_percent: u32, btc_address: BtcAddress) -> Result<(), RuntimeError>;
             async fn execute_replace(&self, replace_id: H256, merkle_proof: &[u8], raw_tx: &[u8]) -> Result<(), RuntimeError>;
             async fn cancel_replace(&self, replace_id: H256) -> Result<(), RuntimeError>;
             async fn get_new_vault_replace_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcReplaceRequest)>, RuntimeError>;
@@ -362,17 +366,20 @@ mod tests {
             async fn get_replace_period(&self) -> Result<u32, RuntimeError>;
             async fn get_replace_request(&self, replace_id: H256) -> Result<InterBtcReplaceRequest, RuntimeError>;
             async fn get_replace_dust_amount(&self) -> Result<u128, RuntimeError>;
+            async fn request_replace_to_threshold(&self, vault_id: &VaultId, target_ratio: FixedU128) -> Result<Option<u128>, RuntimeError>;
         }
 
         #[async_trait]
         pub trait UtilFuncs {
             async fn get_current_chain_height(&self) -> Result<u32, RuntimeError>;
             async fn get_rpc_properties(&self) -> Result<Map<String, Value>, RuntimeError>;
+            async fn runtime_version(&self) -> Result<RuntimeVersion, RuntimeError>;
             fn get_native_currency_id(&self) -> CurrencyId;
             fn get_account_id(&self) -> &AccountId;
             fn is_this_vault(&self, vault_id: &VaultId) -> bool;
             async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, RuntimeError>;
             async fn get_foreign_asset_metadata(&self, id: u32) -> Result<AssetMetadata, RuntimeError>;
+            async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), RuntimeError>;
         }
 
         #[async_trait]
@@ -380,6 +387,7 @@ mod tests {
             async fn get_parachain_status(&self) -> Result<StatusCode, RuntimeError>;
             async fn get_error_codes(&self) -> Result<BTreeSet<ErrorCode>, RuntimeError>;
             async fn get_current_active_block_number(&self) -> Result<u32, RuntimeError>;
+            fn subscribe_best_blocks(&self) -> futures::stream::BoxStream<'static, Result<runtime::BlockNumber, RuntimeError>>;
         }
     }
 
@@ -0,0 +1,239 @@
+use crate::Error;
+use runtime::{
+    CollateralBalancesPallet, FixedU128, InterBtcIssueRequest, InterBtcRedeemRequest, InterBtcReplaceRequest,
+    InterBtcVault, IssuePallet, IssueRequestStatus, PrettyPrint, RedeemPallet, RedeemRequestStatus, ReplacePallet,
+    ReplaceRequestStatus, UtilFuncs, VaultId, VaultRegistryPallet, H256,
+};
+use serde_json::{json, Value};
+
+/// A vault with its balances in the collateral currency.
+#[derive(Debug, Clone)]
+pub struct VaultBalances {
+    pub vault: InterBtcVault,
+    pub free_collateral: u128,
+    pub reserved_collateral: u128,
+    /// `None` if nothing is issued, i.e. the collateralization is infinite
+    pub collateralization: Option<FixedU128>,
+}
+
+/// The open obligations of all vaults of an account, as dumped by `vault dump-state`.
+#[derive(Debug, Clone)]
+pub struct VaultState {
+    pub account_id: String,
+    pub vaults: Vec<VaultBalances>,
+    pub issues: Vec<(H256, InterBtcIssueRequest)>,
+    pub redeems: Vec<(H256, InterBtcRedeemRequest)>,
+    pub replaces: Vec<(H256, InterBtcReplaceRequest)>,
+}
+
+impl VaultState {
+    /// Query the state of the vaults of the account of `parachain_rpc`, keeping only pending requests.
+    pub async fn fetch<P>(parachain_rpc: &P) -> Result<Self, Error>
+    where
+        P: IssuePallet + RedeemPallet + ReplacePallet + VaultRegistryPallet + CollateralBalancesPallet + UtilFuncs,
+    {
+        let account_id = parachain_rpc.get_account_id().clone();
+        let vault_ids = parachain_rpc.get_vaults_by_account_id(&account_id).await?;
+
+        let mut vaults = vec![];
+        for vault_id in vault_ids.iter() {
+            let collateral_currency = vault_id.collateral_currency();
+            vaults.push(VaultBalances {
+                vault: parachain_rpc.get_vault(vault_id).await?,
+                free_collateral: parachain_rpc.get_free_balance(collateral_currency).await?,
+                reserved_collateral: parachain_rpc.get_reserved_balance(collateral_currency).await?,
+                collateralization: parachain_rpc.get_collateralization_ratio(vault_id).await?,
+            });
+        }
+
+        // the issue requests of all vaults of the account at once, rather than scanning the
+        // storage for each vault
+        let issues = parachain_rpc
+            .get_vault_issue_requests(account_id.clone())
+            .await?
+            .into_iter()
+            .filter(|(_, request)| request.status == IssueRequestStatus::Pending)
+            .collect();
+
+        let redeems = parachain_rpc
+            .get_vault_redeem_requests(account_id.clone())
+            .await?
+            .into_iter()
+            .filter(|(_, request)| request.status == RedeemRequestStatus::Pending)
+            .collect();
+
+        let mut replaces = parachain_rpc.get_old_vault_replace_requests(account_id.clone()).await?;
+        replaces.extend(parachain_rpc.get_new_vault_replace_requests(account_id.clone()).await?);
+        replaces.retain(|(_, request)| request.status == ReplaceRequestStatus::Pending);
+        // a replace between two vaults of the same account is listed twice
+        replaces.sort_by_key(|(replace_id, _)| *replace_id);
+        replaces.dedup_by_key(|(replace_id, _)| *replace_id);
+
+        Ok(Self {
+            account_id: account_id.pretty_print(),
+            vaults,
+            issues,
+            redeems,
+            replaces,
+        })
+    }
+
+    /// The state as a single JSON document. Amounts are strings, since they may not fit in a
+    /// JSON number.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "account_id": self.account_id,
+            "vaults": self.vaults.iter().map(|vault| json!({
+                "vault_id": vault_id_json(&vault.vault.id),
+                "status": format!("{:?}", vault.vault.status),
+                "issued_tokens": vault.vault.issued_tokens.to_string(),
+                "to_be_issued_tokens": vault.vault.to_be_issued_tokens.to_string(),
+                "to_be_redeemed_tokens": vault.vault.to_be_redeemed_tokens.to_string(),
+                "to_be_replaced_tokens": vault.vault.to_be_replaced_tokens.to_string(),
+                "free_collateral": vault.free_collateral.to_string(),
+                "reserved_collateral": vault.reserved_collateral.to_string(),
+                "collateralization": vault.collateralization.map(FixedU128::to_float),
+            })).collect::<Vec<_>>(),
+            "issues": self.issues.iter().map(|(issue_id, request)| json!({
+                "id": format!("{:?}", issue_id),
+                "vault_id": vault_id_json(&request.vault),
+                "amount": request.amount.to_string(),
+                "fee": request.fee.to_string(),
+                "griefing_collateral": request.griefing_collateral.to_string(),
+                "opentime": request.opentime,
+                "period": request.period,
+            })).collect::<Vec<_>>(),
+            "redeems": self.redeems.iter().map(|(redeem_id, request)| json!({
+                "id": format!("{:?}", redeem_id),
+                "vault_id": vault_id_json(&request.vault),
+                "amount_btc": request.amount_btc.to_string(),
+                "transfer_fee_btc": request.transfer_fee_btc.to_string(),
+                "opentime": request.opentime,
+                "period": request.period,
+            })).collect::<Vec<_>>(),
+            "replaces": self.replaces.iter().map(|(replace_id, request)| json!({
+                "id": format!("{:?}", replace_id),
+                "old_vault_id": vault_id_json(&request.old_vault),
+                "new_vault_id": vault_id_json(&request.new_vault),
+                "amount": request.amount.to_string(),
+                "accept_time": request.accept_time,
+                "period": request.period,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn vault_id_json(vault_id: &VaultId) -> Value {
+    Value::String(vault_id.pretty_print())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime::{AccountId, BtcPublicKey, Token, VaultStatus, DOT, IBTC};
+
+    fn dummy_vault_id() -> VaultId {
+        VaultId::new(AccountId::new([1u8; 32]), Token(DOT), Token(IBTC))
+    }
+
+    fn dummy_issue_request() -> InterBtcIssueRequest {
+        InterBtcIssueRequest {
+            amount: 100,
+            btc_address: Default::default(),
+            btc_height: Default::default(),
+            fee: 1,
+            griefing_collateral: 10,
+            opentime: 5,
+            period: 50,
+            requester: AccountId::new([2u8; 32]),
+            btc_public_key: BtcPublicKey { 0: [0; 33] },
+            status: IssueRequestStatus::Pending,
+            vault: dummy_vault_id(),
+        }
+    }
+
+    fn dummy_redeem_request() -> InterBtcRedeemRequest {
+        InterBtcRedeemRequest {
+            amount_btc: 200,
+            btc_address: Default::default(),
+            btc_height: Default::default(),
+            fee: Default::default(),
+            transfer_fee_btc: 2,
+            premium: Default::default(),
+            opentime: 6,
+            period: 60,
+            redeemer: AccountId::new([2u8; 32]),
+            status: RedeemRequestStatus::Pending,
+            vault: dummy_vault_id(),
+        }
+    }
+
+    fn dummy_replace_request() -> InterBtcReplaceRequest {
+        InterBtcReplaceRequest {
+            old_vault: dummy_vault_id(),
+            new_vault: VaultId::new(AccountId::new([3u8; 32]), Token(DOT), Token(IBTC)),
+            accept_time: 7,
+            collateral: Default::default(),
+            btc_address: Default::default(),
+            griefing_collateral: Default::default(),
+            amount: 300,
+            period: 70,
+            btc_height: Default::default(),
+            status: ReplaceRequestStatus::Pending,
+        }
+    }
+
+    fn dummy_vault() -> InterBtcVault {
+        InterBtcVault {
+            id: dummy_vault_id(),
+            status: VaultStatus::Active(true),
+            banned_until: None,
+            secure_collateral_threshold: None,
+            to_be_issued_tokens: 100,
+            issued_tokens: 1_000,
+            to_be_redeemed_tokens: 200,
+            to_be_replaced_tokens: 300,
+            replace_collateral: 0,
+            active_replace_collateral: 0,
+            liquidated_collateral: 0,
+        }
+    }
+
+    #[test]
+    fn test_vault_state_json_contains_all_sections() {
+        let state = VaultState {
+            account_id: AccountId::new([1u8; 32]).pretty_print(),
+            vaults: vec![VaultBalances {
+                vault: dummy_vault(),
+                free_collateral: u128::MAX,
+                reserved_collateral: 5_000,
+                collateralization: Some(FixedU128::from_float(1.5)),
+            }],
+            issues: vec![(H256::from_slice(&[1; 32]), dummy_issue_request())],
+            redeems: vec![(H256::from_slice(&[2; 32]), dummy_redeem_request())],
+            replaces: vec![(H256::from_slice(&[3; 32]), dummy_replace_request())],
+        };
+        let json = state.to_json();
+
+        assert_eq!(json["account_id"], AccountId::new([1u8; 32]).pretty_print());
+        let vault = &json["vaults"][0];
+        assert_eq!(vault["vault_id"], dummy_vault_id().pretty_print());
+        assert_eq!(vault["issued_tokens"], "1000");
+        // amounts beyond the range of JSON numbers are preserved
+        assert_eq!(vault["free_collateral"], u128::MAX.to_string());
+        assert_eq!(vault["reserved_collateral"], "5000");
+        assert_eq!(vault["collateralization"], 1.5);
+
+        assert_eq!(json["issues"][0]["id"], format!("{:?}", H256::from_slice(&[1; 32])));
+        assert_eq!(json["issues"][0]["amount"], "100");
+        assert_eq!(json["redeems"][0]["amount_btc"], "200");
+        assert_eq!(json["replaces"][0]["amount"], "300");
+        assert_eq!(
+            json["replaces"][0]["new_vault_id"],
+            VaultId::new(AccountId::new([3u8; 32]), Token(DOT), Token(IBTC)).pretty_print()
+        );
+        for section in ["issues", "redeems", "replaces", "vaults"] {
+            assert_eq!(json[section].as_array().unwrap().len(), 1);
+        }
+    }
+}
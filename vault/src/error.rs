@@ -1,7 +1,7 @@
-use bitcoin::Error as BitcoinError;
+use bitcoin::{Error as BitcoinError, Txid};
 use jsonrpc_core_client::RpcError;
 use parity_scale_codec::Error as CodecError;
-use runtime::Error as RuntimeError;
+use runtime::{Error as RuntimeError, H256};
 use thiserror::Error;
 use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 
@@ -23,6 +23,10 @@ pub enum Error {
     FaucetUrlNotSet,
     #[error("Faucet allowance for `{0}` not set")]
     FaucetAllowanceNotSet(String),
+    #[error("Payment {0} was broadcast but is unknown to the bitcoin wallet")]
+    PaymentNotInWallet(Txid),
+    #[error("Request {0:?} is already being paid")]
+    PaymentInProgress(H256),
 
     #[error("RPC error: {0}")]
     RpcError(#[from] RpcError),
@@ -34,6 +38,8 @@ pub enum Error {
     CodecError(#[from] CodecError),
     #[error("BroadcastStreamRecvError: {0}")]
     BroadcastStreamRecvError(#[from] BroadcastStreamRecvError),
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
 }
 
 impl From<Error> for service::Error<Error> {
@@ -1,7 +1,13 @@
-use crate::{error::Error, metrics::update_bitcoin_metrics, system::VaultData, VaultIdManager, YIELD_RATE};
+use crate::{
+    error::Error,
+    metrics::{update_bitcoin_metrics, PendingPayout},
+    payments::{PaymentClaim, PaymentRecord},
+    system::VaultData,
+    VaultIdManager, YIELD_RATE,
+};
 use bitcoin::{
-    Error as BitcoinError, SatPerVbyte, Transaction, TransactionExt, TransactionMetadata, Txid,
-    BLOCK_INTERVAL as BITCOIN_BLOCK_INTERVAL,
+    Error as BitcoinError, GetTransactionResultDetailCategory, SatPerVbyte, Transaction, TransactionExt,
+    TransactionMetadata, Txid, BLOCK_INTERVAL as BITCOIN_BLOCK_INTERVAL,
 };
 use futures::{future::Either, stream::StreamExt, try_join, TryStreamExt};
 use governor::RateLimiter;
@@ -12,11 +18,21 @@ use runtime::{
     H256,
 };
 use service::{spawn_cancelable, DynBitcoinCoreApi, Error as ServiceError, ShutdownSender};
-use std::{collections::HashMap, convert::TryInto, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::time::sleep;
 use tokio_stream::wrappers::BroadcastStream;
+use tracing::Instrument;
 
 const ON_FORK_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// How long a payout may take to show up in the mempool before its broadcast is considered failed.
+const MEMPOOL_TIMEOUT: Duration = Duration::from_secs(60);
+/// How much earlier than its payment record a payout may appear to have been made in the wallet,
+/// since bitcoind may run on a host whose clock differs.
+const WALLET_CLOCK_MARGIN: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Debug, Clone, PartialEq)]
 struct Deadline {
@@ -141,6 +157,14 @@ impl Request {
         })
     }
 
+    /// Span to process the request in, with the same fields as when it is received from the parachain
+    fn span(&self) -> tracing::Span {
+        match self.request_type {
+            RequestType::Redeem => tracing::info_span!("redeem", redeem_id = ?self.hash),
+            RequestType::Replace => tracing::info_span!("replace", replace_id = ?self.hash),
+        }
+    }
+
     /// returns the fee rate in sat/vByte
     async fn get_fee_rate<P: OraclePallet + Send + Sync>(&self, parachain_rpc: &P) -> Result<SatPerVbyte, Error> {
         let fee_rate: FixedU128 = parachain_rpc.get_bitcoin_fees().await?;
@@ -152,6 +176,21 @@ impl Request {
         Ok(SatPerVbyte(rate))
     }
 
+    /// The fee rate to pay the request with: the estimate of our own node if configured, and the
+    /// oracle's fee rate if not or if the node fails to estimate it.
+    async fn payout_fee_rate<P: OraclePallet + Send + Sync>(
+        &self,
+        parachain_rpc: &P,
+        btc_rpc: &DynBitcoinCoreApi,
+    ) -> Result<SatPerVbyte, Error> {
+        match btc_rpc.estimate_fee_rate().await {
+            Ok(Some(fee_rate)) => return Ok(fee_rate),
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Failed to estimate the fee rate, using the oracle's: {}", err),
+        }
+        self.get_fee_rate(parachain_rpc).await
+    }
+
     /// Makes the bitcoin transfer and executes the request
     pub async fn pay_and_execute<
         P: ReplacePallet
@@ -170,6 +209,7 @@ impl Request {
         vault: VaultData,
         num_confirmations: u32,
         auto_rbf: bool,
+        shutdown_tx: &ShutdownSender,
     ) -> Result<(), Error> {
         // ensure the deadline has not expired yet
         if let Some(ref deadline) = self.deadline {
@@ -180,17 +220,31 @@ impl Request {
             }
         }
 
+        // held until the request is executed, so that no other task pays it in the meantime
+        let payment_claim = vault
+            .payment_journal
+            .as_ref()
+            .map(|journal| journal.claim(self.hash))
+            .transpose()?;
         let tx_metadata = self
             .transfer_btc(
                 &parachain_rpc,
                 &vault.btc_rpc,
+                payment_claim.as_ref(),
                 num_confirmations,
                 self.vault_id.clone(),
                 auto_rbf,
             )
             .await?;
         let _ = update_bitcoin_metrics(&vault, tx_metadata.fee, self.fee_budget).await;
-        self.execute(parachain_rpc, tx_metadata).await
+        self.execute(parachain_rpc, tx_metadata, shutdown_tx).await?;
+
+        if let Some(claim) = &payment_claim {
+            if let Err(err) = claim.journal().remove(self.hash) {
+                tracing::warn!("Failed to remove the payment record: {}", err);
+            }
+        }
+        Ok(())
     }
 
     /// Make a bitcoin transfer to fulfil the request
@@ -206,11 +260,32 @@ impl Request {
         &self,
         parachain_rpc: &P,
         btc_rpc: &DynBitcoinCoreApi,
+        payment_claim: Option<&PaymentClaim>,
         num_confirmations: u32,
         vault_id: VaultId,
         auto_rbf: bool,
     ) -> Result<TransactionMetadata, Error> {
-        let fee_rate = self.get_fee_rate(parachain_rpc).await?;
+        // the payout may have been broadcast before a restart, in which case the wallet knows it
+        if let Some(claim) = payment_claim.filter(|claim| !claim.is_new()) {
+            let journal = claim.journal();
+            if let Some(record) = journal.get(self.hash)? {
+                tracing::info!("Found payment record `{}`, checking the wallet...", record);
+                let since = journal.recorded_at(self.hash)?.unwrap_or(UNIX_EPOCH);
+                match (find_wallet_payment(btc_rpc, self.hash, since).await?, record) {
+                    (Some(txid), _) => {
+                        tracing::info!("Payout {txid} was already broadcast, not paying again");
+                        return self
+                            .wait_for_inclusion(parachain_rpc, btc_rpc, num_confirmations, txid, auto_rbf)
+                            .await;
+                    }
+                    (None, PaymentRecord::Broadcast(txid)) => return Err(Error::PaymentNotInWallet(txid)),
+                    // the broadcast was interrupted before the wallet saw the payout
+                    (None, PaymentRecord::Broadcasting) => {}
+                }
+            }
+        }
+
+        let fee_rate = self.payout_fee_rate(parachain_rpc, btc_rpc).await?;
 
         tracing::debug!("Using fee_rate = {} sat/vByte", fee_rate.0);
 
@@ -224,6 +299,20 @@ impl Request {
                 Some(self.hash),
             )
             .await?;
+        if let Some(claim) = payment_claim {
+            // the `Broadcasting` record still prevents paying twice
+            if let Err(err) = claim.journal().record(self.hash, PaymentRecord::Broadcast(txid)) {
+                tracing::warn!("Failed to record payout {txid}: {}", err);
+            }
+        }
+        // report dropped broadcasts now rather than after waiting for confirmations. The payout has
+        // been broadcast either way, so failing the request here could only get it paid twice
+        if let Err(err) = btc_rpc.wait_for_transaction_in_mempool(txid, MEMPOOL_TIMEOUT).await {
+            tracing::warn!(
+                "Payout {txid} was not seen in the mempool, still waiting for it: {}",
+                err
+            );
+        }
 
         self.wait_for_inclusion(parachain_rpc, btc_rpc, num_confirmations, txid, auto_rbf)
             .await
@@ -247,6 +336,7 @@ impl Request {
         mut txid: Txid,
         auto_rbf: bool,
     ) -> Result<TransactionMetadata, Error> {
+        let mut pending_payout = Some(PendingPayout::track(self.hash));
         'outer: loop {
             tracing::info!("Awaiting bitcoin confirmations for {txid}");
 
@@ -353,6 +443,8 @@ impl Request {
                 }
             };
 
+            // confirmed on bitcoin, so no longer stuck
+            pending_payout.take();
             tracing::info!("Awaiting parachain confirmations...");
 
             match parachain_rpc
@@ -374,17 +466,22 @@ impl Request {
         }
     }
 
-    /// Executes the request. Upon failure it will retry
+    /// Executes the request. Upon failure it will retry until `shutdown_tx` signals a shutdown
     async fn execute<P: ReplacePallet + RedeemPallet>(
         &self,
         parachain_rpc: P,
         tx_metadata: TransactionMetadata,
+        shutdown_tx: &ShutdownSender,
     ) -> Result<(), Error> {
         // select the execute function based on request_type
         let execute = match self.request_type {
             RequestType::Redeem => RedeemPallet::execute_redeem,
             RequestType::Replace => ReplacePallet::execute_replace,
         };
+        let operation = match self.request_type {
+            RequestType::Redeem => "Redeem.execute_redeem",
+            RequestType::Replace => "Replace.execute_replace",
+        };
 
         match (self.fee_budget, tx_metadata.fee.map(|x| x.abs().to_sat() as u128)) {
             (Some(budget), Some(actual)) if budget < actual => {
@@ -397,9 +494,13 @@ impl Request {
             _ => {}
         }
 
-        // Retry until success or timeout, explicitly handle the cases
+        // Retry until success, timeout or shutdown, explicitly handle the cases
         // where the redeem has expired or the rpc has disconnected
-        runtime::notify_retry(
+        let mut shutdown_rx = shutdown_tx.subscribe_with_id(format!("execute {:?}", self.hash));
+        runtime::notify_retry_until_shutdown(
+            operation,
+            &runtime::RetryConfig::default(),
+            &mut shutdown_rx,
             || (execute)(&parachain_rpc, self.hash, &tx_metadata.proof, &tx_metadata.raw_tx),
             |result| async {
                 match result {
@@ -455,6 +556,16 @@ pub async fn execute_open_requests(
         .map(|x| (x.hash, x))
         .collect::<HashMap<_, _>>();
 
+    // the payouts of requests that were completed while the vault was down are no longer needed.
+    // The records of the open requests are not reconciled with the wallet here: every open request
+    // that is not found to be paid in the bitcoin chain below is paid by `pay_and_execute`, which
+    // checks the wallet for its recorded payout first
+    if let Some(journal) = vault_id_manager.payment_journal() {
+        if let Err(err) = journal.retain(|request_id| open_requests.contains_key(request_id)) {
+            tracing::warn!("Failed to prune the payment records: {}", err);
+        }
+    }
+
     // find the height of bitcoin chain corresponding to the earliest btc_height
     let btc_start_height = match open_requests
         .iter()
@@ -509,7 +620,9 @@ pub async fn execute_open_requests(
             // make copies of the variables we move into the task
             let parachain_rpc = parachain_rpc.clone();
             let btc_rpc = vault_id_manager.clone();
-            spawn_cancelable(shutdown_tx.subscribe(), async move {
+            let shutdown_tx = shutdown_tx.clone();
+            let span = request.span();
+            let task = async move {
                 let btc_rpc = match btc_rpc.get_bitcoin_rpc(&request.vault_id).await {
                     Some(x) => x,
                     None => {
@@ -526,7 +639,7 @@ pub async fn execute_open_requests(
                     .await
                 {
                     Ok(tx_metadata) => {
-                        if let Err(e) = request.execute(parachain_rpc.clone(), tx_metadata).await {
+                        if let Err(e) = request.execute(parachain_rpc.clone(), tx_metadata, &shutdown_tx).await {
                             tracing::error!("Failed to execute request #{}: {}", request.hash, e);
                         }
                     }
@@ -534,7 +647,12 @@ pub async fn execute_open_requests(
                         tracing::error!("Error while waiting for inclusion for request #{}: {}", request.hash, e);
                     }
                 }
-            });
+            };
+            let request_limiter = vault_id_manager.request_limiter().clone();
+            spawn_cancelable(
+                shutdown_tx.subscribe(),
+                async move { request_limiter.run(task).await }.instrument(span),
+            );
         }
     }
 
@@ -547,7 +665,10 @@ pub async fn execute_open_requests(
         // make copies of the variables we move into the task
         let parachain_rpc = parachain_rpc.clone();
         let vault_id_manager = vault_id_manager.clone();
-        spawn_cancelable(shutdown_tx.subscribe(), async move {
+        let request_limiter = vault_id_manager.request_limiter().clone();
+        let shutdown_tx = shutdown_tx.clone();
+        let span = request.span();
+        let task = async move {
             let vault = match vault_id_manager.get_vault(&request.vault_id).await {
                 Some(x) => x,
                 None => {
@@ -566,7 +687,7 @@ pub async fn execute_open_requests(
             );
 
             match request
-                .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf)
+                .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf, &shutdown_tx)
                 .await
             {
                 Ok(_) => tracing::info!(
@@ -581,12 +702,43 @@ pub async fn execute_open_requests(
                     e
                 ),
             }
-        });
+        };
+        spawn_cancelable(
+            shutdown_tx.subscribe(),
+            async move { request_limiter.run(task).await }.instrument(span),
+        );
     }
 
     Ok(())
 }
 
+/// The payout of the request `request_id` in the wallet of `btc_rpc`, if any, among the transactions
+/// made `since` its payment record was written. Transactions that conflict with the chain, e.g.
+/// since they were replaced by a fee bump, are skipped.
+async fn find_wallet_payment(
+    btc_rpc: &DynBitcoinCoreApi,
+    request_id: H256,
+    since: SystemTime,
+) -> Result<Option<Txid>, Error> {
+    let min_time = since
+        .checked_sub(WALLET_CLOCK_MARGIN)
+        .and_then(|min_time| min_time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |min_time| min_time.as_secs());
+    for entry in btc_rpc.list_transactions(None).await? {
+        if entry.detail.category != GetTransactionResultDetailCategory::Send
+            || entry.info.confirmations < 0
+            || entry.info.time < min_time
+        {
+            continue;
+        }
+        let tx = btc_rpc.get_transaction(&entry.info.txid, entry.info.blockhash).await?;
+        if tx.get_op_return() == Some(request_id) {
+            return Ok(Some(entry.info.txid));
+        }
+    }
+    Ok(None)
+}
+
 /// Get the Request from the hashmap that the given Transaction satisfies, based
 /// on the OP_RETURN and the amount of btc that is transfered to the address
 fn get_request_for_btc_tx(tx: &Transaction, hash_map: &HashMap<H256, Request>) -> Option<Request> {
@@ -603,19 +755,21 @@ fn get_request_for_btc_tx(tx: &Transaction, hash_map: &HashMap<H256, Request>) -
 #[cfg(all(test, feature = "parachain-metadata-kintsugi-testnet"))]
 mod tests {
     use super::*;
-    use crate::metrics::PerCurrencyMetrics;
+    use crate::{metrics::PerCurrencyMetrics, payments::PaymentJournal};
     use async_trait::async_trait;
     use bitcoin::{
-        json, Address, Amount, BitcoinCoreApi, Block, BlockHash, BlockHeader, Error as BitcoinError, Hash, Network,
-        PrivateKey, PublicKey, Transaction, TransactionMetadata, Txid,
+        json::{self, bitcoin::PackedLockTime},
+        opcodes, Address, Amount, BitcoinCoreApi, Block, BlockHash, BlockHeader, Builder, Error as BitcoinError, Hash,
+        Network, PrivateKey, PublicKey, SignedAmount, Transaction, TransactionMetadata, TxOut, Txid,
     };
     use jsonrpc_core::serde_json::{Map, Value};
     use runtime::{
         sp_core::H160, AccountId, AssetMetadata, BitcoinBlockHeight, BlockNumber, BtcPublicKey, CurrencyId,
-        Error as RuntimeError, ErrorCode, FeeRateUpdateReceiver, InterBtcRichBlockHeader, InterBtcVault, OracleKey,
-        RawBlockHeader, StatusCode, Token, DOT, IBTC,
+        Error as RuntimeError, ErrorCode, FeeRateUpdateReceiver, HealthSummary, InterBtcRichBlockHeader, InterBtcVault,
+        LiquidationPrice, OracleKey, RawBlockHeader, RuntimeVersion, StatusCode, Thresholds, Token, DOT, IBTC,
     };
     use std::{collections::BTreeSet, sync::Arc};
+    use tempdir::TempDir;
 
     macro_rules! assert_ok {
         ( $x:expr $(,)? ) => {
@@ -637,11 +791,13 @@ mod tests {
         pub trait UtilFuncs {
             async fn get_current_chain_height(&self) -> Result<u32, RuntimeError>;
             async fn get_rpc_properties(&self) -> Result<Map<String, Value>, RuntimeError>;
+            async fn runtime_version(&self) -> Result<RuntimeVersion, RuntimeError>;
             fn get_native_currency_id(&self) -> CurrencyId;
             fn get_account_id(&self) -> &AccountId;
             fn is_this_vault(&self, vault_id: &VaultId) -> bool;
             async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, RuntimeError>;
             async fn get_foreign_asset_metadata(&self, id: u32) -> Result<AssetMetadata, RuntimeError>;
+            async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), RuntimeError>;
         }
         #[async_trait]
         pub trait VaultRegistryPallet {
@@ -651,12 +807,17 @@ mod tests {
             async fn register_vault(&self, vault_id: &VaultId, collateral: u128) -> Result<(), RuntimeError>;
             async fn deposit_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn withdraw_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
+            async fn withdraw_collateral_safe(&self, vault_id: &VaultId, amount: u128, min_ratio: FixedU128) -> Result<(), RuntimeError>;
             async fn get_public_key(&self) -> Result<Option<BtcPublicKey>, RuntimeError>;
             async fn register_public_key(&self, public_key: BtcPublicKey) -> Result<(), RuntimeError>;
             async fn get_required_collateral_for_wrapped(&self, amount_btc: u128, collateral_currency: CurrencyId) -> Result<u128, RuntimeError>;
             async fn get_required_collateral_for_vault(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
             async fn get_vault_total_collateral(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
             async fn get_collateralization_from_vault(&self, vault_id: VaultId, only_issued: bool) -> Result<u128, RuntimeError>;
+            async fn get_collateralization_ratio(&self, vault_id: &VaultId) -> Result<Option<FixedU128>, RuntimeError>;
+            async fn health_summary(&self) -> Result<HealthSummary, RuntimeError>;
+            async fn liquidation_price(&self, collateral_currency: CurrencyId) -> Result<Option<LiquidationPrice>, RuntimeError>;
+            async fn thresholds(&self, collateral_currency: CurrencyId) -> Result<Thresholds, RuntimeError>;
             async fn set_current_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
             async fn set_pending_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
         }
@@ -669,6 +830,7 @@ mod tests {
             async fn get_redeem_request(&self, redeem_id: H256) -> Result<InterBtcRedeemRequest, RuntimeError>;
             async fn get_vault_redeem_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcRedeemRequest)>, RuntimeError>;
             async fn get_redeem_period(&self) -> Result<BlockNumber, RuntimeError>;
+            async fn redeems_nearing_deadline(&self, vault_id: &VaultId, within: Duration) -> Result<Vec<(H256, InterBtcRedeemRequest, Duration)>, RuntimeError>;
         }
 
         #[async_trait]
@@ -676,6 +838,7 @@ mod tests {
             async fn request_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn withdraw_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn accept_replace(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, collateral: u128, btc_address: BtcAddress) -> Result<(), RuntimeError>;
+            async fn accept_replace_auto(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, buffer_percent: u32, btc_address: BtcAddress) -> Result<(), RuntimeError>;
             async fn execute_replace(&self, replace_id: H256, merkle_proof: &[u8], raw_tx: &[u8]) -> Result<(), RuntimeError>;
             async fn cancel_replace(&self, replace_id: H256) -> Result<(), RuntimeError>;
             async fn get_new_vault_replace_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcReplaceRequest)>, RuntimeError>;
@@ -683,6 +846,7 @@ mod tests {
             async fn get_replace_period(&self) -> Result<u32, RuntimeError>;
             async fn get_replace_request(&self, replace_id: H256) -> Result<InterBtcReplaceRequest, RuntimeError>;
             async fn get_replace_dust_amount(&self) -> Result<u128, RuntimeError>;
+            async fn request_replace_to_threshold(&self, vault_id: &VaultId, target_ratio: FixedU128) -> Result<Option<u128>, RuntimeError>;
         }
 
         #[async_trait]
@@ -695,6 +859,7 @@ mod tests {
             async fn get_parachain_confirmations(&self) -> Result<BlockNumber, RuntimeError>;
             async fn wait_for_block_in_relay(&self, block_hash: H256Le, btc_confirmations: Option<BlockNumber>) -> Result<(), RuntimeError>;
             async fn verify_block_header_inclusion(&self, block_hash: H256Le) -> Result<(), RuntimeError>;
+            async fn wait_for_confirmations(&self, block_hash: H256Le, required_confirmations: Option<u32>, timeout_after: std::time::Duration) -> Result<(), RuntimeError>;
             async fn initialize_btc_relay(&self, header: RawBlockHeader, height: BitcoinBlockHeight) -> Result<(), RuntimeError>;
             async fn store_block_header(&self, header: RawBlockHeader) -> Result<(), RuntimeError>;
             async fn store_block_headers(&self, headers: Vec<RawBlockHeader>) -> Result<(), RuntimeError>;
@@ -705,16 +870,19 @@ mod tests {
             async fn get_parachain_status(&self) -> Result<StatusCode, RuntimeError>;
             async fn get_error_codes(&self) -> Result<BTreeSet<ErrorCode>, RuntimeError>;
             async fn get_current_active_block_number(&self) -> Result<u32, RuntimeError>;
+            fn subscribe_best_blocks(&self) -> futures::stream::BoxStream<'static, Result<runtime::BlockNumber, RuntimeError>>;
         }
 
         #[async_trait]
         pub trait OraclePallet {
             async fn get_exchange_rate(&self, currency_id: CurrencyId) -> Result<FixedU128, RuntimeError>;
+            async fn get_exchange_rate_at(&self, currency_id: CurrencyId, block: runtime::BlockId) -> Result<Option<FixedU128>, RuntimeError>;
             async fn feed_values(&self, values: Vec<(OracleKey, FixedU128)>) -> Result<(), RuntimeError>;
             async fn set_bitcoin_fees(&self, value: FixedU128) -> Result<(), RuntimeError>;
             async fn get_bitcoin_fees(&self) -> Result<FixedU128, RuntimeError>;
             async fn wrapped_to_collateral(&self, amount: u128, currency_id: CurrencyId) -> Result<u128, RuntimeError>;
             async fn collateral_to_wrapped(&self, amount: u128, currency_id: CurrencyId) -> Result<u128, RuntimeError>;
+            async fn convert(&self, amount: u128, from: CurrencyId, to: CurrencyId) -> Result<u128, RuntimeError>;
             async fn has_updated(&self, key: &OracleKey) -> Result<bool, RuntimeError>;
             fn on_fee_rate_change(&self) -> FeeRateUpdateReceiver;
         }
@@ -734,8 +902,8 @@ mod tests {
         trait BitcoinCoreApi {
             fn network(&self) -> Network;
             async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, BitcoinError>;
-            fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
-            fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
+            async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
+            async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
             async fn get_block_count(&self) -> Result<u64, BitcoinError>;
             async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, BitcoinError>;
             async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, BitcoinError>;
@@ -744,8 +912,8 @@ mod tests {
             async fn get_pruned_height(&self) -> Result<u64, BitcoinError>;
             async fn get_new_address(&self) -> Result<Address, BitcoinError>;
             async fn get_new_public_key(&self) -> Result<PublicKey, BitcoinError>;
-            fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
-            fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
+            async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
+            async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
             async fn add_new_deposit_key(&self, public_key: PublicKey, secret_key: Vec<u8>) -> Result<(), BitcoinError>;
             async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinError>;
             async fn get_block(&self, hash: &BlockHash) -> Result<Block, BitcoinError>;
@@ -757,7 +925,7 @@ mod tests {
             async fn create_or_load_wallet(&self) -> Result<(), BitcoinError>;
             async fn rescan_blockchain(&self, start_height: usize, end_height: usize) -> Result<(), BitcoinError>;
             async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), BitcoinError>;
-            fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
+            async fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
             async fn bump_fee(
                 &self,
                 txid: &Txid,
@@ -766,6 +934,8 @@ mod tests {
             ) -> Result<Txid, BitcoinError>;
             async fn is_in_mempool(&self, txid: Txid) -> Result<bool, BitcoinError>;
             async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, BitcoinError>;
+            async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, BitcoinError>;
+            async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), BitcoinError>;
         }
     }
 
@@ -859,9 +1029,13 @@ mod tests {
             mock_bitcoin
                 .expect_get_block_count()
                 .returning(move || Ok(current_bitcoin_height as u64));
+            mock_bitcoin.expect_estimate_fee_rate().returning(|| Ok(None));
             mock_bitcoin
                 .expect_create_and_send_transaction()
                 .returning(|_, _, _, _| Ok(Txid::all_zeros()));
+            mock_bitcoin
+                .expect_wait_for_transaction_in_mempool()
+                .returning(|_, _| Ok(()));
             mock_bitcoin.expect_wait_for_transaction_metadata().returning(|_, _| {
                 Ok(TransactionMetadata {
                     txid: Txid::all_zeros(),
@@ -894,6 +1068,7 @@ mod tests {
                 vault_id: dummy_vault_id(),
                 btc_rpc,
                 metrics: PerCurrencyMetrics::dummy(),
+                payment_journal: None,
             };
 
             (request, parachain_rpc, vault_data)
@@ -903,21 +1078,33 @@ mod tests {
         async fn should_pay_and_execute_redeem_if_neither_parachain_nor_bitcoin_deadlines_expired() {
             let (request, parachain_rpc, btc_rpc) = should_pay_and_execute_with_deadlines(100, 50, 100, 50);
 
-            assert_ok!(request.pay_and_execute(parachain_rpc, btc_rpc, 6, true).await);
+            assert_ok!(
+                request
+                    .pay_and_execute(parachain_rpc, btc_rpc, 6, true, &ShutdownSender::new())
+                    .await
+            );
         }
 
         #[tokio::test]
         async fn should_pay_and_execute_redeem_if_only_parachain_deadline_expired() {
             let (request, parachain_rpc, btc_rpc) = should_pay_and_execute_with_deadlines(100, 101, 100, 50);
 
-            assert_ok!(request.pay_and_execute(parachain_rpc, btc_rpc, 6, true).await);
+            assert_ok!(
+                request
+                    .pay_and_execute(parachain_rpc, btc_rpc, 6, true, &ShutdownSender::new())
+                    .await
+            );
         }
 
         #[tokio::test]
         async fn should_pay_and_execute_redeem_if_only_bitcoin_deadline_expired() {
             let (request, parachain_rpc, btc_rpc) = should_pay_and_execute_with_deadlines(100, 50, 100, 101);
 
-            assert_ok!(request.pay_and_execute(parachain_rpc, btc_rpc, 6, true).await);
+            assert_ok!(
+                request
+                    .pay_and_execute(parachain_rpc, btc_rpc, 6, true, &ShutdownSender::new())
+                    .await
+            );
         }
 
         #[tokio::test]
@@ -925,12 +1112,45 @@ mod tests {
             let (request, parachain_rpc, btc_rpc) = should_pay_and_execute_with_deadlines(100, 101, 100, 101);
 
             assert_err!(
-                request.pay_and_execute(parachain_rpc, btc_rpc, 6, true).await,
+                request
+                    .pay_and_execute(parachain_rpc, btc_rpc, 6, true, &ShutdownSender::new())
+                    .await,
                 Error::DeadlineExpired
             );
         }
     }
 
+    #[tokio::test]
+    async fn should_fall_back_to_oracle_fee_rate() {
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_get_bitcoin_fees()
+            .returning(|| Ok(FixedU128::from(7)));
+        let request = Request {
+            amount: 100,
+            deadline: None,
+            btc_address: BtcAddress::P2SH(H160::from_slice(&[1; 20])),
+            hash: H256::from_slice(&[1; 32]),
+            btc_height: None,
+            request_type: RequestType::Redeem,
+            vault_id: dummy_vault_id(),
+            fee_budget: None,
+        };
+        let fee_rate = |estimate: fn() -> Result<Option<SatPerVbyte>, BitcoinError>| {
+            let mut mock_bitcoin = MockBitcoin::default();
+            mock_bitcoin.expect_estimate_fee_rate().returning(estimate);
+            let btc_rpc: DynBitcoinCoreApi = Arc::new(mock_bitcoin);
+            let request = &request;
+            let parachain_rpc = &parachain_rpc;
+            async move { request.payout_fee_rate(parachain_rpc, &btc_rpc).await.unwrap() }
+        };
+
+        // the node's estimate is preferred
+        assert_eq!(fee_rate(|| Ok(Some(SatPerVbyte(3)))).await, SatPerVbyte(3));
+        assert_eq!(fee_rate(|| Ok(None)).await, SatPerVbyte(7));
+        assert_eq!(fee_rate(|| Err(BitcoinError::ConnectionRefused)).await, SatPerVbyte(7));
+    }
+
     #[tokio::test]
     async fn should_not_pay_after_expiry() {
         let mut parachain_rpc = MockProvider::default();
@@ -961,10 +1181,13 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         assert_err!(
-            request.pay_and_execute(parachain_rpc, vault_data, 6, true).await,
+            request
+                .pay_and_execute(parachain_rpc, vault_data, 6, true, &ShutdownSender::new())
+                .await,
             Error::DeadlineExpired
         );
     }
@@ -993,9 +1216,13 @@ mod tests {
 
         let mut mock_bitcoin = MockBitcoin::default();
         mock_bitcoin.expect_network().returning(|| Network::Regtest);
+        mock_bitcoin.expect_estimate_fee_rate().returning(|| Ok(None));
         mock_bitcoin
             .expect_create_and_send_transaction()
             .returning(|_, _, _, _| Ok(Txid::all_zeros()));
+        mock_bitcoin
+            .expect_wait_for_transaction_in_mempool()
+            .returning(|_, _| Ok(()));
         mock_bitcoin.expect_wait_for_transaction_metadata().returning(|_, _| {
             Ok(TransactionMetadata {
                 txid: Txid::all_zeros(),
@@ -1027,8 +1254,245 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
-        assert_ok!(request.pay_and_execute(parachain_rpc, vault_data, 6, true).await);
+        assert_ok!(
+            request
+                .pay_and_execute(parachain_rpc, vault_data, 6, true, &ShutdownSender::new())
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_inclusion_if_payout_is_not_seen_in_mempool() {
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_get_bitcoin_fees()
+            .returning(move || Ok(FixedU128::from(1000)));
+        parachain_rpc
+            .expect_get_current_active_block_number()
+            .times(1)
+            .returning(|| Ok(50));
+        parachain_rpc
+            .expect_execute_replace()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        parachain_rpc
+            .expect_wait_for_block_in_relay()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        parachain_rpc
+            .expect_on_fee_rate_change()
+            .returning(|| tokio::sync::broadcast::channel(2).1);
+
+        let mut mock_bitcoin = MockBitcoin::default();
+        mock_bitcoin.expect_network().returning(|| Network::Regtest);
+        mock_bitcoin.expect_estimate_fee_rate().returning(|| Ok(None));
+        mock_bitcoin
+            .expect_create_and_send_transaction()
+            .returning(|_, _, _, _| Ok(Txid::all_zeros()));
+        mock_bitcoin
+            .expect_wait_for_transaction_in_mempool()
+            .times(1)
+            .returning(|txid, _| Err(BitcoinError::MempoolTimeout(txid)));
+        mock_bitcoin
+            .expect_wait_for_transaction_metadata()
+            .times(1)
+            .returning(|_, _| {
+                Ok(TransactionMetadata {
+                    txid: Txid::all_zeros(),
+                    proof: vec![],
+                    raw_tx: vec![],
+                    block_height: 0,
+                    block_hash: BlockHash::all_zeros(),
+                    fee: None,
+                })
+            });
+        mock_bitcoin.expect_get_balance().returning(|_| Ok(Amount::ZERO));
+        let btc_rpc: DynBitcoinCoreApi = Arc::new(mock_bitcoin);
+
+        let request = Request {
+            amount: 100,
+            deadline: Some(Deadline {
+                parachain: 100,
+                bitcoin: 100,
+            }),
+            btc_address: BtcAddress::P2SH(H160::from_slice(&[1; 20])),
+            hash: H256::from_slice(&[1; 32]),
+            btc_height: None,
+            request_type: RequestType::Replace,
+            vault_id: dummy_vault_id(),
+            fee_budget: None,
+        };
+
+        let vault_data = VaultData {
+            vault_id: dummy_vault_id(),
+            btc_rpc,
+            metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
+        };
+
+        assert_ok!(
+            request
+                .pay_and_execute(parachain_rpc, vault_data, 6, true, &ShutdownSender::new())
+                .await
+        );
+    }
+
+    fn wallet_payment(txid: Txid, time: u64) -> json::ListTransactionResult {
+        json::ListTransactionResult {
+            info: json::WalletTxInfo {
+                confirmations: 0,
+                blockhash: None,
+                blockindex: None,
+                blocktime: None,
+                blockheight: None,
+                txid,
+                time,
+                timereceived: time,
+                bip125_replaceable: json::Bip125Replaceable::Yes,
+                wallet_conflicts: vec![],
+            },
+            detail: json::GetTransactionResultDetail {
+                address: None,
+                category: GetTransactionResultDetailCategory::Send,
+                amount: SignedAmount::from_sat(-100),
+                label: None,
+                vout: 1,
+                fee: None,
+                abandoned: None,
+            },
+            trusted: None,
+            comment: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_not_pay_again_after_restart_between_broadcast_and_execute() {
+        let tmp_dir = TempDir::new("payments").unwrap();
+        let request_id = H256::from_slice(&[1; 32]);
+        let txid = Txid::from_slice(&[2; 32]).unwrap();
+        let request = Request {
+            amount: 100,
+            deadline: None,
+            btc_address: BtcAddress::P2SH(H160::from_slice(&[1; 20])),
+            hash: request_id,
+            btc_height: None,
+            request_type: RequestType::Redeem,
+            vault_id: dummy_vault_id(),
+            fee_budget: None,
+        };
+        let vault_data = |mock_bitcoin: MockBitcoin| VaultData {
+            vault_id: dummy_vault_id(),
+            btc_rpc: Arc::new(mock_bitcoin),
+            metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: Some(PaymentJournal::new(tmp_dir.path())),
+        };
+
+        // the vault goes down while awaiting the confirmations of the payout
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_get_bitcoin_fees()
+            .returning(|| Ok(FixedU128::from(1000)));
+        parachain_rpc
+            .expect_on_fee_rate_change()
+            .returning(|| tokio::sync::broadcast::channel(2).1);
+        parachain_rpc.expect_execute_redeem().times(0);
+
+        let mut mock_bitcoin = MockBitcoin::default();
+        mock_bitcoin.expect_network().returning(|| Network::Regtest);
+        mock_bitcoin.expect_estimate_fee_rate().returning(|| Ok(None));
+        mock_bitcoin
+            .expect_create_and_send_transaction()
+            .times(1)
+            .returning(move |_, _, _, _| Ok(txid));
+        mock_bitcoin
+            .expect_wait_for_transaction_in_mempool()
+            .returning(|_, _| Ok(()));
+        mock_bitcoin
+            .expect_wait_for_transaction_metadata()
+            .returning(|_, _| Err(BitcoinError::ConfirmationError));
+
+        assert_err!(
+            request
+                .pay_and_execute(parachain_rpc, vault_data(mock_bitcoin), 6, true, &ShutdownSender::new())
+                .await,
+            Error::BitcoinError(BitcoinError::ConfirmationError)
+        );
+        assert_eq!(
+            PaymentJournal::new(tmp_dir.path()).get(request_id).unwrap(),
+            Some(PaymentRecord::Broadcast(txid))
+        );
+
+        // after the restart, the payout is found in the wallet instead of being made again
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc
+            .expect_on_fee_rate_change()
+            .returning(|| tokio::sync::broadcast::channel(2).1);
+        parachain_rpc
+            .expect_wait_for_block_in_relay()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        parachain_rpc
+            .expect_execute_redeem()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let mut mock_bitcoin = MockBitcoin::default();
+        mock_bitcoin.expect_network().returning(|| Network::Regtest);
+        mock_bitcoin.expect_create_and_send_transaction().times(0);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        mock_bitcoin.expect_list_transactions().returning(move |_| {
+            Ok(vec![
+                // payments made long before the record are not fetched
+                wallet_payment(Txid::from_slice(&[4; 32]).unwrap(), 0),
+                wallet_payment(Txid::from_slice(&[3; 32]).unwrap(), now),
+                wallet_payment(txid, now),
+            ])
+        });
+        let old_txid = Txid::from_slice(&[4; 32]).unwrap();
+        mock_bitcoin
+            .expect_get_transaction()
+            .withf(move |other_txid, _| *other_txid != old_txid)
+            .returning(move |other_txid, _| {
+                // only the payout carries the request id
+                let op_return = if *other_txid == txid { request_id } else { H256::zero() };
+                Ok(Transaction {
+                    version: 2,
+                    lock_time: PackedLockTime::ZERO,
+                    input: vec![],
+                    output: vec![TxOut {
+                        value: 0,
+                        script_pubkey: Builder::new()
+                            .push_opcode(opcodes::OP_RETURN)
+                            .push_slice(op_return.as_bytes())
+                            .into_script(),
+                    }],
+                })
+            });
+        mock_bitcoin
+            .expect_wait_for_transaction_metadata()
+            .withf(move |other_txid, _| *other_txid == txid)
+            .times(1)
+            .returning(move |_, _| {
+                Ok(TransactionMetadata {
+                    txid,
+                    proof: vec![],
+                    raw_tx: vec![],
+                    block_height: 0,
+                    block_hash: BlockHash::all_zeros(),
+                    fee: None,
+                })
+            });
+        mock_bitcoin.expect_get_balance().returning(|_| Ok(Amount::ZERO));
+
+        assert_ok!(
+            request
+                .pay_and_execute(parachain_rpc, vault_data(mock_bitcoin), 6, true, &ShutdownSender::new())
+                .await
+        );
+        // the executed request is forgotten
+        assert_eq!(PaymentJournal::new(tmp_dir.path()).get(request_id).unwrap(), None);
     }
 }
@@ -1,15 +1,18 @@
 use crate::{
-    delay::RandomDelay, metrics::publish_expected_bitcoin_balance, Error, Event, IssueRequests, VaultIdManager,
+    delay::RandomDelay, metrics::publish_expected_bitcoin_balance, Error, Event, IssueRequests, RequestLimiter,
+    VaultIdManager,
 };
 use bitcoin::{BlockHash, Error as BitcoinError, PublicKey, Transaction, TransactionExt};
 use futures::{channel::mpsc::Sender, future, SinkExt, StreamExt, TryFutureExt};
 use runtime::{
-    BtcAddress, BtcPublicKey, BtcRelayPallet, CancelIssueEvent, ExecuteIssueEvent, H256Le, InterBtcParachain,
-    IssuePallet, IssueRequestStatus, PartialAddress, PrettyPrint, RequestIssueEvent, UtilFuncs, VaultId, H256,
+    BlockCursor, BtcAddress, BtcPublicKey, BtcRelayPallet, CancelIssueEvent, ExecuteIssueEvent, H256Le,
+    InterBtcParachain, IssuePallet, IssueRequestStatus, PartialAddress, PrettyPrint, RequestIssueEvent, UtilFuncs,
+    VaultId, H256,
 };
-use service::{DynBitcoinCoreApi, Error as ServiceError};
+use service::{ControlSender, DynBitcoinCoreApi, Error as ServiceError};
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
+use tracing::Instrument;
 
 // initialize `issue_set` with currently open issues, and return the block height
 // from which to start watching the bitcoin chain
@@ -35,7 +38,8 @@ pub(crate) async fn initialize_issue_set(
 }
 
 /// execute issue requests on best-effort (i.e. don't retry on error),
-/// returns an error if stream ends, otherwise runs forever
+/// returns an error if stream ends, otherwise runs forever. Payments seen
+/// while paused are queued and executed once resumed.
 pub async fn process_issue_requests(
     bitcoin_core: DynBitcoinCoreApi,
     btc_parachain: InterBtcParachain,
@@ -43,26 +47,51 @@ pub async fn process_issue_requests(
     btc_start_height: u32,
     num_confirmations: u32,
     random_delay: Arc<Box<dyn RandomDelay + Send + Sync>>,
+    control: ControlSender,
+    request_limiter: RequestLimiter,
 ) -> Result<(), ServiceError<Error>> {
     let mut stream =
         bitcoin::stream_in_chain_transactions(bitcoin_core.clone(), btc_start_height, num_confirmations).await;
+    let mut control_rx = control.subscribe();
+    let mut deferred = Vec::new();
+
+    let execute = |block_hash: BlockHash, transaction: Transaction| {
+        let request_limiter = request_limiter.clone();
+        let task = process_transaction_and_execute_issue(
+            bitcoin_core.clone(),
+            btc_parachain.clone(),
+            issue_set.clone(),
+            num_confirmations,
+            block_hash,
+            transaction,
+            random_delay.clone(),
+        )
+        .map_err(|e| {
+            tracing::warn!("Failed to execute issue request: {}", e.to_string());
+        });
+        tokio::spawn(async move { request_limiter.run(task).await });
+    };
 
-    while let Some(result) = stream.next().await {
+    loop {
+        let result = tokio::select! {
+            _ = control_rx.wait_until_resumed(), if !deferred.is_empty() => {
+                tracing::info!("Resumed, processing {} deferred transactions", deferred.len());
+                for (block_hash, transaction) in deferred.drain(..) {
+                    execute(block_hash, transaction);
+                }
+                continue;
+            }
+            result = stream.next() => match result {
+                Some(result) => result,
+                None => break,
+            },
+        };
         match result {
-            Ok((block_hash, transaction)) => tokio::spawn(
-                process_transaction_and_execute_issue(
-                    bitcoin_core.clone(),
-                    btc_parachain.clone(),
-                    issue_set.clone(),
-                    num_confirmations,
-                    block_hash,
-                    transaction,
-                    random_delay.clone(),
-                )
-                .map_err(|e| {
-                    tracing::warn!("Failed to execute issue request: {}", e.to_string());
-                }),
-            ),
+            Ok((block_hash, transaction)) if control_rx.is_paused() => {
+                tracing::debug!("Paused, deferring issue for transaction {}", transaction.txid());
+                deferred.push((block_hash, transaction));
+            }
+            Ok((block_hash, transaction)) => execute(block_hash, transaction),
             Err(err) => return Err(err.into()),
         };
     }
@@ -156,86 +185,97 @@ async fn process_transaction_and_execute_issue(
         let issue_id = issue_requests.get_key_for_value(address)?;
         Some((*issue_id, *address))
     }) {
-        let issue = btc_parachain.get_issue_request(issue_id).await?;
-        let payload = if let Ok(payload) = address.to_payload() {
-            payload
-        } else {
-            return Ok(());
-        };
-        // tx has output to address
-        match transaction.get_payment_amount_to(payload) {
-            None => {
-                // this should never happen, so use WARN
-                tracing::warn!(
-                    "Could not extract payment amount for transaction {}",
-                    transaction.txid()
-                );
+        return async {
+            let issue = btc_parachain.get_issue_request(issue_id).await?;
+            let payload = if let Ok(payload) = address.to_payload() {
+                payload
+            } else {
                 return Ok(());
-            }
-            Some(transferred) => {
-                let transferred = transferred as u128;
-                let expected = issue.amount + issue.fee;
-                if transferred == expected {
-                    tracing::info!("Found tx for issue with id {:?}", issue_id);
-                } else {
-                    tracing::info!(
-                        "Found tx for issue with id {}. Expected amount = {}, got {}",
-                        issue_id,
-                        expected,
-                        transferred
+            };
+            // tx has output to address
+            match transaction.get_payment_amount_to(payload) {
+                None => {
+                    // this should never happen, so use WARN
+                    tracing::warn!(
+                        "Could not extract payment amount for transaction {}",
+                        transaction.txid()
                     );
-                }
-
-                if transferred < issue.amount + issue.fee {
-                    // insufficient amount, don't execute
                     return Ok(());
                 }
+                Some(transferred) => {
+                    let transferred = transferred as u128;
+                    let expected = issue.amount + issue.fee;
+                    if transferred == expected {
+                        tracing::info!("Found tx for issue with id {:?}", issue_id);
+                    } else {
+                        tracing::info!(
+                            "Found tx for issue with id {}. Expected amount = {}, got {}",
+                            issue_id,
+                            expected,
+                            transferred
+                        );
+                    }
 
-                issue_requests.remove_value(&address);
+                    if transferred < issue.amount + issue.fee {
+                        // insufficient amount, don't execute
+                        return Ok(());
+                    }
 
-                // at this point we know that the transaction has `num_confirmations` on the bitcoin chain,
-                // but the relay can introduce a delay, so wait until the relay also confirms the transaction.
-                btc_parachain
-                    .wait_for_block_in_relay(H256Le::from_bytes_le(&block_hash), Some(num_confirmations))
-                    .await?;
+                    issue_requests.remove_value(&address);
+
+                    // at this point we know that the transaction has `num_confirmations` on the bitcoin chain,
+                    // but the relay can introduce a delay, so wait until the relay also confirms the transaction.
+                    btc_parachain
+                        .wait_for_block_in_relay(H256Le::from_bytes_le(&block_hash), Some(num_confirmations))
+                        .await?;
+
+                    // wait a random amount of blocks, to avoid all vaults flooding the parachain with
+                    // this transaction
+                    (*random_delay).delay(&issue_id.to_fixed_bytes()).await?;
+                    let issue = btc_parachain.get_issue_request(issue_id).await?;
+                    if let IssueRequestStatus::Completed = issue.status {
+                        tracing::info!("Issue {} has already been executed - doing nothing.", issue_id);
+                        return Ok(());
+                    }
 
-                // wait a random amount of blocks, to avoid all vaults flooding the parachain with
-                // this transaction
-                (*random_delay).delay(&issue_id.to_fixed_bytes()).await?;
-                let issue = btc_parachain.get_issue_request(issue_id).await?;
-                if let IssueRequestStatus::Completed = issue.status {
-                    tracing::info!("Issue {} has already been executed - doing nothing.", issue_id);
-                    return Ok(());
-                }
+                    // found tx, submit proof
+                    let txid = transaction.txid();
 
-                // found tx, submit proof
-                let txid = transaction.txid();
-
-                // bitcoin core is currently blocking, no need to try_join
-                let raw_tx = bitcoin_core.get_raw_tx(&txid, &block_hash).await?;
-                let proof = bitcoin_core.get_proof(txid, &block_hash).await?;
-
-                tracing::info!(
-                    "Executing issue #{:?} on behalf of user {:?} with vault {:?}",
-                    issue_id,
-                    issue.requester.pretty_print(),
-                    issue.vault.pretty_print()
-                );
-                match btc_parachain.execute_issue(issue_id, &proof, &raw_tx).await {
-                    Ok(_) => (),
-                    Err(err) if err.is_issue_completed() => {
-                        tracing::info!("Issue #{} has already been completed", issue_id);
-                    }
-                    Err(err) => return Err(err.into()),
-                };
+                    // bitcoin core is currently blocking, no need to try_join
+                    let raw_tx = bitcoin_core.get_raw_tx(&txid, &block_hash).await?;
+                    let proof = bitcoin_core.get_proof(txid, &block_hash).await?;
+
+                    tracing::info!(
+                        "Executing issue #{:?} on behalf of user {:?} with vault {:?}",
+                        issue_id,
+                        issue.requester.pretty_print(),
+                        issue.vault.pretty_print()
+                    );
+                    match btc_parachain.execute_issue(issue_id, &proof, &raw_tx).await {
+                        Ok(_) => (),
+                        Err(err) if err.is_issue_completed() => {
+                            tracing::info!("Issue #{} has already been completed", issue_id);
+                        }
+                        Err(err) => return Err(err.into()),
+                    };
+                }
             }
+            Ok::<_, Error>(())
         }
+        .instrument(issue_span(issue_id))
+        .await;
     }
 
     // no op_return or issue-id
     Ok(())
 }
 
+/// Span to process the issue request in, so that its logs can be told apart from those of issue
+/// requests that are processed concurrently.
+pub(crate) fn issue_span(issue_id: H256) -> tracing::Span {
+    tracing::info_span!("issue", issue_id = ?issue_id)
+}
+
 /// Import the deposit key using the on-chain key derivation scheme
 async fn add_new_deposit_key(
     bitcoin_core: &DynBitcoinCoreApi,
@@ -266,52 +306,65 @@ async fn add_new_deposit_key(
 /// * `btc_parachain` - the parachain RPC handle
 /// * `event_channel` - the channel over which to signal events
 /// * `issue_set` - all issue ids observed since vault started
+/// * `cursor` - if set, the issue requests made since the last processed block are replayed first
 pub async fn listen_for_issue_requests(
     btc_rpc: VaultIdManager,
     btc_parachain: InterBtcParachain,
     event_channel: Sender<Event>,
     issue_set: Arc<IssueRequests>,
+    cursor: Option<BlockCursor>,
 ) -> Result<(), ServiceError<Error>> {
     let btc_parachain = &btc_parachain;
     let event_channel = &event_channel;
     let issue_set = &issue_set;
     let btc_rpc = &btc_rpc;
-    btc_parachain
-        .on_event::<RequestIssueEvent, _, _, _>(
-            |event| async move {
-                if &event.vault_id.account_id == btc_parachain.get_account_id() {
-                    let vault = match btc_rpc.get_vault(&event.vault_id).await {
-                        Some(x) => x,
-                        None => {
-                            tracing::error!(
-                                "No bitcoin_rpc found for vault with id {}",
-                                event.vault_id.pretty_print()
-                            );
-                            return;
-                        }
-                    };
-                    tracing::info!("Received request issue event: {:?}", event);
-                    // try to send the event, but ignore the returned result since
-                    // the only way it can fail is if the channel is closed
-                    let _ = event_channel.clone().send(Event::Opened).await;
+    let on_issue_request = |event: RequestIssueEvent| async move {
+        if &event.vault_id.account_id == btc_parachain.get_account_id() {
+            let vault = match btc_rpc.get_vault(&event.vault_id).await {
+                Some(x) => x,
+                None => {
+                    tracing::error!(
+                        "No bitcoin_rpc found for vault with id {}",
+                        event.vault_id.pretty_print()
+                    );
+                    return;
+                }
+            };
+            tracing::info!("Received request issue event: {:?}", event);
+            // try to send the event, but ignore the returned result since
+            // the only way it can fail is if the channel is closed
+            let _ = event_channel.clone().send(Event::Opened).await;
 
-                    let _ = publish_expected_bitcoin_balance(&vault, btc_parachain.clone()).await;
+            let _ = publish_expected_bitcoin_balance(&vault, btc_parachain.clone()).await;
 
-                    if let Err(e) = add_new_deposit_key(&vault.btc_rpc, event.issue_id, event.vault_public_key).await {
-                        tracing::error!("Failed to add new deposit key #{}: {}", event.issue_id, e.to_string());
-                    }
-                }
+            if let Err(e) = add_new_deposit_key(&vault.btc_rpc, event.issue_id, event.vault_public_key).await {
+                tracing::error!("Failed to add new deposit key #{}: {}", event.issue_id, e.to_string());
+            }
+        }
 
-                tracing::trace!(
-                    "watching issue #{} for payment to {:?}",
-                    event.issue_id,
-                    event.vault_address
-                );
-                issue_set.insert(event.issue_id, event.vault_address).await;
-            },
-            |error| tracing::error!("Error reading request issue event: {}", error.to_string()),
-        )
-        .await?;
+        tracing::trace!(
+            "watching issue #{} for payment to {:?}",
+            event.issue_id,
+            event.vault_address
+        );
+        issue_set.insert(event.issue_id, event.vault_address).await;
+    };
+    match cursor {
+        Some(cursor) => {
+            btc_parachain
+                .on_event_from_cursor(&cursor, on_issue_request, |error| {
+                    tracing::error!("Error reading request issue event: {}", error.to_string())
+                })
+                .await?
+        }
+        None => {
+            btc_parachain
+                .on_event::<RequestIssueEvent, _, _, _>(on_issue_request, |error| {
+                    tracing::error!("Error reading request issue event: {}", error.to_string())
+                })
+                .await?
+        }
+    }
     Ok(())
 }
 
@@ -372,3 +425,38 @@ pub async fn listen_for_issue_cancels(
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use service::testing::SharedBuffer;
+
+    #[tokio::test]
+    async fn test_issue_logs_are_tagged_with_issue_id() {
+        let buffer = SharedBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let issue_id = H256::repeat_byte(0x42);
+        async {
+            tracing::info!("Found tx for issue");
+            tokio::task::yield_now().await;
+            tracing::info!("Executing issue");
+        }
+        .instrument(issue_span(issue_id))
+        .await;
+        tracing::info!("Unrelated");
+
+        let output = buffer.contents();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let tag = format!("issue{{issue_id={:?}}}", issue_id);
+        assert!(lines[0].contains(&format!("{}: {}", tag, "Found tx for issue")));
+        assert!(lines[1].contains(&format!("{}: {}", tag, "Executing issue")));
+        assert!(!lines[2].contains(&tag));
+    }
+}
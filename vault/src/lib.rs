@@ -3,11 +3,14 @@
 
 mod cancellation;
 pub mod delay;
+mod dump;
 mod error;
 mod execution;
 mod faucet;
 mod issue;
+mod limiter;
 pub mod metrics;
+mod payments;
 pub mod process;
 mod redeem;
 pub mod relay;
@@ -35,7 +38,14 @@ pub use system::{VaultService, VaultServiceConfig, ABOUT, AUTHORS, NAME, VERSION
 
 use runtime::{InterBtcParachain, VaultId, VaultRegistryPallet};
 
-pub use crate::{cancellation::Event, error::Error, types::IssueRequests};
+pub use crate::{
+    cancellation::Event,
+    dump::VaultState,
+    error::Error,
+    limiter::RequestLimiter,
+    payments::{PaymentClaim, PaymentJournal, PaymentRecord},
+    types::IssueRequests,
+};
 pub use delay::{OrderedVaultsDelay, RandomDelay, ZeroDelay};
 pub use system::VaultIdManager;
 
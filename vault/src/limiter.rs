@@ -0,0 +1,83 @@
+use crate::metrics::CONCURRENT_REQUESTS;
+use futures::Future;
+use runtime::prometheus::IntGauge;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Bounds the number of requests that are processed in parallel. The other requests are queued
+/// and processed in the order they arrived, once a running request completes.
+#[derive(Clone)]
+pub struct RequestLimiter(Arc<Semaphore>);
+
+impl RequestLimiter {
+    pub fn new(max_parallel_requests: u32) -> Self {
+        Self(Arc::new(Semaphore::new(max_parallel_requests as usize)))
+    }
+
+    pub fn unlimited() -> Self {
+        Self(Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)))
+    }
+
+    /// Run `task` once fewer than the maximum number of requests are processed.
+    pub async fn run<F: Future>(&self, task: F) -> F::Output {
+        let queued = GaugeGuard::inc(CONCURRENT_REQUESTS.with_label_values(&["queued"]));
+        // the semaphore is never closed
+        let _permit = self.0.acquire().await.expect("semaphore closed");
+        drop(queued);
+
+        let _active = GaugeGuard::inc(CONCURRENT_REQUESTS.with_label_values(&["active"]));
+        task.await
+    }
+}
+
+/// Keeps the gauge incremented until dropped, also if the request is cancelled.
+struct GaugeGuard(IntGauge);
+
+impl GaugeGuard {
+    fn inc(gauge: IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_concurrency_never_exceeds_limit() {
+        let limiter = RequestLimiter::new(3);
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..20).map(|_| {
+            let (limiter, running, max_running) = (limiter.clone(), running.clone(), max_running.clone());
+            tokio::spawn(async move {
+                limiter
+                    .run(async {
+                        let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(now_running, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await
+            })
+        });
+        for task in futures::future::join_all(tasks).await {
+            task.unwrap();
+        }
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(running.load(Ordering::SeqCst), 0);
+    }
+}
@@ -22,7 +22,10 @@ use service::{
     warp::{Rejection, Reply},
     Error as ServiceError,
 };
-use std::time::Duration;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 use tokio::{sync::RwLock, time::sleep};
 use tokio_metrics::TaskMetrics;
 
@@ -33,6 +36,8 @@ const CURRENCY_LABEL: &str = "currency";
 const BTC_BALANCE_TYPE_LABEL: &str = "type";
 const REQUEST_STATUS_LABEL: &str = "status";
 const TASK_NAME: &str = "task";
+const AGE_LABEL: &str = "age";
+const REQUEST_STATE_LABEL: &str = "state";
 const TOKIO_POLLING_INTERVAL_MS: u64 = 10000;
 
 // Metrics are stored under the [`CURRENCY_LABEL`] key so that multiple vaults can be easily
@@ -96,6 +101,86 @@ lazy_static! {
             .expect("Failed to create prometheus metric");
     pub static ref RESTART_COUNT: IntCounter =
         IntCounter::new("restart_count", "Number of service restarts").expect("Failed to create prometheus metric");
+    pub static ref STUCK_PAYOUTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "stuck_payout_count",
+            "Number of payouts unconfirmed for longer than the threshold"
+        ),
+        &[AGE_LABEL]
+    )
+    .expect("Failed to create prometheus metric");
+    pub static ref CONCURRENT_REQUESTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "concurrent_request_count",
+            "Number of requests being processed (active) or waiting to be processed (queued)"
+        ),
+        &[REQUEST_STATE_LABEL]
+    )
+    .expect("Failed to create prometheus metric");
+    static ref PENDING_PAYOUTS: Mutex<PendingPayouts> = Mutex::new(PendingPayouts::default());
+}
+
+/// Age buckets of [`STUCK_PAYOUTS`], by their lower bound.
+const PAYOUT_AGE_BUCKETS: [(&str, Duration); 4] = [
+    ("lt_1h", Duration::ZERO),
+    ("1h_6h", Duration::from_secs(3600)),
+    ("6h_24h", Duration::from_secs(6 * 3600)),
+    ("gt_24h", Duration::from_secs(24 * 3600)),
+];
+
+/// Payouts that were broadcast but not confirmed yet, by request, with the time they started
+/// being watched. Fee bumps replace the transaction, but keep the payout's age.
+#[derive(Debug, Default)]
+struct PendingPayouts(HashMap<H256, Instant>);
+
+impl PendingPayouts {
+    /// Number of payouts that are pending for at least `threshold`, by age bucket.
+    fn stuck_by_age(&self, now: Instant, threshold: Duration) -> [(&'static str, i64); 4] {
+        let mut counts = PAYOUT_AGE_BUCKETS.map(|(label, _)| (label, 0));
+        for age in self
+            .0
+            .values()
+            .map(|broadcast| now.saturating_duration_since(*broadcast))
+        {
+            if age < threshold {
+                continue;
+            }
+            if let Some(bucket) = PAYOUT_AGE_BUCKETS
+                .iter()
+                .rposition(|(_, lower_bound)| age >= *lower_bound)
+            {
+                counts[bucket].1 += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// Keeps a payout in [`STUCK_PAYOUTS`] until it is dropped.
+pub(crate) struct PendingPayout(H256);
+
+impl PendingPayout {
+    pub(crate) fn track(request_id: H256) -> Self {
+        PENDING_PAYOUTS
+            .lock()
+            .unwrap()
+            .0
+            .entry(request_id)
+            .or_insert_with(Instant::now);
+        Self(request_id)
+    }
+}
+
+impl Drop for PendingPayout {
+    fn drop(&mut self) {
+        PENDING_PAYOUTS.lock().unwrap().0.remove(&self.0);
+    }
+}
+
+fn publish_stuck_payouts(pending_payouts: &PendingPayouts, now: Instant, threshold: Duration) {
+    for (label, count) in pending_payouts.stuck_by_age(now, threshold) {
+        STUCK_PAYOUTS.with_label_values(&[label]).set(count);
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -259,7 +344,7 @@ impl PerCurrencyMetrics {
     }
 
     pub async fn initialize_values(parachain_rpc: InterBtcParachain, vault: &VaultData) {
-        let bitcoin_transactions = match vault.btc_rpc.list_transactions(None) {
+        let bitcoin_transactions = match vault.btc_rpc.list_transactions(None).await {
             Ok(x) => x
                 .into_iter()
                 .filter(|x| x.detail.category == GetTransactionResultDetailCategory::Send)
@@ -274,8 +359,8 @@ impl PerCurrencyMetrics {
             .fold((0, 0), |(total, count), x| (total + x, count + 1));
         *vault.metrics.average_btc_fee.data.write().await = AverageTracker { total, count };
 
-        publish_utxo_count(vault);
-        publish_bitcoin_balance(vault);
+        publish_utxo_count(vault).await;
+        publish_bitcoin_balance(vault).await;
 
         let _ = tokio::join!(
             Self::initialize_fee_budget_surplus(vault, parachain_rpc.clone(), bitcoin_transactions),
@@ -304,6 +389,9 @@ pub fn register_custom_metrics() -> Result<(), RuntimeError> {
     REGISTRY.register(Box::new(MEAN_SCHEDULED_DURATION.clone()))?;
     REGISTRY.register(Box::new(REMAINING_TIME_TO_REDEEM_HOURS.clone()))?;
     REGISTRY.register(Box::new(RESTART_COUNT.clone()))?;
+    REGISTRY.register(Box::new(STUCK_PAYOUTS.clone()))?;
+    REGISTRY.register(Box::new(CONCURRENT_REQUESTS.clone()))?;
+    runtime::register_runtime_metrics(&REGISTRY)?;
 
     Ok(())
 }
@@ -397,7 +485,7 @@ pub async fn update_bitcoin_metrics(
         publish_fee_budget_surplus(vault).await?;
     }
 
-    publish_bitcoin_balance(vault);
+    publish_bitcoin_balance(vault).await;
     Ok(())
 }
 
@@ -419,8 +507,8 @@ async fn publish_average_bitcoin_fee(vault: &VaultData) {
     vault.metrics.average_btc_fee.gauge.set(average);
 }
 
-fn publish_bitcoin_balance(vault: &VaultData) {
-    match vault.btc_rpc.get_balance(None) {
+async fn publish_bitcoin_balance(vault: &VaultData) {
+    match vault.btc_rpc.get_balance(None).await {
         Ok(bitcoin_balance) => vault.metrics.btc_balance.actual.set(bitcoin_balance.to_btc() as f64),
         Err(e) => {
             // unexpected error, but not critical so just continue
@@ -440,8 +528,8 @@ async fn publish_native_currency_balance<P: CollateralBalancesPallet + UtilFuncs
     Ok(())
 }
 
-fn publish_utxo_count(vault: &VaultData) {
-    if let Ok(count) = vault.btc_rpc.get_utxo_count() {
+async fn publish_utxo_count(vault: &VaultData) {
+    if let Ok(count) = vault.btc_rpc.get_utxo_count().await {
         if let Ok(count_i64) = count.try_into() {
             vault.metrics.utxo_count.set(count_i64);
         }
@@ -601,14 +689,18 @@ pub async fn monitor_bridge_metrics(
     Ok(())
 }
 
-pub async fn poll_metrics<P: CollateralBalancesPallet + RedeemPallet + IssuePallet + SecurityPallet + UtilFuncs>(
+pub async fn poll_metrics<
+    P: CollateralBalancesPallet + RedeemPallet + IssuePallet + SecurityPallet + VaultRegistryPallet + UtilFuncs + Clone,
+>(
     parachain_rpc: P,
     vault_id_manager: VaultIdManager,
+    stuck_payout_threshold: Duration,
 ) -> Result<(), ServiceError<Error>> {
     let parachain_rpc = &parachain_rpc;
     let vault_id_manager = &vault_id_manager;
 
     loop {
+        publish_stuck_payouts(&PENDING_PAYOUTS.lock().unwrap(), Instant::now(), stuck_payout_threshold);
         publish_native_currency_balance(parachain_rpc).await?;
         publish_issue_count(parachain_rpc, vault_id_manager).await;
         if let Ok(redeems) = parachain_rpc
@@ -620,7 +712,8 @@ pub async fn poll_metrics<P: CollateralBalancesPallet + RedeemPallet + IssuePall
         }
 
         for vault in vault_id_manager.get_entries().await {
-            publish_utxo_count(&vault);
+            publish_utxo_count(&vault).await;
+            publish_collateralization(&vault, parachain_rpc.clone()).await;
         }
 
         sleep(SLEEP_DURATION).await;
@@ -683,8 +776,9 @@ mod tests {
     use jsonrpc_core::serde_json::{Map, Value};
     use runtime::{
         AccountId, AssetMetadata, Balance, BlockNumber, BtcAddress, BtcPublicKey, CurrencyId, Error as RuntimeError,
-        ErrorCode, InterBtcIssueRequest, InterBtcRedeemRequest, InterBtcReplaceRequest, InterBtcVault,
-        RequestIssueEvent, StatusCode, Token, VaultId, VaultStatus, DOT, H256, IBTC, INTR,
+        ErrorCode, HealthSummary, InterBtcIssueRequest, InterBtcRedeemRequest, InterBtcReplaceRequest, InterBtcVault,
+        IssueRequestsPage, LiquidationPrice, RequestIssueEvent, RuntimeVersion, StatusCode, Thresholds, Token, VaultId,
+        VaultStatus, DOT, H256, IBTC, INTR,
     };
     use service::DynBitcoinCoreApi;
     use std::collections::BTreeSet;
@@ -696,11 +790,13 @@ mod tests {
         pub trait UtilFuncs {
             async fn get_current_chain_height(&self) -> Result<u32, RuntimeError>;
             async fn get_rpc_properties(&self) -> Result<Map<String, Value>, RuntimeError>;
+            async fn runtime_version(&self) -> Result<RuntimeVersion, RuntimeError>;
             fn get_native_currency_id(&self) -> CurrencyId;
             fn get_account_id(&self) -> &AccountId;
             fn is_this_vault(&self, vault_id: &VaultId) -> bool;
             async fn get_foreign_assets_metadata(&self) -> Result<Vec<(u32, AssetMetadata)>, RuntimeError>;
             async fn get_foreign_asset_metadata(&self, id: u32) -> Result<AssetMetadata, RuntimeError>;
+            async fn wait_for_sync(&self, tolerance: u32, timeout_after: Duration) -> Result<(), RuntimeError>;
         }
 
         #[async_trait]
@@ -712,6 +808,8 @@ mod tests {
             async fn get_vault_issue_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcIssueRequest)>, RuntimeError>;
             async fn get_issue_period(&self) -> Result<u32, RuntimeError>;
             async fn get_all_active_issues(&self) -> Result<Vec<(H256, InterBtcIssueRequest)>, RuntimeError>;
+            async fn list_issue_requests(&self, vault_id: &VaultId, page_size: u32, cursor: Option<Vec<u8>>) -> Result<IssueRequestsPage, RuntimeError>;
+            fn subscribe_issue_requests(&self) -> futures::stream::BoxStream<'static, Result<RequestIssueEvent, RuntimeError>>;
         }
 
         #[async_trait]
@@ -722,6 +820,7 @@ mod tests {
             async fn get_redeem_request(&self, redeem_id: H256) -> Result<InterBtcRedeemRequest, RuntimeError>;
             async fn get_vault_redeem_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcRedeemRequest)>, RuntimeError>;
             async fn get_redeem_period(&self) -> Result<BlockNumber, RuntimeError>;
+            async fn redeems_nearing_deadline(&self, vault_id: &VaultId, within: Duration) -> Result<Vec<(H256, InterBtcRedeemRequest, Duration)>, RuntimeError>;
         }
 
         #[async_trait]
@@ -732,12 +831,17 @@ mod tests {
             async fn register_vault(&self, vault_id: &VaultId, collateral: u128) -> Result<(), RuntimeError>;
             async fn deposit_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn withdraw_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
+            async fn withdraw_collateral_safe(&self, vault_id: &VaultId, amount: u128, min_ratio: FixedU128) -> Result<(), RuntimeError>;
             async fn get_public_key(&self) -> Result<Option<BtcPublicKey>, RuntimeError>;
             async fn register_public_key(&self, public_key: BtcPublicKey) -> Result<(), RuntimeError>;
             async fn get_required_collateral_for_wrapped(&self, amount_btc: u128, collateral_currency: CurrencyId) -> Result<u128, RuntimeError>;
             async fn get_required_collateral_for_vault(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
             async fn get_vault_total_collateral(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
             async fn get_collateralization_from_vault(&self, vault_id: VaultId, only_issued: bool) -> Result<u128, RuntimeError>;
+            async fn get_collateralization_ratio(&self, vault_id: &VaultId) -> Result<Option<FixedU128>, RuntimeError>;
+            async fn health_summary(&self) -> Result<HealthSummary, RuntimeError>;
+            async fn liquidation_price(&self, collateral_currency: CurrencyId) -> Result<Option<LiquidationPrice>, RuntimeError>;
+            async fn thresholds(&self, collateral_currency: CurrencyId) -> Result<Thresholds, RuntimeError>;
             async fn set_current_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
             async fn set_pending_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
         }
@@ -746,6 +850,7 @@ mod tests {
         pub trait CollateralBalancesPallet {
             async fn get_free_balance(&self, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
             async fn get_free_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
+            async fn get_balances(&self, id: AccountId, currency_ids: &[CurrencyId]) -> Result<Vec<(CurrencyId, Balance)>, RuntimeError>;
             async fn get_reserved_balance(&self, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
             async fn get_reserved_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
             async fn transfer_to(&self, recipient: &AccountId, amount: u128, currency_id: CurrencyId) -> Result<(), RuntimeError>;
@@ -756,6 +861,7 @@ mod tests {
             async fn request_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn withdraw_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
             async fn accept_replace(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, collateral: u128, btc_address: BtcAddress) -> Result<(), RuntimeError>;
+            async fn accept_replace_auto(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, buffer_percent: u32, btc_address: BtcAddress) -> Result<(), RuntimeError>;
             async fn execute_replace(&self, replace_id: H256, merkle_proof: &[u8], raw_tx: &[u8]) -> Result<(), RuntimeError>;
             async fn cancel_replace(&self, replace_id: H256) -> Result<(), RuntimeError>;
             async fn get_new_vault_replace_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcReplaceRequest)>, RuntimeError>;
@@ -763,6 +869,7 @@ mod tests {
             async fn get_replace_period(&self) -> Result<u32, RuntimeError>;
             async fn get_replace_request(&self, replace_id: H256) -> Result<InterBtcReplaceRequest, RuntimeError>;
             async fn get_replace_dust_amount(&self) -> Result<u128, RuntimeError>;
+            async fn request_replace_to_threshold(&self, vault_id: &VaultId, target_ratio: FixedU128) -> Result<Option<u128>, RuntimeError>;
         }
 
         #[async_trait]
@@ -773,6 +880,7 @@ mod tests {
 
             /// Gets the current active block number of the parachain
             async fn get_current_active_block_number(&self) -> Result<u32, RuntimeError>;
+            fn subscribe_best_blocks(&self) -> futures::stream::BoxStream<'static, Result<runtime::BlockNumber, RuntimeError>>;
         }
     }
 
@@ -790,8 +898,8 @@ mod tests {
         trait BitcoinCoreApi {
             fn network(&self) -> Network;
             async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, BitcoinError>;
-            fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
-            fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
+            async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
+            async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
             async fn get_block_count(&self) -> Result<u64, BitcoinError>;
             async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, BitcoinError>;
             async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, BitcoinError>;
@@ -800,8 +908,8 @@ mod tests {
             async fn get_pruned_height(&self) -> Result<u64, BitcoinError>;
             async fn get_new_address(&self) -> Result<Address, BitcoinError>;
             async fn get_new_public_key(&self) -> Result<PublicKey, BitcoinError>;
-            fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
-            fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
+            async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
+            async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
             async fn add_new_deposit_key(&self, public_key: PublicKey, secret_key: Vec<u8>) -> Result<(), BitcoinError>;
             async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinError>;
             async fn get_block(&self, hash: &BlockHash) -> Result<Block, BitcoinError>;
@@ -813,7 +921,7 @@ mod tests {
             async fn create_or_load_wallet(&self) -> Result<(), BitcoinError>;
             async fn rescan_blockchain(&self, start_height: usize, end_height: usize) -> Result<(), BitcoinError>;
             async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), BitcoinError>;
-            fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
+            async fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
             async fn bump_fee(
                 &self,
                 txid: &Txid,
@@ -822,6 +930,8 @@ mod tests {
             ) -> Result<Txid, BitcoinError>;
             async fn is_in_mempool(&self, txid: Txid) -> Result<bool, BitcoinError>;
             async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, BitcoinError>;
+            async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, BitcoinError>;
+            async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), BitcoinError>;
         }
     }
 
@@ -997,6 +1107,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         publish_expected_bitcoin_balance(&vault_data, parachain_rpc)
@@ -1028,6 +1139,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         update_bitcoin_metrics(&vault_data, Some(SignedAmount::from_sat(125)), Some(122))
@@ -1052,8 +1164,9 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
-        publish_utxo_count(&vault_data);
+        publish_utxo_count(&vault_data).await;
 
         let utxo_count = vault_data.metrics.utxo_count.get();
         assert_eq!(utxo_count, 102);
@@ -1076,6 +1189,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         publish_locked_collateral(&vault_data, parachain_rpc).await.unwrap();
@@ -1098,6 +1212,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         publish_collateralization(&vault_data, parachain_rpc).await;
@@ -1127,6 +1242,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         publish_required_collateral(&vault_data, parachain_rpc).await.unwrap();
@@ -1194,6 +1310,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         let mut vault_id_manager = MockVaultIdManager::default();
@@ -1250,6 +1367,7 @@ mod tests {
             vault_id: dummy_vault_id(),
             btc_rpc,
             metrics: PerCurrencyMetrics::dummy(),
+            payment_journal: None,
         };
 
         let mut vault_id_manager = MockVaultIdManager::default();
@@ -1350,4 +1468,40 @@ mod tests {
             Some(full_duration * 3)
         );
     }
+
+    #[test]
+    fn test_stuck_payouts_gauge() {
+        const HOUR: Duration = Duration::from_secs(3600);
+        let broadcast = Instant::now();
+        // evaluated 30h after the first broadcast
+        let now = broadcast + 30 * HOUR;
+        let pending_payouts = PendingPayouts(HashMap::from([
+            (H256::from_low_u64_be(1), broadcast),                        // 30h
+            (H256::from_low_u64_be(2), broadcast + 6 * HOUR),             // 24h
+            (H256::from_low_u64_be(3), broadcast + 20 * HOUR),            // 10h
+            (H256::from_low_u64_be(4), broadcast + 27 * HOUR),            // 3h
+            (H256::from_low_u64_be(5), broadcast + 29 * HOUR),            // 1h
+            (H256::from_low_u64_be(6), broadcast + 29 * HOUR + HOUR / 2), // 30min
+            (H256::from_low_u64_be(7), now),                              // just broadcast
+        ]));
+
+        publish_stuck_payouts(&pending_payouts, now, HOUR / 4);
+        let count = |label: &str| STUCK_PAYOUTS.with_label_values(&[label]).get();
+        assert_eq!(count("lt_1h"), 1);
+        assert_eq!(count("1h_6h"), 2);
+        assert_eq!(count("6h_24h"), 1);
+        assert_eq!(count("gt_24h"), 2);
+
+        // payouts younger than the threshold are not stuck
+        publish_stuck_payouts(&pending_payouts, now, 2 * HOUR);
+        assert_eq!(count("lt_1h"), 0);
+        assert_eq!(count("1h_6h"), 1);
+
+        // confirmed payouts are no longer tracked
+        let request_id = H256::from_low_u64_be(8);
+        let pending_payout = PendingPayout::track(request_id);
+        assert!(PENDING_PAYOUTS.lock().unwrap().0.contains_key(&request_id));
+        drop(pending_payout);
+        assert!(!PENDING_PAYOUTS.lock().unwrap().0.contains_key(&request_id));
+    }
 }
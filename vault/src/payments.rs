@@ -0,0 +1,262 @@
+use crate::Error;
+use bitcoin::Txid;
+use runtime::H256;
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+/// The payout of a request, as recorded before and after broadcasting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentRecord {
+    /// the payout is about to be broadcast, so it may or may not have reached the wallet
+    Broadcasting,
+    /// the payout was broadcast in the transaction with this id, or a replacement of it
+    Broadcast(Txid),
+}
+
+impl fmt::Display for PaymentRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentRecord::Broadcasting => write!(f, "broadcasting"),
+            PaymentRecord::Broadcast(txid) => write!(f, "{}", txid),
+        }
+    }
+}
+
+/// Payouts by request id, persisted so that a request whose payout was broadcast before a
+/// restart is not paid a second time. Every request has its own file in `dir`.
+#[derive(Debug, Clone)]
+pub struct PaymentJournal {
+    dir: PathBuf,
+    // requests that are being paid by this process
+    claimed: Arc<Mutex<HashSet<H256>>>,
+}
+
+/// The exclusive right of this process to pay a request, released when dropped.
+#[derive(Debug)]
+pub struct PaymentClaim {
+    journal: PaymentJournal,
+    request_id: H256,
+    is_new: bool,
+}
+
+impl PaymentClaim {
+    pub fn journal(&self) -> &PaymentJournal {
+        &self.journal
+    }
+
+    /// Whether the claim created the record of the request, i.e. it was not paid before.
+    pub fn is_new(&self) -> bool {
+        self.is_new
+    }
+}
+
+impl Drop for PaymentClaim {
+    fn drop(&mut self) {
+        self.journal
+            .claimed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.request_id);
+    }
+}
+
+impl PaymentJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            claimed: Default::default(),
+        }
+    }
+
+    fn path(&self, request_id: H256) -> PathBuf {
+        self.dir.join(format!("{:x}", request_id))
+    }
+
+    /// The recorded payout of `request_id`. A corrupt record is treated as an interrupted
+    /// broadcast, so the wallet decides whether the request was paid.
+    pub fn get(&self, request_id: H256) -> Result<Option<PaymentRecord>, Error> {
+        let path = self.path(request_id);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        // the trailing newline tells a complete record from a truncated one
+        let record = match contents.strip_suffix('\n') {
+            Some("broadcasting") => Some(PaymentRecord::Broadcasting),
+            Some(txid) => Txid::from_str(txid).ok().map(PaymentRecord::Broadcast),
+            None => None,
+        };
+        match record {
+            Some(record) => Ok(Some(record)),
+            None => {
+                tracing::warn!("Corrupt payment record {}, checking the wallet", path.display());
+                Ok(Some(PaymentRecord::Broadcasting))
+            }
+        }
+    }
+
+    /// When the record of `request_id` was last written. Its payout can't have been broadcast
+    /// much earlier than that.
+    pub fn recorded_at(&self, request_id: H256) -> Result<Option<SystemTime>, Error> {
+        match fs::metadata(self.path(request_id)) {
+            Ok(metadata) => Ok(Some(metadata.modified()?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Claim the payout of `request_id`, failing with `Error::PaymentInProgress` if another task
+    /// holds the claim. Unless there is a record of an earlier payout already, the `Broadcasting`
+    /// record is created, atomically, so that the claim is new for only one of any concurrent
+    /// claims, even across processes.
+    pub fn claim(&self, request_id: H256) -> Result<PaymentClaim, Error> {
+        if !self
+            .claimed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(request_id)
+        {
+            return Err(Error::PaymentInProgress(request_id));
+        }
+        // releases the claim on error
+        let mut claim = PaymentClaim {
+            journal: self.clone(),
+            request_id,
+            is_new: false,
+        };
+        fs::create_dir_all(&self.dir)?;
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.path(request_id))
+        {
+            Ok(mut file) => {
+                // a crash before the record is complete leaves a corrupt record, which is read as
+                // `Broadcasting`
+                claim.is_new = true;
+                writeln!(file, "{}", PaymentRecord::Broadcasting)?;
+                file.sync_all()?;
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(claim)
+    }
+
+    /// Replace the record of `request_id`. The file is synced and then renamed into place, so a
+    /// crash leaves either the previous or the new record.
+    pub fn record(&self, request_id: H256, record: PaymentRecord) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(request_id);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        writeln!(file, "{}", record)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Forget the payout of `request_id`, once the request is executed.
+    pub fn remove(&self, request_id: H256) -> Result<(), Error> {
+        match fs::remove_file(self.path(request_id)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget the payouts of all requests for which `is_open` returns `false`, i.e. those that
+    /// were executed or cancelled while the vault was down.
+    pub fn retain(&self, is_open: impl Fn(&H256) -> bool) -> Result<(), Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            // leftovers of interrupted writes are not records
+            let request_id = match entry.file_name().to_str().and_then(|name| H256::from_str(name).ok()) {
+                Some(request_id) => request_id,
+                None => continue,
+            };
+            if !is_open(&request_id) {
+                self.remove(request_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Hash;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_payment_journal_round_trip() {
+        let tmp_dir = TempDir::new("payments").unwrap();
+        let journal = PaymentJournal::new(tmp_dir.path().join("payments"));
+        let (open, closed) = (H256::from_slice(&[1; 32]), H256::from_slice(&[2; 32]));
+        let txid = Txid::from_slice(&[3; 32]).unwrap();
+
+        assert_eq!(journal.get(open).unwrap(), None);
+        journal.record(open, PaymentRecord::Broadcasting).unwrap();
+        assert_eq!(journal.get(open).unwrap(), Some(PaymentRecord::Broadcasting));
+        journal.record(open, PaymentRecord::Broadcast(txid)).unwrap();
+        journal.record(closed, PaymentRecord::Broadcast(txid)).unwrap();
+        // a restarted vault reads the same records
+        let journal = PaymentJournal::new(tmp_dir.path().join("payments"));
+        assert_eq!(journal.get(open).unwrap(), Some(PaymentRecord::Broadcast(txid)));
+
+        journal.retain(|request_id| *request_id == open).unwrap();
+        assert_eq!(journal.get(open).unwrap(), Some(PaymentRecord::Broadcast(txid)));
+        assert_eq!(journal.get(closed).unwrap(), None);
+
+        // a truncated record may still belong to a broadcast payout
+        fs::write(journal.path(open), txid.to_string()).unwrap();
+        assert_eq!(journal.get(open).unwrap(), Some(PaymentRecord::Broadcasting));
+
+        journal.remove(open).unwrap();
+        journal.remove(open).unwrap();
+        assert_eq!(journal.get(open).unwrap(), None);
+        assert_eq!(journal.recorded_at(open).unwrap(), None);
+    }
+
+    #[test]
+    fn test_payment_journal_claim() {
+        let tmp_dir = TempDir::new("payments").unwrap();
+        let journal = PaymentJournal::new(tmp_dir.path().join("payments"));
+        let request_id = H256::from_slice(&[1; 32]);
+
+        let claim = journal.claim(request_id).unwrap();
+        assert!(claim.is_new());
+        assert_eq!(journal.get(request_id).unwrap(), Some(PaymentRecord::Broadcasting));
+        assert!(journal.recorded_at(request_id).unwrap().is_some());
+        // other tasks can't claim the request in the meantime
+        assert!(matches!(
+            journal.clone().claim(request_id),
+            Err(Error::PaymentInProgress(other)) if other == request_id
+        ));
+        // nor can another process, which finds the record instead
+        let other_journal = PaymentJournal::new(tmp_dir.path().join("payments"));
+        assert!(!other_journal.claim(request_id).unwrap().is_new());
+
+        drop(claim);
+        let claim = journal.claim(request_id).unwrap();
+        assert!(!claim.is_new());
+        journal.remove(request_id).unwrap();
+        drop(claim);
+        assert!(journal.claim(request_id).unwrap().is_new());
+    }
+}
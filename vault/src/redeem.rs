@@ -1,72 +1,92 @@
 use crate::{execution::*, metrics::publish_expected_bitcoin_balance, system::VaultIdManager, Error};
 use runtime::{InterBtcParachain, RedeemPallet, RequestRedeemEvent};
-use service::{spawn_cancelable, Error as ServiceError, ShutdownSender};
+use service::{spawn_cancelable, ControlSender, Error as ServiceError, ShutdownSender};
 use std::time::Duration;
+use tracing::Instrument;
 
 /// Listen for RequestRedeemEvent directed at this vault; upon reception, transfer
 /// bitcoin and call execute_redeem
 ///
 /// # Arguments
 ///
+/// * `control` - while paused, new redeem requests are only executed once resumed
 /// * `parachain_rpc` - the parachain RPC handle
 /// * `btc_rpc` - the bitcoin RPC handle
 /// * `network` - network the bitcoin network used (i.e. regtest/testnet/mainnet)
 /// * `num_confirmations` - the number of bitcoin confirmation to await
 pub async fn listen_for_redeem_requests(
     shutdown_tx: ShutdownSender,
+    control: ControlSender,
     parachain_rpc: InterBtcParachain,
     vault_id_manager: VaultIdManager,
     num_confirmations: u32,
     payment_margin: Duration,
     auto_rbf: bool,
 ) -> Result<(), ServiceError<Error>> {
-    parachain_rpc
-        .on_event::<RequestRedeemEvent, _, _, _>(
-            |event| async {
-                let vault = match vault_id_manager.get_vault(&event.vault_id).await {
-                    Some(x) => x,
-                    None => return, // event not directed at this vault
-                };
+    let on_redeem_request = |event: RequestRedeemEvent| async {
+        let vault = match vault_id_manager.get_vault(&event.vault_id).await {
+            Some(x) => x,
+            None => return, // event not directed at this vault
+        };
 
-                tracing::info!("Received redeem request: {:?}", event);
+        tracing::info!("Received redeem request: {:?}", event);
 
-                let _ = publish_expected_bitcoin_balance(&vault, parachain_rpc.clone()).await;
+        let _ = publish_expected_bitcoin_balance(&vault, parachain_rpc.clone()).await;
 
-                // within this event callback, we captured the arguments of listen_for_redeem_requests
-                // by reference. Since spawn requires static lifetimes, we will need to capture the
-                // arguments by value rather than by reference, so clone these:
-                let parachain_rpc = parachain_rpc.clone();
-                // Spawn a new task so that we handle these events concurrently
-                spawn_cancelable(shutdown_tx.subscribe(), async move {
-                    tracing::info!("Executing redeem #{:?}", event.redeem_id);
-                    let result = async {
-                        let request = Request::from_redeem_request(
-                            event.redeem_id,
-                            parachain_rpc.get_redeem_request(event.redeem_id).await?,
-                            payment_margin,
-                        )?;
-                        request
-                            .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf)
-                            .await
-                    }
-                    .await;
+        // within this event callback, we captured the arguments of listen_for_redeem_requests
+        // by reference. Since spawn requires static lifetimes, we will need to capture the
+        // arguments by value rather than by reference, so clone these:
+        let parachain_rpc = parachain_rpc.clone();
+        let task_shutdown_tx = shutdown_tx.clone();
+        let mut control_rx = control.subscribe();
+        // tag all logs of this redeem with its id
+        let span = tracing::info_span!("redeem", redeem_id = ?event.redeem_id);
+        let task = async move {
+            // don't drop the request while paused, the vault would be punished if it expires
+            if control_rx.is_paused() {
+                tracing::info!("Paused, deferring redeem #{:?}", event.redeem_id);
+                control_rx.wait_until_resumed().await;
+            }
+            tracing::info!("Executing redeem #{:?}", event.redeem_id);
+            let result = async {
+                let request = Request::from_redeem_request(
+                    event.redeem_id,
+                    parachain_rpc.get_redeem_request(event.redeem_id).await?,
+                    payment_margin,
+                )?;
+                request
+                    .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf, &task_shutdown_tx)
+                    .await
+            }
+            .await;
 
-                    match result {
-                        Ok(_) => tracing::info!(
-                            "Completed redeem request #{} with amount {}",
-                            event.redeem_id,
-                            event.amount
-                        ),
-                        Err(e) => tracing::error!(
-                            "Failed to process redeem request #{}: {}",
-                            event.redeem_id,
-                            e.to_string()
-                        ),
-                    }
-                });
-            },
-            |error| tracing::error!("Error reading redeem event: {}", error.to_string()),
-        )
+            match result {
+                Ok(_) => tracing::info!(
+                    "Completed redeem request #{} with amount {}",
+                    event.redeem_id,
+                    event.amount
+                ),
+                Err(e) => tracing::error!(
+                    "Failed to process redeem request #{}: {}",
+                    event.redeem_id,
+                    e.to_string()
+                ),
+            }
+        };
+        // Spawn a new task so that we handle these events concurrently
+        let request_limiter = vault_id_manager.request_limiter().clone();
+        spawn_cancelable(
+            shutdown_tx.subscribe(),
+            async move { request_limiter.run(task).await }.instrument(span),
+        );
+    };
+    // the redeem requests made while the vault was down are not replayed from a cursor, since
+    // `execute_open_requests` already pays all pending ones at startup and a replay would pay them
+    // a second time
+    parachain_rpc
+        .on_event::<RequestRedeemEvent, _, _, _>(on_redeem_request, |error| {
+            tracing::error!("Error reading redeem event: {}", error.to_string())
+        })
         .await?;
     Ok(())
 }
@@ -38,13 +38,14 @@ pub async fn listen_for_refund_requests(
                 // by reference. Since spawn requires static lifetimes, we will need to capture the
                 // arguments by value rather than by reference, so clone these:
                 let parachain_rpc = parachain_rpc.clone();
+                let task_shutdown_tx = shutdown_tx.clone();
                 // Spawn a new task so that we handle these events concurrently
                 spawn_cancelable(shutdown_tx.subscribe(), async move {
                     tracing::info!("Executing refund #{:?}", event.refund_id);
                     // prepare the action that will be executed after the bitcoin transfer
                     let request = Request::from_refund_request_event(&event);
                     let result = request
-                        .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf)
+                        .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf, &task_shutdown_tx)
                         .await;
 
                     match result {
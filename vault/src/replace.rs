@@ -45,8 +45,8 @@ pub async fn listen_for_accept_replace(
                 // by reference. Since spawn requires static lifetimes, we will need to capture the
                 // arguments by value rather than by reference, so clone these:
                 let parachain_rpc = parachain_rpc.clone();
-                // Spawn a new task so that we handle these events concurrently
-                spawn_cancelable(shutdown_tx.subscribe(), async move {
+                let task_shutdown_tx = shutdown_tx.clone();
+                let task = async move {
                     tracing::info!("Executing accept replace #{:?}", event.replace_id);
 
                     let result = async {
@@ -56,7 +56,7 @@ pub async fn listen_for_accept_replace(
                             payment_margin,
                         )?;
                         request
-                            .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf)
+                            .pay_and_execute(parachain_rpc, vault, num_confirmations, auto_rbf, &task_shutdown_tx)
                             .await
                     }
                     .await;
@@ -73,7 +73,10 @@ pub async fn listen_for_accept_replace(
                             e.to_string()
                         ),
                     }
-                });
+                };
+                // Spawn a new task so that we handle these events concurrently
+                let request_limiter = vault_id_manager.request_limiter().clone();
+                spawn_cancelable(shutdown_tx.subscribe(), async move { request_limiter.run(task).await });
             },
             |error| tracing::error!("Error reading accept_replace_event: {}", error.to_string()),
         )
@@ -213,8 +216,8 @@ mod tests {
         PrivateKey, PublicKey, SatPerVbyte, Transaction, TransactionMetadata, Txid,
     };
     use runtime::{
-        AccountId, Balance, BtcAddress, BtcPublicKey, CurrencyId, Error as RuntimeError, InterBtcReplaceRequest,
-        InterBtcVault, Token, DOT, H256, IBTC,
+        AccountId, Balance, BtcAddress, BtcPublicKey, CurrencyId, Error as RuntimeError, FixedU128, HealthSummary,
+        InterBtcReplaceRequest, InterBtcVault, LiquidationPrice, Thresholds, Token, DOT, H256, IBTC,
     };
     use std::{str::FromStr, sync::Arc};
 
@@ -235,8 +238,8 @@ mod tests {
         trait BitcoinCoreApi {
             fn network(&self) -> Network;
             async fn wait_for_block(&self, height: u32, num_confirmations: u32) -> Result<Block, BitcoinError>;
-            fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
-            fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
+            async fn get_balance(&self, min_confirmations: Option<u32>) -> Result<Amount, BitcoinError>;
+            async fn list_transactions(&self, max_count: Option<usize>) -> Result<Vec<json::ListTransactionResult>, BitcoinError>;
             async fn get_block_count(&self) -> Result<u64, BitcoinError>;
             async fn get_raw_tx(&self, txid: &Txid, block_hash: &BlockHash) -> Result<Vec<u8>, BitcoinError>;
             async fn get_transaction(&self, txid: &Txid, block_hash: Option<BlockHash>) -> Result<Transaction, BitcoinError>;
@@ -245,8 +248,8 @@ mod tests {
             async fn get_pruned_height(&self) -> Result<u64, BitcoinError>;
             async fn get_new_address(&self) -> Result<Address, BitcoinError>;
             async fn get_new_public_key(&self) -> Result<PublicKey, BitcoinError>;
-            fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
-            fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
+            async fn dump_derivation_key(&self, public_key: &PublicKey) -> Result<PrivateKey, BitcoinError>;
+            async fn import_derivation_key(&self, private_key: &PrivateKey) -> Result<(), BitcoinError>;
             async fn add_new_deposit_key(
                 &self,
                 public_key: PublicKey,
@@ -281,7 +284,7 @@ mod tests {
             async fn create_or_load_wallet(&self) -> Result<(), BitcoinError>;
             async fn rescan_blockchain(&self, start_height: usize, end_height: usize) -> Result<(), BitcoinError>;
             async fn rescan_electrs_for_addresses(&self, addresses: Vec<Address>) -> Result<(), BitcoinError>;
-            fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
+            async fn get_utxo_count(&self) -> Result<usize, BitcoinError>;
             async fn bump_fee(
                 &self,
                 txid: &Txid,
@@ -290,6 +293,8 @@ mod tests {
             ) -> Result<Txid, BitcoinError>;
             async fn is_in_mempool(&self, txid: Txid) -> Result<bool, BitcoinError>;
             async fn fee_rate(&self, txid: Txid) -> Result<SatPerVbyte, BitcoinError>;
+            async fn estimate_fee_rate(&self) -> Result<Option<SatPerVbyte>, BitcoinError>;
+            async fn wait_for_transaction_in_mempool(&self, txid: Txid, timeout: Duration) -> Result<(), BitcoinError>;
         }
     }
 
@@ -311,12 +316,17 @@ mod tests {
         async fn register_vault(&self, vault_id: &VaultId, collateral: u128) -> Result<(), RuntimeError>;
         async fn deposit_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
         async fn withdraw_collateral(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
+        async fn withdraw_collateral_safe(&self, vault_id: &VaultId, amount: u128, min_ratio: FixedU128) -> Result<(), RuntimeError>;
         async fn get_public_key(&self) -> Result<Option<BtcPublicKey>, RuntimeError>;
         async fn register_public_key(&self, public_key: BtcPublicKey) -> Result<(), RuntimeError>;
         async fn get_required_collateral_for_wrapped(&self, amount_btc: u128, collateral_currency: CurrencyId) -> Result<u128, RuntimeError>;
         async fn get_required_collateral_for_vault(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
         async fn get_vault_total_collateral(&self, vault_id: VaultId) -> Result<u128, RuntimeError>;
         async fn get_collateralization_from_vault(&self, vault_id: VaultId, only_issued: bool) -> Result<u128, RuntimeError>;
+        async fn get_collateralization_ratio(&self, vault_id: &VaultId) -> Result<Option<FixedU128>, RuntimeError>;
+        async fn health_summary(&self) -> Result<HealthSummary, RuntimeError>;
+        async fn liquidation_price(&self, collateral_currency: CurrencyId) -> Result<Option<LiquidationPrice>, RuntimeError>;
+        async fn thresholds(&self, collateral_currency: CurrencyId) -> Result<Thresholds, RuntimeError>;
         async fn set_current_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
         async fn set_pending_client_release(&self, uri: &[u8], code_hash: &H256) -> Result<(), RuntimeError>;
     }
@@ -326,6 +336,7 @@ mod tests {
         async fn request_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
         async fn withdraw_replace(&self, vault_id: &VaultId, amount: u128) -> Result<(), RuntimeError>;
         async fn accept_replace(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, collateral: u128, btc_address: BtcAddress) -> Result<(), RuntimeError>;
+        async fn accept_replace_auto(&self, new_vault: &VaultId, old_vault: &VaultId, amount_btc: u128, buffer_percent: u32, btc_address: BtcAddress) -> Result<(), RuntimeError>;
         async fn execute_replace(&self, replace_id: H256, merkle_proof: &[u8], raw_tx: &[u8]) -> Result<(), RuntimeError>;
         async fn cancel_replace(&self, replace_id: H256) -> Result<(), RuntimeError>;
         async fn get_new_vault_replace_requests(&self, account_id: AccountId) -> Result<Vec<(H256, InterBtcReplaceRequest)>, RuntimeError>;
@@ -333,6 +344,7 @@ mod tests {
         async fn get_replace_period(&self) -> Result<u32, RuntimeError>;
         async fn get_replace_request(&self, replace_id: H256) -> Result<InterBtcReplaceRequest, RuntimeError>;
         async fn get_replace_dust_amount(&self) -> Result<u128, RuntimeError>;
+        async fn request_replace_to_threshold(&self, vault_id: &VaultId, target_ratio: FixedU128) -> Result<Option<u128>, RuntimeError>;
     }
 
 
@@ -340,6 +352,7 @@ mod tests {
     pub trait CollateralBalancesPallet {
         async fn get_free_balance(&self, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
         async fn get_free_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
+        async fn get_balances(&self, id: AccountId, currency_ids: &[CurrencyId]) -> Result<Vec<(CurrencyId, Balance)>, RuntimeError>;
         async fn get_reserved_balance(&self, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
         async fn get_reserved_balance_for_id(&self, id: AccountId, currency_id: CurrencyId) -> Result<Balance, RuntimeError>;
         async fn transfer_to(&self, recipient: &AccountId, amount: u128, currency_id: CurrencyId) -> Result<(), RuntimeError>;         }
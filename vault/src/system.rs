@@ -2,13 +2,15 @@ use crate::{
     delay::{OrderedVaultsDelay, RandomDelay, ZeroDelay},
     error::Error,
     faucet, issue,
+    limiter::RequestLimiter,
     metrics::{poll_metrics, publish_tokio_metrics, PerCurrencyMetrics},
+    payments::PaymentJournal,
     relay::run_relayer,
     service::*,
     Event, IssueRequests, CHAIN_HEIGHT_POLLING_INTERVAL,
 };
 use async_trait::async_trait;
-use bitcoin::{Error as BitcoinError, Network, PublicKey};
+use bitcoin::{BitcoinNetwork, Error as BitcoinError, ObserverBitcoin, PublicKey};
 use clap::Parser;
 use futures::{
     channel::{mpsc, mpsc::Sender},
@@ -18,12 +20,15 @@ use futures::{
 use git_version::git_version;
 use runtime::{
     cli::{parse_duration_minutes, parse_duration_ms},
-    BtcRelayPallet, CollateralBalancesPallet, CurrencyId, Error as RuntimeError, InterBtcParachain, PrettyPrint,
-    RegisterVaultEvent, StoreMainChainHeaderEvent, TryFromSymbol, UpdateActiveBlockEvent, UtilFuncs, VaultCurrencyPair,
-    VaultId, VaultRegistryPallet,
+    AssetRegistry, BlockCursor, BtcRelayPallet, CollateralBalancesPallet, CurrencyArg, CurrencyId,
+    Error as RuntimeError, InterBtcParachain, PrettyPrint, RegisterVaultEvent, StoreMainChainHeaderEvent,
+    TimestampPallet, UpdateActiveBlockEvent, UtilFuncs, VaultCurrencyPair, VaultId, VaultRegistryPallet,
 };
-use service::{wait_or_shutdown, DynBitcoinCoreApi, Error as ServiceError, MonitoringConfig, Service, ShutdownSender};
-use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
+use service::{
+    wait_or_shutdown, ControlSender, DynBitcoinCoreApi, Error as ServiceError, MonitoringConfig, Service,
+    ShutdownReason, ShutdownSender,
+};
+use std::{collections::HashMap, path::PathBuf, pin::Pin, sync::Arc, time::Duration};
 use tokio::{sync::RwLock, time::sleep};
 
 pub const VERSION: &str = git_version!(args = ["--tags"]);
@@ -35,14 +40,14 @@ const RESTART_INTERVAL: Duration = Duration::from_secs(10800); // restart every
 
 fn parse_collateral_and_amount(
     s: &str,
-) -> Result<(String, Option<u128>), Box<dyn std::error::Error + Send + Sync + 'static>> {
+) -> Result<(CurrencyArg, Option<u128>), Box<dyn std::error::Error + Send + Sync + 'static>> {
     let pos = s
         .find('=')
         .ok_or_else(|| format!("invalid CurrencyId=amount: no `=` found in `{}`", s))?;
 
     let val = &s[pos + 1..];
     Ok((
-        s[..pos].to_string(),
+        s[..pos].parse()?,
         if val.contains("faucet") {
             None
         } else {
@@ -51,11 +56,39 @@ fn parse_collateral_and_amount(
     ))
 }
 
+/// The confirmations required by the parachain plus the `extra` ones, never fewer than the former.
+fn effective_btc_confirmations(chain_confirmations: u32, extra: u32) -> u32 {
+    chain_confirmations.saturating_add(extra)
+}
+
+/// The bitcoin confirmations to wait for: `--btc-confirmations` if set, otherwise the ones
+/// required by the parachain plus `--extra-btc-confirmations`.
+async fn num_btc_confirmations<P: BtcRelayPallet>(
+    parachain_rpc: &P,
+    config: &VaultServiceConfig,
+) -> Result<u32, RuntimeError> {
+    if let Some(num_confirmations) = config.btc_confirmations {
+        tracing::info!("Using {} bitcoin confirmations", num_confirmations);
+        return Ok(num_confirmations);
+    }
+    let chain_confirmations = parachain_rpc.get_bitcoin_confirmations().await?;
+    let num_confirmations = effective_btc_confirmations(chain_confirmations, config.extra_btc_confirmations);
+    tracing::info!(
+        "Using {} bitcoin confirmations ({} required by the parachain, {} extra)",
+        num_confirmations,
+        chain_confirmations,
+        config.extra_btc_confirmations
+    );
+    Ok(num_confirmations)
+}
+
 #[derive(Parser, Clone, Debug)]
 pub struct VaultServiceConfig {
-    /// Automatically register the vault with the given amount of collateral and a newly generated address.
+    /// Automatically register the vault with the given amount of collateral and a newly generated address,
+    /// as `CURRENCY=AMOUNT` or `CURRENCY=faucet`. The currency is one of DOT, IBTC, INTR, KSM, KBTC, KINT,
+    /// the symbol of a foreign asset or FOREIGN(<id>).
     #[clap(long, value_parser = parse_collateral_and_amount)]
-    pub auto_register: Vec<(String, Option<u128>)>,
+    pub auto_register: Vec<(CurrencyArg, Option<u128>)>,
 
     /// Pass the faucet URL for auto-registration.
     #[clap(long)]
@@ -86,6 +119,11 @@ pub struct VaultServiceConfig {
     #[clap(long)]
     pub btc_confirmations: Option<u32>,
 
+    /// Bitcoin confirmations to wait for in addition to the ones required by the parachain,
+    /// before considering a payment final.
+    #[clap(long, default_value = "0", conflicts_with = "btc_confirmations")]
+    pub extra_btc_confirmations: u32,
+
     /// Minimum time to the the redeem/replace execution deadline to make the bitcoin payment.
     #[clap(long, value_parser = parse_duration_minutes, default_value = "120")]
     pub payment_margin_minutes: Duration,
@@ -94,6 +132,10 @@ pub struct VaultServiceConfig {
     #[clap(long, value_parser = parse_duration_ms, default_value = "6000")]
     pub bitcoin_poll_interval_ms: Duration,
 
+    /// Interval in minutes to reload the registered foreign assets from the parachain.
+    #[clap(long, value_parser = parse_duration_minutes, default_value = "60")]
+    pub asset_registry_refresh_interval_minutes: Duration,
+
     /// Starting height to relay block headers, if not defined
     /// use the best height as reported by the relay module.
     #[clap(long)]
@@ -116,9 +158,48 @@ pub struct VaultServiceConfig {
     pub no_auto_refund: bool,
 
     /// Bump bitcoin tx fees whenever the oracle reports a new,
-    /// higher inclusion fee estimate.
+    /// higher inclusion fee estimate. Requires `--btc-rbf`.
     #[clap(long)]
     pub auto_rbf: bool,
+
+    /// Directory to persist the vault's state in, e.g. the last processed parachain block, so
+    /// that the issue requests made while the vault was down are watched after a restart, and the
+    /// broadcast payouts, so that they are not repeated after a restart. Without it, only issue
+    /// requests made while the vault is running are watched.
+    #[clap(long)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Maximum number of issue, redeem and replace requests that are processed in parallel, the
+    /// others are queued. Unlimited by default, since a request keeps its slot while it waits for
+    /// bitcoin confirmations. Reported in the `concurrent_request_count` metric. Unlike
+    /// `--max-concurrent-requests`, this does not limit the calls to the parachain node.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_parallel_requests: Option<u32>,
+
+    /// Only observe the parachain and bitcoin: every extrinsic and bitcoin wallet write is logged
+    /// as "would submit" instead of being made, e.g. to test a configuration against a live
+    /// network. The bitcoin relay and issue executor are not run, and the public key and vaults
+    /// are not registered, so the vault must already be registered and its wallets loaded in
+    /// bitcoind.
+    #[clap(long)]
+    pub observer: bool,
+
+    /// Minutes after which an unconfirmed payout counts as stuck in the `stuck_payout_count` metric.
+    #[clap(long, value_parser = parse_duration_minutes, default_value = "60")]
+    pub stuck_payout_threshold_minutes: Duration,
+
+    /// Number of blocks the parachain node may lag behind its peers
+    /// for the vault to start.
+    #[clap(long, default_value = "2")]
+    pub max_sync_lag_blocks: u32,
+
+    /// Timeout in minutes to wait for the parachain node to sync on startup.
+    #[clap(long, value_parser = parse_duration_minutes, default_value = "60")]
+    pub sync_timeout_minutes: Duration,
+
+    /// Warn if the parachain time differs from the local clock by more than this many milliseconds.
+    #[clap(long, value_parser = parse_duration_ms, default_value = "60000")]
+    pub max_clock_drift_ms: Duration,
 }
 
 async fn active_block_listener(
@@ -159,11 +240,17 @@ async fn relay_block_listener(
     Ok(())
 }
 
+async fn runtime_upgrade_listener(parachain_rpc: InterBtcParachain) -> Result<(), ServiceError<Error>> {
+    parachain_rpc.shutdown_on_runtime_upgrade().await?;
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct VaultData {
     pub vault_id: VaultId,
     pub btc_rpc: DynBitcoinCoreApi,
     pub metrics: PerCurrencyMetrics,
+    pub payment_journal: Option<PaymentJournal>,
 }
 
 #[derive(Clone)]
@@ -174,6 +261,9 @@ pub struct VaultIdManager {
     // TODO: refactor this
     #[allow(clippy::type_complexity)]
     constructor: Arc<Box<dyn Fn(VaultId) -> Result<DynBitcoinCoreApi, BitcoinError> + Send + Sync>>,
+    payment_journal: Option<PaymentJournal>,
+    request_limiter: RequestLimiter,
+    observer: bool,
 }
 
 impl VaultIdManager {
@@ -187,9 +277,39 @@ impl VaultIdManager {
             constructor: Arc::new(Box::new(constructor)),
             btc_rpc_master_wallet,
             btc_parachain,
+            payment_journal: None,
+            request_limiter: RequestLimiter::unlimited(),
+            observer: false,
         }
     }
 
+    /// Don't copy the derivation key between the wallets or import the keys of past issues when
+    /// adding a vault, since the wallets are only observed.
+    pub fn with_observer_mode(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Record the payouts of all vaults in `payment_journal`, so that they survive restarts.
+    pub fn with_payment_journal(mut self, payment_journal: PaymentJournal) -> Self {
+        self.payment_journal = Some(payment_journal);
+        self
+    }
+
+    pub fn payment_journal(&self) -> Option<&PaymentJournal> {
+        self.payment_journal.as_ref()
+    }
+
+    /// Bound the number of requests of all vaults that are processed in parallel.
+    pub fn with_request_limiter(mut self, request_limiter: RequestLimiter) -> Self {
+        self.request_limiter = request_limiter;
+        self
+    }
+
+    pub fn request_limiter(&self) -> &RequestLimiter {
+        &self.request_limiter
+    }
+
     // used for testing only
     pub fn from_map(
         btc_parachain: InterBtcParachain,
@@ -205,6 +325,7 @@ impl VaultIdManager {
                         vault_id: key,
                         btc_rpc: value,
                         metrics: PerCurrencyMetrics::dummy(),
+                        payment_journal: None,
                     },
                 )
             })
@@ -214,10 +335,17 @@ impl VaultIdManager {
             constructor: Arc::new(Box::new(|_| unimplemented!())),
             btc_rpc_master_wallet,
             btc_parachain,
+            payment_journal: None,
+            request_limiter: RequestLimiter::unlimited(),
+            observer: false,
         }
     }
 
     async fn add_vault_id(&self, vault_id: VaultId) -> Result<(), Error> {
+        // the wallet is named after the collateral, so foreign assets must be known by now
+        if let CurrencyId::ForeignAsset(foreign_asset_id) = vault_id.collateral_currency() {
+            AssetRegistry::get_or_fetch_asset_metadata(&self.btc_parachain, foreign_asset_id).await?;
+        }
         let btc_rpc = (*self.constructor)(vault_id.clone())?;
 
         // load wallet. Exit on failure, since without wallet we can't do a lot
@@ -226,6 +354,29 @@ impl VaultIdManager {
             .await
             .map_err(Error::WalletInitializationFailure)?;
 
+        if self.observer {
+            tracing::info!("Observer mode: not adding the derivation key or the keys from past issues");
+        } else {
+            self.add_keys(&btc_rpc, &vault_id).await?;
+        }
+
+        tracing::info!("Initializing metrics...");
+        let metrics = PerCurrencyMetrics::new(&vault_id);
+        let data = VaultData {
+            vault_id: vault_id.clone(),
+            btc_rpc: btc_rpc.clone(),
+            metrics: metrics.clone(),
+            payment_journal: self.payment_journal.clone(),
+        };
+        PerCurrencyMetrics::initialize_values(self.btc_parachain.clone(), &data).await;
+
+        self.vault_data.write().await.insert(vault_id, data.clone());
+
+        Ok(())
+    }
+
+    /// Add the derivation key and the deposit keys of past issues to the wallet of the vault.
+    async fn add_keys(&self, btc_rpc: &DynBitcoinCoreApi, vault_id: &VaultId) -> Result<(), Error> {
         tracing::info!("Adding derivation key...");
         let derivation_key = self
             .btc_parachain
@@ -237,14 +388,14 @@ impl VaultIdManager {
         // currency-specific wallet to the master wallet. This can be removed once all
         // vaults have migrated
         let public_key = PublicKey::from_slice(&derivation_key.0).map_err(BitcoinError::KeyError)?;
-        if let Ok(private_key) = btc_rpc.dump_derivation_key(&public_key) {
-            self.btc_rpc_master_wallet.import_derivation_key(&private_key)?;
+        if let Ok(private_key) = btc_rpc.dump_derivation_key(&public_key).await {
+            self.btc_rpc_master_wallet.import_derivation_key(&private_key).await?;
         }
 
         // Copy the derivation key from the master wallet to use currency-specific wallet
-        match self.btc_rpc_master_wallet.dump_derivation_key(&public_key) {
+        match self.btc_rpc_master_wallet.dump_derivation_key(&public_key).await {
             Ok(private_key) => {
-                btc_rpc.import_derivation_key(&private_key)?;
+                btc_rpc.import_derivation_key(&private_key).await?;
             }
             Err(err) => {
                 tracing::error!("Could not find the derivation key in the bitcoin wallet");
@@ -253,19 +404,7 @@ impl VaultIdManager {
         }
 
         tracing::info!("Adding keys from past issues...");
-        issue::add_keys_from_past_issue_request(&btc_rpc, &self.btc_parachain, &vault_id).await?;
-
-        tracing::info!("Initializing metrics...");
-        let metrics = PerCurrencyMetrics::new(&vault_id);
-        let data = VaultData {
-            vault_id: vault_id.clone(),
-            btc_rpc: btc_rpc.clone(),
-            metrics: metrics.clone(),
-        };
-        PerCurrencyMetrics::initialize_values(self.btc_parachain.clone(), &data).await;
-
-        self.vault_data.write().await.insert(vault_id, data.clone());
-
+        issue::add_keys_from_past_issue_request(btc_rpc, &self.btc_parachain, vault_id).await?;
         Ok(())
     }
 
@@ -351,6 +490,7 @@ pub struct VaultService {
     config: VaultServiceConfig,
     monitoring_config: MonitoringConfig,
     shutdown: ShutdownSender,
+    control: ControlSender,
     vault_id_manager: VaultIdManager,
 }
 
@@ -365,6 +505,7 @@ impl Service<VaultServiceConfig, Error> for VaultService {
         config: VaultServiceConfig,
         monitoring_config: MonitoringConfig,
         shutdown: ShutdownSender,
+        control: ControlSender,
         constructor: Box<dyn Fn(VaultId) -> Result<DynBitcoinCoreApi, BitcoinError> + Send + Sync>,
     ) -> Self {
         VaultService::new(
@@ -373,6 +514,7 @@ impl Service<VaultServiceConfig, Error> for VaultService {
             config,
             monitoring_config,
             shutdown,
+            control,
             constructor,
         )
     }
@@ -419,6 +561,7 @@ async fn run_and_monitor_tasks(
 }
 
 type Task = Pin<Box<dyn Future<Output = Result<(), ServiceError<Error>>> + Send + 'static>>;
+type BitcoinConstructor = Box<dyn Fn(VaultId) -> Result<DynBitcoinCoreApi, BitcoinError> + Send + Sync>;
 
 enum ServiceTask {
     Optional(bool, Task),
@@ -448,18 +591,59 @@ impl VaultService {
         config: VaultServiceConfig,
         monitoring_config: MonitoringConfig,
         shutdown: ShutdownSender,
+        control: ControlSender,
         constructor: impl Fn(VaultId) -> Result<DynBitcoinCoreApi, BitcoinError> + Send + Sync + 'static,
     ) -> Self {
+        let (btc_parachain, btc_rpc_master_wallet, constructor) = if config.observer {
+            tracing::info!("Running in observer mode, nothing will be submitted");
+            let constructor: BitcoinConstructor = Box::new(move |vault_id| {
+                Ok(Arc::new(ObserverBitcoin::new(constructor(vault_id)?)) as DynBitcoinCoreApi)
+            });
+            (
+                btc_parachain.with_observer_mode(),
+                Arc::new(ObserverBitcoin::new(btc_rpc_master_wallet)) as DynBitcoinCoreApi,
+                constructor,
+            )
+        } else {
+            (
+                btc_parachain,
+                btc_rpc_master_wallet,
+                Box::new(constructor) as BitcoinConstructor,
+            )
+        };
+        let mut vault_id_manager =
+            VaultIdManager::new(btc_parachain.clone(), btc_rpc_master_wallet.clone(), constructor)
+                .with_request_limiter(
+                    config
+                        .max_parallel_requests
+                        .map_or_else(RequestLimiter::unlimited, RequestLimiter::new),
+                )
+                .with_observer_mode(config.observer);
+        if let Some(data_dir) = &config.data_dir {
+            let account_id = btc_parachain.get_account_id().pretty_print();
+            vault_id_manager = vault_id_manager
+                .with_payment_journal(PaymentJournal::new(data_dir.join(format!("payments_{}", account_id))));
+        }
         Self {
-            btc_parachain: btc_parachain.clone(),
-            btc_rpc_master_wallet: btc_rpc_master_wallet.clone(),
+            btc_parachain,
+            btc_rpc_master_wallet,
             config,
             monitoring_config,
             shutdown,
-            vault_id_manager: VaultIdManager::new(btc_parachain, btc_rpc_master_wallet, constructor),
+            control,
+            vault_id_manager,
         }
     }
 
+    /// Cursor of the listener `name` in the `data_dir`, if set.
+    fn event_cursor(&self, name: &str) -> Option<BlockCursor> {
+        let account_id = self.btc_parachain.get_account_id().pretty_print();
+        self.config
+            .data_dir
+            .as_ref()
+            .map(|dir| BlockCursor::new(dir.join(format!("{}_{}.cursor", name, account_id))))
+    }
+
     fn get_vault_id(&self, collateral_currency: CurrencyId) -> VaultId {
         let account_id = self.btc_parachain.get_account_id();
         let wrapped_currency = self.btc_parachain.wrapped_currency_id;
@@ -474,12 +658,7 @@ impl VaultService {
     }
 
     async fn validate_bitcoin_network(&self) -> Result<(), Error> {
-        let bitcoin_network = match self.btc_rpc_master_wallet.network() {
-            Network::Bitcoin => "mainnet",
-            Network::Testnet => "testnet",
-            Network::Regtest => "regtest",
-            Network::Signet => "signet",
-        };
+        let bitcoin_network = BitcoinNetwork::from(self.btc_rpc_master_wallet.network()).to_string();
         let system_properties = self.btc_parachain.get_rpc_properties().await.unwrap_or_default();
 
         if let Some(parachain_bitcoin_network) = system_properties.get("bitcoinNetwork") {
@@ -488,12 +667,10 @@ impl VaultService {
             // source: https://github.com/interlay/interbtc/blob/a71b970616b0a4a59cd2e709a606a9a78fce80ff/primitives/src/lib.rs#L23
             // `bitcoin_network` can be `mainnet`, `testnet`, regtest.
             // source: https://developer.bitcoin.org/reference/rpc/getblockchaininfo.html
-            if !parachain_bitcoin_network_string.contains(bitcoin_network) {
-                return Err(runtime::Error::BitcoinNetworkMismatch(
-                    parachain_bitcoin_network_string,
-                    bitcoin_network.to_string(),
-                )
-                .into());
+            if !parachain_bitcoin_network_string.contains(&bitcoin_network) {
+                return Err(
+                    runtime::Error::BitcoinNetworkMismatch(parachain_bitcoin_network_string, bitcoin_network).into(),
+                );
             }
         }
 
@@ -503,6 +680,18 @@ impl VaultService {
     async fn run_service(&self) -> Result<(), ServiceError<Error>> {
         self.validate_bitcoin_network().await.map_err(ServiceError::Abort)?;
 
+        // a node that is still catching up would serve stale state
+        tracing::info!("Waiting for the parachain node to sync...");
+        self.btc_parachain
+            .wait_for_sync(self.config.max_sync_lag_blocks, self.config.sync_timeout_minutes)
+            .await?;
+
+        // deadlines are computed from the parachain time, so a skewed clock on either side matters
+        let clock_drift = self.btc_parachain.clock_drift().await?;
+        if clock_drift.magnitude() > self.config.max_clock_drift_ms {
+            tracing::warn!("Parachain time is {} the local clock", clock_drift);
+        }
+
         let account_id = self.btc_parachain.get_account_id().clone();
 
         let parsed_auto_register = self
@@ -510,7 +699,7 @@ impl VaultService {
             .auto_register
             .clone()
             .into_iter()
-            .map(|(symbol, amount)| Ok((CurrencyId::try_from_symbol(symbol)?, amount)))
+            .map(|(currency, amount)| Ok((currency.resolve()?, amount)))
             .into_iter()
             .collect::<Result<Vec<_>, Error>>()
             .map_err(ServiceError::Abort)?;
@@ -521,11 +710,11 @@ impl VaultService {
             return Err(ServiceError::Abort(Error::FaucetUrlNotSet));
         }
 
-        let num_confirmations = match self.config.btc_confirmations {
-            Some(x) => x,
-            None => self.btc_parachain.get_bitcoin_confirmations().await?,
-        };
-        tracing::info!("Using {} bitcoin confirmations", num_confirmations);
+        let num_confirmations = num_btc_confirmations(&self.btc_parachain, &self.config).await?;
+
+        if let Some(data_dir) = &self.config.data_dir {
+            std::fs::create_dir_all(data_dir)?;
+        }
 
         // Subscribe to an event (any event will do) so that a period of inactivity
         // does not close the jsonrpsee connection
@@ -540,20 +729,24 @@ impl VaultService {
         });
         tokio::task::spawn(err_listener);
 
-        self.maybe_register_public_key().await?;
-        match join_all(
-            parsed_auto_register
-                .iter()
-                .map(|(currency_id, amount)| self.maybe_register_vault(currency_id, amount)),
-        )
-        .await
-        .into_iter()
-        .collect::<Result<(), Error>>()
-        {
-            Err(Error::RuntimeError(err)) if err.is_threshold_not_set() => Err(ServiceError::Abort(err.into())),
-            Err(err) => Err(err.into()),
-            Ok(_) => Ok(()),
-        }?;
+        if self.config.observer {
+            tracing::info!("Observer mode: not registering the public key or vaults");
+        } else {
+            self.maybe_register_public_key().await?;
+            match join_all(
+                parsed_auto_register
+                    .iter()
+                    .map(|(currency_id, amount)| self.maybe_register_vault(currency_id, amount)),
+            )
+            .await
+            .into_iter()
+            .collect::<Result<(), Error>>()
+            {
+                Err(Error::RuntimeError(err)) if err.is_threshold_not_set() => Err(ServiceError::Abort(err.into())),
+                Err(err) => Err(err.into()),
+                Ok(_) => Ok(()),
+            }?;
+        }
 
         // purposefully _after_ maybe_register_vault and _before_ other calls
         self.vault_id_manager.fetch_vault_ids().await?;
@@ -577,7 +770,8 @@ impl VaultService {
                 Ok(_) => tracing::info!("Done processing open requests"),
                 Err(e) => {
                     tracing::error!("Failed to process open requests: {}", e);
-                    if let Err(err) = shutdown_clone.send(()) {
+                    let reason = ShutdownReason::Custom(format!("failed to process open requests: {}", e));
+                    if let Err(err) = shutdown_clone.shutdown(reason) {
                         tracing::error!("Failed to send shutdown signal: {}", err);
                     }
                 }
@@ -604,6 +798,11 @@ impl VaultService {
         let listen_for_registered_assets =
             |rpc: InterBtcParachain| async move { rpc.listen_for_registered_assets().await };
 
+        let asset_registry_refresh_interval = self.config.asset_registry_refresh_interval_minutes;
+        let refresh_asset_registry = |rpc: InterBtcParachain| async move {
+            AssetRegistry::refresh_periodically(&rpc, asset_registry_refresh_interval).await
+        };
+
         let listen_for_fee_rate_estimate_changes =
             |rpc: InterBtcParachain| async move { rpc.listen_for_fee_rate_changes().await };
 
@@ -613,6 +812,10 @@ impl VaultService {
                 "Registered Asset Listener",
                 run(listen_for_registered_assets(self.btc_parachain.clone())),
             ),
+            (
+                "Asset Registry Refresher",
+                run(refresh_asset_registry(self.btc_parachain.clone())),
+            ),
             (
                 "Fee Estimate Listener",
                 run(listen_for_fee_rate_estimate_changes(self.btc_parachain.clone())),
@@ -624,6 +827,7 @@ impl VaultService {
                     self.btc_parachain.clone(),
                     issue_event_tx.clone(),
                     issue_set.clone(),
+                    self.event_cursor("issue_requests"),
                 )),
             ),
             (
@@ -701,10 +905,15 @@ impl VaultService {
                     replace_event_tx.clone(),
                 )),
             ),
+            (
+                "Runtime Upgrade Listener",
+                run(runtime_upgrade_listener(self.btc_parachain.clone())),
+            ),
             (
                 "Redeem Request Listener",
                 run(listen_for_redeem_requests(
                     self.shutdown.clone(),
+                    self.control.clone(),
                     self.btc_parachain.clone(),
                     self.vault_id_manager.clone(),
                     num_confirmations,
@@ -719,7 +928,7 @@ impl VaultService {
             (
                 "Bitcoin Relay",
                 maybe_run(
-                    !self.config.no_bitcoin_block_relay,
+                    !self.config.no_bitcoin_block_relay && !self.config.observer,
                     run_relayer(Runner::new(
                         self.btc_rpc_master_wallet.clone(),
                         self.btc_parachain.clone(),
@@ -736,7 +945,7 @@ impl VaultService {
             (
                 "Issue Executor",
                 maybe_run(
-                    !self.config.no_issue_execution,
+                    !self.config.no_issue_execution && !self.config.observer,
                     issue::process_issue_requests(
                         self.btc_rpc_master_wallet.clone(),
                         self.btc_parachain.clone(),
@@ -744,6 +953,8 @@ impl VaultService {
                         oldest_issue_btc_height,
                         num_confirmations,
                         random_delay,
+                        self.control.clone(),
+                        self.vault_id_manager.request_limiter().clone(),
                     ),
                 ),
             ),
@@ -758,7 +969,11 @@ impl VaultService {
                 "Bridge Metrics Poller",
                 maybe_run(
                     !self.monitoring_config.no_prometheus,
-                    poll_metrics(self.btc_parachain.clone(), self.vault_id_manager.clone()),
+                    poll_metrics(
+                        self.btc_parachain.clone(),
+                        self.vault_id_manager.clone(),
+                        self.config.stuck_payout_threshold_minutes,
+                    ),
                 ),
             ),
             (
@@ -859,3 +1074,70 @@ pub(crate) async fn is_vault_registered(parachain_rpc: &InterBtcParachain, vault
         Err(err) => Err(err.into()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime::{BitcoinBlockHeight, BlockNumber, H256Le, InterBtcRichBlockHeader, RawBlockHeader};
+
+    mockall::mock! {
+        Provider {}
+
+        #[async_trait]
+        pub trait BtcRelayPallet {
+            async fn get_best_block(&self) -> Result<H256Le, RuntimeError>;
+            async fn get_best_block_height(&self) -> Result<u32, RuntimeError>;
+            async fn get_block_hash(&self, height: u32) -> Result<H256Le, RuntimeError>;
+            async fn get_block_header(&self, hash: H256Le) -> Result<InterBtcRichBlockHeader, RuntimeError>;
+            async fn get_bitcoin_confirmations(&self) -> Result<u32, RuntimeError>;
+            async fn get_parachain_confirmations(&self) -> Result<BlockNumber, RuntimeError>;
+            async fn wait_for_block_in_relay(&self, block_hash: H256Le, btc_confirmations: Option<BlockNumber>) -> Result<(), RuntimeError>;
+            async fn verify_block_header_inclusion(&self, block_hash: H256Le) -> Result<(), RuntimeError>;
+            async fn wait_for_confirmations(&self, block_hash: H256Le, required_confirmations: Option<u32>, timeout_after: std::time::Duration) -> Result<(), RuntimeError>;
+            async fn initialize_btc_relay(&self, header: RawBlockHeader, height: BitcoinBlockHeight) -> Result<(), RuntimeError>;
+            async fn store_block_header(&self, header: RawBlockHeader) -> Result<(), RuntimeError>;
+            async fn store_block_headers(&self, headers: Vec<RawBlockHeader>) -> Result<(), RuntimeError>;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_num_btc_confirmations_adds_extra_to_chain_value() {
+        let mut parachain_rpc = MockProvider::default();
+        parachain_rpc.expect_get_bitcoin_confirmations().returning(|| Ok(6));
+
+        let config = VaultServiceConfig::try_parse_from(["vault", "--extra-btc-confirmations", "3"]).unwrap();
+        assert_eq!(num_btc_confirmations(&parachain_rpc, &config).await.unwrap(), 9);
+
+        let config = VaultServiceConfig::try_parse_from(["vault"]).unwrap();
+        assert_eq!(num_btc_confirmations(&parachain_rpc, &config).await.unwrap(), 6);
+
+        // an explicit number of confirmations does not query the parachain
+        let config = VaultServiceConfig::try_parse_from(["vault", "--btc-confirmations", "1"]).unwrap();
+        assert_eq!(
+            num_btc_confirmations(&MockProvider::default(), &config).await.unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_effective_btc_confirmations() {
+        assert_eq!(effective_btc_confirmations(6, 0), 6);
+        assert_eq!(effective_btc_confirmations(6, 4), 10);
+        assert_eq!(effective_btc_confirmations(0, 4), 4);
+        // never below the parachain's requirement
+        assert_eq!(effective_btc_confirmations(u32::MAX, 4), u32::MAX);
+
+        let config = VaultServiceConfig::try_parse_from(["vault", "--extra-btc-confirmations", "3"]).unwrap();
+        assert_eq!(config.extra_btc_confirmations, 3);
+        assert_eq!(config.btc_confirmations, None);
+        // an explicit number of confirmations replaces the parachain's
+        assert!(VaultServiceConfig::try_parse_from([
+            "vault",
+            "--btc-confirmations",
+            "1",
+            "--extra-btc-confirmations",
+            "3"
+        ])
+        .is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use bitcoin::{stream_blocks, SatPerVbyte};
+use bitcoin::{stream_blocks, ObserverBitcoin, SatPerVbyte};
 use frame_support::assert_ok;
 use futures::{
     channel::mpsc,
@@ -13,10 +13,10 @@ use runtime::{
     BtcAddress, CurrencyId, FixedPointNumber, FixedU128, InterBtcParachain, InterBtcRedeemRequest, IssuePallet,
     PartialAddress, RedeemPallet, ReplacePallet, ShutdownSender, SudoPallet, UtilFuncs, VaultId, VaultRegistryPallet,
 };
-use service::DynBitcoinCoreApi;
+use service::{ControlSender, DynBitcoinCoreApi};
 use sp_keyring::AccountKeyring;
 use std::{sync::Arc, time::Duration};
-use vault::{self, Event as CancellationEvent, IssueRequests, VaultIdManager, ZeroDelay};
+use vault::{self, Event as CancellationEvent, IssueRequests, RequestLimiter, VaultIdManager, ZeroDelay};
 
 const TIMEOUT: Duration = Duration::from_secs(90);
 
@@ -89,6 +89,7 @@ async fn test_redeem_succeeds() {
             join(
                 vault::service::listen_for_redeem_requests(
                     shutdown_tx,
+                    ControlSender::new(),
                     vault_provider.clone(),
                     vault_id_manager,
                     0,
@@ -109,6 +110,109 @@ async fn test_redeem_succeeds() {
     .await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_observer_adds_registered_vault() {
+    test_with_vault(|client, vault_id, vault_provider| async move {
+        let relayer_provider = setup_provider(client.clone(), AccountKeyring::Bob).await;
+        let user_provider = setup_provider(client.clone(), AccountKeyring::Dave).await;
+
+        let mock_bitcoin_core = MockBitcoinCore::new(relayer_provider.clone()).await;
+        let btc_rpc: DynBitcoinCoreApi = Arc::new(mock_bitcoin_core);
+
+        let issue_amount = 100000;
+        let vault_collateral =
+            get_required_vault_collateral_for_issue(&vault_provider, issue_amount, vault_id.collateral_currency())
+                .await;
+        assert_ok!(
+            vault_provider
+                .register_vault_with_public_key(
+                    &vault_id,
+                    vault_collateral,
+                    btc_rpc.get_new_public_key().await.unwrap().inner.serialize().into(),
+                )
+                .await
+        );
+
+        assert_issue(&user_provider, &btc_rpc, &vault_id, issue_amount).await;
+
+        // the observer wallets reject the key imports and the rescan of past issues
+        let observer_wallet: DynBitcoinCoreApi = Arc::new(ObserverBitcoin::new(btc_rpc.clone()));
+        let vault_id_manager = VaultIdManager::new(vault_provider.clone(), observer_wallet.clone(), move |_| {
+            Ok(observer_wallet.clone())
+        })
+        .with_observer_mode(true);
+
+        assert_ok!(vault_id_manager.fetch_vault_ids().await);
+        assert_eq!(vault_id_manager.get_vault_ids().await, vec![vault_id.clone()]);
+        assert!(vault_id_manager.get_bitcoin_rpc(&vault_id).await.is_some());
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_redeem_is_deferred_while_paused() {
+    test_with_vault(|client, vault_id, vault_provider| async move {
+        let relayer_provider = setup_provider(client.clone(), AccountKeyring::Bob).await;
+        let user_provider = setup_provider(client.clone(), AccountKeyring::Dave).await;
+
+        let mock_bitcoin_core = MockBitcoinCore::new(relayer_provider.clone()).await;
+        let btc_rpc: DynBitcoinCoreApi = Arc::new(mock_bitcoin_core);
+
+        let btc_rpcs = vec![(vault_id.clone(), btc_rpc.clone())].into_iter().collect();
+        let btc_rpc_master_wallet = btc_rpc.clone();
+        let vault_id_manager = VaultIdManager::from_map(vault_provider.clone(), btc_rpc_master_wallet, btc_rpcs);
+
+        let issue_amount = 100000;
+        let vault_collateral =
+            get_required_vault_collateral_for_issue(&vault_provider, issue_amount, vault_id.collateral_currency())
+                .await;
+        assert_ok!(
+            vault_provider
+                .register_vault_with_public_key(
+                    &vault_id,
+                    vault_collateral,
+                    btc_rpc.get_new_public_key().await.unwrap().inner.serialize().into(),
+                )
+                .await
+        );
+
+        assert_issue(&user_provider, &btc_rpc, &vault_id, issue_amount).await;
+
+        let shutdown_tx = ShutdownSender::new();
+        let control_tx = ControlSender::new();
+        control_tx.pause();
+
+        test_service(
+            join(
+                vault::service::listen_for_redeem_requests(
+                    shutdown_tx,
+                    control_tx.clone(),
+                    vault_provider.clone(),
+                    vault_id_manager,
+                    0,
+                    Duration::from_secs(0),
+                    true,
+                ),
+                periodically_produce_blocks(user_provider.clone()),
+            ),
+            async {
+                let address = BtcAddress::P2PKH(H160::from_slice(&[2; 20]));
+                let redeem_id = user_provider.request_redeem(10000, address, &vault_id).await.unwrap();
+
+                // the request is seen, but not executed while paused
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                let redeem = user_provider.get_redeem_request(redeem_id).await.unwrap();
+                assert_eq!(redeem.status, RedeemRequestStatus::Pending);
+
+                control_tx.resume();
+                assert_redeem_event(TIMEOUT, user_provider, redeem_id).await;
+            },
+        )
+        .await;
+    })
+    .await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_replace_succeeds() {
     test_with_vault(|client, old_vault_id, old_vault_provider| async move {
@@ -350,6 +454,7 @@ async fn test_cancellation_succeeds() {
             new_vault_provider.clone(),
             issue_cancellation_event_tx.clone(),
             issue_set.clone(),
+            None,
         );
 
         let issue_cancellation_scheduler = vault::service::CancellationScheduler::new(
@@ -622,6 +727,109 @@ async fn test_automatic_issue_execution_succeeds() {
                 vault2_provider.clone(),
                 issue_event_tx.clone(),
                 issue_set.clone(),
+                None,
+            ),
+            vault::service::process_issue_requests(
+                btc_rpc.clone(),
+                vault2_provider.clone(),
+                issue_set.clone(),
+                1,
+                0,
+                Arc::new(Box::new(ZeroDelay)),
+                ControlSender::new(),
+                RequestLimiter::unlimited(),
+            ),
+            periodically_produce_blocks(vault2_provider.clone()),
+        );
+
+        test_service(service, fut_user).await;
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_automatic_issue_execution_is_deferred_while_paused() {
+    test_with_vault(|client, vault1_id, _vault1_provider| async move {
+        let relayer_provider = setup_provider(client.clone(), AccountKeyring::Bob).await;
+        let vault1_provider = setup_provider(client.clone(), AccountKeyring::Charlie).await;
+        let vault2_provider = setup_provider(client.clone(), AccountKeyring::Eve).await;
+        let vault2_id = VaultId::new(
+            AccountKeyring::Eve.into(),
+            DEFAULT_TESTING_CURRENCY,
+            DEFAULT_WRAPPED_CURRENCY,
+        );
+        let user_provider = setup_provider(client.clone(), AccountKeyring::Dave).await;
+
+        let mock_bitcoin_core = MockBitcoinCore::new(relayer_provider.clone()).await;
+        let btc_rpc: DynBitcoinCoreApi = Arc::new(mock_bitcoin_core);
+
+        let btc_rpcs = vec![(vault2_id.clone(), btc_rpc.clone())].into_iter().collect();
+        let btc_rpc_master_wallet = btc_rpc.clone();
+        let vault_id_manager = VaultIdManager::from_map(vault2_provider.clone(), btc_rpc_master_wallet, btc_rpcs);
+
+        let issue_amount = 100000;
+        let vault_collateral =
+            get_required_vault_collateral_for_issue(&vault1_provider, issue_amount, vault1_id.collateral_currency())
+                .await;
+
+        assert_ok!(
+            vault1_provider
+                .register_vault_with_public_key(
+                    &vault1_id,
+                    vault_collateral,
+                    btc_rpc.get_new_public_key().await.unwrap().inner.serialize().into(),
+                )
+                .await
+        );
+        assert_ok!(
+            vault2_provider
+                .register_vault_with_public_key(
+                    &vault2_id,
+                    vault_collateral,
+                    btc_rpc.get_new_public_key().await.unwrap().inner.serialize().into(),
+                )
+                .await
+        );
+
+        let control_tx = ControlSender::new();
+        control_tx.pause();
+
+        let fut_user = async {
+            let issue = user_provider.request_issue(issue_amount, &vault1_id).await.unwrap();
+            assert_ok!(
+                btc_rpc
+                    .send_to_address(
+                        issue.vault_address.to_address(btc_rpc.network()).unwrap(),
+                        (issue.amount + issue.fee) as u64,
+                        None,
+                        SatPerVbyte(1000),
+                        0
+                    )
+                    .await
+            );
+
+            // the payment is seen, but the issue is not executed while paused
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            let request = user_provider.get_issue_request(issue.issue_id).await.unwrap();
+            assert_eq!(request.status, IssueRequestStatus::Pending);
+
+            // the deferred payment is processed once resumed, without a new transaction
+            control_tx.resume();
+            assert_event::<ExecuteIssueEvent, _>(TIMEOUT, user_provider.clone(), move |x| {
+                x.vault_id == vault1_id.clone()
+            })
+            .await;
+        };
+
+        let issue_set = Arc::new(IssueRequests::new());
+        let (issue_event_tx, _issue_event_rx) = mpsc::channel::<CancellationEvent>(16);
+        let service = join3(
+            vault::service::listen_for_issue_requests(
+                vault_id_manager.clone(),
+                vault2_provider.clone(),
+                issue_event_tx.clone(),
+                issue_set.clone(),
+                None,
             ),
             vault::service::process_issue_requests(
                 btc_rpc.clone(),
@@ -630,6 +838,8 @@ async fn test_automatic_issue_execution_succeeds() {
                 1,
                 0,
                 Arc::new(Box::new(ZeroDelay)),
+                control_tx.clone(),
+                RequestLimiter::unlimited(),
             ),
             periodically_produce_blocks(vault2_provider.clone()),
         );
@@ -712,6 +922,7 @@ async fn test_automatic_issue_execution_succeeds_with_big_transaction() {
                 vault2_provider.clone(),
                 issue_event_tx.clone(),
                 issue_set.clone(),
+                None,
             ),
             vault::service::process_issue_requests(
                 btc_rpc.clone(),
@@ -720,6 +931,8 @@ async fn test_automatic_issue_execution_succeeds_with_big_transaction() {
                 1,
                 0,
                 Arc::new(Box::new(ZeroDelay)),
+                ControlSender::new(),
+                RequestLimiter::unlimited(),
             ),
             periodically_produce_blocks(vault2_provider.clone()),
         );
@@ -921,7 +1134,7 @@ mod test_with_bitcoind {
 
         // fund the wallet by mining blocks
         for _ in 0..102 {
-            ret.mine_block().unwrap();
+            ret.mine_block().await.unwrap();
         }
 
         ret
@@ -1022,7 +1235,7 @@ mod test_with_bitcoind {
             }));
 
         tracing::trace!("Step 4: mine bitcoin block");
-        let block_hash = btc_rpc.mine_block().unwrap();
+        let block_hash = btc_rpc.mine_block().await.unwrap();
 
         tracing::trace!("Step 5: check that tx got included without changes");
         btc_rpc
@@ -1092,7 +1305,7 @@ mod test_with_bitcoind {
         assert!(btc_rpc.fee_rate(new_tx.txid()).await.unwrap().0 >= 10);
 
         tracing::trace!("Step 5: mine bitcoin block");
-        let block_hash = btc_rpc.mine_block().unwrap();
+        let block_hash = btc_rpc.mine_block().await.unwrap();
 
         tracing::trace!("Step 6: check that only new tx got included");
         btc_rpc.get_transaction(&new_tx.txid(), Some(block_hash)).await.unwrap();
@@ -1165,6 +1378,7 @@ mod test_with_bitcoind {
             let service = join(
                 vault::service::listen_for_redeem_requests(
                     shutdown_tx,
+                    ControlSender::new(),
                     vault_provider.clone(),
                     vault_id_manager,
                     0,